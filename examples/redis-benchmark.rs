@@ -0,0 +1,34 @@
+use std::env;
+use std::process;
+
+use redis_starter_rust::cli::benchmark::{run_benchmark, BenchmarkOptions, USAGE};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map_or(false, |arg| arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match BenchmarkOptions::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(1);
+        }
+    };
+
+    for (name, commands) in options.benchmarks() {
+        match run_benchmark(&options, &name, &commands) {
+            Ok(report) => {
+                println!("{}", report.format(&options));
+                if !options.quiet {
+                    println!();
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                process::exit(1);
+            }
+        }
+    }
+}
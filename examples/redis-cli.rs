@@ -0,0 +1,68 @@
+use std::env;
+use std::io;
+use std::process;
+
+use redis_starter_rust::cli::history::History;
+use redis_starter_rust::cli::options::{CliOptions, USAGE};
+use redis_starter_rust::cli::output::stdout_is_terminal;
+use redis_starter_rust::cli::pipe::mass_insert;
+use redis_starter_rust::cli::repl::{run_command, Repl};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map_or(false, |arg| arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let mut options = match CliOptions::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(1);
+        }
+    };
+
+    if !options.pipe && options.command.is_empty() {
+        let mut repl = Repl::new(options, History::new(History::default_path()));
+        if let Err(e) = repl.run(io::stdin().lock(), &mut io::stdout()) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let mut client = match options.connect() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Could not connect to Redis at {}: {}", options.address(), e);
+            process::exit(1);
+        }
+    };
+
+    if options.pipe {
+        match mass_insert(&mut client, io::stdin()) {
+            Ok(summary) => {
+                for error in summary.errors.iter() {
+                    eprintln!("{}", error);
+                }
+                println!("All data transferred. Last reply received from server.");
+                println!("errors: {}, replies: {}", summary.errors.len(), summary.replies);
+                if !summary.errors.is_empty() {
+                    process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Replies are formatted for people in a terminal, and raw for scripts
+    options.raw = Some(options.raw.unwrap_or(!stdout_is_terminal()));
+    if let Err(e) = run_command(&mut client, &options, &mut io::stdout()) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
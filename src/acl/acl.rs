@@ -0,0 +1,321 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::sync::Mutex;
+
+use chrono::Utc;
+
+use crate::acl::user::User;
+use crate::request_response::command::Command;
+use crate::util::global::Global;
+
+// Users of the server and denied attempts, shared by every connection
+static ACL: Global<Mutex<Option<Acl>>> = Global::new();
+
+pub const DEFAULT_USER: &str = "default";
+// Maximum number of entries kept by ACL LOG, like acllog-max-len
+const MAX_LOG_ENTRIES: usize = 128;
+// Denials of the same kind within this time are counted in a single log entry
+const LOG_GROUPING_MS: i64 = 60_000;
+
+// Why a command was not allowed to run, as recorded by ACL LOG
+#[derive(Debug, Clone, PartialEq)]
+pub enum Denial {
+    Command,
+    Key(String),
+    Channel(String),
+    Auth,
+}
+
+impl Denial {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Denial::Command => "command",
+            Denial::Key(_) => "key",
+            Denial::Channel(_) => "channel",
+            Denial::Auth => "auth",
+        }
+    }
+
+    pub fn error(&self, username: &str, command: &Command) -> String {
+        match self {
+            Denial::Command => format!(
+                "NOPERM User {} has no permissions to run the '{}' command",
+                username,
+                command.name()
+            ),
+            Denial::Key(_) => String::from("NOPERM No permissions to access a key"),
+            Denial::Channel(_) => String::from("NOPERM No permissions to access a channel"),
+            Denial::Auth => String::from("WRONGPASS invalid username-password pair or user is disabled."),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub count: u64,
+    pub reason: &'static str,
+    // Whether the command was sent at the top level or in a transaction
+    pub context: &'static str,
+    pub object: String,
+    pub username: String,
+    pub entry_id: u64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug)]
+pub struct Acl {
+    users: BTreeMap<String, User>,
+    // Newest entries first
+    log: VecDeque<LogEntry>,
+    next_entry_id: u64,
+}
+
+impl Default for Acl {
+    fn default() -> Self {
+        Acl::new()
+    }
+}
+
+impl Acl {
+    pub fn new() -> Acl {
+        let mut users = BTreeMap::new();
+        users.insert(String::from(DEFAULT_USER), default_user());
+        Acl {
+            users,
+            log: VecDeque::new(),
+            next_entry_id: 0,
+        }
+    }
+
+    pub fn users(&self) -> impl Iterator<Item = &User> {
+        self.users.values()
+    }
+
+    pub fn get_user(&self, name: &str) -> Option<&User> {
+        self.users.get(name)
+    }
+
+    /// Creates the user if needed and applies the rules. Nothing is changed if any rule is invalid
+    pub fn set_user(&mut self, name: &str, rules: &[String]) -> Result<(), String> {
+        let mut user = self.users.get(name).cloned().unwrap_or_else(|| User::new(name));
+        for rule in rules {
+            user.apply_rule(rule)
+                .map_err(|e| format!("Error in ACL SETUSER modifier '{}': {}", rule, e))?;
+        }
+        self.users.insert(String::from(name), user);
+        Ok(())
+    }
+
+    /// Returns the number of users that were deleted
+    pub fn delete_users(&mut self, names: &[String]) -> Result<usize, String> {
+        if names.iter().any(|name| name == DEFAULT_USER) {
+            return Err(String::from("The 'default' user cannot be removed"));
+        }
+        Ok(names.iter().filter(|name| self.users.remove(name.as_str()).is_some()).count())
+    }
+
+    /// Connections do not need to AUTH while the default user is enabled without a password
+    pub fn is_default_user_open(&self) -> bool {
+        self.users
+            .get(DEFAULT_USER)
+            .map_or(false, |user| user.is_enabled() && user.is_nopass())
+    }
+
+    /// Sets the password of the default user, or removes it if empty, as requirepass does
+    pub fn set_default_password(&mut self, password: &str) {
+        let rules = if password.is_empty() {
+            vec![String::from("nopass")]
+        } else {
+            vec![String::from("resetpass"), format!(">{}", password)]
+        };
+        // Both rules are always valid
+        let _ = self.set_user(DEFAULT_USER, &rules);
+    }
+
+    pub fn authenticate(&self, username: &str, password: &str) -> bool {
+        self.users
+            .get(username)
+            .map_or(false, |user| user.is_enabled() && user.check_password(password))
+    }
+
+    /// Checks that the user can run the command, and access its keys and channels
+    pub fn check(&self, username: &str, command: &Command, args: &[String]) -> Result<(), Denial> {
+        let user = self.users.get(username).ok_or(Denial::Command)?;
+        if !user.can_run(command) {
+            return Err(Denial::Command);
+        }
+
+        for (key, access) in command.key_args(args) {
+            if !user.can_access_key(key, access) {
+                return Err(Denial::Key(String::from(key)));
+            }
+        }
+
+        let (channels, is_pattern) = match command {
+            Command::PUBLISH => (args.get(..1).unwrap_or_default(), false),
+            Command::SUBSCRIBE => (args, false),
+            Command::PSUBSCRIBE => (args, true),
+            _ => (&[] as &[String], false),
+        };
+        for channel in channels {
+            if !user.can_access_channel(channel, is_pattern) {
+                return Err(Denial::Channel(channel.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a denial, counting it in a recent entry of the same kind if there is one
+    pub fn add_log_entry(&mut self, denial: &Denial, context: &'static str, object: &str, username: &str) {
+        let now = Utc::now().timestamp_millis();
+        let similar = self.log.iter().position(|entry| {
+            entry.reason == denial.reason()
+                && entry.context == context
+                && entry.object == object
+                && entry.username == username
+                && now - entry.updated_at < LOG_GROUPING_MS
+        });
+
+        if let Some(index) = similar {
+            let mut entry = self.log.remove(index).unwrap();
+            entry.count += 1;
+            entry.updated_at = now;
+            self.log.push_front(entry);
+            return;
+        }
+
+        self.log.push_front(LogEntry {
+            count: 1,
+            reason: denial.reason(),
+            context,
+            object: String::from(object),
+            username: String::from(username),
+            entry_id: self.next_entry_id,
+            created_at: now,
+            updated_at: now,
+        });
+        self.next_entry_id += 1;
+        self.log.truncate(MAX_LOG_ENTRIES);
+    }
+
+    pub fn log_entries(&self, count: usize) -> Vec<&LogEntry> {
+        self.log.iter().take(count).collect()
+    }
+
+    pub fn reset_log(&mut self) {
+        self.log.clear();
+    }
+
+    /// Replaces the users with those in the file. Nothing is changed if any line is invalid
+    pub fn load_file(&mut self, path: &str) -> Result<(), String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Error loading ACLs, opening file '{}': {}", path, e))?;
+
+        let mut users: BTreeMap<String, User> = BTreeMap::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |e: &str| format!("{}:{}: {}", path, index + 1, e);
+            let mut words = line.split_whitespace();
+            if words.next() != Some("user") {
+                return Err(error("line should start with user keyword"));
+            }
+            let name = words.next().ok_or_else(|| error("a user name is required"))?;
+            if users.contains_key(name) {
+                return Err(error(&format!("Duplicate user '{}' found", name)));
+            }
+
+            let mut user = User::new(name);
+            for rule in words {
+                user.apply_rule(rule).map_err(|e| error(&e))?;
+            }
+            users.insert(String::from(name), user);
+        }
+
+        // The default user is recreated if the file does not define it
+        users.entry(String::from(DEFAULT_USER)).or_insert_with(default_user);
+        self.users = users;
+        Ok(())
+    }
+
+    pub fn save_file(&self, path: &str) -> Result<(), String> {
+        let contents: String = self.users.values().map(|user| format!("{}\n", user.describe())).collect();
+        fs::write(path, contents).map_err(|e| format!("Error saving ACLs: {}", e))
+    }
+}
+
+// The default user can do anything, and needs no password until requirepass is set
+fn default_user() -> User {
+    let mut user = User::new(DEFAULT_USER);
+    for rule in ["on", "nopass", "allkeys", "allchannels", "allcommands"] {
+        user.apply_rule(rule).unwrap();
+    }
+    user
+}
+
+/// Runs the function with exclusive access to the users and log
+pub fn with_acl<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut Acl) -> R,
+{
+    let mut acl = ACL.get_or_init(|| Mutex::new(None)).lock().unwrap_or_else(|e| e.into_inner());
+    f(acl.get_or_insert_with(Acl::new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| String::from(*arg)).collect()
+    }
+
+    #[test]
+    fn check_denies_commands_keys_and_channels() {
+        let mut acl = Acl::new();
+        acl.set_user("alice", &to_args(&["on", "+@read", "+publish", "~cache:*", "&news"]))
+            .unwrap();
+
+        assert_eq!(acl.check("alice", &Command::GET, &to_args(&["cache:1"])), Ok(()));
+        assert_eq!(acl.check("alice", &Command::SET, &to_args(&["cache:1", "1"])), Err(Denial::Command));
+        assert_eq!(
+            acl.check("alice", &Command::GET, &to_args(&["other"])),
+            Err(Denial::Key(String::from("other")))
+        );
+        assert_eq!(acl.check("alice", &Command::PUBLISH, &to_args(&["news", "hello"])), Ok(()));
+        assert_eq!(
+            acl.check("alice", &Command::PUBLISH, &to_args(&["sport", "hello"])),
+            Err(Denial::Channel(String::from("sport")))
+        );
+        assert_eq!(acl.check("default", &Command::FLUSHALL, &[]), Ok(()));
+    }
+
+    #[test]
+    fn set_user_is_atomic() {
+        let mut acl = Acl::new();
+        assert_eq!(
+            acl.set_user("alice", &to_args(&["on", "+nothing"])),
+            Err(String::from(
+                "Error in ACL SETUSER modifier '+nothing': Unknown command or category name in ACL"
+            ))
+        );
+        assert!(acl.get_user("alice").is_none());
+        assert!(acl.delete_users(&to_args(&["default"])).is_err());
+    }
+
+    #[test]
+    fn log_entries_of_the_same_kind_are_counted_together() {
+        let mut acl = Acl::new();
+        acl.add_log_entry(&Denial::Command, "toplevel", "get", "alice");
+        acl.add_log_entry(&Denial::Key(String::from("a")), "toplevel", "a", "alice");
+        acl.add_log_entry(&Denial::Command, "toplevel", "get", "alice");
+
+        let entries = acl.log_entries(10);
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].object.as_str(), entries[0].count, entries[0].entry_id), ("get", 2, 0));
+        assert_eq!((entries[1].object.as_str(), entries[1].count, entries[1].entry_id), ("a", 1, 1));
+    }
+}
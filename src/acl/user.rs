@@ -0,0 +1,386 @@
+use std::collections::BTreeSet;
+
+use crate::request_response::command::{Command, KeyAccess, ALL_COMMANDS, CATEGORIES};
+use crate::util::glob::glob_match;
+use crate::util::sha256::sha256_hex;
+
+// Key pattern of a user, which only allows the access given with %R~ or %W~
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyPattern {
+    pattern: String,
+    access: KeyAccess,
+}
+
+impl KeyPattern {
+    fn describe(&self) -> String {
+        match (self.access.read, self.access.write) {
+            (true, false) => format!("%R~{}", self.pattern),
+            (false, true) => format!("%W~{}", self.pattern),
+            _ => format!("~{}", self.pattern),
+        }
+    }
+}
+
+// An ACL user, which is modified by applying rules such as `on`, `>password`, `~key:*` and `+@read`
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    name: String,
+    enabled: bool,
+    // Any password is accepted
+    nopass: bool,
+    // SHA-256 hashes of the passwords, in lowercase hex
+    passwords: BTreeSet<String>,
+    // Names of the commands that can be run
+    commands: BTreeSet<String>,
+    // Command rules in the order they were applied, which describe the commands
+    command_rules: Vec<String>,
+    keys: Vec<KeyPattern>,
+    // Set by `allkeys` or `~*`, after which other key patterns are pointless
+    all_keys: bool,
+    channels: Vec<String>,
+    // Set by `allchannels` or `&*`
+    all_channels: bool,
+}
+
+impl User {
+    /// A new user is disabled and cannot do anything, like redis
+    pub fn new(name: &str) -> User {
+        User {
+            name: String::from(name),
+            enabled: false,
+            nopass: false,
+            passwords: BTreeSet::new(),
+            commands: BTreeSet::new(),
+            command_rules: vec![String::from("-@all")],
+            keys: Vec::new(),
+            all_keys: false,
+            channels: Vec::new(),
+            all_channels: false,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_nopass(&self) -> bool {
+        self.nopass
+    }
+
+    /// Applies a rule, returning the reason if it is invalid
+    pub fn apply_rule(&mut self, rule: &str) -> Result<(), String> {
+        match rule.to_lowercase().as_str() {
+            "on" => self.enabled = true,
+            "off" => self.enabled = false,
+            "nopass" => {
+                self.nopass = true;
+                self.passwords.clear();
+            }
+            "resetpass" => {
+                self.nopass = false;
+                self.passwords.clear();
+            }
+            "allkeys" | "~*" => {
+                self.all_keys = true;
+                self.keys = vec![KeyPattern {
+                    pattern: String::from("*"),
+                    access: KeyAccess::READ_WRITE,
+                }];
+            }
+            "resetkeys" => {
+                self.all_keys = false;
+                self.keys.clear();
+            }
+            "allchannels" | "&*" => {
+                self.all_channels = true;
+                self.channels = vec![String::from("*")];
+            }
+            "resetchannels" => {
+                self.all_channels = false;
+                self.channels.clear();
+            }
+            "allcommands" | "+@all" => {
+                self.commands = ALL_COMMANDS.iter().map(|command| command.name()).collect();
+                self.command_rules = vec![String::from("+@all")];
+            }
+            "nocommands" | "-@all" => {
+                self.commands.clear();
+                self.command_rules = vec![String::from("-@all")];
+            }
+            "reset" => *self = User::new(&self.name),
+            _ => return self.apply_value_rule(rule),
+        }
+        Ok(())
+    }
+
+    // Rules that start with a symbol followed by a value
+    fn apply_value_rule(&mut self, rule: &str) -> Result<(), String> {
+        let mut chars = rule.chars();
+        let (symbol, value) = match chars.next() {
+            Some(symbol) => (symbol, chars.as_str()),
+            None => return Err(String::from("Syntax error")),
+        };
+
+        match symbol {
+            '>' => self.add_password(sha256_hex(value.as_bytes())),
+            '<' => self.remove_password(&sha256_hex(value.as_bytes()))?,
+            '#' => self.add_password(parse_password_hash(value)?),
+            '!' => self.remove_password(&parse_password_hash(value)?)?,
+            '~' => self.add_key_pattern(value, KeyAccess::READ_WRITE)?,
+            '%' => {
+                let (flags, pattern) = value.split_once('~').ok_or_else(|| String::from("Syntax error"))?;
+                self.add_key_pattern(pattern, parse_key_access(flags)?)?;
+            }
+            '&' => {
+                if self.all_channels {
+                    return Err(String::from(
+                        "Adding a pattern after the * pattern (or the 'allchannels' flag) is not valid and does not have any effect. Try 'resetchannels' to start with an empty list of channels",
+                    ));
+                }
+                self.channels.push(String::from(value));
+            }
+            '+' | '-' => self.apply_command_rule(symbol == '+', value)?,
+            _ => return Err(String::from("Syntax error")),
+        }
+        Ok(())
+    }
+
+    fn add_password(&mut self, hash: String) {
+        self.nopass = false;
+        self.passwords.insert(hash);
+    }
+
+    fn remove_password(&mut self, hash: &str) -> Result<(), String> {
+        if !self.passwords.remove(hash) {
+            return Err(String::from(
+                "The password you are trying to remove from the user does not exist",
+            ));
+        }
+        Ok(())
+    }
+
+    fn add_key_pattern(&mut self, pattern: &str, access: KeyAccess) -> Result<(), String> {
+        if self.all_keys {
+            return Err(String::from(
+                "Adding a pattern after the * pattern (or the 'allkeys' flag) is not valid and does not have any effect. Try 'resetkeys' to start with an empty list of patterns",
+            ));
+        }
+        self.keys.push(KeyPattern {
+            pattern: String::from(pattern),
+            access,
+        });
+        Ok(())
+    }
+
+    // +<command>, -<command>, +@<category> or -@<category>
+    fn apply_command_rule(&mut self, allow: bool, name: &str) -> Result<(), String> {
+        let unknown = || String::from("Unknown command or category name in ACL");
+        let names: Vec<String> = match name.strip_prefix('@') {
+            Some(category) => {
+                let category = category.to_lowercase();
+                if !CATEGORIES.contains(&category.as_str()) {
+                    return Err(unknown());
+                }
+                ALL_COMMANDS
+                    .iter()
+                    .filter(|command| command.categories().contains(&category.as_str()))
+                    .map(|command| command.name())
+                    .collect()
+            }
+            None => vec![Command::from(name).ok_or_else(unknown)?.name()],
+        };
+
+        for name in names {
+            if allow {
+                self.commands.insert(name);
+            } else {
+                self.commands.remove(&name);
+            }
+        }
+        let symbol = if allow { '+' } else { '-' };
+        self.command_rules.push(format!("{}{}", symbol, name.to_lowercase()));
+        Ok(())
+    }
+
+    pub fn check_password(&self, password: &str) -> bool {
+        self.nopass || self.passwords.contains(&sha256_hex(password.as_bytes()))
+    }
+
+    pub fn can_run(&self, command: &Command) -> bool {
+        self.commands.contains(&command.name())
+    }
+
+    /// A single pattern must match the key and allow all of the access that the command needs
+    pub fn can_access_key(&self, key: &str, access: KeyAccess) -> bool {
+        self.all_keys
+            || self.keys.iter().any(|pattern| {
+                (pattern.access.read || !access.read)
+                    && (pattern.access.write || !access.write)
+                    && glob_match(&pattern.pattern, key)
+            })
+    }
+
+    /// Patterns given to PSUBSCRIBE must be identical to one of the user's channel patterns
+    pub fn can_access_channel(&self, channel: &str, is_pattern: bool) -> bool {
+        self.all_channels
+            || self.channels.iter().any(|pattern| {
+                if is_pattern {
+                    pattern == channel
+                } else {
+                    glob_match(pattern, channel)
+                }
+            })
+    }
+
+    /// Flags as listed by ACL GETUSER
+    pub fn flags(&self) -> Vec<&'static str> {
+        let mut flags = vec![if self.enabled { "on" } else { "off" }];
+        if self.nopass {
+            flags.push("nopass");
+        }
+        flags
+    }
+
+    pub fn password_hashes(&self) -> Vec<&str> {
+        self.passwords.iter().map(|hash| hash.as_str()).collect()
+    }
+
+    pub fn describe_commands(&self) -> String {
+        self.command_rules.join(" ")
+    }
+
+    pub fn describe_keys(&self) -> String {
+        self.keys.iter().map(|pattern| pattern.describe()).collect::<Vec<String>>().join(" ")
+    }
+
+    pub fn describe_channels(&self) -> String {
+        self.channels.iter().map(|channel| format!("&{}", channel)).collect::<Vec<String>>().join(" ")
+    }
+
+    /// The user as rules that recreate it, as shown by ACL LIST and saved to the ACL file
+    pub fn describe(&self) -> String {
+        let mut rules: Vec<String> = self.flags().iter().map(|flag| String::from(*flag)).collect();
+        rules.extend(self.passwords.iter().map(|hash| format!("#{}", hash)));
+        if !self.keys.is_empty() {
+            rules.push(self.describe_keys());
+        }
+        if self.channels.is_empty() {
+            rules.push(String::from("resetchannels"));
+        } else {
+            rules.push(self.describe_channels());
+        }
+        rules.push(self.describe_commands());
+        format!("user {} {}", self.name, rules.join(" "))
+    }
+}
+
+fn parse_password_hash(hash: &str) -> Result<String, String> {
+    let is_valid = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c));
+    if !is_valid {
+        return Err(String::from(
+            "The password hash must be exactly 64 characters and contain only lowercase hexadecimal characters",
+        ));
+    }
+    Ok(String::from(hash))
+}
+
+// The flags between % and ~, i.e. R, W or RW
+fn parse_key_access(flags: &str) -> Result<KeyAccess, String> {
+    let mut access = KeyAccess { read: false, write: false };
+    for flag in flags.chars() {
+        match flag.to_ascii_uppercase() {
+            'R' => access.read = true,
+            'W' => access.write = true,
+            _ => return Err(String::from("Syntax error")),
+        }
+    }
+    if !access.read && !access.write {
+        return Err(String::from("Syntax error"));
+    }
+    Ok(access)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_with_rules(rules: &[&str]) -> User {
+        let mut user = User::new("alice");
+        for rule in rules {
+            user.apply_rule(rule).unwrap();
+        }
+        user
+    }
+
+    #[test]
+    fn command_rules_are_applied_in_order() {
+        let user = user_with_rules(&["+@all", "-@dangerous", "+config"]);
+        assert!(user.can_run(&Command::GET));
+        assert!(!user.can_run(&Command::FLUSHALL));
+        assert!(user.can_run(&Command::CONFIG));
+        assert_eq!(user.describe_commands(), "+@all -@dangerous +config");
+
+        let user = user_with_rules(&["+@read", "-xinfo"]);
+        assert!(user.can_run(&Command::XRANGE));
+        assert!(!user.can_run(&Command::XINFO));
+        assert!(!user.can_run(&Command::SET));
+        assert_eq!(user.describe_commands(), "-@all +@read -xinfo");
+
+        let mut user = User::new("alice");
+        assert!(user.apply_rule("+@nothing").is_err());
+        assert!(user.apply_rule("+nothing").is_err());
+        assert!(user.apply_rule("?").is_err());
+    }
+
+    #[test]
+    fn key_patterns_allow_read_and_write_separately() {
+        let user = user_with_rules(&["~cache:*", "%R~config:*", "%W~log:*"]);
+        assert!(user.can_access_key("cache:1", KeyAccess::READ_WRITE));
+        assert!(user.can_access_key("config:1", KeyAccess::READ));
+        assert!(!user.can_access_key("config:1", KeyAccess::WRITE));
+        assert!(user.can_access_key("log:1", KeyAccess::WRITE));
+        assert!(!user.can_access_key("log:1", KeyAccess::READ_WRITE));
+        assert!(!user.can_access_key("other", KeyAccess::READ));
+        assert_eq!(user.describe_keys(), "~cache:* %R~config:* %W~log:*");
+
+        let mut user = user_with_rules(&["allkeys"]);
+        assert!(user.can_access_key("other", KeyAccess::READ_WRITE));
+        assert!(user.apply_rule("~cache:*").is_err());
+        assert!(user.apply_rule("%X~cache:*").is_err());
+    }
+
+    #[test]
+    fn channel_patterns_must_match_psubscribe_patterns_exactly() {
+        let user = user_with_rules(&["&news.*"]);
+        assert!(user.can_access_channel("news.tech", false));
+        assert!(!user.can_access_channel("news.tech", true));
+        assert!(user.can_access_channel("news.*", true));
+        assert!(!user.can_access_channel("sport", false));
+    }
+
+    #[test]
+    fn passwords_are_stored_as_hashes() {
+        let mut user = user_with_rules(&["on", ">secret"]);
+        assert!(user.check_password("secret"));
+        assert!(!user.check_password("wrong"));
+        assert_eq!(
+            user.password_hashes(),
+            vec!["2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b"]
+        );
+        assert_eq!(
+            user.describe(),
+            "user alice on #2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b resetchannels -@all"
+        );
+
+        assert!(user.apply_rule("<wrong").is_err());
+        assert!(user.apply_rule("#ABC").is_err());
+        user.apply_rule("<secret").unwrap();
+        assert!(!user.check_password("secret"));
+        user.apply_rule("nopass").unwrap();
+        assert!(user.check_password("anything"));
+    }
+}
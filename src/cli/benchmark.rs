@@ -0,0 +1,475 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::cli::options::{next_value, parse_number};
+use crate::client::connection::{ClientError, ClientResult, RedisClient};
+use crate::config::config::DEFAULT_PORT;
+use crate::parser::parser::RESPOutput;
+
+pub const USAGE: &str = "Usage: redis-benchmark [OPTIONS] [COMMAND ARGS...]
+  -h <hostname>      Server hostname (default 127.0.0.1)
+  -p <port>          Server port (default 6379)
+  -c <clients>       Number of parallel connections (default 50)
+  -n <requests>      Total number of requests (default 100000)
+  -d <size>          Data size of SET/GET value in bytes (default 3)
+  -P <numreq>        Pipeline <numreq> requests (default 1, i.e. no pipeline)
+  -r <keyspacelen>   Use random keys from 0 to keyspacelen-1, which replace __rand_int__ in
+                     keys and arguments of a command
+  -t <tests>         Only run the comma separated list of tests (default all), of:
+                     ping, set, get, xadd, pfadd, geoadd and publish
+  --mix <weights>    Run a single test that mixes the tests with weights, e.g. set:1,get:9
+  -q                 Quiet. Just show requests/sec and the median latency
+  --help             Output this help and exit
+
+Examples:
+  redis-benchmark -c 20 -n 100000 -P 16 -r 100000 -t set,get
+  redis-benchmark --mix set:1,get:9 -r 1000 -q
+  redis-benchmark -r 1000 -n 10000 XRANGE stream:__rand_int__ - +";
+
+// Tests that run unless -t, --mix or a command are given
+pub const TESTS: [&str; 7] = ["ping", "set", "get", "xadd", "pfadd", "geoadd", "publish"];
+const RAND_INT: &str = "__rand_int__";
+
+// Arguments of a command, with how often it is chosen relative to the others of a mix
+pub type WeightedCommand = (Vec<String>, u32);
+
+/// Options of redis-benchmark, e.g. `-c 20 -n 10000 -P 16 -r 1000 -t set,get`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkOptions {
+    pub host: String,
+    pub port: u16,
+    pub clients: usize,
+    pub requests: usize,
+    // Bytes of the values that are written
+    pub data_size: usize,
+    // Requests that a client sends before reading their replies
+    pub pipeline: usize,
+    // Keys are chosen at random from this many if not 0, otherwise the same key is used
+    pub keyspace: u64,
+    pub tests: Vec<String>,
+    // Tests with their weights, which run as a single test if not empty
+    pub mix: Vec<(String, u32)>,
+    pub quiet: bool,
+    // Command that is run instead of the tests
+    pub command: Vec<String>,
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> Self {
+        BenchmarkOptions::new()
+    }
+}
+
+impl BenchmarkOptions {
+    pub fn new() -> BenchmarkOptions {
+        BenchmarkOptions {
+            host: String::from("127.0.0.1"),
+            port: DEFAULT_PORT,
+            clients: 50,
+            requests: 100000,
+            data_size: 3,
+            pipeline: 1,
+            keyspace: 0,
+            tests: TESTS.iter().map(|test| String::from(*test)).collect(),
+            mix: vec![],
+            quiet: false,
+            command: vec![],
+        }
+    }
+
+    /// Options come before the command, e.g. `redis-benchmark -n 1000 XLEN stream:__rand_int__`
+    pub fn parse(args: &[String]) -> Result<BenchmarkOptions, String> {
+        let mut options = BenchmarkOptions::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" => options.host = String::from(next_value(&mut args, arg)?),
+                "-p" => options.port = parse_number(next_value(&mut args, arg)?, arg)?,
+                "-c" => options.clients = parse_positive(next_value(&mut args, arg)?, arg)?,
+                "-n" => options.requests = parse_positive(next_value(&mut args, arg)?, arg)?,
+                "-d" => options.data_size = parse_number(next_value(&mut args, arg)?, arg)?,
+                "-P" => options.pipeline = parse_positive(next_value(&mut args, arg)?, arg)?,
+                "-r" => options.keyspace = parse_number(next_value(&mut args, arg)?, arg)?,
+                "-t" => {
+                    options.tests = next_value(&mut args, arg)?
+                        .split(',')
+                        .map(|test| test.trim().to_lowercase())
+                        .collect();
+                    for test in options.tests.iter() {
+                        if !TESTS.contains(&test.as_str()) {
+                            return Err(format!("Unknown test '{}'", test));
+                        }
+                    }
+                }
+                "--mix" => options.mix = parse_mix(next_value(&mut args, arg)?)?,
+                "-q" => options.quiet = true,
+                _ if arg.starts_with('-') => return Err(format!("Unrecognized option '{}'", arg)),
+                _ => {
+                    options.command = std::iter::once(arg).chain(args).cloned().collect();
+                    break;
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// Arguments of a test, where __rand_int__ is replaced by a random key
+    pub fn test_command(&self, test: &str) -> Vec<String> {
+        let value = "x".repeat(self.data_size);
+        let args: Vec<&str> = match test {
+            "ping" => vec!["PING"],
+            "set" => vec!["SET", "key:__rand_int__", &value],
+            "get" => vec!["GET", "key:__rand_int__"],
+            "xadd" => vec!["XADD", "stream:__rand_int__", "*", "field", &value],
+            "pfadd" => vec!["PFADD", "hll", "element:__rand_int__"],
+            "geoadd" => vec!["GEOADD", "places", "13.361389", "38.115556", "member:__rand_int__"],
+            "publish" => vec!["PUBLISH", "channel", &value],
+            _ => vec![],
+        };
+        args.into_iter().map(String::from).collect()
+    }
+
+    /// Names of the benchmarks with their commands and weights, in the order that they run
+    pub fn benchmarks(&self) -> Vec<(String, Vec<WeightedCommand>)> {
+        if !self.command.is_empty() {
+            return vec![(self.command.join(" "), vec![(self.command.clone(), 1)])];
+        }
+        if !self.mix.is_empty() {
+            let name = self
+                .mix
+                .iter()
+                .map(|(test, weight)| format!("{}:{}", test.to_uppercase(), weight))
+                .collect::<Vec<String>>()
+                .join(",");
+            let commands = self
+                .mix
+                .iter()
+                .map(|(test, weight)| (self.test_command(test), *weight))
+                .collect();
+            return vec![(format!("MIX {}", name), commands)];
+        }
+        self.tests
+            .iter()
+            .map(|test| (test.to_uppercase(), vec![(self.test_command(test), 1)]))
+            .collect()
+    }
+}
+
+/// Requests of a benchmark, with their latency
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkReport {
+    pub name: String,
+    pub requests: usize,
+    // Error replies, e.g. WRONGTYPE for a command of the wrong type of key
+    pub errors: usize,
+    pub duration: Duration,
+    // Latency of every request in microseconds, in ascending order
+    pub latencies: Vec<u64>,
+}
+
+impl BenchmarkReport {
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests as f64 / self.duration.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Latency in milliseconds that this percentage of the requests was within, e.g. 99.9
+    pub fn percentile(&self, percentage: f64) -> f64 {
+        if self.latencies.is_empty() {
+            return 0.0;
+        }
+        // In thousandths of a percent, so that e.g. 99.9% of 1000 requests is exactly 999 of them
+        let thousandths = (percentage * 1000.0).round() as usize;
+        let rank = (thousandths * self.latencies.len() + 100 * 1000 - 1) / (100 * 1000);
+        let index = rank.clamp(1, self.latencies.len()) - 1;
+        self.latencies[index] as f64 / 1000.0
+    }
+
+    pub fn average(&self) -> f64 {
+        if self.latencies.is_empty() {
+            return 0.0;
+        }
+        self.latencies.iter().sum::<u64>() as f64 / self.latencies.len() as f64 / 1000.0
+    }
+
+    /// A line with the throughput and median latency for -q, or a summary of the run
+    pub fn format(&self, options: &BenchmarkOptions) -> String {
+        let throughput = format!("{:.2} requests per second", self.requests_per_second());
+        if options.quiet {
+            return format!("{}: {}, p50={:.3} msec", self.name, throughput, self.percentile(50.0));
+        }
+
+        let mut lines = vec![
+            format!("====== {} ======", self.name),
+            format!(
+                "  {} requests completed in {:.2} seconds",
+                self.requests,
+                self.duration.as_secs_f64()
+            ),
+            format!("  {} parallel clients", options.clients),
+            format!("  {} bytes payload", options.data_size),
+            format!("  pipeline {}", options.pipeline),
+        ];
+        if self.errors > 0 {
+            lines.push(format!("  {} error replies", self.errors));
+        }
+        lines.push(String::from("Latency summary (msec):"));
+        lines.push(format!(
+            "  min={:.3} avg={:.3} p50={:.3} p95={:.3} p99={:.3} p99.9={:.3} max={:.3}",
+            self.percentile(0.0),
+            self.average(),
+            self.percentile(50.0),
+            self.percentile(95.0),
+            self.percentile(99.0),
+            self.percentile(99.9),
+            self.percentile(100.0)
+        ));
+        lines.push(format!("Throughput: {}", throughput));
+        lines.join("\n")
+    }
+}
+
+/// Sends the requests of the benchmark from parallel clients, which take batches of `pipeline`
+/// requests until all were sent. Each request of a batch has the latency of the whole batch
+pub fn run_benchmark(
+    options: &BenchmarkOptions,
+    name: &str,
+    commands: &[WeightedCommand],
+) -> ClientResult<BenchmarkReport> {
+    // Clients connect before the clock starts
+    let mut clients = vec![];
+    for _ in 0..options.clients {
+        clients.push(RedisClient::connect(options.address())?);
+    }
+
+    let sent = Arc::new(AtomicUsize::new(0));
+    let start = Instant::now();
+    let workers: Vec<_> = clients
+        .into_iter()
+        .enumerate()
+        .map(|(index, mut client)| {
+            let options = options.clone();
+            let commands = commands.to_vec();
+            let sent = Arc::clone(&sent);
+            thread::spawn(move || -> ClientResult<(usize, Vec<u64>)> {
+                let mut random = Random::new(index as u64);
+                let mut errors = 0;
+                let mut latencies = vec![];
+                loop {
+                    let batch = options.pipeline.min(options.requests.saturating_sub(sent.fetch_add(
+                        options.pipeline,
+                        Ordering::SeqCst,
+                    )));
+                    if batch == 0 {
+                        return Ok((errors, latencies));
+                    }
+
+                    let requests: Vec<Vec<String>> = (0..batch)
+                        .map(|_| random.command(&commands, options.keyspace))
+                        .collect();
+                    let requests: Vec<Vec<&str>> = requests
+                        .iter()
+                        .map(|args| args.iter().map(|arg| arg.as_str()).collect())
+                        .collect();
+                    let requests: Vec<&[&str]> = requests.iter().map(|args| args.as_slice()).collect();
+
+                    let batch_start = Instant::now();
+                    client.write_commands(&requests)?;
+                    for _ in 0..batch {
+                        if let RESPOutput::Error(_) = client.read_reply()? {
+                            errors += 1;
+                        }
+                    }
+                    let latency = batch_start.elapsed().as_micros() as u64;
+                    latencies.extend(std::iter::repeat(latency).take(batch));
+                }
+            })
+        })
+        .collect();
+
+    let mut report = BenchmarkReport {
+        name: String::from(name),
+        requests: options.requests,
+        errors: 0,
+        duration: Duration::default(),
+        latencies: vec![],
+    };
+    for worker in workers {
+        let (errors, latencies) = worker
+            .join()
+            .unwrap_or_else(|_| Err(ClientError::Io(String::from("benchmark client panicked"))))?;
+        report.errors += errors;
+        report.latencies.extend(latencies);
+    }
+    report.duration = start.elapsed();
+    report.latencies.sort_unstable();
+    Ok(report)
+}
+
+// e.g. set:1,get:9
+fn parse_mix(value: &str) -> Result<Vec<(String, u32)>, String> {
+    value
+        .split(',')
+        .map(|part| {
+            let (test, weight) = part.split_once(':').unwrap_or((part, "1"));
+            let test = test.trim().to_lowercase();
+            if !TESTS.contains(&test.as_str()) {
+                return Err(format!("Unknown test '{}'", test));
+            }
+            match weight.parse() {
+                Ok(weight) if weight > 0 => Ok((test, weight)),
+                _ => Err(format!("Invalid weight '{}' of test '{}'", weight, test)),
+            }
+        })
+        .collect()
+}
+
+fn parse_positive(value: &str, option: &str) -> Result<usize, String> {
+    match parse_number(value, option)? {
+        0 => Err(format!("Invalid value for option '{}'", option)),
+        number => Ok(number),
+    }
+}
+
+// xorshift, which is random enough to spread keys and choose commands of a mix
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn new(seed: u64) -> Random {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+        // The state must not be 0
+        Random {
+            state: (nanos ^ seed.wrapping_mul(0x9e37_79b9_7f4a_7c15)) | 1,
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // Picks a command by weight, with random keys in the keyspace
+    fn command(&mut self, commands: &[WeightedCommand], keyspace: u64) -> Vec<String> {
+        let total: u64 = commands.iter().map(|(_, weight)| *weight as u64).sum();
+        let mut pick = self.next() % total.max(1);
+        let mut args = &commands[0].0;
+        for (command, weight) in commands.iter() {
+            if pick < *weight as u64 {
+                args = command;
+                break;
+            }
+            pick -= *weight as u64;
+        }
+
+        let key = match keyspace {
+            0 => 0,
+            keyspace => self.next() % keyspace,
+        };
+        args.iter().map(|arg| replace_rand_int(arg, key)).collect()
+    }
+}
+
+// Keys have 12 digits, like in redis-benchmark
+fn replace_rand_int(arg: &str, key: u64) -> String {
+    arg.replace(RAND_INT, &format!("{:012}", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| String::from(*arg)).collect()
+    }
+
+    #[test]
+    fn parse_benchmark_options() {
+        let options = BenchmarkOptions::parse(&strings(&[
+            "-c", "4", "-n", "1000", "-d", "10", "-P", "16", "-r", "100", "-t", "set,GET", "-q",
+        ]))
+        .unwrap();
+        assert_eq!((options.clients, options.requests, options.data_size), (4, 1000, 10));
+        assert_eq!((options.pipeline, options.keyspace, options.quiet), (16, 100, true));
+        assert_eq!(options.tests, strings(&["set", "get"]));
+        assert_eq!(
+            options.benchmarks(),
+            vec![
+                (String::from("SET"), vec![(strings(&["SET", "key:__rand_int__", "xxxxxxxxxx"]), 1)]),
+                (String::from("GET"), vec![(strings(&["GET", "key:__rand_int__"]), 1)]),
+            ]
+        );
+
+        let options = BenchmarkOptions::parse(&strings(&["--mix", "set:1,get:9"])).unwrap();
+        assert_eq!(options.benchmarks()[0].0, "MIX SET:1,GET:9");
+        let options = BenchmarkOptions::parse(&strings(&["-r", "10", "XLEN", "stream:__rand_int__"])).unwrap();
+        assert_eq!(
+            options.benchmarks(),
+            vec![(String::from("XLEN stream:__rand_int__"), vec![(strings(&["XLEN", "stream:__rand_int__"]), 1)])]
+        );
+
+        let input = [
+            strings(&["-c", "0"]),
+            strings(&["-t", "set,incr"]),
+            strings(&["--mix", "set:0"]),
+            strings(&["-x"]),
+        ];
+        let expected = [
+            "Invalid value for option '-c'",
+            "Unknown test 'incr'",
+            "Invalid weight '0' of test 'set'",
+            "Unrecognized option '-x'",
+        ];
+        for (index, args) in input.iter().enumerate() {
+            assert_eq!(BenchmarkOptions::parse(args), Err(String::from(expected[index])));
+        }
+    }
+
+    #[test]
+    fn random_commands_follow_the_weights_and_keyspace() {
+        let commands = vec![(strings(&["SET", "key:__rand_int__"]), 1), (strings(&["GET", "key:__rand_int__"]), 3)];
+        let mut random = Random::new(1);
+        let mut gets = 0;
+        for _ in 0..4000 {
+            let args = random.command(&commands, 10);
+            assert!(args[1].starts_with("key:0000000000") && args[1].len() == 16);
+            if args[0] == "GET" {
+                gets += 1;
+            }
+        }
+        assert!((2700..3300).contains(&gets));
+        assert_eq!(random.command(&commands[..1], 0), strings(&["SET", "key:000000000000"]));
+    }
+
+    #[test]
+    fn report_percentiles_and_throughput() {
+        let report = BenchmarkReport {
+            name: String::from("PING"),
+            requests: 1000,
+            errors: 0,
+            duration: Duration::from_millis(500),
+            latencies: (1..=1000).collect(),
+        };
+        assert_eq!(report.requests_per_second(), 2000.0);
+        assert_eq!(report.percentile(0.0), 0.001);
+        assert_eq!(report.percentile(50.0), 0.5);
+        assert_eq!(report.percentile(99.9), 0.999);
+        assert_eq!(report.percentile(100.0), 1.0);
+        assert_eq!(report.average(), 0.5005);
+
+        let mut options = BenchmarkOptions::new();
+        options.quiet = true;
+        assert_eq!(report.format(&options), "PING: 2000.00 requests per second, p50=0.500 msec");
+    }
+}
@@ -0,0 +1,120 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::parser::parser::Parser;
+
+pub const MAX_HISTORY_LEN: usize = 1000;
+
+/// Lines entered at the prompt, which are kept in a file between sessions like redis-cli does
+#[derive(Debug)]
+pub struct History {
+    // Lines are only kept in memory if there is no file
+    path: Option<PathBuf>,
+    lines: Vec<String>,
+}
+
+impl History {
+    /// Loads the lines of the file, if it exists
+    pub fn new(path: Option<PathBuf>) -> History {
+        let lines = match &path {
+            Some(path) => fs::read_to_string(path)
+                .map(|content| content.lines().map(String::from).collect())
+                .unwrap_or_default(),
+            None => vec![],
+        };
+        History { path, lines }
+    }
+
+    /// REDISCLI_HISTFILE, or ~/.rediscli_history. Setting REDISCLI_HISTFILE to /dev/null disables it
+    pub fn default_path() -> Option<PathBuf> {
+        match env::var("REDISCLI_HISTFILE") {
+            Ok(path) if path == "/dev/null" => None,
+            Ok(path) if !path.is_empty() => Some(PathBuf::from(path)),
+            _ => env::var("HOME")
+                .ok()
+                .map(|home| PathBuf::from(home).join(".rediscli_history")),
+        }
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Adds the line and saves the history, unless the line repeats the last one or contains a
+    /// password. The oldest lines are dropped after MAX_HISTORY_LEN
+    pub fn add(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() || self.lines.last().map_or(false, |last| last == line) || is_sensitive(line) {
+            return;
+        }
+        self.lines.push(String::from(line));
+        if self.lines.len() > MAX_HISTORY_LEN {
+            self.lines.remove(0);
+        }
+
+        // The prompt works without the file, e.g. in a read-only home directory
+        if let Some(path) = &self.path {
+            let _ = fs::write(path, self.lines.join("\n") + "\n");
+        }
+    }
+}
+
+// AUTH, HELLO with AUTH, ACL SETUSER and CONFIG SET of passwords are not kept
+fn is_sensitive(line: &str) -> bool {
+    let args: Vec<String> = match Parser::split_args(line) {
+        Ok(args) => args.iter().map(|arg| arg.to_lowercase()).collect(),
+        Err(_) => return false,
+    };
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    match args.as_slice() {
+        ["auth", ..] | ["acl", "setuser", ..] => true,
+        ["config", "set", parameter, ..] => parameter.contains("pass") || parameter.contains("auth"),
+        ["hello", rest @ ..] => rest.contains(&"auth"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_skips_repeated_and_sensitive_lines() {
+        let mut history = History::new(None);
+        for line in [
+            "SET key value",
+            "SET key value",
+            " ",
+            "auth secret",
+            "HELLO 3 AUTH alice secret",
+            "ACL SETUSER alice on >secret",
+            "CONFIG SET requirepass secret",
+            "CONFIG SET timeout 0",
+            "GET key",
+        ] {
+            history.add(line);
+        }
+        assert_eq!(history.lines(), &["SET key value", "CONFIG SET timeout 0", "GET key"]);
+
+        for index in 0..MAX_HISTORY_LEN {
+            history.add(&format!("ECHO {}", index));
+        }
+        assert_eq!(history.lines().len(), MAX_HISTORY_LEN);
+        assert_eq!(history.lines()[0], "ECHO 0");
+    }
+
+    #[test]
+    fn history_is_kept_in_a_file() {
+        let path = env::temp_dir().join(format!("rediscli_history_{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut history = History::new(Some(path.clone()));
+        history.add("PING");
+        history.add("GET key");
+        let history = History::new(Some(path.clone()));
+        assert_eq!(history.lines(), &["PING", "GET key"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,6 @@
+pub mod benchmark;
+pub mod history;
+pub mod options;
+pub mod output;
+pub mod pipe;
+pub mod repl;
@@ -0,0 +1,202 @@
+use std::slice::Iter;
+use std::time::Duration;
+
+use crate::client::connection::{ClientResult, RedisClient};
+use crate::config::config::DEFAULT_PORT;
+
+pub const USAGE: &str = "Usage: redis-cli [OPTIONS] [cmd [arg [arg ...]]]
+  -h <hostname>      Server hostname (default: 127.0.0.1)
+  -p <port>          Server port (default: 6379)
+  -a <password>      Password to use when connecting to the server
+  --user <username>  Used to send ACL style 'AUTH username pass'. Needs -a
+  -n <db>            Database number
+  -3                 Start session in RESP3 protocol mode
+  -r <repeat>        Execute specified command N times, forever if negative
+  -i <interval>      When -r is used, waits <interval> seconds per command, e.g. -i 0.1
+  --raw              Use raw formatting for replies (default when STDOUT is not a tty)
+  --no-raw           Force formatted output even when STDOUT is not a tty
+  --pipe             Transfer raw commands from STDIN to the server
+  --help             Output this help and exit
+
+Examples:
+  redis-cli -r 100 -i 1 XADD ticks * n 1
+  redis-cli --pipe < commands.txt
+  redis-cli -n 1 SET key \"hello world\"";
+
+/// Options of redis-cli, e.g. `-p 6380 -r 3 -i 0.5 PING`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliOptions {
+    pub host: String,
+    pub port: u16,
+    // Authenticates as the default user if no user is given
+    pub user: Option<String>,
+    pub password: Option<String>,
+    // Database that is selected once connected
+    pub db: usize,
+    // Switches the connection to RESP3 with HELLO 3
+    pub resp3: bool,
+    // Times that the command is run, forever if negative
+    pub repeat: i64,
+    // Delay between the runs of the command
+    pub interval: Duration,
+    // Replies are printed without types or quotes if set, otherwise depending on whether the
+    // output is a terminal
+    pub raw: Option<bool>,
+    // Sends the commands of the standard input as they are, instead of running a command
+    pub pipe: bool,
+    // Command that is run instead of starting the prompt
+    pub command: Vec<String>,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        CliOptions::new()
+    }
+}
+
+impl CliOptions {
+    pub fn new() -> CliOptions {
+        CliOptions {
+            host: String::from("127.0.0.1"),
+            port: DEFAULT_PORT,
+            user: None,
+            password: None,
+            db: 0,
+            resp3: false,
+            repeat: 1,
+            interval: Duration::from_secs(0),
+            raw: None,
+            pipe: false,
+            command: vec![],
+        }
+    }
+
+    /// Options come before the command, so that the arguments of the command are never taken as
+    /// options, e.g. `redis-cli -r 2 SET key -1`
+    pub fn parse(args: &[String]) -> Result<CliOptions, String> {
+        let mut options = CliOptions::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" => options.host = String::from(next_value(&mut args, arg)?),
+                "-p" => options.port = parse_number(next_value(&mut args, arg)?, arg)?,
+                "-a" => options.password = Some(String::from(next_value(&mut args, arg)?)),
+                "--user" => options.user = Some(String::from(next_value(&mut args, arg)?)),
+                "-n" => options.db = parse_number(next_value(&mut args, arg)?, arg)?,
+                "-3" => options.resp3 = true,
+                "-r" => options.repeat = parse_number(next_value(&mut args, arg)?, arg)?,
+                "-i" => {
+                    let seconds: f64 = parse_number(next_value(&mut args, arg)?, arg)?;
+                    if !seconds.is_finite() || seconds < 0.0 {
+                        return Err(format!("Invalid value for option '{}'", arg));
+                    }
+                    options.interval = Duration::from_secs_f64(seconds);
+                }
+                "--raw" => options.raw = Some(true),
+                "--no-raw" => options.raw = Some(false),
+                "--pipe" => options.pipe = true,
+                _ if arg.starts_with('-') => return Err(format!("Unrecognized option '{}'", arg)),
+                _ => {
+                    options.command = std::iter::once(arg).chain(args).cloned().collect();
+                    break;
+                }
+            }
+        }
+
+        if options.user.is_some() && options.password.is_none() {
+            return Err(String::from("Option '--user' needs a password with '-a'"));
+        }
+        Ok(options)
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// Connects, then authenticates, switches the protocol and selects the database as requested
+    pub fn connect(&self) -> ClientResult<RedisClient> {
+        let mut client = RedisClient::connect(self.address())?;
+        if let Some(password) = &self.password {
+            client.auth(self.user.as_deref(), password)?;
+        }
+        if self.resp3 {
+            client.hello(3)?;
+        }
+        if self.db != 0 {
+            client.select(self.db)?;
+        }
+        Ok(client)
+    }
+}
+
+pub(crate) fn next_value<'a>(args: &mut Iter<'a, String>, option: &str) -> Result<&'a str, String> {
+    args.next()
+        .map(|value| value.as_str())
+        .ok_or_else(|| format!("Missing value for option '{}'", option))
+}
+
+pub(crate) fn parse_number<T: std::str::FromStr>(value: &str, option: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for option '{}'", option))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| String::from(*arg)).collect()
+    }
+
+    #[test]
+    fn parse_options_before_the_command() {
+        let options = CliOptions::parse(&strings(&[
+            "-h", "localhost", "-p", "6380", "-a", "secret", "--user", "alice", "-n", "2", "-3", "-r", "-1", "-i",
+            "0.5", "--raw", "SET", "key", "-1",
+        ]))
+        .unwrap();
+        assert_eq!(
+            options,
+            CliOptions {
+                host: String::from("localhost"),
+                port: 6380,
+                user: Some(String::from("alice")),
+                password: Some(String::from("secret")),
+                db: 2,
+                resp3: true,
+                repeat: -1,
+                interval: Duration::from_millis(500),
+                raw: Some(true),
+                pipe: false,
+                command: strings(&["SET", "key", "-1"]),
+            }
+        );
+        assert_eq!(options.address(), "localhost:6380");
+
+        assert_eq!(CliOptions::parse(&[]), Ok(CliOptions::new()));
+        assert!(CliOptions::parse(&strings(&["--pipe"])).unwrap().pipe);
+    }
+
+    #[test]
+    fn parse_invalid_options() {
+        let input = [
+            strings(&["-p"]),
+            strings(&["-p", "port"]),
+            strings(&["-i", "-1"]),
+            strings(&["--verbose", "PING"]),
+            strings(&["--user", "alice"]),
+        ];
+        let expected = [
+            "Missing value for option '-p'",
+            "Invalid value for option '-p'",
+            "Invalid value for option '-i'",
+            "Unrecognized option '--verbose'",
+            "Option '--user' needs a password with '-a'",
+        ];
+
+        for (index, args) in input.iter().enumerate() {
+            assert_eq!(CliOptions::parse(args), Err(String::from(expected[index])));
+        }
+    }
+}
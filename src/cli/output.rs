@@ -0,0 +1,227 @@
+use crate::parser::parser::RESPOutput;
+
+/// Formats a reply like redis-cli does in a terminal, e.g.
+/// ```text
+/// 1) "key"
+/// 2) 1) (integer) 1
+///    2) (nil)
+/// ```
+pub fn format_reply(reply: &RESPOutput) -> String {
+    match reply {
+        RESPOutput::SimpleString(string) => string.clone(),
+        RESPOutput::Error(error) => format!("(error) {}", error),
+        RESPOutput::BulkString(string) => quote(string),
+        RESPOutput::Integer(integer) => format!("(integer) {}", integer),
+        RESPOutput::Null | RESPOutput::NullArray => String::from("(nil)"),
+        RESPOutput::Double(double) => format!("(double) {}", format_double(*double)),
+        RESPOutput::Boolean(boolean) => format!("({})", boolean),
+        RESPOutput::BigNumber(number) => format!("(big number) {}", number),
+        RESPOutput::VerbatimString(_, text) => text.clone(),
+        RESPOutput::Array(elements) | RESPOutput::Push(elements) if elements.is_empty() => {
+            String::from("(empty array)")
+        }
+        RESPOutput::Set(elements) if elements.is_empty() => String::from("(empty set)"),
+        RESPOutput::Map(pairs) if pairs.is_empty() => String::from("(empty hash)"),
+        RESPOutput::Array(elements) | RESPOutput::Push(elements) | RESPOutput::Set(elements) => {
+            let width = elements.len().to_string().len();
+            let lines: Vec<String> = elements
+                .iter()
+                .enumerate()
+                .map(|(index, element)| {
+                    let prefix = format!("{:>width$}) ", index + 1, width = width);
+                    indent(&prefix, &format_reply(element))
+                })
+                .collect();
+            lines.join("\n")
+        }
+        RESPOutput::Map(pairs) => {
+            let width = pairs.len().to_string().len();
+            let lines: Vec<String> = pairs
+                .iter()
+                .enumerate()
+                .map(|(index, (key, value))| {
+                    let prefix = format!("{:>width$}# {} => ", index + 1, format_reply(key), width = width);
+                    indent(&prefix, &format_reply(value))
+                })
+                .collect();
+            lines.join("\n")
+        }
+        // Attributes are only shown by redis-cli when asked for
+        RESPOutput::Attribute(_, reply) => format_reply(reply),
+    }
+}
+
+/// Formats a reply without types or quotes, like redis-cli does when the output is not a terminal,
+/// e.g. for scripts. Elements of aggregates are written on separate lines
+pub fn format_raw(reply: &RESPOutput) -> String {
+    match reply {
+        RESPOutput::SimpleString(string) | RESPOutput::BulkString(string) => string.clone(),
+        RESPOutput::VerbatimString(_, string) | RESPOutput::BigNumber(string) => string.clone(),
+        RESPOutput::Error(error) => format!("(error) {}", error),
+        RESPOutput::Integer(integer) => integer.to_string(),
+        RESPOutput::Null | RESPOutput::NullArray => String::new(),
+        RESPOutput::Double(double) => format_double(*double),
+        RESPOutput::Boolean(boolean) => String::from(if *boolean { "1" } else { "0" }),
+        RESPOutput::Array(elements) | RESPOutput::Push(elements) | RESPOutput::Set(elements) => {
+            let lines: Vec<String> = elements.iter().map(format_raw).collect();
+            lines.join("\n")
+        }
+        RESPOutput::Map(pairs) => {
+            let lines: Vec<String> = pairs
+                .iter()
+                .flat_map(|(key, value)| vec![format_raw(key), format_raw(value)])
+                .collect();
+            lines.join("\n")
+        }
+        RESPOutput::Attribute(_, reply) => format_raw(reply),
+    }
+}
+
+// The first line follows the prefix, and the others are aligned with it
+fn indent(prefix: &str, text: &str) -> String {
+    let padding = " ".repeat(prefix.chars().count());
+    let lines: Vec<String> = text
+        .split('\n')
+        .enumerate()
+        .map(|(index, line)| match index {
+            0 => format!("{}{}", prefix, line),
+            _ => format!("{}{}", padding, line),
+        })
+        .collect();
+    lines.join("\n")
+}
+
+// Escapes quotes, backslashes and bytes that are not printable, so that the string fits on a line
+fn quote(string: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\x07' => quoted.push_str("\\a"),
+            '\x08' => quoted.push_str("\\b"),
+            c if c.is_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn format_double(double: f64) -> String {
+    match double {
+        double if double == f64::INFINITY => String::from("inf"),
+        double if double == f64::NEG_INFINITY => String::from("-inf"),
+        double => double.to_string(),
+    }
+}
+
+/// Whether the standard output is a terminal rather than a pipe or a file
+#[cfg(unix)]
+pub fn stdout_is_terminal() -> bool {
+    extern "C" {
+        fn isatty(fd: i32) -> i32;
+    }
+    // isatty accepts any file descriptor, and the C library is linked by std
+    unsafe { isatty(1) == 1 }
+}
+
+// Replies are formatted when it cannot be told
+#[cfg(not(unix))]
+pub fn stdout_is_terminal() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bulk(string: &str) -> RESPOutput {
+        RESPOutput::BulkString(String::from(string))
+    }
+
+    #[test]
+    fn format_reply_of_each_type() {
+        let input = [
+            RESPOutput::SimpleString(String::from("OK")),
+            RESPOutput::Error(String::from("ERR unknown command")),
+            bulk("say \"hi\"\r\n"),
+            RESPOutput::Integer(-3),
+            RESPOutput::Null,
+            RESPOutput::Double(f64::NEG_INFINITY),
+            RESPOutput::Boolean(true),
+            RESPOutput::BigNumber(String::from("12345678901234567890")),
+            RESPOutput::VerbatimString(String::from("txt"), String::from("line 1\nline 2")),
+            RESPOutput::Array(vec![]),
+            RESPOutput::Set(vec![]),
+            RESPOutput::Attribute(vec![], Box::new(RESPOutput::Integer(1))),
+        ];
+        let expected = [
+            "OK",
+            "(error) ERR unknown command",
+            "\"say \\\"hi\\\"\\r\\n\"",
+            "(integer) -3",
+            "(nil)",
+            "(double) -inf",
+            "(true)",
+            "(big number) 12345678901234567890",
+            "line 1\nline 2",
+            "(empty array)",
+            "(empty set)",
+            "(integer) 1",
+        ];
+
+        for (index, reply) in input.iter().enumerate() {
+            assert_eq!(format_reply(reply), expected[index]);
+        }
+    }
+
+    #[test]
+    fn format_reply_of_nested_aggregates() {
+        let mut elements: Vec<RESPOutput> = (1..=9).map(RESPOutput::Integer).collect();
+        elements.push(RESPOutput::Array(vec![bulk("a"), RESPOutput::Array(vec![RESPOutput::Null, bulk("b")])]));
+        assert_eq!(
+            format_reply(&RESPOutput::Array(elements)),
+            [
+                " 1) (integer) 1",
+                " 2) (integer) 2",
+                " 3) (integer) 3",
+                " 4) (integer) 4",
+                " 5) (integer) 5",
+                " 6) (integer) 6",
+                " 7) (integer) 7",
+                " 8) (integer) 8",
+                " 9) (integer) 9",
+                "10) 1) \"a\"",
+                "    2) 1) (nil)",
+                "       2) \"b\"",
+            ]
+            .join("\n")
+        );
+
+        let map = RESPOutput::Map(vec![
+            (bulk("server"), bulk("redis")),
+            (bulk("modules"), RESPOutput::Array(vec![bulk("geo"), bulk("hll")])),
+        ]);
+        assert_eq!(
+            format_reply(&map),
+            "1# \"server\" => \"redis\"\n2# \"modules\" => 1) \"geo\"\n                2) \"hll\""
+        );
+    }
+
+    #[test]
+    fn format_raw_of_each_type() {
+        let reply = RESPOutput::Array(vec![
+            bulk("hello world"),
+            RESPOutput::Integer(1),
+            RESPOutput::Null,
+            RESPOutput::Boolean(false),
+            RESPOutput::Map(vec![(bulk("proto"), RESPOutput::Integer(3))]),
+        ]);
+        assert_eq!(format_raw(&reply), "hello world\n1\n\n0\nproto\n3");
+        assert_eq!(format_raw(&RESPOutput::Error(String::from("ERR syntax error"))), "(error) ERR syntax error");
+    }
+}
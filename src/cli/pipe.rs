@@ -0,0 +1,56 @@
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::client::connection::{encode_command, ClientError, ClientResult, RedisClient};
+use crate::parser::parser::RESPOutput;
+
+/// Replies to the commands of a mass insertion
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipeSummary {
+    pub replies: usize,
+    // Error replies, in the order of the commands that caused them
+    pub errors: Vec<String>,
+}
+
+/// Sends the input as it is, e.g. a file of commands that a script wrote in RESP, while reading the
+/// replies. The number of commands is not known without parsing them, so an ECHO of a marker is
+/// sent after them, and its reply is the last one
+pub fn mass_insert<R: Read + Send + 'static>(client: &mut RedisClient, mut input: R) -> ClientResult<PipeSummary> {
+    let marker = marker();
+    let mut writer = client.writer()?;
+    let echo = encode_command(&["ECHO", &marker]);
+    let sender = thread::spawn(move || -> io::Result<()> {
+        io::copy(&mut input, &mut writer)?;
+        writer.write_all(&echo)
+    });
+
+    let mut summary = PipeSummary {
+        replies: 0,
+        errors: vec![],
+    };
+    let result = loop {
+        match client.read_reply() {
+            Ok(RESPOutput::BulkString(ref reply)) if *reply == marker => break Ok(()),
+            Ok(RESPOutput::Error(error)) => summary.errors.push(error),
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+        summary.replies += 1;
+    };
+
+    // An error of the sender explains why the replies stopped
+    match sender.join() {
+        Ok(Err(e)) => Err(ClientError::from(e)),
+        _ => result.map(|_| summary),
+    }
+}
+
+// Random enough that no command of the input echoes it
+fn marker() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    format!("mass-insert:{}:{:x}", std::process::id(), nanos)
+}
@@ -0,0 +1,164 @@
+use std::io::{self, BufRead, Write};
+use std::thread;
+
+use crate::cli::history::History;
+use crate::cli::options::CliOptions;
+use crate::cli::output::{format_raw, format_reply};
+use crate::client::connection::{ClientResult, RedisClient};
+use crate::parser::parser::{Parser, RESPOutput};
+
+/// Runs the command of the options `repeat` times, `interval` apart, and writes the replies
+pub fn run_command<W: Write>(client: &mut RedisClient, options: &CliOptions, output: &mut W) -> ClientResult<()> {
+    let args: Vec<&str> = options.command.iter().map(|arg| arg.as_str()).collect();
+    let mut run = 0;
+    while options.repeat < 0 || run < options.repeat {
+        if run > 0 {
+            thread::sleep(options.interval);
+        }
+        client.write_commands(&[&args])?;
+        let reply = client.read_reply()?;
+        writeln!(output, "{}", format(&reply, options.raw.unwrap_or(false)))?;
+        run += 1;
+    }
+    Ok(())
+}
+
+/// Interactive prompt, which reconnects once the connection fails
+pub struct Repl {
+    options: CliOptions,
+    client: Option<RedisClient>,
+    history: History,
+}
+
+impl Repl {
+    pub fn new(options: CliOptions, history: History) -> Repl {
+        Repl {
+            options,
+            client: None,
+            history,
+        }
+    }
+
+    /// e.g. `127.0.0.1:6379[1]> ` once database 1 is selected, or `not connected> `
+    pub fn prompt(&self) -> String {
+        match (&self.client, self.options.db) {
+            (None, _) => String::from("not connected> "),
+            (Some(_), 0) => format!("{}> ", self.options.address()),
+            (Some(_), db) => format!("{}[{}]> ", self.options.address(), db),
+        }
+    }
+
+    /// Reads lines until the input ends or `quit` is entered
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> io::Result<()> {
+        self.reconnect(output)?;
+        write!(output, "{}", self.prompt())?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            if !self.execute_line(&line, output)? {
+                break;
+            }
+            write!(output, "{}", self.prompt())?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    // Returns false once the prompt should exit
+    fn execute_line<W: Write>(&mut self, line: &str, output: &mut W) -> io::Result<bool> {
+        let mut args = match Parser::split_args(line) {
+            Ok(args) if args.is_empty() => return Ok(true),
+            Ok(args) => args,
+            Err(_) => {
+                writeln!(output, "Invalid argument(s)")?;
+                return Ok(true);
+            }
+        };
+        self.history.add(line);
+
+        let name = args[0].to_lowercase();
+        if name == "quit" || name == "exit" {
+            return Ok(false);
+        }
+
+        // `3 PING` runs PING 3 times
+        let mut repeat = 1;
+        if args.len() > 1 {
+            if let Ok(count) = args[0].parse::<u64>() {
+                repeat = count;
+                args.remove(0);
+            }
+        }
+
+        for _ in 0..repeat {
+            if self.client.is_none() {
+                self.reconnect(output)?;
+            }
+            let client = match self.client.as_mut() {
+                Some(client) => client,
+                None => return Ok(true),
+            };
+            let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+            match execute(client, &args, self.options.raw.unwrap_or(false), output) {
+                Ok(reply) => {
+                    if let (Some(db), RESPOutput::BulkString(_) | RESPOutput::SimpleString(_)) =
+                        (selected_db(&args), reply)
+                    {
+                        self.options.db = db;
+                    }
+                }
+                Err(e) => {
+                    writeln!(output, "Error: {}", e)?;
+                    self.client = None;
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    // The failure is reported, and the prompt shows that it is not connected
+    fn reconnect<W: Write>(&mut self, output: &mut W) -> io::Result<()> {
+        match self.options.connect() {
+            Ok(client) => self.client = Some(client),
+            Err(e) => {
+                self.client = None;
+                writeln!(output, "Could not connect to Redis at {}: {}", self.options.address(), e)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Writes the reply of the command, or the messages until the connection fails once subscribed
+fn execute<W: Write>(client: &mut RedisClient, args: &[&str], raw: bool, output: &mut W) -> ClientResult<RESPOutput> {
+    client.write_commands(&[args])?;
+    let subscribed = matches!(args[0].to_lowercase().as_str(), "subscribe" | "psubscribe");
+    if subscribed {
+        writeln!(output, "Reading messages... (press Ctrl-C to quit)")?;
+    }
+
+    let reply = client.read_reply()?;
+    writeln!(output, "{}", format(&reply, raw))?;
+    if subscribed && !matches!(reply, RESPOutput::Error(_)) {
+        loop {
+            let message = client.read_reply()?;
+            writeln!(output, "{}", format(&message, raw))?;
+            output.flush()?;
+        }
+    }
+    Ok(reply)
+}
+
+fn selected_db(args: &[&str]) -> Option<usize> {
+    match args {
+        [name, db] if name.eq_ignore_ascii_case("select") => db.parse().ok(),
+        _ => None,
+    }
+}
+
+fn format(reply: &RESPOutput, raw: bool) -> String {
+    match raw {
+        true => format_raw(reply),
+        false => format_reply(reply),
+    }
+}
@@ -0,0 +1,269 @@
+use std::time::Duration;
+
+use crate::client::connection::{ClientError, ClientResult, RedisClient};
+use crate::parser::parser::RESPOutput;
+
+// Id of a stream entry, with its fields and values
+pub type StreamEntry = (String, Vec<(String, String)>);
+
+// Typed helpers for the commands that the server supports. Commands queued between MULTI and EXEC
+// reply with QUEUED rather than their result, so they are sent with `command` instead
+impl RedisClient {
+    pub fn ping(&mut self) -> ClientResult<String> {
+        expect_string(self.command(&["PING"])?)
+    }
+
+    pub fn echo(&mut self, message: &str) -> ClientResult<String> {
+        expect_string(self.command(&["ECHO", message])?)
+    }
+
+    pub fn get(&mut self, key: &str) -> ClientResult<Option<String>> {
+        expect_optional_string(self.command(&["GET", key])?)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> ClientResult<()> {
+        expect_ok(self.command(&["SET", key, value])?)
+    }
+
+    pub fn set_with_expiry(&mut self, key: &str, value: &str, expiry: Duration) -> ClientResult<()> {
+        let milliseconds = expiry.as_millis().to_string();
+        expect_ok(self.command(&["SET", key, value, "PX", &milliseconds])?)
+    }
+
+    // id is usually `*`, for the server to generate it
+    pub fn xadd(&mut self, key: &str, id: &str, fields: &[(&str, &str)]) -> ClientResult<String> {
+        let mut args = vec!["XADD", key, id];
+        for (field, value) in fields.iter() {
+            args.push(field);
+            args.push(value);
+        }
+        expect_string(self.command(&args)?)
+    }
+
+    pub fn xlen(&mut self, key: &str) -> ClientResult<i64> {
+        expect_integer(self.command(&["XLEN", key])?)
+    }
+
+    pub fn xrange(&mut self, key: &str, start: &str, end: &str) -> ClientResult<Vec<StreamEntry>> {
+        expect_array(self.command(&["XRANGE", key, start, end])?)?
+            .into_iter()
+            .map(expect_stream_entry)
+            .collect()
+    }
+
+    pub fn xdel(&mut self, key: &str, ids: &[&str]) -> ClientResult<i64> {
+        let mut args = vec!["XDEL", key];
+        args.extend_from_slice(ids);
+        expect_integer(self.command(&args)?)
+    }
+
+    // Returns whether the estimated cardinality changed
+    pub fn pfadd(&mut self, key: &str, elements: &[&str]) -> ClientResult<bool> {
+        let mut args = vec!["PFADD", key];
+        args.extend_from_slice(elements);
+        Ok(expect_integer(self.command(&args)?)? == 1)
+    }
+
+    pub fn pfcount(&mut self, keys: &[&str]) -> ClientResult<i64> {
+        let mut args = vec!["PFCOUNT"];
+        args.extend_from_slice(keys);
+        expect_integer(self.command(&args)?)
+    }
+
+    pub fn pfmerge(&mut self, destination: &str, sources: &[&str]) -> ClientResult<()> {
+        let mut args = vec!["PFMERGE", destination];
+        args.extend_from_slice(sources);
+        expect_ok(self.command(&args)?)
+    }
+
+    // Members are given with their longitude and latitude. Returns the number of members added
+    pub fn geoadd(&mut self, key: &str, members: &[(f64, f64, &str)]) -> ClientResult<i64> {
+        let mut args = vec![String::from("GEOADD"), String::from(key)];
+        for (longitude, latitude, member) in members.iter() {
+            args.push(longitude.to_string());
+            args.push(latitude.to_string());
+            args.push(String::from(*member));
+        }
+        expect_integer(self.command_strings(&args)?)
+    }
+
+    // Distance in meters, or None if either member does not exist
+    pub fn geodist(&mut self, key: &str, member1: &str, member2: &str) -> ClientResult<Option<f64>> {
+        match self.command(&["GEODIST", key, member1, member2])? {
+            RESPOutput::Null => Ok(None),
+            RESPOutput::Double(distance) => Ok(Some(distance)),
+            RESPOutput::BulkString(distance) => distance
+                .parse()
+                .map(Some)
+                .map_err(|_| ClientError::UnexpectedReply(RESPOutput::BulkString(distance))),
+            reply => Err(ClientError::UnexpectedReply(reply)),
+        }
+    }
+
+    // Returns the number of subscribers that received the message
+    pub fn publish(&mut self, channel: &str, message: &str) -> ClientResult<i64> {
+        expect_integer(self.command(&["PUBLISH", channel, message])?)
+    }
+
+    pub fn select(&mut self, db: usize) -> ClientResult<()> {
+        expect_ok(self.command(&["SELECT", &db.to_string()])?)
+    }
+
+    pub fn swapdb(&mut self, db1: usize, db2: usize) -> ClientResult<()> {
+        expect_ok(self.command(&["SWAPDB", &db1.to_string(), &db2.to_string()])?)
+    }
+
+    // MOVE, which is a keyword in Rust. Returns whether the key was moved
+    pub fn move_key(&mut self, key: &str, db: usize) -> ClientResult<bool> {
+        Ok(expect_integer(self.command(&["MOVE", key, &db.to_string()])?)? == 1)
+    }
+
+    pub fn flushdb(&mut self) -> ClientResult<()> {
+        expect_ok(self.command(&["FLUSHDB"])?)
+    }
+
+    pub fn flushall(&mut self) -> ClientResult<()> {
+        expect_ok(self.command(&["FLUSHALL"])?)
+    }
+
+    // Authenticates as the default user if no username is given
+    pub fn auth(&mut self, username: Option<&str>, password: &str) -> ClientResult<()> {
+        let reply = match username {
+            Some(username) => self.command(&["AUTH", username, password])?,
+            None => self.command(&["AUTH", password])?,
+        };
+        expect_ok(reply)
+    }
+
+    // Switches the protocol of the replies, and returns the properties of the server
+    pub fn hello(&mut self, protocol: i64) -> ClientResult<Vec<(String, RESPOutput)>> {
+        let reply = self.command(&["HELLO", &protocol.to_string()])?;
+        expect_map(reply)?
+            .into_iter()
+            .map(|(name, value)| Ok((expect_string(name)?, value)))
+            .collect()
+    }
+
+    pub fn client_id(&mut self) -> ClientResult<i64> {
+        expect_integer(self.command(&["CLIENT", "ID"])?)
+    }
+
+    pub fn client_setname(&mut self, name: &str) -> ClientResult<()> {
+        expect_ok(self.command(&["CLIENT", "SETNAME", name])?)
+    }
+
+    pub fn client_getname(&mut self) -> ClientResult<Option<String>> {
+        expect_optional_string(self.command(&["CLIENT", "GETNAME"])?)
+    }
+
+    // Parameters matching the pattern, with their values
+    pub fn config_get(&mut self, pattern: &str) -> ClientResult<Vec<(String, String)>> {
+        expect_map(self.command(&["CONFIG", "GET", pattern])?)?
+            .into_iter()
+            .map(|(name, value)| Ok((expect_string(name)?, expect_string(value)?)))
+            .collect()
+    }
+
+    pub fn config_set(&mut self, name: &str, value: &str) -> ClientResult<()> {
+        expect_ok(self.command(&["CONFIG", "SET", name, value])?)
+    }
+
+    pub fn multi(&mut self) -> ClientResult<()> {
+        expect_ok(self.command(&["MULTI"])?)
+    }
+
+    // Replies of the queued commands, or None if a watched key changed
+    pub fn exec(&mut self) -> ClientResult<Option<Vec<RESPOutput>>> {
+        match self.command(&["EXEC"])? {
+            RESPOutput::Null => Ok(None),
+            reply => expect_array(reply).map(Some),
+        }
+    }
+
+    pub fn discard(&mut self) -> ClientResult<()> {
+        expect_ok(self.command(&["DISCARD"])?)
+    }
+
+    pub fn watch(&mut self, keys: &[&str]) -> ClientResult<()> {
+        let mut args = vec!["WATCH"];
+        args.extend_from_slice(keys);
+        expect_ok(self.command(&args)?)
+    }
+
+    pub fn unwatch(&mut self) -> ClientResult<()> {
+        expect_ok(self.command(&["UNWATCH"])?)
+    }
+
+    fn command_strings(&mut self, args: &[String]) -> ClientResult<RESPOutput> {
+        let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+        self.command(&args)
+    }
+}
+
+// SET replies with OK as a bulk string
+fn expect_ok(reply: RESPOutput) -> ClientResult<()> {
+    match reply {
+        RESPOutput::SimpleString(ref status) | RESPOutput::BulkString(ref status) if status == "OK" => Ok(()),
+        reply => Err(ClientError::UnexpectedReply(reply)),
+    }
+}
+
+fn expect_integer(reply: RESPOutput) -> ClientResult<i64> {
+    match reply {
+        RESPOutput::Integer(integer) => Ok(integer),
+        reply => Err(ClientError::UnexpectedReply(reply)),
+    }
+}
+
+fn expect_string(reply: RESPOutput) -> ClientResult<String> {
+    match reply {
+        RESPOutput::BulkString(string) | RESPOutput::SimpleString(string) | RESPOutput::VerbatimString(_, string) => {
+            Ok(string)
+        }
+        reply => Err(ClientError::UnexpectedReply(reply)),
+    }
+}
+
+fn expect_optional_string(reply: RESPOutput) -> ClientResult<Option<String>> {
+    match reply {
+        RESPOutput::Null => Ok(None),
+        reply => expect_string(reply).map(Some),
+    }
+}
+
+fn expect_array(reply: RESPOutput) -> ClientResult<Vec<RESPOutput>> {
+    match reply {
+        RESPOutput::Array(elements) | RESPOutput::Set(elements) => Ok(elements),
+        reply => Err(ClientError::UnexpectedReply(reply)),
+    }
+}
+
+// Maps are flat arrays of keys and values in RESP2
+fn expect_map(reply: RESPOutput) -> ClientResult<Vec<(RESPOutput, RESPOutput)>> {
+    match reply {
+        RESPOutput::Map(pairs) => Ok(pairs),
+        RESPOutput::Array(elements) if elements.len() % 2 == 0 => {
+            let mut pairs = vec![];
+            let mut elements = elements.into_iter();
+            while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+                pairs.push((key, value));
+            }
+            Ok(pairs)
+        }
+        reply => Err(ClientError::UnexpectedReply(reply)),
+    }
+}
+
+fn expect_stream_entry(reply: RESPOutput) -> ClientResult<StreamEntry> {
+    match reply {
+        RESPOutput::Array(mut entry) if entry.len() == 2 => {
+            let fields = expect_map(entry.pop().unwrap_or(RESPOutput::Null))?
+                .into_iter()
+                .map(|(field, value)| Ok((expect_string(field)?, expect_string(value)?)))
+                .collect::<ClientResult<Vec<(String, String)>>>()?;
+            let id = expect_string(entry.pop().unwrap_or(RESPOutput::Null))?;
+            Ok((id, fields))
+        }
+        reply => Err(ClientError::UnexpectedReply(reply)),
+    }
+}
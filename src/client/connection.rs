@@ -0,0 +1,187 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::parser::parser::{ParseError, ParseLimits, Parser, RESPOutput};
+use crate::parser::scanner::ValueScanner;
+
+#[derive(Debug, PartialEq)]
+pub enum ClientError {
+    Io(String),
+    ConnectionClosed,
+    // The reply does not follow the protocol
+    Parse(ParseError),
+    // Error reply of the server, e.g. "ERR unknown command"
+    Server(String),
+    // The reply has a different type than the command returns
+    UnexpectedReply(RESPOutput),
+}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        ClientError::Io(e.to_string())
+    }
+}
+
+// e.g. "Error: Server closed the connection", like redis-cli prints it
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Io(e) => write!(f, "{}", e),
+            ClientError::ConnectionClosed => write!(f, "Server closed the connection"),
+            ClientError::Parse(e) => write!(f, "Protocol error: {}", e),
+            ClientError::Server(e) => write!(f, "{}", e),
+            ClientError::UnexpectedReply(reply) => write!(f, "Unexpected reply: {:?}", reply),
+        }
+    }
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+/// A connection to a redis server, which sends commands and decodes their replies
+#[derive(Debug)]
+pub struct RedisClient {
+    stream: TcpStream,
+    // Bytes read after the last decoded reply, i.e. the start of the next ones
+    buffer: Vec<u8>,
+    // How much of the next reply is in the buffer
+    scanner: ValueScanner,
+    limits: ParseLimits,
+    // Set once the connection fails, so that a pool does not reuse it
+    broken: bool,
+}
+
+impl RedisClient {
+    pub fn connect<A: ToSocketAddrs>(address: A) -> ClientResult<RedisClient> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        Ok(RedisClient {
+            stream,
+            buffer: Vec::new(),
+            scanner: ValueScanner::default(),
+            limits: ParseLimits::replies(),
+            broken: false,
+        })
+    }
+
+    /// Reads fail with ClientError::Io once nothing arrives within the timeout
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> ClientResult<()> {
+        Ok(self.stream.set_read_timeout(timeout)?)
+    }
+
+    /// Replies are not bounded in length unless limits are set
+    pub fn set_parse_limits(&mut self, limits: ParseLimits) {
+        self.limits = limits;
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
+
+    /// Sends a command and returns its reply, where an error reply is returned as ClientError::Server
+    pub fn command(&mut self, args: &[&str]) -> ClientResult<RESPOutput> {
+        self.write_commands(&[args])?;
+        match self.read_reply()? {
+            RESPOutput::Error(error) => Err(ClientError::Server(error)),
+            reply => Ok(reply),
+        }
+    }
+
+    /// Sends the commands in a single write, without waiting for their replies
+    pub fn write_commands(&mut self, commands: &[&[&str]]) -> ClientResult<()> {
+        let mut request = Vec::new();
+        for args in commands.iter() {
+            request.extend_from_slice(&encode_command(args));
+        }
+        let result = self.stream.write_all(&request);
+        self.check(result.map_err(ClientError::from))
+    }
+
+    /// A second handle of the connection, which can write while this one reads the replies, e.g.
+    /// so that neither side waits for the other when sending more commands than fit in the buffers
+    pub fn writer(&self) -> ClientResult<TcpStream> {
+        Ok(self.stream.try_clone()?)
+    }
+
+    /// Reads the next reply, including error replies, and pushes such as pub/sub messages in RESP3
+    pub fn read_reply(&mut self) -> ClientResult<RESPOutput> {
+        let result = self.decode_reply();
+        self.check(result)
+    }
+
+    fn decode_reply(&mut self) -> ClientResult<RESPOutput> {
+        let mut chunk = [0; 4096];
+        loop {
+            // The reply is parsed once all of it has arrived, rather than again after every read
+            if let Some(length) = self.scanner.scan(&self.buffer, &self.limits) {
+                let (reply, remaining) =
+                    Parser::parse_resp_with_limits(&self.buffer[..length], &self.limits).map_err(ClientError::Parse)?;
+                let consumed = length - remaining.len();
+                self.buffer.drain(..consumed);
+                return Ok(reply);
+            }
+
+            let size = self.stream.read(&mut chunk)?;
+            if size == 0 {
+                return Err(ClientError::ConnectionClosed);
+            }
+            self.buffer.extend_from_slice(&chunk[..size]);
+        }
+    }
+
+    // Errors other than replies of the server leave the connection in an unknown state
+    fn check<T>(&mut self, result: ClientResult<T>) -> ClientResult<T> {
+        if let Err(ClientError::Io(_) | ClientError::ConnectionClosed | ClientError::Parse(_)) = result {
+            self.broken = true;
+        }
+        result
+    }
+}
+
+/// Encodes a command as an array of bulk strings, like clients send them
+pub fn encode_command(args: &[&str]) -> Vec<u8> {
+    let mut request = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args.iter() {
+        request.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        request.extend_from_slice(arg.as_bytes());
+        request.extend_from_slice(b"\r\n");
+    }
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::DEFAULT_MAX_MULTIBULK_LEN;
+
+    #[test]
+    fn replies_are_not_bounded_like_requests() {
+        let count = DEFAULT_MAX_MULTIBULK_LEN + 1;
+        let mut reply = format!("*{}\r\n", count).into_bytes();
+        for _ in 0..count {
+            reply.extend_from_slice(b":1\r\n");
+        }
+
+        assert!(Parser::parse_resp(&reply).is_err());
+        let (parsed, _) = Parser::parse_resp_with_limits(&reply, &ParseLimits::replies()).unwrap();
+        assert!(matches!(parsed, RESPOutput::Array(elements) if elements.len() == count));
+    }
+
+    #[test]
+    fn encode_command_as_bulk_strings() {
+        assert_eq!(encode_command(&["PING"]), b"*1\r\n$4\r\nPING\r\n".to_vec());
+        assert_eq!(
+            encode_command(&["SET", "key", "hello world", ""]),
+            b"*4\r\n$3\r\nSET\r\n$3\r\nkey\r\n$11\r\nhello world\r\n$0\r\n\r\n".to_vec()
+        );
+
+        let (parsed, _) = Parser::parse_resp(&encode_command(&["ECHO", "a\r\nb"])).unwrap();
+        assert_eq!(
+            parsed,
+            RESPOutput::Array(vec![
+                RESPOutput::BulkString(String::from("ECHO")),
+                RESPOutput::BulkString(String::from("a\r\nb")),
+            ])
+        );
+    }
+}
@@ -0,0 +1,6 @@
+pub mod commands;
+pub mod connection;
+pub mod pipeline;
+pub mod pool;
+pub mod registry;
+pub mod subscription;
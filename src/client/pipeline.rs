@@ -0,0 +1,45 @@
+use crate::client::connection::{ClientResult, RedisClient};
+use crate::parser::parser::RESPOutput;
+
+/// Commands that are sent together, so that their replies are read after a single round trip
+#[derive(Debug, Default)]
+pub struct Pipeline {
+    commands: Vec<Vec<String>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline { commands: Vec::new() }
+    }
+
+    pub fn command(&mut self, args: &[&str]) -> &mut Pipeline {
+        self.commands.push(args.iter().map(|arg| String::from(*arg)).collect());
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Sends the commands and returns their replies in order. Error replies are returned as
+    /// RESPOutput::Error, so that one failing command does not hide the replies of the others
+    pub fn execute(&self, client: &mut RedisClient) -> ClientResult<Vec<RESPOutput>> {
+        let commands: Vec<Vec<&str>> = self
+            .commands
+            .iter()
+            .map(|args| args.iter().map(|arg| arg.as_str()).collect())
+            .collect();
+        let commands: Vec<&[&str]> = commands.iter().map(|args| args.as_slice()).collect();
+        client.write_commands(&commands)?;
+
+        let mut replies = Vec::with_capacity(commands.len());
+        for _ in 0..commands.len() {
+            replies.push(client.read_reply()?);
+        }
+        Ok(replies)
+    }
+}
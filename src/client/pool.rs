@@ -0,0 +1,79 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+use crate::client::connection::{ClientResult, RedisClient};
+
+/// Connections to a server that are reused by threads instead of connecting for every use
+#[derive(Debug)]
+pub struct Pool {
+    address: String,
+    // Connections that are kept for reuse once returned, while others are closed
+    max_idle: usize,
+    idle: Mutex<Vec<RedisClient>>,
+}
+
+impl Pool {
+    pub fn new(address: &str, max_idle: usize) -> Pool {
+        Pool {
+            address: String::from(address),
+            max_idle,
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns an idle connection, or a new one if there is none. It goes back to the pool when
+    /// dropped, as it is, e.g. with the database that it selected
+    pub fn get(&self) -> ClientResult<PooledClient<'_>> {
+        let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        let client = match idle {
+            Some(client) => client,
+            None => RedisClient::connect(self.address.as_str())?,
+        };
+        Ok(PooledClient {
+            pool: self,
+            client: Some(client),
+        })
+    }
+
+    pub fn idle_count(&self) -> usize {
+        self.idle.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    // Connections that failed are not reused
+    fn put(&self, client: RedisClient) {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        if !client.is_broken() && idle.len() < self.max_idle {
+            idle.push(client);
+        }
+    }
+}
+
+/// A connection borrowed from a pool
+#[derive(Debug)]
+pub struct PooledClient<'a> {
+    pool: &'a Pool,
+    // Only taken when it is dropped
+    client: Option<RedisClient>,
+}
+
+impl Deref for PooledClient<'_> {
+    type Target = RedisClient;
+
+    fn deref(&self) -> &RedisClient {
+        self.client.as_ref().expect("pooled client is only taken when dropped")
+    }
+}
+
+impl DerefMut for PooledClient<'_> {
+    fn deref_mut(&mut self) -> &mut RedisClient {
+        self.client.as_mut().expect("pooled client is only taken when dropped")
+    }
+}
+
+impl Drop for PooledClient<'_> {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            self.pool.put(client);
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::util::global::Global;
+
+// Connected clients, so that they can be listed and killed by other connections
+static CLIENTS: Global<Mutex<Option<ClientRegistry>>> = Global::new();
+
+pub type ClientId = u64;
+
+// Closes the connection of a client, waking up its thread if it is blocked reading
+pub type ShutdownHandle = Box<dyn Fn() + Send>;
+
+// What CLIENT LIST shows about a connection, as of its last command
+pub struct ClientInfo {
+    pub id: ClientId,
+    pub addr: String,
+    pub laddr: String,
+    pub is_unix_socket: bool,
+    pub name: Option<String>,
+    pub db: usize,
+    pub user: String,
+    pub subscriptions: usize,
+    pub pattern_subscriptions: usize,
+    // Number of queued commands while in a transaction
+    pub multi: Option<usize>,
+    // Bytes of input received but not yet executed
+    pub query_buffer: usize,
+    pub no_evict: bool,
+    pub last_command: String,
+    pub created_at: Instant,
+    pub last_interaction: Instant,
+    killed: bool,
+    shutdown: Option<ShutdownHandle>,
+}
+
+impl ClientInfo {
+    fn new(id: ClientId) -> ClientInfo {
+        let now = Instant::now();
+        ClientInfo {
+            id,
+            addr: String::new(),
+            laddr: String::new(),
+            is_unix_socket: false,
+            name: None,
+            db: 0,
+            user: String::new(),
+            subscriptions: 0,
+            pattern_subscriptions: 0,
+            multi: None,
+            query_buffer: 0,
+            no_evict: false,
+            last_command: String::from("NULL"),
+            created_at: now,
+            last_interaction: now,
+            killed: false,
+            shutdown: None,
+        }
+    }
+
+    pub fn is_pubsub(&self) -> bool {
+        self.subscriptions + self.pattern_subscriptions > 0
+    }
+
+    /// Flags as shown by CLIENT LIST, i.e. N for a normal client without other flags
+    pub fn flags(&self) -> String {
+        let mut flags = String::new();
+        if self.is_pubsub() {
+            flags.push('P');
+        }
+        if self.multi.is_some() {
+            flags.push('x');
+        }
+        if self.no_evict {
+            flags.push('e');
+        }
+        if self.is_unix_socket {
+            flags.push('U');
+        }
+        if flags.is_empty() {
+            flags.push('N');
+        }
+        flags
+    }
+
+    /// A line of CLIENT LIST and the reply of CLIENT INFO
+    pub fn describe(&self) -> String {
+        format!(
+            "id={} addr={} laddr={} name={} age={} idle={} flags={} db={} sub={} psub={} multi={} qbuf={} obl=0 oll=0 omem=0 user={} cmd={}",
+            self.id,
+            self.addr,
+            self.laddr,
+            self.name.as_deref().unwrap_or_default(),
+            self.created_at.elapsed().as_secs(),
+            self.last_interaction.elapsed().as_secs(),
+            self.flags(),
+            self.db,
+            self.subscriptions,
+            self.pattern_subscriptions,
+            self.multi.map_or(-1, |count| count as i64),
+            self.query_buffer,
+            self.user,
+            self.last_command
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct ClientRegistry {
+    next_id: ClientId,
+    clients: BTreeMap<ClientId, ClientInfo>,
+}
+
+impl ClientRegistry {
+    pub fn new() -> ClientRegistry {
+        ClientRegistry::default()
+    }
+
+    /// Registers a client with a new id, which is never reused
+    pub fn register(&mut self) -> ClientId {
+        self.next_id += 1;
+        self.clients.insert(self.next_id, ClientInfo::new(self.next_id));
+        self.next_id
+    }
+
+    pub fn unregister(&mut self, id: ClientId) {
+        self.clients.remove(&id);
+    }
+
+    /// Forgets every client, i.e. those of a server that ran before, while ids are still not reused
+    pub fn clear(&mut self) {
+        self.clients.clear();
+    }
+
+    pub fn get(&self, id: ClientId) -> Option<&ClientInfo> {
+        self.clients.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: ClientId) -> Option<&mut ClientInfo> {
+        self.clients.get_mut(&id)
+    }
+
+    pub fn clients(&self) -> impl Iterator<Item = &ClientInfo> {
+        self.clients.values()
+    }
+
+    pub fn set_shutdown_handle(&mut self, id: ClientId, shutdown: ShutdownHandle) {
+        if let Some(client) = self.clients.get_mut(&id) {
+            client.shutdown = Some(shutdown);
+        }
+    }
+
+    /// Marks the client to be closed before its next command, and closes its connection if it has one
+    pub fn kill(&mut self, id: ClientId) {
+        if let Some(client) = self.clients.get_mut(&id) {
+            client.killed = true;
+            if let Some(shutdown) = client.shutdown.as_ref() {
+                shutdown();
+            }
+        }
+    }
+
+    pub fn is_killed(&self, id: ClientId) -> bool {
+        self.clients.get(&id).map_or(false, |client| client.killed)
+    }
+}
+
+/// Runs the function with exclusive access to the connected clients
+pub fn with_clients<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut ClientRegistry) -> R,
+{
+    let mut clients = CLIENTS.get_or_init(|| Mutex::new(None)).lock().unwrap_or_else(|e| e.into_inner());
+    f(clients.get_or_insert_with(ClientRegistry::new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn kill_marks_the_client_and_closes_its_connection() {
+        let mut registry = ClientRegistry::new();
+        let first = registry.register();
+        let second = registry.register();
+        assert_eq!((first, second), (1, 2));
+
+        let closed = Arc::new(AtomicBool::new(false));
+        let closed_by_handle = Arc::clone(&closed);
+        registry.set_shutdown_handle(first, Box::new(move || closed_by_handle.store(true, Ordering::SeqCst)));
+
+        registry.kill(first);
+        assert!(registry.is_killed(first));
+        assert!(closed.load(Ordering::SeqCst));
+        assert!(!registry.is_killed(second));
+
+        registry.unregister(first);
+        assert!(registry.get(first).is_none());
+        assert_eq!(registry.register(), 3);
+    }
+
+    #[test]
+    fn flags_describe_the_state_of_the_client() {
+        let mut client = ClientInfo::new(1);
+        assert_eq!(client.flags(), "N");
+
+        client.subscriptions = 1;
+        client.multi = Some(0);
+        client.no_evict = true;
+        assert_eq!(client.flags(), "Pxe");
+        assert!(client.describe().starts_with("id=1 addr= laddr= name= age=0 idle=0 flags=Pxe db=0 sub=1 psub=0 multi=0"));
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::client::connection::{ClientError, ClientResult, RedisClient};
+use crate::parser::parser::RESPOutput;
+
+// A message published to a channel that the connection is subscribed to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub channel: String,
+    // Pattern that matched the channel, for subscriptions with PSUBSCRIBE
+    pub pattern: Option<String>,
+    pub payload: String,
+}
+
+/// A connection in subscribed mode, which can be iterated for the messages published to its
+/// channels and patterns
+#[derive(Debug)]
+pub struct Subscription {
+    client: RedisClient,
+    channels: BTreeSet<String>,
+    patterns: BTreeSet<String>,
+    // Messages that arrived while waiting for the confirmation of a change of subscriptions
+    pending: VecDeque<Message>,
+}
+
+impl RedisClient {
+    /// Subscribes to the channels, after which the connection only receives messages
+    pub fn subscribe(self, channels: &[&str]) -> ClientResult<Subscription> {
+        let mut subscription = Subscription::new(self);
+        subscription.subscribe(channels)?;
+        Ok(subscription)
+    }
+
+    pub fn psubscribe(self, patterns: &[&str]) -> ClientResult<Subscription> {
+        let mut subscription = Subscription::new(self);
+        subscription.psubscribe(patterns)?;
+        Ok(subscription)
+    }
+}
+
+impl Subscription {
+    fn new(client: RedisClient) -> Subscription {
+        Subscription {
+            client,
+            channels: BTreeSet::new(),
+            patterns: BTreeSet::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn subscribe(&mut self, channels: &[&str]) -> ClientResult<()> {
+        self.change("subscribe", channels)?;
+        self.channels.extend(channels.iter().map(|channel| String::from(*channel)));
+        Ok(())
+    }
+
+    pub fn psubscribe(&mut self, patterns: &[&str]) -> ClientResult<()> {
+        self.change("psubscribe", patterns)?;
+        self.patterns.extend(patterns.iter().map(|pattern| String::from(*pattern)));
+        Ok(())
+    }
+
+    /// Unsubscribes from the channels, or from every channel if none are given
+    pub fn unsubscribe(&mut self, channels: &[&str]) -> ClientResult<()> {
+        self.change("unsubscribe", channels)?;
+        if channels.is_empty() {
+            self.channels.clear();
+        }
+        for channel in channels.iter() {
+            self.channels.remove(*channel);
+        }
+        Ok(())
+    }
+
+    /// Unsubscribes from the patterns, or from every pattern if none are given
+    pub fn punsubscribe(&mut self, patterns: &[&str]) -> ClientResult<()> {
+        self.change("punsubscribe", patterns)?;
+        if patterns.is_empty() {
+            self.patterns.clear();
+        }
+        for pattern in patterns.iter() {
+            self.patterns.remove(*pattern);
+        }
+        Ok(())
+    }
+
+    /// Waits for the next message, up to the read timeout of the connection
+    pub fn next_message(&mut self) -> ClientResult<Message> {
+        if let Some(message) = self.pending.pop_front() {
+            return Ok(message);
+        }
+        loop {
+            let reply = self.client.read_reply()?;
+            if let Some(message) = parse_message(&reply) {
+                return Ok(message);
+            }
+        }
+    }
+
+    /// Leaves subscribed mode, so that the connection can send other commands again
+    pub fn into_client(mut self) -> ClientResult<RedisClient> {
+        self.unsubscribe(&[])?;
+        self.punsubscribe(&[])?;
+        Ok(self.client)
+    }
+
+    // Sends the command, then waits for the server to confirm each of the names, or that it
+    // unsubscribed from all of them if there are none
+    fn change(&mut self, kind: &str, names: &[&str]) -> ClientResult<()> {
+        let confirmations = match (names.len(), kind) {
+            (0, "unsubscribe") => self.channels.len().max(1),
+            (0, "punsubscribe") => self.patterns.len().max(1),
+            // The server replies with an error
+            (0, _) => 1,
+            (count, _) => count,
+        };
+
+        let mut args = vec![kind];
+        args.extend_from_slice(names);
+        self.client.write_commands(&[&args])?;
+
+        let mut confirmed = 0;
+        while confirmed < confirmations {
+            let reply = self.client.read_reply()?;
+            if let Some(message) = parse_message(&reply) {
+                self.pending.push_back(message);
+                continue;
+            }
+            match reply {
+                RESPOutput::Error(error) => return Err(ClientError::Server(error)),
+                RESPOutput::Array(ref elements) | RESPOutput::Push(ref elements) => {
+                    if matches!(elements.first(), Some(RESPOutput::BulkString(reply_kind)) if reply_kind == kind) {
+                        confirmed += 1;
+                    }
+                }
+                _ => return Err(ClientError::UnexpectedReply(reply)),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Subscription {
+    type Item = ClientResult<Message>;
+
+    // Ends once the connection is closed
+    fn next(&mut self) -> Option<ClientResult<Message>> {
+        match self.next_message() {
+            Err(ClientError::ConnectionClosed) => None,
+            result => Some(result),
+        }
+    }
+}
+
+// Messages are arrays in RESP2 and pushes in RESP3
+fn parse_message(reply: &RESPOutput) -> Option<Message> {
+    let elements = match reply {
+        RESPOutput::Array(elements) | RESPOutput::Push(elements) => elements,
+        _ => return None,
+    };
+    let strings: Vec<&String> = elements
+        .iter()
+        .filter_map(|element| match element {
+            RESPOutput::BulkString(string) => Some(string),
+            _ => None,
+        })
+        .collect();
+
+    match strings.as_slice() {
+        [kind, channel, payload] if kind.as_str() == "message" && elements.len() == 3 => Some(Message {
+            channel: String::from(channel.as_str()),
+            pattern: None,
+            payload: String::from(payload.as_str()),
+        }),
+        [kind, pattern, channel, payload] if kind.as_str() == "pmessage" && elements.len() == 4 => Some(Message {
+            channel: String::from(channel.as_str()),
+            pattern: Some(String::from(pattern.as_str())),
+            payload: String::from(payload.as_str()),
+        }),
+        _ => None,
+    }
+}
@@ -0,0 +1,289 @@
+use std::sync::Mutex;
+
+use crate::parser::parser::DEFAULT_MAX_BULK_LEN;
+use crate::pubsub::keyspace_events::{flags_to_string, string_to_flags};
+use crate::util::global::Global;
+
+// Configuration of the server, which can be changed at runtime with CONFIG SET
+static CONFIG: Global<Mutex<Option<Config>>> = Global::new();
+
+// Parameters supported by CONFIG GET and CONFIG SET
+pub const PARAMETERS: [&str; 12] = [
+    "aclfile",
+    "bind",
+    "databases",
+    "maxclients",
+    "notify-keyspace-events",
+    "port",
+    "proto-max-bulk-len",
+    "requirepass",
+    "tcp-keepalive",
+    "timeout",
+    "unixsocket",
+    "unixsocketperm",
+];
+// Parameters that can only be set when the server starts
+pub const IMMUTABLE_PARAMETERS: [&str; 6] = ["aclfile", "bind", "databases", "port", "unixsocket", "unixsocketperm"];
+
+pub const DEFAULT_BIND: &str = "127.0.0.1";
+pub const DEFAULT_PORT: u16 = 6379;
+pub const DEFAULT_DATABASES: usize = 16;
+pub const DEFAULT_MAXCLIENTS: usize = 10000;
+pub const DEFAULT_TCP_KEEPALIVE: u64 = 300;
+const MIN_PROTO_MAX_BULK_LEN: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    // File that ACL users are loaded from at startup and by ACL LOAD, disabled if empty
+    pub aclfile: String,
+    // Addresses that TCP connections are accepted on. A `-` prefix skips an address that is unavailable
+    pub bind: Vec<String>,
+    // Number of databases that SELECT can choose from
+    pub databases: usize,
+    // Number of connections after which new ones are refused
+    pub maxclients: usize,
+    // Classes of keyspace events that are published, see keyspace_events
+    pub notify_keyspace_events: u32,
+    // Password of the default user, which connections must AUTH with. Disabled if empty
+    pub requirepass: String,
+    // TCP port of every bind address, disabled if 0
+    pub port: u16,
+    // Bytes that a bulk string of a request may have
+    pub proto_max_bulk_len: usize,
+    // Seconds between keepalive probes of idle TCP connections, disabled if 0
+    pub tcp_keepalive: u64,
+    // Seconds after which connections without traffic are closed, disabled if 0. Subscribed
+    // connections are never closed, since they only wait for messages
+    pub timeout: u64,
+    // Path of a Unix socket that connections are also accepted on, disabled if empty
+    pub unixsocket: String,
+    // Permissions of the Unix socket, left as created if 0
+    pub unixsocketperm: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config::new()
+    }
+}
+
+impl Config {
+    pub fn new() -> Config {
+        Config {
+            aclfile: String::new(),
+            bind: vec![String::from(DEFAULT_BIND)],
+            databases: DEFAULT_DATABASES,
+            maxclients: DEFAULT_MAXCLIENTS,
+            notify_keyspace_events: 0,
+            requirepass: String::new(),
+            port: DEFAULT_PORT,
+            proto_max_bulk_len: DEFAULT_MAX_BULK_LEN,
+            tcp_keepalive: DEFAULT_TCP_KEEPALIVE,
+            timeout: 0,
+            unixsocket: String::new(),
+            unixsocketperm: 0,
+        }
+    }
+
+    /// Returns None if the parameter is not supported
+    pub fn get(&self, name: &str) -> Option<String> {
+        match name.to_lowercase().as_str() {
+            "aclfile" => Some(self.aclfile.clone()),
+            "bind" => Some(self.bind.join(" ")),
+            "databases" => Some(self.databases.to_string()),
+            "maxclients" => Some(self.maxclients.to_string()),
+            "notify-keyspace-events" => Some(flags_to_string(self.notify_keyspace_events)),
+            "port" => Some(self.port.to_string()),
+            "proto-max-bulk-len" => Some(self.proto_max_bulk_len.to_string()),
+            "requirepass" => Some(self.requirepass.clone()),
+            "tcp-keepalive" => Some(self.tcp_keepalive.to_string()),
+            "timeout" => Some(self.timeout.to_string()),
+            "unixsocket" => Some(self.unixsocket.clone()),
+            "unixsocketperm" => Some(format!("{:o}", self.unixsocketperm)),
+            _ => None,
+        }
+    }
+
+    /// Returns the reason if the parameter is not supported, or its value is invalid
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name.to_lowercase().as_str() {
+            "aclfile" => self.aclfile = String::from(value),
+            "bind" => self.bind = value.split_whitespace().map(String::from).collect(),
+            "databases" => {
+                self.databases = value
+                    .parse()
+                    .ok()
+                    .filter(|databases| *databases > 0)
+                    .ok_or_else(|| String::from("argument must be a positive integer"))?;
+            }
+            "maxclients" => {
+                self.maxclients = value
+                    .parse()
+                    .ok()
+                    .filter(|maxclients| *maxclients > 0)
+                    .ok_or_else(|| String::from("argument must be a positive integer"))?;
+            }
+            "notify-keyspace-events" => {
+                self.notify_keyspace_events = string_to_flags(value)
+                    .ok_or_else(|| String::from("Invalid event class character. Use 'Ag$lshzxeKEtmn'."))?;
+            }
+            "port" => {
+                self.port = value
+                    .parse()
+                    .map_err(|_| String::from("argument must be a port number between 0 and 65535"))?;
+            }
+            "proto-max-bulk-len" => {
+                self.proto_max_bulk_len = parse_memory(value)
+                    .filter(|len| *len >= MIN_PROTO_MAX_BULK_LEN)
+                    .ok_or_else(|| String::from("argument must be a memory value of at least 1mb"))?;
+            }
+            "requirepass" => self.requirepass = String::from(value),
+            "tcp-keepalive" => self.tcp_keepalive = parse_seconds(value)?,
+            "timeout" => self.timeout = parse_seconds(value)?,
+            "unixsocket" => self.unixsocket = String::from(value),
+            "unixsocketperm" => {
+                self.unixsocketperm = u32::from_str_radix(value, 8)
+                    .ok()
+                    .filter(|perm| *perm <= 0o777)
+                    .ok_or_else(|| String::from("argument must be an octal number between 0 and 777"))?;
+            }
+            _ => return Err(String::from("Unknown option")),
+        }
+        Ok(())
+    }
+
+    /// Applies parameters given on the command line as `--name value [value ...]`, including
+    /// immutable ones. Values of a parameter are joined with spaces, e.g. `--bind 127.0.0.1 ::1`
+    pub fn apply_args(&mut self, args: &[String]) -> Result<(), String> {
+        let mut args = args.iter().peekable();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("Invalid argument '{}'", arg))?;
+
+            let mut values: Vec<&str> = Vec::new();
+            while let Some(value) = args.next_if(|value| !value.starts_with("--")) {
+                values.push(value);
+            }
+            if values.is_empty() {
+                return Err(format!("Missing value for argument '{}'", arg));
+            }
+
+            self.set(name, &values.join(" "))
+                .map_err(|e| format!("Invalid argument '{}' - {}", arg, e))?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_seconds(value: &str) -> Result<u64, String> {
+    value
+        .parse()
+        .map_err(|_| String::from("argument must be a non-negative number of seconds"))
+}
+
+// Memory values are a number of bytes, optionally with a unit such as 512mb, where k, m and g are
+// powers of 1000 and kb, mb and gb are powers of 1024, like redis
+fn parse_memory(value: &str) -> Option<usize> {
+    let value = value.to_lowercase();
+    let digits_end = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(digits_end);
+    let multiplier: usize = match unit {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+/// Runs the function with exclusive access to the configuration
+pub fn with_config<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut Config) -> R,
+{
+    let mut config = CONFIG.get_or_init(|| Mutex::new(None)).lock().unwrap_or_else(|e| e.into_inner());
+    f(config.get_or_insert_with(Config::new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pubsub::keyspace_events::{NOTIFY_GENERIC, NOTIFY_KEYSPACE};
+
+    #[test]
+    fn set_and_get_notify_keyspace_events() {
+        let mut config = Config::new();
+        assert_eq!(config.get("notify-keyspace-events"), Some(String::new()));
+
+        assert_eq!(config.set("NOTIFY-KEYSPACE-EVENTS", "gK"), Ok(()));
+        assert_eq!(config.notify_keyspace_events, NOTIFY_GENERIC | NOTIFY_KEYSPACE);
+        assert_eq!(config.get("notify-keyspace-events"), Some(String::from("gK")));
+
+        assert!(config.set("notify-keyspace-events", "Kq").is_err());
+        assert_eq!(config.notify_keyspace_events, NOTIFY_GENERIC | NOTIFY_KEYSPACE);
+        assert_eq!(config.set("maxmemory", "1"), Err(String::from("Unknown option")));
+        assert_eq!(config.get("maxmemory"), None);
+    }
+
+    #[test]
+    fn apply_args_sets_parameters() {
+        let mut config = Config::new();
+        let args = vec![String::from("--databases"), String::from("4")];
+        assert_eq!(config.apply_args(&args), Ok(()));
+        assert_eq!(config.databases, 4);
+
+        let args = vec![String::from("--databases"), String::from("0")];
+        assert!(config.apply_args(&args).is_err());
+        let args = vec![String::from("databases"), String::from("4")];
+        assert!(config.apply_args(&args).is_err());
+        let args = vec![String::from("--databases")];
+        assert!(config.apply_args(&args).is_err());
+
+        let args: Vec<String> = ["--bind", "127.0.0.1", "::1", "--unixsocketperm", "700"]
+            .iter()
+            .map(|arg| String::from(*arg))
+            .collect();
+        assert_eq!(config.apply_args(&args), Ok(()));
+        assert_eq!(config.bind, vec![String::from("127.0.0.1"), String::from("::1")]);
+        assert_eq!(config.unixsocketperm, 0o700);
+        assert_eq!(config.get("unixsocketperm"), Some(String::from("700")));
+    }
+
+    #[test]
+    fn set_connection_limits() {
+        let mut config = Config::new();
+        assert_eq!(config.get("tcp-keepalive"), Some(String::from("300")));
+
+        assert_eq!(config.set("maxclients", "2"), Ok(()));
+        assert_eq!(config.set("timeout", "60"), Ok(()));
+        assert_eq!(config.set("tcp-keepalive", "0"), Ok(()));
+        assert_eq!((config.maxclients, config.timeout, config.tcp_keepalive), (2, 60, 0));
+
+        assert!(config.set("maxclients", "0").is_err());
+        assert!(config.set("timeout", "-1").is_err());
+        assert_eq!(config.get("timeout"), Some(String::from("60")));
+    }
+
+    #[test]
+    fn set_proto_max_bulk_len() {
+        let mut config = Config::new();
+        assert_eq!(config.get("proto-max-bulk-len"), Some(String::from("536870912")));
+
+        assert_eq!(config.set("proto-max-bulk-len", "2mb"), Ok(()));
+        assert_eq!(config.proto_max_bulk_len, 2 * 1024 * 1024);
+        assert_eq!(config.set("proto-max-bulk-len", "1000000000"), Ok(()));
+        assert_eq!(config.set("proto-max-bulk-len", "1G"), Ok(()));
+        assert_eq!(config.proto_max_bulk_len, 1000 * 1000 * 1000);
+
+        assert!(config.set("proto-max-bulk-len", "1kb").is_err());
+        assert!(config.set("proto-max-bulk-len", "mb").is_err());
+        assert!(config.set("proto-max-bulk-len", "-1mb").is_err());
+        assert!(config.set("proto-max-bulk-len", "2tb").is_err());
+        assert_eq!(config.proto_max_bulk_len, 1000 * 1000 * 1000);
+    }
+}
@@ -1,11 +1,23 @@
-use std::convert::TryInto;
-use std::io::{Error, ErrorKind, Read, Write};
-use std::net::TcpStream;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::time::{Duration, Instant};
 
 pub mod request_response;
 pub mod store;
 pub mod parser;
+pub mod config;
+pub mod client;
+pub mod cli;
+pub mod acl;
+pub mod pubsub;
+pub mod server;
+pub mod util;
 
+use crate::client::registry::{with_clients, ShutdownHandle};
+use crate::config::config::with_config;
+use crate::server::keepalive;
 use crate::request_response::client_input::HandleClientInput;
 use request_response::client_input::ClientInput;
 use crate::parser::parser::{ParseError, RESPOutput};
@@ -17,34 +29,166 @@ pub enum AppError {
     ConnectionClosed(String),
     ParseError(String),
     IncompleteInput(String),
+    // Nothing was read before the read timeout, i.e. while waiting for messages in subscribed mode
+    ReadTimedOut,
     Error(String),
 }
 
 impl From<ParseError> for AppError {
     fn from(e: ParseError) -> Self {
         match e {
-            ParseError::InvalidInput => AppError::ParseError(String::from("Invalid input")),
-            ParseError::CRLFNotFound => AppError::ParseError(String::from("CRLF is not found")),
-            ParseError::UnrecognisedSymbol => AppError::ParseError(String::from("Unrecognised symbol")),
-            ParseError::IncompleteInput => AppError::IncompleteInput(String::from("Incomplete input"))
+            ParseError::IncompleteInput => AppError::IncompleteInput(String::from("Incomplete input")),
+            // Replies include where the input went wrong, so that misbehaving clients can be debugged
+            e => AppError::ParseError(format!("ERR Protocol error: {}", e)),
         }
     }
 }
 
-// Receives TcpStream so that we can use its methods
+// A transport that connections can be served over, i.e. TCP and Unix sockets, so that handle_connection
+// does not depend on TCP. There is no TLS transport, since TLS needs a library that cannot be added
+// to the dependencies
+pub trait Connection: Read + Write + Send {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+
+    /// Addresses of the client and of the server, as shown by CLIENT LIST
+    fn addresses(&self) -> (String, String);
+
+    /// Closes the connection from another thread, i.e. for CLIENT KILL
+    fn shutdown_handle(&self) -> Option<ShutdownHandle>;
+
+    /// Writes to the connection from another thread, i.e. the messages of its subscriptions
+    fn writer(&self) -> Option<Box<dyn Write + Send>>;
+
+    fn is_unix_socket(&self) -> bool {
+        false
+    }
+
+    /// Probes the peer after `interval` without traffic, if the transport supports it
+    fn set_keepalive(&self, _interval: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Connection for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn addresses(&self) -> (String, String) {
+        let format = |address: io::Result<SocketAddr>| address.map(|address| address.to_string()).unwrap_or_default();
+        (format(self.peer_addr()), format(self.local_addr()))
+    }
+
+    fn shutdown_handle(&self) -> Option<ShutdownHandle> {
+        let stream = self.try_clone().ok()?;
+        Some(Box::new(move || {
+            let _ = stream.shutdown(Shutdown::Both);
+        }))
+    }
+
+    fn writer(&self) -> Option<Box<dyn Write + Send>> {
+        let stream = self.try_clone().ok()?;
+        Some(Box::new(stream))
+    }
+
+    fn set_keepalive(&self, interval: Option<Duration>) -> io::Result<()> {
+        keepalive::set_keepalive(self, interval)
+    }
+}
+
+// Clients of a Unix socket have no address, so both addresses are the socket path, like redis
+#[cfg(unix)]
+impl Connection for UnixStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+
+    fn addresses(&self) -> (String, String) {
+        let path = self
+            .local_addr()
+            .ok()
+            .and_then(|address| address.as_pathname().map(|path| path.display().to_string()))
+            .unwrap_or_default();
+        (format!("{}:0", path), format!("{}:0", path))
+    }
+
+    fn shutdown_handle(&self) -> Option<ShutdownHandle> {
+        let stream = self.try_clone().ok()?;
+        Some(Box::new(move || {
+            let _ = stream.shutdown(Shutdown::Both);
+        }))
+    }
+
+    fn writer(&self) -> Option<Box<dyn Write + Send>> {
+        let stream = self.try_clone().ok()?;
+        Some(Box::new(stream))
+    }
+
+    fn is_unix_socket(&self) -> bool {
+        true
+    }
+}
+
+// Receives a Connection so that we can use its methods
 // TODO: ideally should respond to valid inputs here as well
-pub fn handle_connection(mut stream: TcpStream) {
+pub fn handle_connection<S: Connection>(mut stream: S) {
     let mut client_input = ClientInput::new();
+    client_input.attach_connection(&stream);
+
+    // The new connection is already registered, so it is counted
+    let (maxclients, tcp_keepalive) = with_config(|config| (config.maxclients, config.tcp_keepalive));
+    if with_clients(|clients| clients.clients().count()) > maxclients {
+        client_input.respond_error(&mut stream, "ERR max number of clients reached");
+        return;
+    }
+    let interval = if tcp_keepalive > 0 { Some(Duration::from_secs(tcp_keepalive)) } else { None };
+    if let Err(e) = stream.set_keepalive(interval) {
+        println!("Unable to set keepalive: {}", e);
+    }
+
+    let mut read_timeout = None;
+    let mut last_activity = Instant::now();
     loop {
+        // Messages are written by a thread of their own, unless the connection cannot be written from one
+        client_input.write_pending_messages(&mut stream);
+
+        // Set before every read, so that clients that never send a command are closed as well,
+        // and CONFIG SET timeout applies to connections that are already open
+        let idle_timeout = with_config(|config| config.timeout);
+        let idle_timeout = if idle_timeout > 0 { Some(Duration::from_secs(idle_timeout)) } else { None };
+        if idle_timeout != read_timeout {
+            read_timeout = idle_timeout;
+            if let Err(e) = stream.set_read_timeout(idle_timeout) {
+                println!("Unable to set read timeout: {}", e);
+            }
+        }
+
         let result = handle_connection_helper(&mut stream, &mut client_input);
-        if result.is_err() {
-            let error = result.unwrap_err();
+        if client_input.is_closing() {
+            break;
+        }
+
+        // Idle connections are closed, apart from subscribed ones which wait for messages
+        if result != Err(AppError::ReadTimedOut) {
+            last_activity = Instant::now();
+        } else if !client_input.is_subscribed() && idle_timeout.map_or(false, |timeout| last_activity.elapsed() >= timeout) {
+            println!("Closing idle connection");
+            break;
+        }
+
+        if let Err(error) = result {
             if let AppError::IncompleteInput(_) = error {
                 println!("Incomplete input. Waiting for more input.");
                 continue;
             }
+            if let AppError::ReadTimedOut = error {
+                continue;
+            }
 
-            println!("Error: {:?}", error);
+            match &error {
+                AppError::ParseError(e) => println!("Protocol error from client {}: {}", client_input.id(), e),
+                _ => println!("Error: {:?}", error),
+            }
 
             match error {
                 AppError::ParseError(e)  | AppError::Error(e) => {
@@ -71,13 +215,32 @@ pub fn handle_connection_helper<T: Read + Write + Send>(mut stream: T, client_in
                 return Err(AppError::ConnectionClosed(String::from("Connection closed")));
             }
 
-            let parsed = client_input.parse_input(&buffer[..size])?;
-            // Move these things below into another file
-            let parsed_command = resp_output_to_parsed_command(&parsed);
+            let mut parsed = client_input.parse_input(&buffer[..size])?;
+            loop {
+                // Empty lines, e.g. from telnet, are ignored like redis
+                if parsed != RESPOutput::Array(vec![]) {
+                    // Move these things below into another file
+                    let parsed_command = resp_output_to_parsed_command(&parsed);
+
+                    client_input.record_command(&parsed_command);
+                    client_input.respond_before_messages(&mut stream, parsed_command);
+                    client_input.sync_client_info();
+                }
 
-            client_input.respond(&mut stream, parsed_command);
-            client_input.reset();
-            Ok(())
+                // Pipelined commands may have arrived with the same read
+                if client_input.get_input().is_empty() || client_input.is_closing() {
+                    return Ok(());
+                }
+                parsed = match client_input.parse_input(&[]) {
+                    Ok(parsed) => parsed,
+                    // The rest of the command arrives with the next read
+                    Err(ParseError::IncompleteInput) => return Ok(()),
+                    Err(e) => return Err(AppError::from(e)),
+                };
+            }
+        }
+        Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+            Err(AppError::ReadTimedOut)
         }
         // TODO: test
         Err(e) => Err(AppError::Error(e.to_string())),
@@ -95,15 +258,7 @@ pub fn resp_output_to_parsed_command(resp_output: &RESPOutput) -> ParsedCommand
         println!("command_resp: {:?}, args_resp: {:?}", command_resp, args_resp);
 
         if let RESPOutput::BulkString(command) = command_resp {
-            if command.to_lowercase() == "ping" {
-                parsed_command.set_command(Some(Command::PING))
-            } else if command.to_lowercase() == "echo" {
-                parsed_command.set_command(Some(Command::ECHO))
-            } else if command.to_lowercase() == "get" {
-                parsed_command.set_command(Some(Command::GET))
-            } else if command.to_lowercase() == "set" {
-                parsed_command.set_command(Some(Command::SET))
-            }
+            parsed_command.set_command(Command::from(command));
         }
 
         for arg_resp in args_resp {
@@ -121,7 +276,7 @@ mod tests {
 
     #[test]
     fn resp_output_to_parsed_command_success() {
-        let input = vec![
+        let input = [
             RESPOutput::Array(
                 vec![
                     RESPOutput::BulkString(String::from("ping"))
@@ -148,7 +303,7 @@ mod tests {
             ),
         ];
 
-        let expected = vec![
+        let expected = [
             ParsedCommand {
                 command: Some(Command::PING),
                 args: Vec::new()
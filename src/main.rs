@@ -6,22 +6,33 @@ use std::env;
 use std::fs;
 #[allow(unused_imports)]
 use std::net::{TcpListener, TcpStream};
-use std::thread;
 
-use redis_starter_rust::handle_connection;
-use redis_starter_rust::store::redis::{RedisStore, Store};
+use redis_starter_rust::config::config::with_config;
+use redis_starter_rust::server::server::Server;
 
 fn main() {
     // You can use print statements as follows for debugging, they'll be visible when running tests.
     println!("Logs from your program will appear here!");
 
-    // Uncomment this block to pass the first stage
-    let listener = TcpListener::bind("127.0.0.1:6379").unwrap();
+    // Parameters such as `--databases 4` are applied before the store is initialised
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = with_config(|config| config.apply_args(&args)) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 
-    RedisStore::initialise();
+    // Listens on 127.0.0.1:6379 unless `bind`, `port` or `unixsocket` are given
+    let config = with_config(|config| config.clone());
+    let server = match Server::builder().config(config).start() {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
-    for wrapped_stream in listener.incoming() {
-        let stream = wrapped_stream.unwrap();
-        thread::spawn(move || handle_connection(stream));
+    for address in server.addresses() {
+        println!("Ready to accept connections on {}", address);
     }
+    server.wait();
 }
@@ -1 +1,3 @@
-pub mod parser;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod parser;
+pub mod scanner;
@@ -1,5 +1,3 @@
-use std::io::{ErrorKind};
-
 // https://redis.io/docs/reference/protocol-spec/
 
 /*
@@ -10,6 +8,12 @@ use std::io::{ErrorKind};
   Integer: :1000\r\n
   Array: *2\r\n$3\r\nhey\r\n$5\r\nthere\r\r(2 strings), *3\r\n:1\r\n:2\r\n:3\r\n(3 integers), *0\r\n(empty), *-1\r\n(null)
     - Nested array: *2\r\n*3\r\n:1\r\n:2\r\n:3\r\n*2\r\n+Hello\r\n-World\r\n
+  Inline command: SET key "hello world"\r\n, parsed as an array of bulk strings
+  RESP3:
+    Map: %1\r\n+key\r\n:1\r\n, Set: ~2\r\n:1\r\n:2\r\n, Push: >2\r\n+message\r\n+hello\r\n
+    Double: ,1.5\r\n, ,inf\r\n, Boolean: #t\r\n, Big number: (12345678901234567890\r\n, Null: _\r\n
+    Verbatim string: =9\r\ntxt:hello\r\n
+    Attribute: |1\r\n+ttl\r\n:10\r\n$5\r\nhello\r\n(attributes of the reply that follows them)
 */
 
 /*
@@ -32,7 +36,7 @@ use std::io::{ErrorKind};
     - Custom errors: unrecognised first character, CRLF not found, incomplete input(bulk string, array)
 */
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RESPOutput {
     SimpleString(String),
     Error(String),
@@ -40,14 +44,108 @@ pub enum RESPOutput {
     Integer(i64),
     Array(Vec<RESPOutput>),
     Null,
+    // Null in place of an array, e.g. a missing position of GEOPOS, which RESP2 encodes as *-1 instead
+    // of $-1. Only replies are encoded with it, since *-1 is parsed as Null
+    NullArray,
+    // RESP3 types, which replies encode as RESP2 types unless the connection switched with HELLO 3
+    Map(Vec<(RESPOutput, RESPOutput)>),
+    Set(Vec<RESPOutput>),
+    Double(f64),
+    Boolean(bool),
+    BigNumber(String),
+    // Format of the text, e.g. txt, and the text
+    VerbatimString(String, String),
+    // Data that is not a reply to a command, e.g. pub/sub messages
+    Push(Vec<RESPOutput>),
+    // Attributes of the reply that follows them
+    Attribute(Vec<(RESPOutput, RESPOutput)>, Box<RESPOutput>),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParseError {
-    UnrecognisedSymbol,
-    CRLFNotFound,
+    UnrecognisedSymbol(ParseErrorDetail),
+    CRLFNotFound(ParseErrorDetail),
     IncompleteInput,
-    InvalidInput,
+    InvalidInput(ParseErrorDetail),
+    // Input that a well-behaved client never sends, with the reason that redis gives for it,
+    // e.g. "invalid bulk length" for a negative or oversized length
+    Protocol(String, ParseErrorDetail),
+}
+
+/// Where the input stopped following the protocol, so that a misbehaving client can be debugged
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseErrorDetail {
+    // Number of bytes before the error in the input
+    pub offset: usize,
+    // Type that was being parsed, e.g. "bulk string"
+    pub resp_type: &'static str,
+    pub expected: String,
+    // Input at the error, shortened to FOUND_MAX_LEN bytes
+    pub found: String,
+}
+
+const FOUND_MAX_LEN: usize = 32;
+
+impl ParseErrorDetail {
+    // `at` is the input from the error onwards, and `found` what is there instead of `expected`
+    fn new(resp_type: &'static str, at: &[u8], found: &[u8], expected: impl Into<String>) -> ParseErrorDetail {
+        let found = match found.get(..FOUND_MAX_LEN) {
+            Some(shortened) if found.len() > FOUND_MAX_LEN => format!("{}...", String::from_utf8_lossy(shortened)),
+            _ => String::from(String::from_utf8_lossy(found)),
+        };
+        ParseErrorDetail {
+            offset: at.len(),
+            resp_type,
+            expected: expected.into(),
+            found,
+        }
+    }
+}
+
+impl ParseError {
+    pub fn detail(&self) -> Option<&ParseErrorDetail> {
+        match self {
+            ParseError::UnrecognisedSymbol(detail)
+            | ParseError::CRLFNotFound(detail)
+            | ParseError::InvalidInput(detail)
+            | ParseError::Protocol(_, detail) => Some(detail),
+            ParseError::IncompleteInput => None,
+        }
+    }
+
+    // Parsers only see the input that is left, so they record the number of bytes left at the error,
+    // which is turned into an offset once the error reaches the start of the input
+    fn relative_to(mut self, input_len: usize) -> ParseError {
+        match &mut self {
+            ParseError::UnrecognisedSymbol(detail)
+            | ParseError::CRLFNotFound(detail)
+            | ParseError::InvalidInput(detail)
+            | ParseError::Protocol(_, detail) => detail.offset = input_len - detail.offset,
+            ParseError::IncompleteInput => {}
+        }
+        self
+    }
+}
+
+// e.g. invalid bulk length at offset 5 (bulk string): expected a length between -1 and 512, found "abc"
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            ParseError::UnrecognisedSymbol(_) => "unrecognised symbol",
+            ParseError::CRLFNotFound(_) => "CRLF not found",
+            ParseError::IncompleteInput => "incomplete input",
+            ParseError::InvalidInput(_) => "invalid input",
+            ParseError::Protocol(reason, _) => reason.as_str(),
+        };
+        match self.detail() {
+            Some(detail) => write!(
+                f,
+                "{} at offset {} ({}): expected {}, found {:?}",
+                reason, detail.offset, detail.resp_type, detail.expected, detail.found
+            ),
+            None => write!(f, "{}", reason),
+        }
+    }
 }
 
 pub type ParseResult<'a> = std::result::Result<(RESPOutput, &'a [u8]), ParseError>;
@@ -56,125 +154,431 @@ pub type ParseCRLFResult<'a> = std::result::Result<(&'a [u8], &'a [u8]), ParseEr
 const CR: u8 = b'\r';
 const LF: u8 = b'\n';
 
-// TODO: Improve error handling with error smessage
+pub const DEFAULT_MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+pub const DEFAULT_MAX_MULTIBULK_LEN: usize = 1024 * 1024;
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 32;
+// Inline commands and lines of RESP have to fit in this many bytes
+pub const MAX_INLINE_LEN: usize = 64 * 1024;
+
+/// Bounds on the input, so that a client cannot make the server buffer, allocate or recurse without
+/// limit by sending huge lengths or deeply nested aggregates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParseLimits {
+    // Bytes of a bulk string
+    pub max_bulk_len: usize,
+    // Elements of an array, or entries of another aggregate type
+    pub max_multibulk_len: usize,
+    // Aggregates within aggregates
+    pub max_nesting_depth: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            max_bulk_len: DEFAULT_MAX_BULK_LEN,
+            max_multibulk_len: DEFAULT_MAX_MULTIBULK_LEN,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// Limits for replies of a server, whose strings and aggregates may be of any length. Nesting
+    /// is still bounded, since aggregates are parsed recursively
+    pub fn replies() -> Self {
+        ParseLimits {
+            max_bulk_len: usize::MAX,
+            max_multibulk_len: usize::MAX,
+            ..ParseLimits::default()
+        }
+    }
+}
 
 pub struct Parser {}
 
 impl Parser {
-    pub fn parse_resp(input: &[u8]) -> ParseResult {
-        if input.len() == 0 || input[0] == 0 {
+    /// Parses a request from a client, which is either RESP or an inline command such as `PING\r\n`
+    pub fn parse_request<'a>(input: &'a [u8], limits: &ParseLimits) -> ParseResult<'a> {
+        match input.first() {
+            Some(b'+' | b'-' | b'$' | b':' | b'*') | None => Parser::parse_value(input, limits, 0),
+            Some(_) => Parser::parse_inline(input),
+        }
+        .map_err(|e| e.relative_to(input.len()))
+    }
+
+    pub fn parse_resp(input: &[u8]) -> ParseResult<'_> {
+        Parser::parse_resp_with_limits(input, &ParseLimits::default())
+    }
+
+    /// Same as parse_resp, but with other limits than those of requests, i.e. for replies
+    pub fn parse_resp_with_limits<'a>(input: &'a [u8], limits: &ParseLimits) -> ParseResult<'a> {
+        Parser::parse_value(input, limits, 0).map_err(|e| e.relative_to(input.len()))
+    }
+
+    /// Splits a line that a user typed into the arguments of a command, with the quoting rules of
+    /// inline commands
+    pub fn split_args(line: &str) -> Result<Vec<String>, ParseError> {
+        let input = format!("{}\n", line.trim_end_matches(['\r', '\n']));
+        let (args, _) = Parser::parse_inline(input.as_bytes()).map_err(|e| e.relative_to(input.len()))?;
+        match args {
+            RESPOutput::Array(args) => Ok(args
+                .into_iter()
+                .filter_map(|arg| match arg {
+                    RESPOutput::BulkString(arg) => Some(arg),
+                    _ => None,
+                })
+                .collect()),
+            _ => Ok(vec![]),
+        }
+    }
+
+    // Parses a value within `depth` aggregates
+    fn parse_value<'a>(input: &'a [u8], limits: &ParseLimits, depth: usize) -> ParseResult<'a> {
+        if input.is_empty() || input[0] == 0 {
             return Err(ParseError::IncompleteInput);
         }
+        if matches!(input[0], b'*' | b'%' | b'~' | b'>' | b'|') && depth >= limits.max_nesting_depth {
+            let expected = format!("at most {} nested aggregates", limits.max_nesting_depth);
+            let detail = ParseErrorDetail::new(Parser::type_name(input[0]), input, &input[..1], expected);
+            return Err(ParseError::Protocol(String::from("too many nested aggregates"), detail));
+        }
         let symbol_temp = String::from_utf8_lossy(&input[0..1]);
         let symbol = symbol_temp.as_ref();
         let remaining = &input[1..];
         match symbol {
             "+" => Parser::parse_simple_string(remaining),
             "-" => Parser::parse_error(remaining),
-            "$" => Parser::parse_bulk_string(remaining),
+            "$" => Parser::parse_bulk_string(remaining, limits),
             ":" => Parser::parse_integer(remaining),
-            "*" => Parser::parse_array(remaining),
-            _ => return Err(ParseError::UnrecognisedSymbol),
+            "*" => Parser::parse_array(remaining, limits, depth),
+            "%" => Parser::parse_elements(remaining, "map", 2, limits, depth)
+                .map(|(elements, remaining)| (RESPOutput::Map(Parser::into_pairs(elements)), remaining)),
+            "~" => Parser::parse_elements(remaining, "set", 1, limits, depth)
+                .map(|(elements, remaining)| (RESPOutput::Set(elements), remaining)),
+            ">" => Parser::parse_elements(remaining, "push", 1, limits, depth)
+                .map(|(elements, remaining)| (RESPOutput::Push(elements), remaining)),
+            "|" => Parser::parse_attribute(remaining, limits, depth),
+            "," => Parser::parse_double(remaining),
+            "#" => Parser::parse_boolean(remaining),
+            "(" => Parser::parse_until_crlf(remaining, "big number").map(|(result, remaining)| {
+                (RESPOutput::BigNumber(String::from(String::from_utf8_lossy(result))), remaining)
+            }),
+            "=" => Parser::parse_verbatim_string(remaining, limits),
+            "_" => match Parser::parse_until_crlf(remaining, "null")? {
+                (b"", remaining) => Ok((RESPOutput::Null, remaining)),
+                (result, _) => Err(ParseError::InvalidInput(ParseErrorDetail::new("null", remaining, result, "CRLF"))),
+            },
+            _ => Err(ParseError::UnrecognisedSymbol(ParseErrorDetail::new(
+                "value",
+                input,
+                &input[..1],
+                "a type symbol such as '+', '$' or '*'",
+            ))),
+        }
+    }
+
+    // Name of the type that starts with the symbol, for errors
+    fn type_name(symbol: u8) -> &'static str {
+        match symbol {
+            b'+' => "simple string",
+            b'-' => "error",
+            b'$' => "bulk string",
+            b':' => "integer",
+            b'*' => "array",
+            b'%' => "map",
+            b'~' => "set",
+            b'>' => "push",
+            b'|' => "attribute",
+            b',' => "double",
+            b'#' => "boolean",
+            b'(' => "big number",
+            b'=' => "verbatim string",
+            b'_' => "null",
+            _ => "value",
         }
     }
 
-    fn parse_simple_string(input: &[u8]) -> ParseResult {
-        Parser::parse_until_crlf(input).map(|(result, remaining)| {
+    fn parse_simple_string(input: &[u8]) -> ParseResult<'_> {
+        Parser::parse_until_crlf(input, "simple string").map(|(result, remaining)| {
             let string = String::from(String::from_utf8_lossy(result));
             (RESPOutput::SimpleString(string), remaining)
         })
     }
 
-    fn parse_error(input: &[u8]) -> ParseResult {
-        Parser::parse_until_crlf(input).map(|(result, remaining)| {
+    fn parse_error(input: &[u8]) -> ParseResult<'_> {
+        Parser::parse_until_crlf(input, "error").map(|(result, remaining)| {
             let string = String::from(String::from_utf8_lossy(result));
             (RESPOutput::Error(string), remaining)
         })
     }
 
-    fn parse_bulk_string(input: &[u8]) -> ParseResult {
+    fn parse_bulk_string<'a>(input: &'a [u8], limits: &ParseLimits) -> ParseResult<'a> {
         // First parse is to get the number of bytes in the bulk string
-        let parsed = Parser::parse_until_crlf(input);
-        if parsed.is_err() {
-            return Err(parsed.unwrap_err());
-        }
-
-        let (num_bytes, remaining) = parsed.unwrap();
-        if String::from_utf8_lossy(num_bytes) == "-1" {
-            return Ok((RESPOutput::Null, "".as_bytes()));
-        }
-
-        // Second parse is to get the string itself
-        let parsed = Parser::parse_until_crlf(remaining);
-        if parsed.is_err() {
-            return Err(parsed.unwrap_err());
-        }
-
-        let (result, remaining) = parsed.unwrap();
+        let (num_bytes, remaining) = Parser::parse_until_crlf(input, "bulk string")?;
+        let num_bytes_int = match Parser::parse_length(num_bytes) {
+            Some(-1) => return Ok((RESPOutput::Null, remaining)),
+            Some(length) if length >= 0 && length as u64 <= limits.max_bulk_len as u64 => length as usize,
+            _ => {
+                let expected = format!("a length between -1 and {}", limits.max_bulk_len);
+                let detail = ParseErrorDetail::new("bulk string", input, num_bytes, expected);
+                return Err(ParseError::Protocol(String::from("invalid bulk length"), detail));
+            }
+        };
 
-        let num_bytes_int: usize = String::from_utf8_lossy(num_bytes).parse().unwrap();
-        if result.len().lt(&num_bytes_int) {
+        // The string itself is read by its length rather than up to CRLF, so that it may contain any byte
+        if remaining.len() < num_bytes_int {
             return Err(ParseError::IncompleteInput);
         }
-        if result.len().gt(&num_bytes_int) {
-            return Err(ParseError::InvalidInput);
+        let (result, remaining) = remaining.split_at(num_bytes_int);
+        match remaining {
+            [CR, LF, ..] => {}
+            [] | [CR] => return Err(ParseError::IncompleteInput),
+            _ => {
+                let found = &remaining[..remaining.len().min(2)];
+                let expected = format!("CRLF after {} bytes", num_bytes_int);
+                return Err(ParseError::InvalidInput(ParseErrorDetail::new("bulk string", remaining, found, expected)));
+            }
         }
+        let remaining = &remaining[2..];
 
         let res = String::from(String::from_utf8_lossy(result));
         Ok((RESPOutput::BulkString(res), remaining))
     }
 
-    fn parse_integer(input: &[u8]) -> ParseResult {
-        let parsed = Parser::parse_until_crlf(input);
-        if parsed.is_err() {
-            return Err(parsed.unwrap_err());
-        }
-
-        let (result, remaining) = parsed.unwrap();
+    fn parse_integer(input: &[u8]) -> ParseResult<'_> {
+        let (result, remaining) = Parser::parse_until_crlf(input, "integer")?;
         let string = String::from(String::from_utf8_lossy(result));
         let num: i64 = match string.parse() {
             Ok(res) => res,
-            Err(_) => return Err(ParseError::InvalidInput),
+            Err(_) => return Err(ParseError::InvalidInput(ParseErrorDetail::new("integer", input, result, "an integer"))),
         };
         Ok((RESPOutput::Integer(num), remaining))
     }
 
-    fn parse_array(input: &[u8]) -> ParseResult {
+    fn parse_array<'a>(input: &'a [u8], limits: &ParseLimits, depth: usize) -> ParseResult<'a> {
         // First parse is to get the number of elements in the array
-        let parsed = Parser::parse_until_crlf(input);
-        if parsed.is_err() {
-            return Err(parsed.unwrap_err());
-        }
-
-        let (num_elements, remaining) = parsed.unwrap();
-        if String::from_utf8_lossy(num_elements) == "-1" {
-            return Ok((RESPOutput::Null, "".as_bytes()));
+        let (num_elements, remaining) = Parser::parse_until_crlf(input, "array")?;
+        if Parser::parse_length(num_elements) == Some(-1) {
+            return Ok((RESPOutput::Null, remaining));
         }
-
-        let num_elements_int: u32 = match String::from(String::from_utf8_lossy(num_elements)).parse() {
-            Ok(res) => res,
-            Err(_) => return Err(ParseError::InvalidInput),
-        };
+        let num_elements_int = Parser::parse_multibulk_length(input, num_elements, "array", 1, limits)?;
 
         let mut resp_result: Vec<RESPOutput> = vec![];
         let mut remaining = remaining;
 
         // Recursively parse for each element in the array
         for _ in 0..num_elements_int {
-            let parsed = Parser::parse_resp(remaining);
-            if parsed.is_err() {
-                return Err(parsed.unwrap_err());
-            }
-            let (result, rem) = parsed.unwrap();
+            let (result, rem) = Parser::parse_value(remaining, limits, depth + 1)?;
             resp_result.push(result);
             remaining = rem;
         }
-        return Ok((RESPOutput::Array(resp_result), remaining));
+        Ok((RESPOutput::Array(resp_result), remaining))
+    }
+
+    // Parses the number of entries of an aggregate type, then its elements, e.g. two for each entry of a map
+    fn parse_elements<'a>(
+        input: &'a [u8],
+        resp_type: &'static str,
+        elements_per_entry: usize,
+        limits: &ParseLimits,
+        depth: usize,
+    ) -> Result<(Vec<RESPOutput>, &'a [u8]), ParseError> {
+        let (num_entries, remaining) = Parser::parse_until_crlf(input, resp_type)?;
+        let num_elements = Parser::parse_multibulk_length(input, num_entries, resp_type, elements_per_entry, limits)?;
+
+        let mut elements: Vec<RESPOutput> = vec![];
+        let mut remaining = remaining;
+        for _ in 0..num_elements {
+            let (element, rem) = Parser::parse_value(remaining, limits, depth + 1)?;
+            elements.push(element);
+            remaining = rem;
+        }
+        Ok((elements, remaining))
+    }
+
+    fn into_pairs(elements: Vec<RESPOutput>) -> Vec<(RESPOutput, RESPOutput)> {
+        let mut pairs = vec![];
+        let mut elements = elements.into_iter();
+        while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+            pairs.push((key, value));
+        }
+        pairs
+    }
+
+    fn parse_attribute<'a>(input: &'a [u8], limits: &ParseLimits, depth: usize) -> ParseResult<'a> {
+        let (elements, remaining) = Parser::parse_elements(input, "attribute", 2, limits, depth)?;
+        let (reply, remaining) = Parser::parse_value(remaining, limits, depth)?;
+        Ok((RESPOutput::Attribute(Parser::into_pairs(elements), Box::new(reply)), remaining))
+    }
+
+    // Doubles may also be inf, -inf or nan
+    fn parse_double(input: &[u8]) -> ParseResult<'_> {
+        let (result, remaining) = Parser::parse_until_crlf(input, "double")?;
+        let num: f64 = String::from_utf8_lossy(result).parse().map_err(|_| {
+            ParseError::InvalidInput(ParseErrorDetail::new("double", input, result, "a number, inf, -inf or nan"))
+        })?;
+        Ok((RESPOutput::Double(num), remaining))
+    }
+
+    fn parse_boolean(input: &[u8]) -> ParseResult<'_> {
+        match Parser::parse_until_crlf(input, "boolean")? {
+            (b"t", remaining) => Ok((RESPOutput::Boolean(true), remaining)),
+            (b"f", remaining) => Ok((RESPOutput::Boolean(false), remaining)),
+            (result, _) => Err(ParseError::InvalidInput(ParseErrorDetail::new("boolean", input, result, "'t' or 'f'"))),
+        }
+    }
+
+    // Verbatim strings are bulk strings that start with a format of 3 characters and a colon
+    fn parse_verbatim_string<'a>(input: &'a [u8], limits: &ParseLimits) -> ParseResult<'a> {
+        match Parser::parse_bulk_string(input, limits)? {
+            (RESPOutput::BulkString(string), remaining) if string.get(3..4) == Some(":") => {
+                let (format, text) = string.split_at(3);
+                Ok((RESPOutput::VerbatimString(String::from(format), String::from(&text[1..])), remaining))
+            }
+            // The text starts after the length
+            (output, _) => {
+                let text = input.splitn(2, |byte| *byte == LF).nth(1).unwrap_or_default();
+                let found = match output {
+                    RESPOutput::BulkString(string) => string,
+                    _ => String::from("null"),
+                };
+                let expected = "a format of 3 characters and ':'";
+                Err(ParseError::InvalidInput(ParseErrorDetail::new("verbatim string", text, found.as_bytes(), expected)))
+            }
+        }
+    }
+
+    // Splits a line into arguments like redis-cli does, i.e. separated by whitespace, where double
+    // quoted arguments may contain escapes such as \n and \x41, and single quoted ones may contain \'
+    fn parse_inline(input: &[u8]) -> ParseResult<'_> {
+        let too_big = || {
+            let expected = format!("at most {} bytes", MAX_INLINE_LEN);
+            let detail = ParseErrorDetail::new("inline command", input, input, expected);
+            ParseError::Protocol(String::from("too big inline request"), detail)
+        };
+        let line_end = match input.iter().position(|byte| *byte == LF) {
+            Some(line_end) if line_end > MAX_INLINE_LEN => return Err(too_big()),
+            Some(line_end) => line_end,
+            None if input.len() > MAX_INLINE_LEN => return Err(too_big()),
+            None => return Err(ParseError::IncompleteInput),
+        };
+        let (line, remaining) = (&input[..line_end], &input[line_end + 1..]);
+        let line = line.strip_suffix(&[CR]).unwrap_or(line);
+
+        // `at` is the index in the line, which starts the input
+        let unbalanced_quotes = |at: usize, expected: &str| {
+            let detail = ParseErrorDetail::new("inline command", &input[at..], &line[at..], expected);
+            ParseError::Protocol(String::from("unbalanced quotes in request"), detail)
+        };
+        let mut args = vec![];
+        let mut i = 0;
+        loop {
+            while i < line.len() && line[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i == line.len() {
+                break;
+            }
+
+            let quote = match line[i] {
+                b'"' | b'\'' => Some(line[i]),
+                _ => None,
+            };
+            let arg_start = i;
+            if quote.is_some() {
+                i += 1;
+            }
+
+            let mut arg: Vec<u8> = vec![];
+            loop {
+                let byte = match (line.get(i), quote) {
+                    (Some(byte), _) => *byte,
+                    (None, None) => break,
+                    // Unbalanced quotes
+                    (None, Some(_)) => return Err(unbalanced_quotes(arg_start, "a closing quote")),
+                };
+                match quote {
+                    None if byte.is_ascii_whitespace() => break,
+                    None => arg.push(byte),
+                    Some(quote) if byte == quote => {
+                        // A closing quote must end the argument
+                        if line.get(i + 1).map_or(false, |next| !next.is_ascii_whitespace()) {
+                            return Err(unbalanced_quotes(i + 1, "a space after the closing quote"));
+                        }
+                        i += 1;
+                        break;
+                    }
+                    Some(b'"') if byte == b'\\' && i + 1 < line.len() => {
+                        let (escaped, length) = Parser::parse_inline_escape(&line[i + 1..]);
+                        arg.push(escaped);
+                        i += length;
+                    }
+                    Some(_) if byte == b'\\' && line.get(i + 1) == Some(&b'\'') => {
+                        arg.push(b'\'');
+                        i += 1;
+                    }
+                    Some(_) => arg.push(byte),
+                }
+                i += 1;
+            }
+            args.push(RESPOutput::BulkString(String::from(String::from_utf8_lossy(&arg))));
+        }
+        Ok((RESPOutput::Array(args), remaining))
+    }
+
+    // Returns the escaped byte and the number of bytes after the backslash that it took
+    fn parse_inline_escape(input: &[u8]) -> (u8, usize) {
+        let hex = input.get(1..3).and_then(|digits| std::str::from_utf8(digits).ok());
+        match input[0] {
+            b'x' if hex.map_or(false, |hex| u8::from_str_radix(hex, 16).is_ok()) => {
+                (u8::from_str_radix(hex.unwrap(), 16).unwrap(), 3)
+            }
+            b'n' => (b'\n', 1),
+            b'r' => (b'\r', 1),
+            b't' => (b'\t', 1),
+            b'b' => (0x08, 1),
+            b'a' => (0x07, 1),
+            byte => (byte, 1),
+        }
+    }
+
+    // Lengths are decimal integers without a sign other than `-`, or leading zeros, like redis
+    fn parse_length(input: &[u8]) -> Option<i64> {
+        let digits = input.strip_prefix(b"-").unwrap_or(input);
+        let is_valid = !digits.is_empty()
+            && digits.iter().all(|byte| byte.is_ascii_digit())
+            && (digits[0] != b'0' || digits.len() == 1);
+        if !is_valid {
+            return None;
+        }
+        std::str::from_utf8(input).ok()?.parse().ok()
     }
 
-    fn parse_until_crlf(input: &[u8]) -> ParseCRLFResult {
-        if input.len() == 0 {
-            return Ok((&[0], &[0]));
+    // Returns the number of elements of an aggregate with the given number of entries, where `at` is
+    // the input from the length onwards
+    fn parse_multibulk_length(
+        at: &[u8],
+        length: &[u8],
+        resp_type: &'static str,
+        elements_per_entry: usize,
+        limits: &ParseLimits,
+    ) -> Result<usize, ParseError> {
+        match Parser::parse_length(length) {
+            Some(length) if length >= 0 && length as u64 <= limits.max_multibulk_len as u64 => {
+                Ok(length as usize * elements_per_entry)
+            }
+            _ => {
+                let expected = format!("a length between 0 and {}", limits.max_multibulk_len);
+                let detail = ParseErrorDetail::new(resp_type, at, length, expected);
+                Err(ParseError::Protocol(String::from("invalid multibulk length"), detail))
+            }
+        }
+    }
+
+    fn parse_until_crlf<'a>(input: &'a [u8], resp_type: &'static str) -> ParseCRLFResult<'a> {
+        // e.g. only the type symbol has been read so far
+        if input.is_empty() {
+            return Err(ParseError::IncompleteInput);
         }
         for i in 0..input.len() - 1 {
             if input[i] == 0 {
@@ -184,7 +588,12 @@ impl Parser {
                 return Ok((&input[0..i], &input[i + 2..]));
             }
         }
-        Err(ParseError::CRLFNotFound)
+        // The rest of the line may not have been read yet, unless it is already too long
+        if input.len() <= MAX_INLINE_LEN {
+            return Err(ParseError::IncompleteInput);
+        }
+        let expected = format!("CRLF within {} bytes", MAX_INLINE_LEN);
+        Err(ParseError::CRLFNotFound(ParseErrorDetail::new(resp_type, input, input, expected)))
     }
 }
 
@@ -192,16 +601,25 @@ impl Parser {
 mod tests {
     use super::*;
 
+    fn detail(offset: usize, resp_type: &'static str, expected: &str, found: &str) -> ParseErrorDetail {
+        ParseErrorDetail {
+            offset,
+            resp_type,
+            expected: String::from(expected),
+            found: String::from(found),
+        }
+    }
+
     #[test]
     fn parse_until_crlf_success() {
-        let input = vec!["hello world\r\n".as_bytes(), "5\r\nhello\r\n".as_bytes()];
-        let expected = vec![
+        let input = ["hello world\r\n".as_bytes(), "5\r\nhello\r\n".as_bytes()];
+        let expected = [
             ("hello world".as_bytes(), "".as_bytes()),
             ("5".as_bytes(), "hello\r\n".as_bytes()),
         ];
 
         for (index, inp) in input.iter().enumerate() {
-            let result = Parser::parse_until_crlf(*inp);
+            let result = Parser::parse_until_crlf(inp, "simple string");
 
             match result {
                 Ok(res) => {
@@ -214,12 +632,20 @@ mod tests {
 
     #[test]
     fn parse_until_crlf_error() {
-        let input = "hello world".as_bytes();
-        let expected = ParseError::CRLFNotFound;
-
-        let result = Parser::parse_until_crlf(input);
+        assert_eq!(Parser::parse_until_crlf("hello world".as_bytes(), "simple string"), Err(ParseError::IncompleteInput));
+
+        let input = "a".repeat(MAX_INLINE_LEN + 1);
+        let input = input.as_bytes();
+        let expected = ParseError::CRLFNotFound(detail(
+            0,
+            "simple string",
+            "CRLF within 65536 bytes",
+            "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa...",
+        ));
+
+        let result = Parser::parse_until_crlf(input, "simple string").map_err(|e| e.relative_to(input.len()));
         match result {
-            Ok(res) => panic!(),
+            Ok(_) => panic!(),
             Err(e) => assert_eq!(e, expected),
         }
     }
@@ -253,23 +679,25 @@ mod tests {
 
     #[test]
     fn parse_bulk_string_success() {
-        let input = vec![
+        let input = [
             "11\r\nhello world\r\n".as_bytes(),
             "0\r\n\r\n".as_bytes(),
             "-1\r\n".as_bytes(),
+            "6\r\na\r\nb\0c\r\n".as_bytes(),
         ];
 
-        let expected = vec![
+        let expected = [
             (
                 (RESPOutput::BulkString(String::from("hello world"))),
                 "".as_bytes(),
             ),
-            ((RESPOutput::BulkString(String::from("")), "".as_bytes())),
-            ((RESPOutput::Null, "".as_bytes())),
+            (RESPOutput::BulkString(String::from("")), "".as_bytes()),
+            (RESPOutput::Null, "".as_bytes()),
+            (RESPOutput::BulkString(String::from("a\r\nb\0c")), "".as_bytes()),
         ];
 
         for (index, inp) in input.iter().enumerate() {
-            let result = Parser::parse_bulk_string(inp);
+            let result = Parser::parse_bulk_string(inp, &ParseLimits::default());
             match result {
                 Ok(res) => assert_eq!(res, expected[index]),
                 Err(e) => panic!("{:?}", e),
@@ -279,13 +707,16 @@ mod tests {
 
     #[test]
     fn parse_bulk_string_error() {
-        let input = vec!["11\r\nhello\r\n".as_bytes(), "3\r\nhello\r\n".as_bytes()];
-        let expected = vec![ParseError::IncompleteInput, ParseError::InvalidInput];
+        let input = ["11\r\nhello\r\n".as_bytes(), "3\r\nhello\r\n".as_bytes()];
+        let expected = [
+            ParseError::IncompleteInput,
+            ParseError::InvalidInput(detail(6, "bulk string", "CRLF after 3 bytes", "lo")),
+        ];
 
         for (index, inp) in input.iter().enumerate() {
-            let result = Parser::parse_bulk_string(inp);
+            let result = Parser::parse_bulk_string(inp, &ParseLimits::default()).map_err(|e| e.relative_to(inp.len()));
             match result {
-                Ok(res) => panic!(),
+                Ok(_) => panic!(),
                 Err(e) => assert_eq!(e, expected[index]),
             }
         }
@@ -306,25 +737,25 @@ mod tests {
     #[test]
     fn parse_integer_error() {
         let input = "1a\r\n".as_bytes();
-        let expected = ParseError::InvalidInput;
+        let expected = ParseError::InvalidInput(detail(0, "integer", "an integer", "1a"));
 
-        let result = Parser::parse_integer(input);
+        let result = Parser::parse_integer(input).map_err(|e| e.relative_to(input.len()));
         match result {
-            Ok(res) => panic!(),
+            Ok(_) => panic!(),
             Err(e) => assert_eq!(e, expected),
         }
     }
 
     #[test]
     fn parse_array_success() {
-        let input = vec![
+        let input = [
             "2\r\n$5\r\nhello\r\n$5\r\nworld\r\n".as_bytes(),
             "3\r\n:1000\r\n+hello world\r\n-got error\r\n".as_bytes(),
             "0\r\n".as_bytes(),
             "-1\r\n".as_bytes(),
             "2\r\n*3\r\n:1\r\n:2\r\n:3\r\n*2\r\n$5\r\nhello\r\n$5\r\nworld\r\n".as_bytes(),
         ];
-        let expected = vec![
+        let expected = [
             (
                 RESPOutput::Array(vec![
                     RESPOutput::BulkString(String::from("hello")),
@@ -359,7 +790,7 @@ mod tests {
         ];
 
         for (index, inp) in input.iter().enumerate() {
-            let result = Parser::parse_array(*inp);
+            let result = Parser::parse_array(inp, &ParseLimits::default(), 0);
             match result {
                 Ok(res) => assert_eq!(res, expected[index]),
                 Err(e) => panic!("{:?}", e),
@@ -369,21 +800,21 @@ mod tests {
 
     #[test]
     fn parse_array_error() {
-        let input = vec![
+        let input = [
             "2\r\n$5\r\nhello\r\n".as_bytes(),
             "3\r\n:1000\r\n+hello world\r\n$5\r\nhello world\r\n".as_bytes(),
             "2\r\n*3\r\n:1\r\n:2\r\n:3\r\n*2\r\n$5\r\nhello\r\n$5\r\nworld".as_bytes(),
         ];
-        let expected = vec![
+        let expected = [
+            ParseError::IncompleteInput,
+            ParseError::InvalidInput(detail(33, "bulk string", "CRLF after 5 bytes", " w")),
             ParseError::IncompleteInput,
-            ParseError::InvalidInput,
-            ParseError::CRLFNotFound,
         ];
 
         for (index, inp) in input.iter().enumerate() {
-            let result = Parser::parse_array(*inp);
+            let result = Parser::parse_array(inp, &ParseLimits::default(), 0).map_err(|e| e.relative_to(inp.len()));
             match result {
-                Ok(res) => panic!(),
+                Ok(_) => panic!(),
                 Err(e) => assert_eq!(e, expected[index]),
             }
         }
@@ -391,24 +822,26 @@ mod tests {
 
     #[test]
     fn parse_resp_incomplete_input() {
-        let input = "*2\r\n:1\r\n".as_bytes();
+        let input = ["*2\r\n:1\r\n".as_bytes(), "*".as_bytes(), "*1\r\n$".as_bytes()];
         let expected = ParseError::IncompleteInput;
 
-        let result = Parser::parse_resp(input);
-        match result {
-            Ok(res) => panic!(),
-            Err(e) => assert_eq!(e, expected),
+        for inp in input.iter() {
+            let result = Parser::parse_resp(inp);
+            match result {
+                Ok(_) => panic!(),
+                Err(e) => assert_eq!(e, expected),
+            }
         }
     }
 
     #[test]
     fn parse_resp_unrecognised_input() {
         let input = "5\r\nhello\r\n".as_bytes();
-        let expected = ParseError::UnrecognisedSymbol;
+        let expected = ParseError::UnrecognisedSymbol(detail(0, "value", "a type symbol such as '+', '$' or '*'", "5"));
 
         let result = Parser::parse_resp(input);
         match result {
-            Ok(res) => panic!(),
+            Ok(_) => panic!(),
             Err(e) => assert_eq!(e, expected),
         }
     }
@@ -442,19 +875,19 @@ mod tests {
 
     #[test]
     fn parse_resp_bulk_string_success() {
-        let input = vec![
+        let input = [
             "$11\r\nhello world\r\n".as_bytes(),
             "$0\r\n\r\n".as_bytes(),
             "$-1\r\n".as_bytes(),
         ];
 
-        let expected = vec![
+        let expected = [
             (
                 (RESPOutput::BulkString(String::from("hello world"))),
                 "".as_bytes(),
             ),
-            ((RESPOutput::BulkString(String::from("")), "".as_bytes())),
-            ((RESPOutput::Null, "".as_bytes())),
+            (RESPOutput::BulkString(String::from("")), "".as_bytes()),
+            (RESPOutput::Null, "".as_bytes()),
         ];
 
         for (index, inp) in input.iter().enumerate() {
@@ -468,13 +901,16 @@ mod tests {
 
     #[test]
     fn parse_resp_bulk_string_error() {
-        let input = vec!["$11\r\nhello\r\n".as_bytes(), "$3\r\nhello\r\n".as_bytes()];
-        let expected = vec![ParseError::IncompleteInput, ParseError::InvalidInput];
+        let input = ["$11\r\nhello\r\n".as_bytes(), "$3\r\nhello\r\n".as_bytes()];
+        let expected = [
+            ParseError::IncompleteInput,
+            ParseError::InvalidInput(detail(7, "bulk string", "CRLF after 3 bytes", "lo")),
+        ];
 
         for (index, inp) in input.iter().enumerate() {
             let result = Parser::parse_resp(inp);
             match result {
-                Ok(res) => panic!(),
+                Ok(_) => panic!(),
                 Err(e) => assert_eq!(e, expected[index]),
             }
         }
@@ -495,25 +931,25 @@ mod tests {
     #[test]
     fn parse_resp_integer_error() {
         let input = ":1a\r\n".as_bytes();
-        let expected = ParseError::InvalidInput;
+        let expected = ParseError::InvalidInput(detail(1, "integer", "an integer", "1a"));
 
         let result = Parser::parse_resp(input);
         match result {
-            Ok(res) => panic!(),
+            Ok(_) => panic!(),
             Err(e) => assert_eq!(e, expected),
         }
     }
 
     #[test]
     fn parse_resp_array_success() {
-        let input = vec![
+        let input = [
             "*2\r\n$5\r\nhello\r\n$5\r\nworld\r\n".as_bytes(),
             "*3\r\n:1000\r\n+hello world\r\n-got error\r\n".as_bytes(),
             "*0\r\n".as_bytes(),
             "*-1\r\n".as_bytes(),
             "*2\r\n*3\r\n:1\r\n:2\r\n:3\r\n*2\r\n$5\r\nhello\r\n$5\r\nworld\r\n".as_bytes(),
         ];
-        let expected = vec![
+        let expected = [
             (
                 RESPOutput::Array(vec![
                     RESPOutput::BulkString(String::from("hello")),
@@ -548,7 +984,7 @@ mod tests {
         ];
 
         for (index, inp) in input.iter().enumerate() {
-            let result = Parser::parse_resp(*inp);
+            let result = Parser::parse_resp(inp);
             match result {
                 Ok(res) => assert_eq!(res, expected[index]),
                 Err(e) => panic!("{:?}", e),
@@ -558,23 +994,232 @@ mod tests {
 
     #[test]
     fn parse_resp_array_error() {
-        let input = vec![
+        let input = [
             "*2\r\n$5\r\nhello\r\n".as_bytes(),
             "*3\r\n:1000\r\n+hello world\r\n$5\r\nhello world\r\n".as_bytes(),
             "*2\r\n*3\r\n:1\r\n:2\r\n:3\r\n*2\r\n$5\r\nhello\r\n$5\r\nworld".as_bytes(),
         ];
-        let expected = vec![
+        let expected = [
+            ParseError::IncompleteInput,
+            ParseError::InvalidInput(detail(34, "bulk string", "CRLF after 5 bytes", " w")),
+            ParseError::IncompleteInput,
+        ];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Parser::parse_resp(inp);
+            match result {
+                Ok(_) => panic!(),
+                Err(e) => assert_eq!(e, expected[index]),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_inline_success() {
+        let bulk = |string: &str| RESPOutput::BulkString(String::from(string));
+        let input = [
+            "PING\r\n".as_bytes(),
+            "  set key  value\n".as_bytes(),
+            "SET key \"hello world\\r\\n\\x41\\\"\" ''\r\nGET key\r\n".as_bytes(),
+            "SET key 'it\\'s \"quoted\"'\r\n".as_bytes(),
+            "\r\n".as_bytes(),
+        ];
+        let expected = [
+            (RESPOutput::Array(vec![bulk("PING")]), "".as_bytes()),
+            (RESPOutput::Array(vec![bulk("set"), bulk("key"), bulk("value")]), "".as_bytes()),
+            (
+                RESPOutput::Array(vec![bulk("SET"), bulk("key"), bulk("hello world\r\nA\""), bulk("")]),
+                "GET key\r\n".as_bytes(),
+            ),
+            (RESPOutput::Array(vec![bulk("SET"), bulk("key"), bulk("it's \"quoted\"")]), "".as_bytes()),
+            (RESPOutput::Array(vec![]), "".as_bytes()),
+        ];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Parser::parse_request(inp, &ParseLimits::default());
+            match result {
+                Ok(res) => assert_eq!(res, expected[index]),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_inline_error() {
+        let input = [
+            "PING".as_bytes(),
+            "SET key \"value\r\n".as_bytes(),
+            "SET key \"value\"suffix\r\n".as_bytes(),
+        ];
+        let unbalanced_quotes = |detail| ParseError::Protocol(String::from("unbalanced quotes in request"), detail);
+        let expected = [
+            ParseError::IncompleteInput,
+            unbalanced_quotes(detail(8, "inline command", "a closing quote", "\"value")),
+            unbalanced_quotes(detail(15, "inline command", "a space after the closing quote", "suffix")),
+        ];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Parser::parse_request(inp, &ParseLimits::default());
+            match result {
+                Ok(_) => panic!(),
+                Err(e) => assert_eq!(e, expected[index]),
+            }
+        }
+    }
+
+    #[test]
+    fn split_args_of_typed_lines() {
+        let strings = |args: &[&str]| args.iter().map(|arg| String::from(*arg)).collect::<Vec<String>>();
+        assert_eq!(Parser::split_args("SET key \"hello world\""), Ok(strings(&["SET", "key", "hello world"])));
+        assert_eq!(Parser::split_args("  ECHO 'it\\'s' \"a\\tb\"\n"), Ok(strings(&["ECHO", "it's", "a\tb"])));
+        assert_eq!(Parser::split_args("   "), Ok(vec![]));
+        // The offset is within the line
+        let unbalanced_quotes = ParseError::Protocol(
+            String::from("unbalanced quotes in request"),
+            detail(4, "inline command", "a closing quote", "\"key"),
+        );
+        assert_eq!(Parser::split_args("GET \"key"), Err(unbalanced_quotes));
+    }
+
+    #[test]
+    fn parse_resp3_success() {
+        let bulk = |string: &str| RESPOutput::BulkString(String::from(string));
+        let input = [
+            "%2\r\n+key\r\n:1\r\n$5\r\nother\r\n_\r\n".as_bytes(),
+            "~2\r\n:1\r\n:2\r\n".as_bytes(),
+            ">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n".as_bytes(),
+            ",1.5\r\n".as_bytes(),
+            ",-inf\r\n".as_bytes(),
+            "#f\r\n".as_bytes(),
+            "(12345678901234567890\r\n".as_bytes(),
+            "=9\r\ntxt:hello\r\n".as_bytes(),
+            "|1\r\n+ttl\r\n:10\r\n$5\r\nhello\r\n".as_bytes(),
+        ];
+        let expected = [
+            RESPOutput::Map(vec![
+                (RESPOutput::SimpleString(String::from("key")), RESPOutput::Integer(1)),
+                (bulk("other"), RESPOutput::Null),
+            ]),
+            RESPOutput::Set(vec![RESPOutput::Integer(1), RESPOutput::Integer(2)]),
+            RESPOutput::Push(vec![bulk("message"), bulk("news"), bulk("hello")]),
+            RESPOutput::Double(1.5),
+            RESPOutput::Double(f64::NEG_INFINITY),
+            RESPOutput::Boolean(false),
+            RESPOutput::BigNumber(String::from("12345678901234567890")),
+            RESPOutput::VerbatimString(String::from("txt"), String::from("hello")),
+            RESPOutput::Attribute(
+                vec![(RESPOutput::SimpleString(String::from("ttl")), RESPOutput::Integer(10))],
+                Box::new(bulk("hello")),
+            ),
+        ];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Parser::parse_resp(inp);
+            match result {
+                Ok(res) => assert_eq!(res, (expected[index].clone(), "".as_bytes())),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_resp3_error() {
+        let input = [
+            "#x\r\n".as_bytes(),
+            ",one\r\n".as_bytes(),
+            "=5\r\nhello\r\n".as_bytes(),
+            "_x\r\n".as_bytes(),
+            "%1\r\n+key\r\n".as_bytes(),
+        ];
+        let expected = [
+            ParseError::InvalidInput(detail(1, "boolean", "'t' or 'f'", "x")),
+            ParseError::InvalidInput(detail(1, "double", "a number, inf, -inf or nan", "one")),
+            ParseError::InvalidInput(detail(4, "verbatim string", "a format of 3 characters and ':'", "hello")),
+            ParseError::InvalidInput(detail(1, "null", "CRLF", "x")),
             ParseError::IncompleteInput,
-            ParseError::InvalidInput,
-            ParseError::CRLFNotFound,
         ];
 
         for (index, inp) in input.iter().enumerate() {
-            let result = Parser::parse_resp(*inp);
+            let result = Parser::parse_resp(inp);
+            match result {
+                Ok(_) => panic!(),
+                Err(e) => assert_eq!(e, expected[index]),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_request_limits() {
+        let limits = ParseLimits {
+            max_bulk_len: 5,
+            max_multibulk_len: 3,
+            max_nesting_depth: 2,
+        };
+        let too_big_inline = format!("SET key {}\r\n", "a".repeat(MAX_INLINE_LEN));
+        let too_big_partial_inline = "a".repeat(MAX_INLINE_LEN + 1);
+        let input = [
+            "*1\r\n$abc\r\nhello\r\n".as_bytes(),
+            "*1\r\n$-2\r\n".as_bytes(),
+            "*1\r\n$+5\r\nhello\r\n".as_bytes(),
+            "*1\r\n$05\r\nhello\r\n".as_bytes(),
+            "*1\r\n$6\r\n".as_bytes(),
+            "*1\r\n$99999999999999999999\r\n".as_bytes(),
+            "*x\r\n".as_bytes(),
+            "*-2\r\n".as_bytes(),
+            "*4\r\n".as_bytes(),
+            "*\r\n".as_bytes(),
+            "*1\r\n*1\r\n*1\r\n$4\r\nPING\r\n".as_bytes(),
+            "*1\r\n%1\r\n~0\r\n:1\r\n".as_bytes(),
+            too_big_inline.as_bytes(),
+            too_big_partial_inline.as_bytes(),
+        ];
+        let protocol_error = |message: &str, detail| ParseError::Protocol(String::from(message), detail);
+        let bulk_length = |found| protocol_error("invalid bulk length", detail(5, "bulk string", "a length between -1 and 5", found));
+        let multibulk_length =
+            |found| protocol_error("invalid multibulk length", detail(1, "array", "a length between 0 and 3", found));
+        let inline = |found| protocol_error("too big inline request", detail(0, "inline command", "at most 65536 bytes", found));
+        let expected = [
+            bulk_length("abc"),
+            bulk_length("-2"),
+            bulk_length("+5"),
+            bulk_length("05"),
+            bulk_length("6"),
+            bulk_length("99999999999999999999"),
+            multibulk_length("x"),
+            multibulk_length("-2"),
+            multibulk_length("4"),
+            multibulk_length(""),
+            protocol_error("too many nested aggregates", detail(8, "array", "at most 2 nested aggregates", "*")),
+            protocol_error("too many nested aggregates", detail(8, "set", "at most 2 nested aggregates", "~")),
+            inline("SET key aaaaaaaaaaaaaaaaaaaaaaaa..."),
+            inline("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa..."),
+        ];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Parser::parse_request(inp, &limits);
             match result {
-                Ok(res) => panic!(),
+                Ok(_) => panic!("{}", index),
                 Err(e) => assert_eq!(e, expected[index]),
             }
         }
+
+        // Inputs within the limits, where a null bulk string does not end the array
+        let bulk = |string: &str| RESPOutput::BulkString(String::from(string));
+        let result = Parser::parse_request("*3\r\n$5\r\nhello\r\n$-1\r\n*1\r\n$0\r\n\r\n".as_bytes(), &limits);
+        let expected = RESPOutput::Array(vec![bulk("hello"), RESPOutput::Null, RESPOutput::Array(vec![bulk("")])]);
+        assert_eq!(result, Ok((expected, "".as_bytes())));
+    }
+
+    #[test]
+    fn parse_error_display() {
+        let error = Parser::parse_resp("*2\r\n$3\r\nGET\r\n$x\r\n".as_bytes()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid bulk length at offset 14 (bulk string): expected a length between -1 and 536870912, found \"x\""
+        );
+
+        let error = Parser::parse_resp(":1\r2\r\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "invalid input at offset 1 (integer): expected an integer, found \"1\\r2\"");
+        assert_eq!(ParseError::IncompleteInput.to_string(), "incomplete input");
     }
 }
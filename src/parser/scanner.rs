@@ -0,0 +1,112 @@
+use crate::parser::parser::ParseLimits;
+
+// Finds where a value ends by reading only the lengths in it. The scan resumes where the previous one
+// stopped, so that reading a large value in chunks takes time linear in its size, and the value is
+// parsed once all of it has arrived
+#[derive(Debug, Default)]
+pub struct ValueScanner {
+    // Bytes of the buffer that belong to values scanned so far
+    offset: usize,
+    // Number of values still missing from each aggregate that the scan is in
+    missing: Vec<usize>,
+}
+
+impl ValueScanner {
+    /// Returns the length of the first value in the buffer once all of it has arrived. Lengths that
+    /// are invalid or beyond the limits are left for the parser to report, so the whole buffer is
+    /// returned as soon as the scan reaches one
+    pub fn scan(&mut self, buffer: &[u8], limits: &ParseLimits) -> Option<usize> {
+        loop {
+            let input = &buffer[self.offset..];
+            let line_len = input.windows(2).position(|window| window == b"\r\n")?;
+            let length = std::str::from_utf8(&input[1.min(line_len)..line_len])
+                .ok()
+                .and_then(|length| length.parse::<i64>().ok());
+            let is_aggregate = matches!(input[0], b'*' | b'~' | b'>' | b'%' | b'|');
+            let max_len = if is_aggregate { limits.max_multibulk_len } else { limits.max_bulk_len };
+            let is_valid = length.map_or(false, |length| length >= -1 && length as u64 <= max_len as u64)
+                && !(is_aggregate && self.missing.len() >= limits.max_nesting_depth);
+
+            let mut size = line_len + 2;
+            let missing = match (input[0], length) {
+                (b'$' | b'=' | b'*' | b'~' | b'>' | b'%' | b'|', _) if !is_valid => {
+                    self.offset = 0;
+                    self.missing.clear();
+                    return Some(buffer.len());
+                }
+                (b'$' | b'=', Some(length)) if length >= 0 => {
+                    size += length as usize + 2;
+                    if input.len() < size {
+                        return None;
+                    }
+                    0
+                }
+                (b'*' | b'~' | b'>', Some(length)) if length > 0 => length as usize,
+                (b'%', Some(length)) if length > 0 => length as usize * 2,
+                // Attributes are followed by the value that they are about
+                (b'|', Some(length)) if length >= 0 => length as usize * 2 + 1,
+                _ => 0,
+            };
+            self.offset += size;
+            if missing > 0 {
+                self.missing.push(missing);
+                continue;
+            }
+
+            // The value may complete the aggregates that it is the last value of
+            while let Some(missing) = self.missing.last_mut() {
+                *missing -= 1;
+                if *missing > 0 {
+                    break;
+                }
+                self.missing.pop();
+            }
+            if self.missing.is_empty() {
+                let length = self.offset;
+                self.offset = 0;
+                return Some(length);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_finds_the_end_of_a_value_that_arrives_in_parts() {
+        let reply = b"|1\r\n+ttl\r\n:10\r\n*3\r\n$5\r\nhello\r\n%1\r\n+key\r\n*0\r\n$-1\r\n";
+        let mut buffer = reply.to_vec();
+        buffer.extend_from_slice(b"+OK\r\n");
+
+        let limits = ParseLimits::replies();
+        let mut scanner = ValueScanner::default();
+        for end in 0..reply.len() {
+            assert_eq!(scanner.scan(&buffer[..end], &limits), None);
+        }
+        assert_eq!(scanner.scan(&buffer, &limits), Some(reply.len()));
+
+        buffer.drain(..reply.len());
+        assert_eq!(scanner.scan(&buffer, &limits), Some(5));
+        assert_eq!(scanner.scan(b"?\r\n", &limits), Some(3));
+    }
+
+    #[test]
+    fn scan_leaves_lengths_beyond_the_limits_to_the_parser() {
+        let limits = ParseLimits {
+            max_bulk_len: 5,
+            max_multibulk_len: 2,
+            max_nesting_depth: 1,
+        };
+        let mut scanner = ValueScanner::default();
+
+        assert_eq!(scanner.scan(b"*2\r\n$5\r\nhello\r\n", &limits), None);
+        assert_eq!(scanner.scan(b"*2\r\n$5\r\nhello\r\n$6\r\n", &limits), Some(19));
+        assert_eq!(scanner.scan(b"*3\r\n", &limits), Some(4));
+        assert_eq!(scanner.scan(b"*1\r\n*1\r\n", &limits), Some(8));
+        assert_eq!(scanner.scan(b"*1\r\n$abc\r\n", &limits), Some(10));
+        assert_eq!(scanner.scan(b"*1\r\n$-5\r\n", &limits), Some(9));
+        assert_eq!(scanner.scan(b"*1\r\n$5\r\nhello\r\n", &limits), Some(15));
+    }
+}
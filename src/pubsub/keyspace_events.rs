@@ -0,0 +1,115 @@
+use crate::config::config::with_config;
+use crate::pubsub::pubsub::with_pubsub;
+
+// Classes of keyspace events, which are enabled with the characters of notify-keyspace-events
+pub const NOTIFY_KEYSPACE: u32 = 1 << 0; // K
+pub const NOTIFY_KEYEVENT: u32 = 1 << 1; // E
+pub const NOTIFY_GENERIC: u32 = 1 << 2; // g
+pub const NOTIFY_STRING: u32 = 1 << 3; // $
+pub const NOTIFY_LIST: u32 = 1 << 4; // l
+pub const NOTIFY_SET: u32 = 1 << 5; // s
+pub const NOTIFY_HASH: u32 = 1 << 6; // h
+pub const NOTIFY_ZSET: u32 = 1 << 7; // z
+pub const NOTIFY_EXPIRED: u32 = 1 << 8; // x
+pub const NOTIFY_EVICTED: u32 = 1 << 9; // e
+pub const NOTIFY_STREAM: u32 = 1 << 10; // t
+pub const NOTIFY_KEY_MISS: u32 = 1 << 11; // m
+pub const NOTIFY_NEW: u32 = 1 << 12; // n
+// A, which does not include key miss and new key events
+pub const NOTIFY_ALL: u32 = NOTIFY_GENERIC
+    | NOTIFY_STRING
+    | NOTIFY_LIST
+    | NOTIFY_SET
+    | NOTIFY_HASH
+    | NOTIFY_ZSET
+    | NOTIFY_EXPIRED
+    | NOTIFY_EVICTED
+    | NOTIFY_STREAM;
+
+const CLASS_CHARACTERS: [(char, u32); 9] = [
+    ('g', NOTIFY_GENERIC),
+    ('$', NOTIFY_STRING),
+    ('l', NOTIFY_LIST),
+    ('s', NOTIFY_SET),
+    ('h', NOTIFY_HASH),
+    ('z', NOTIFY_ZSET),
+    ('x', NOTIFY_EXPIRED),
+    ('e', NOTIFY_EVICTED),
+    ('t', NOTIFY_STREAM),
+];
+
+/// Returns None if the string contains a character that is not an event class
+pub fn string_to_flags(classes: &str) -> Option<u32> {
+    let mut flags = 0;
+    for c in classes.chars() {
+        flags |= match c {
+            'A' => NOTIFY_ALL,
+            'K' => NOTIFY_KEYSPACE,
+            'E' => NOTIFY_KEYEVENT,
+            'm' => NOTIFY_KEY_MISS,
+            'n' => NOTIFY_NEW,
+            _ => CLASS_CHARACTERS.iter().find(|(class, _)| *class == c)?.1,
+        };
+    }
+    Some(flags)
+}
+
+// Formats the flags in the same order as redis, i.e. CONFIG GET returns "AKE" for "KEA"
+pub fn flags_to_string(flags: u32) -> String {
+    let mut classes = String::new();
+    if flags & NOTIFY_ALL == NOTIFY_ALL {
+        classes.push('A');
+    } else {
+        for (c, flag) in CLASS_CHARACTERS.iter() {
+            if flags & flag != 0 {
+                classes.push(*c);
+            }
+        }
+    }
+
+    for (c, flag) in [('K', NOTIFY_KEYSPACE), ('E', NOTIFY_KEYEVENT), ('m', NOTIFY_KEY_MISS), ('n', NOTIFY_NEW)] {
+        if flags & flag != 0 {
+            classes.push(c);
+        }
+    }
+    classes
+}
+
+/// Publishes the event to __keyspace@<db>__:<key> and __keyevent@<db>__:<event>,
+/// if its class is enabled by notify-keyspace-events
+pub fn notify_keyspace_event(class: u32, event: &str, key: &str, db: usize) {
+    let flags = with_config(|config| config.notify_keyspace_events);
+    if flags & class == 0 {
+        return;
+    }
+
+    with_pubsub(|pubsub| {
+        if flags & NOTIFY_KEYSPACE != 0 {
+            pubsub.publish(&format!("__keyspace@{}__:{}", db, key), event);
+        }
+        if flags & NOTIFY_KEYEVENT != 0 {
+            pubsub.publish(&format!("__keyevent@{}__:{}", db, event), key);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_to_flags_parses_event_classes() {
+        assert_eq!(string_to_flags(""), Some(0));
+        assert_eq!(string_to_flags("KEA"), Some(NOTIFY_KEYSPACE | NOTIFY_KEYEVENT | NOTIFY_ALL));
+        assert_eq!(string_to_flags("Ex$"), Some(NOTIFY_KEYEVENT | NOTIFY_EXPIRED | NOTIFY_STRING));
+        assert_eq!(string_to_flags("Kw"), None);
+    }
+
+    #[test]
+    fn flags_to_string_uses_redis_order() {
+        assert_eq!(flags_to_string(string_to_flags("KEA").unwrap()), "AKE");
+        assert_eq!(flags_to_string(string_to_flags("nEx$g").unwrap()), "g$xEn");
+        assert_eq!(flags_to_string(string_to_flags("Eglshz$xet").unwrap()), "AE");
+        assert_eq!(flags_to_string(0), "");
+    }
+}
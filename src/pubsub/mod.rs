@@ -0,0 +1,3 @@
+pub mod keyspace_events;
+#[allow(clippy::module_inception)]
+pub mod pubsub;
@@ -0,0 +1,268 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::Mutex;
+
+use crate::client::registry::{with_clients, ClientId};
+use crate::parser::parser::RESPOutput;
+use crate::util::glob::glob_match;
+use crate::util::global::Global;
+
+// Subscribers of every connection, shared so that PUBLISH can fan out messages
+static PUBSUB: Global<Mutex<PubSub>> = Global::new();
+
+pub type SubscriberId = u64;
+
+// Messages that a subscriber has not written out yet, beyond which its client is disconnected,
+// like client-output-buffer-limit pubsub in redis
+pub const MAX_PENDING_MESSAGES: usize = 16 * 1024;
+
+#[derive(Debug)]
+struct Subscriber {
+    sender: SyncSender<RESPOutput>,
+    client_id: ClientId,
+}
+
+// Connections receive messages through their own channel, which a thread of each
+// connection writes out as they arrive
+#[derive(Debug, Default)]
+pub struct PubSub {
+    next_id: SubscriberId,
+    subscribers: BTreeMap<SubscriberId, Subscriber>,
+    channels: BTreeMap<String, BTreeSet<SubscriberId>>,
+    patterns: BTreeMap<String, BTreeSet<SubscriberId>>,
+}
+
+impl PubSub {
+    pub fn new() -> PubSub {
+        PubSub {
+            next_id: 0,
+            subscribers: BTreeMap::new(),
+            channels: BTreeMap::new(),
+            patterns: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a subscriber for the client, which is killed if it falls behind by MAX_PENDING_MESSAGES
+    pub fn register(&mut self, client_id: ClientId) -> (SubscriberId, Receiver<RESPOutput>) {
+        let (sender, receiver) = sync_channel(MAX_PENDING_MESSAGES);
+        self.next_id += 1;
+        self.subscribers.insert(self.next_id, Subscriber { sender, client_id });
+        (self.next_id, receiver)
+    }
+
+    /// Removes the subscriber, along with all of its subscriptions
+    pub fn unregister(&mut self, id: SubscriberId) {
+        self.subscribers.remove(&id);
+        remove_subscriber(&mut self.channels, id);
+        remove_subscriber(&mut self.patterns, id);
+    }
+
+    /// Removes every subscriber, i.e. those of a server that ran before, while ids are still not reused
+    pub fn clear(&mut self) {
+        self.subscribers.clear();
+        self.channels.clear();
+        self.patterns.clear();
+    }
+
+    pub fn subscribe(&mut self, id: SubscriberId, channel: &str) {
+        self.channels.entry(String::from(channel)).or_default().insert(id);
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriberId, channel: &str) {
+        unsubscribe_from(&mut self.channels, id, channel);
+    }
+
+    pub fn psubscribe(&mut self, id: SubscriberId, pattern: &str) {
+        self.patterns.entry(String::from(pattern)).or_default().insert(id);
+    }
+
+    pub fn punsubscribe(&mut self, id: SubscriberId, pattern: &str) {
+        unsubscribe_from(&mut self.patterns, id, pattern);
+    }
+
+    /// Sends the message to subscribers of the channel and of matching patterns,
+    /// and returns the number of subscribers that received it
+    pub fn publish(&mut self, channel: &str, message: &str) -> usize {
+        let mut receivers = 0;
+        let mut overflowed = vec![];
+
+        if let Some(ids) = self.channels.get(channel) {
+            let push = RESPOutput::Push(vec![
+                RESPOutput::BulkString(String::from("message")),
+                RESPOutput::BulkString(String::from(channel)),
+                RESPOutput::BulkString(String::from(message)),
+            ]);
+            receivers += self.send(ids, &push, &mut overflowed);
+        }
+
+        for (pattern, ids) in self.patterns.iter() {
+            if !glob_match(pattern, channel) {
+                continue;
+            }
+            let push = RESPOutput::Push(vec![
+                RESPOutput::BulkString(String::from("pmessage")),
+                RESPOutput::BulkString(String::from(pattern)),
+                RESPOutput::BulkString(String::from(channel)),
+                RESPOutput::BulkString(String::from(message)),
+            ]);
+            receivers += self.send(ids, &push, &mut overflowed);
+        }
+
+        for id in overflowed {
+            self.disconnect(id);
+        }
+        receivers
+    }
+
+    /// Channels with at least one subscriber, optionally filtered by a glob pattern
+    pub fn active_channels(&self, pattern: Option<&str>) -> Vec<&str> {
+        self.channels
+            .keys()
+            .filter(|channel| pattern.map_or(true, |pattern| glob_match(pattern, channel)))
+            .map(|channel| channel.as_str())
+            .collect()
+    }
+
+    pub fn num_subscribers(&self, channel: &str) -> usize {
+        self.channels.get(channel).map_or(0, |ids| ids.len())
+    }
+
+    /// Number of unique patterns subscribed to by all connections
+    pub fn num_patterns(&self) -> usize {
+        self.patterns.len()
+    }
+
+    // Subscribers whose channel is full are added to `overflowed` instead of waiting for them
+    fn send(&self, ids: &BTreeSet<SubscriberId>, push: &RESPOutput, overflowed: &mut Vec<SubscriberId>) -> usize {
+        let mut receivers = 0;
+        for id in ids.iter() {
+            let subscriber = match self.subscribers.get(id) {
+                Some(subscriber) => subscriber,
+                None => continue,
+            };
+            match subscriber.sender.try_send(push.clone()) {
+                Ok(()) => receivers += 1,
+                Err(TrySendError::Full(_)) => overflowed.push(*id),
+                Err(TrySendError::Disconnected(_)) => {}
+            }
+        }
+        receivers
+    }
+
+    // Removes the subscriber and closes the connection of its client, which is not reading
+    // the messages that are published to it
+    fn disconnect(&mut self, id: SubscriberId) {
+        if let Some(subscriber) = self.subscribers.get(&id) {
+            let client_id = subscriber.client_id;
+            println!("Closing client {} that is {} messages behind", client_id, MAX_PENDING_MESSAGES);
+            self.unregister(id);
+            with_clients(|clients| clients.kill(client_id));
+        }
+    }
+}
+
+fn unsubscribe_from(subscriptions: &mut BTreeMap<String, BTreeSet<SubscriberId>>, id: SubscriberId, name: &str) {
+    if let Some(ids) = subscriptions.get_mut(name) {
+        ids.remove(&id);
+        if ids.is_empty() {
+            subscriptions.remove(name);
+        }
+    }
+}
+
+fn remove_subscriber(subscriptions: &mut BTreeMap<String, BTreeSet<SubscriberId>>, id: SubscriberId) {
+    subscriptions.retain(|_, ids| {
+        ids.remove(&id);
+        !ids.is_empty()
+    });
+}
+
+/// Runs the function with exclusive access to the subscribers of all connections
+pub fn with_pubsub<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut PubSub) -> R,
+{
+    let mut pubsub = PUBSUB.get_or_init(|| Mutex::new(PubSub::new())).lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut pubsub)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(channel: &str, message: &str) -> RESPOutput {
+        RESPOutput::Push(vec![
+            RESPOutput::BulkString(String::from("message")),
+            RESPOutput::BulkString(String::from(channel)),
+            RESPOutput::BulkString(String::from(message)),
+        ])
+    }
+
+    #[test]
+    fn publish_sends_to_channel_and_pattern_subscribers() {
+        let mut pubsub = PubSub::new();
+        let (first, first_receiver) = pubsub.register(0);
+        let (second, second_receiver) = pubsub.register(0);
+
+        pubsub.subscribe(first, "news.tech");
+        pubsub.psubscribe(second, "news.*");
+        pubsub.psubscribe(second, "sport.*");
+
+        assert_eq!(pubsub.publish("news.tech", "hello"), 2);
+        assert_eq!(first_receiver.try_recv().unwrap(), message("news.tech", "hello"));
+        assert_eq!(
+            second_receiver.try_recv().unwrap(),
+            RESPOutput::Push(vec![
+                RESPOutput::BulkString(String::from("pmessage")),
+                RESPOutput::BulkString(String::from("news.*")),
+                RESPOutput::BulkString(String::from("news.tech")),
+                RESPOutput::BulkString(String::from("hello")),
+            ])
+        );
+        assert!(second_receiver.try_recv().is_err());
+        assert_eq!(pubsub.publish("weather", "sunny"), 0);
+    }
+
+    #[test]
+    fn unsubscribe_removes_empty_channels() {
+        let mut pubsub = PubSub::new();
+        let (first, _first_receiver) = pubsub.register(0);
+        let (second, _second_receiver) = pubsub.register(0);
+
+        pubsub.subscribe(first, "news");
+        pubsub.subscribe(second, "news");
+        pubsub.subscribe(second, "weather");
+        pubsub.psubscribe(second, "news.*");
+        assert_eq!(pubsub.active_channels(None), vec!["news", "weather"]);
+        assert_eq!(pubsub.active_channels(Some("n*")), vec!["news"]);
+        assert_eq!(pubsub.num_subscribers("news"), 2);
+        assert_eq!(pubsub.num_patterns(), 1);
+
+        pubsub.unsubscribe(first, "news");
+        assert_eq!(pubsub.num_subscribers("news"), 1);
+
+        pubsub.unregister(second);
+        assert!(pubsub.active_channels(None).is_empty());
+        assert_eq!(pubsub.num_patterns(), 0);
+        assert_eq!(pubsub.publish("news", "hello"), 0);
+    }
+
+    #[test]
+    fn subscriber_that_falls_behind_is_disconnected() {
+        let client_id = with_clients(|clients| clients.register());
+        let mut pubsub = PubSub::new();
+        let (id, receiver) = pubsub.register(client_id);
+        pubsub.subscribe(id, "news");
+
+        for _ in 0..MAX_PENDING_MESSAGES {
+            assert_eq!(pubsub.publish("news", "hello"), 1);
+        }
+        assert!(!with_clients(|clients| clients.is_killed(client_id)));
+
+        assert_eq!(pubsub.publish("news", "hello"), 0);
+        assert_eq!(pubsub.num_subscribers("news"), 0);
+        assert!(with_clients(|clients| clients.is_killed(client_id)));
+        assert_eq!(receiver.try_iter().count(), MAX_PENDING_MESSAGES);
+        with_clients(|clients| clients.unregister(client_id));
+    }
+}
@@ -0,0 +1,190 @@
+use std::io::Write;
+
+use chrono::Utc;
+
+use crate::acl::acl::{with_acl, Denial, LogEntry};
+use crate::config::config::with_config;
+use crate::parser::parser::RESPOutput;
+use crate::request_response::client_input::ClientInput;
+use crate::request_response::command::{Command, ALL_COMMANDS, CATEGORIES};
+use crate::request_response::command_args::{parse_integer_arg, wrong_number_of_arguments};
+use crate::request_response::response_helper;
+
+// Number of entries that ACL LOG replies with by default
+const DEFAULT_LOG_COUNT: usize = 10;
+
+impl ClientInput {
+    pub(crate) fn user_exists(&self) -> bool {
+        with_acl(|acl| acl.get_user(&self.user).is_some())
+    }
+
+    // Denied commands are logged as being sent in a transaction while one is queued or executed
+    pub(crate) fn acl_log_context(&self) -> &'static str {
+        if self.is_in_transaction() || self.executing_transaction {
+            "multi"
+        } else {
+            "toplevel"
+        }
+    }
+
+    // Checks that the user of the connection can run the command, logging it if not
+    pub(crate) fn check_permissions(&self, command: &Command, args: &[String]) -> Result<(), String> {
+        if ClientInput::is_allowed_without_auth(command) {
+            return Ok(());
+        }
+
+        let context = self.acl_log_context();
+        with_acl(|acl| {
+            acl.check(&self.user, command, args).map_err(|denial| {
+                let object = match &denial {
+                    Denial::Key(name) | Denial::Channel(name) => name.clone(),
+                    _ => command.name(),
+                };
+                acl.add_log_entry(&denial, context, &object, &self.user);
+                denial.error(&self.user, command)
+            })
+        })
+    }
+
+    // acl SETUSER <username> [rule ...]
+    // acl GETUSER <username>
+    // acl DELUSER <username> [username ...]
+    // acl LIST | WHOAMI | LOAD | SAVE
+    // acl CAT [category]
+    // acl LOG [count | RESET]
+    pub(crate) fn respond_acl<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.acl(args));
+    }
+
+    fn acl(&self, args: &[String]) -> Result<RESPOutput, String> {
+        let subcommand = args.first().ok_or_else(|| wrong_number_of_arguments("acl"))?.to_uppercase();
+        let arity_ok = match subcommand.as_str() {
+            "SETUSER" | "DELUSER" => args.len() >= 2,
+            "GETUSER" => args.len() == 2,
+            "LIST" | "WHOAMI" | "LOAD" | "SAVE" => args.len() == 1,
+            "CAT" | "LOG" => args.len() <= 2,
+            _ => return Err(format!("ERR unknown subcommand '{}'. Try ACL HELP.", args[0])),
+        };
+        if !arity_ok {
+            return Err(format!(
+                "ERR wrong number of arguments for 'acl|{}' command",
+                subcommand.to_lowercase()
+            ));
+        }
+
+        let args = &args[1..];
+        match subcommand.as_str() {
+            "SETUSER" => with_acl(|acl| acl.set_user(&args[0], &args[1..]))
+                .map(|_| RESPOutput::SimpleString(String::from("OK")))
+                .map_err(|e| format!("ERR {}", e)),
+            "GETUSER" => Ok(acl_getuser(&args[0])),
+            "DELUSER" => with_acl(|acl| acl.delete_users(args))
+                .map(|deleted| RESPOutput::Integer(deleted as i64))
+                .map_err(|e| format!("ERR {}", e)),
+            "LIST" => Ok(with_acl(|acl| {
+                RESPOutput::Array(acl.users().map(|user| RESPOutput::BulkString(user.describe())).collect())
+            })),
+            "WHOAMI" => Ok(RESPOutput::BulkString(self.user.clone())),
+            "CAT" => acl_cat(args.first()),
+            "LOG" => acl_log(args.first()),
+            "LOAD" => {
+                let path = acl_file()?;
+                with_acl(|acl| acl.load_file(&path)).map_err(|e| format!("ERR {}", e))?;
+                Ok(RESPOutput::SimpleString(String::from("OK")))
+            }
+            _ => {
+                let path = acl_file()?;
+                with_acl(|acl| acl.save_file(&path)).map_err(|e| format!("ERR {}", e))?;
+                Ok(RESPOutput::SimpleString(String::from("OK")))
+            }
+        }
+    }
+}
+
+fn acl_getuser(name: &str) -> RESPOutput {
+    let field = |name: &str| RESPOutput::BulkString(String::from(name));
+    let strings = |values: Vec<&str>| values.into_iter().map(field).collect();
+
+    with_acl(|acl| match acl.get_user(name) {
+        Some(user) => RESPOutput::Map(vec![
+            (field("flags"), RESPOutput::Set(strings(user.flags()))),
+            (field("passwords"), RESPOutput::Array(strings(user.password_hashes()))),
+            (field("commands"), RESPOutput::BulkString(user.describe_commands())),
+            (field("keys"), RESPOutput::BulkString(user.describe_keys())),
+            (field("channels"), RESPOutput::BulkString(user.describe_channels())),
+            (field("selectors"), RESPOutput::Array(Vec::new())),
+        ]),
+        None => RESPOutput::Null,
+    })
+}
+
+// Lists the categories, or the commands in a category
+fn acl_cat(category: Option<&String>) -> Result<RESPOutput, String> {
+    let names: Vec<String> = match category {
+        None => CATEGORIES.iter().map(|category| String::from(*category)).collect(),
+        Some(category) => {
+            let category = category.to_lowercase();
+            if !CATEGORIES.contains(&category.as_str()) {
+                return Err(format!("ERR Unknown category '{}'", category));
+            }
+            ALL_COMMANDS
+                .iter()
+                .filter(|command| command.categories().contains(&category.as_str()))
+                .map(|command| command.name())
+                .collect()
+        }
+    };
+    Ok(RESPOutput::Array(names.into_iter().map(RESPOutput::BulkString).collect()))
+}
+
+fn acl_log(arg: Option<&String>) -> Result<RESPOutput, String> {
+    let count = match arg {
+        Some(arg) if arg.eq_ignore_ascii_case("reset") => {
+            with_acl(|acl| acl.reset_log());
+            return Ok(RESPOutput::SimpleString(String::from("OK")));
+        }
+        Some(arg) => {
+            let count = parse_integer_arg(arg)?;
+            if count < 0 {
+                return Err(String::from("ERR value is out of range, must be positive"));
+            }
+            count as usize
+        }
+        None => DEFAULT_LOG_COUNT,
+    };
+
+    let now = Utc::now().timestamp_millis();
+    Ok(with_acl(|acl| {
+        RESPOutput::Array(
+            acl.log_entries(count)
+                .into_iter()
+                .map(|entry| log_entry_output(entry, now))
+                .collect(),
+        )
+    }))
+}
+
+fn log_entry_output(entry: &LogEntry, now: i64) -> RESPOutput {
+    let field = |name: &str| RESPOutput::BulkString(String::from(name));
+    let age_seconds = (now - entry.created_at) as f64 / 1000.0;
+
+    RESPOutput::Map(vec![
+        (field("count"), RESPOutput::Integer(entry.count as i64)),
+        (field("reason"), field(entry.reason)),
+        (field("context"), field(entry.context)),
+        (field("object"), field(&entry.object)),
+        (field("username"), field(&entry.username)),
+        (field("age-seconds"), RESPOutput::Double(age_seconds)),
+        (field("entry-id"), RESPOutput::Integer(entry.entry_id as i64)),
+        (field("timestamp-created"), RESPOutput::Integer(entry.created_at)),
+        (field("timestamp-last-updated"), RESPOutput::Integer(entry.updated_at)),
+    ])
+}
+
+fn acl_file() -> Result<String, String> {
+    let path = with_config(|config| config.aclfile.clone());
+    if path.is_empty() {
+        return Err(String::from("ERR This Redis instance is not configured to use an ACL file. You may want to specify users via the ACL SETUSER command and then issue a CONFIG REWRITE (assuming you have a Redis configuration file set) in order to store users in the Redis configuration."));
+    }
+    Ok(path)
+}
@@ -0,0 +1,295 @@
+use std::io::Write;
+use std::time::Instant;
+
+use crate::acl::acl::with_acl;
+use crate::client::registry::{with_clients, ClientId, ClientInfo};
+use crate::parser::parser::RESPOutput;
+use crate::request_response::client_input::ClientInput;
+use crate::request_response::command_args::{wrong_number_of_arguments, SYNTAX_ERROR};
+use crate::request_response::parsed_command::ParsedCommand;
+use crate::request_response::response_helper;
+use crate::Connection;
+
+// Filters of CLIENT KILL and CLIENT LIST, which all have to match
+#[derive(Debug, Default)]
+struct ClientFilter {
+    ids: Option<Vec<ClientId>>,
+    addr: Option<String>,
+    laddr: Option<String>,
+    user: Option<String>,
+    client_type: Option<String>,
+}
+
+impl ClientFilter {
+    fn matches(&self, client: &ClientInfo) -> bool {
+        self.ids.as_ref().map_or(true, |ids| ids.contains(&client.id))
+            && self.addr.as_ref().map_or(true, |addr| *addr == client.addr)
+            && self.laddr.as_ref().map_or(true, |laddr| *laddr == client.laddr)
+            && self.user.as_ref().map_or(true, |user| *user == client.user)
+            && self.client_type.as_ref().map_or(true, |client_type| match client_type.as_str() {
+                "normal" => !client.is_pubsub(),
+                "pubsub" => client.is_pubsub(),
+                // There are no replication links
+                _ => false,
+            })
+    }
+}
+
+impl ClientInput {
+    /// Records the addresses of the connection, how to close it when the client is killed,
+    /// and how to write the messages of its subscriptions
+    pub fn attach_connection<S: Connection>(&mut self, connection: &S) {
+        let (addr, laddr) = connection.addresses();
+        let shutdown = connection.shutdown_handle();
+        self.message_writer = connection.writer();
+        with_clients(|clients| {
+            if let Some(client) = clients.get_mut(self.id) {
+                client.addr = addr;
+                client.laddr = laddr;
+                client.is_unix_socket = connection.is_unix_socket();
+            }
+            if let Some(shutdown) = shutdown {
+                clients.set_shutdown_handle(self.id, shutdown);
+            }
+        });
+    }
+
+    /// Records the command that the client is about to run
+    pub(crate) fn record_command(&self, parsed: &ParsedCommand) {
+        let command = match parsed.command() {
+            Some(command) => command,
+            None => return,
+        };
+        let name = match parsed.args().first() {
+            Some(subcommand) if command.has_subcommands() => {
+                format!("{}|{}", command.name(), subcommand.to_lowercase())
+            }
+            _ => command.name(),
+        };
+
+        let query_buffer = self.get_input().len();
+        with_clients(|clients| {
+            if let Some(client) = clients.get_mut(self.id) {
+                client.last_command = name;
+                client.last_interaction = Instant::now();
+                client.query_buffer = query_buffer;
+            }
+        });
+        self.sync_client_info();
+    }
+
+    /// Copies the state of the connection to the registry, so that other clients can list it
+    pub(crate) fn sync_client_info(&self) {
+        let (subscriptions, pattern_subscriptions) = self.subscription_counts();
+        let multi = self.queued_command_count();
+        let query_buffer = self.get_input().len();
+        with_clients(|clients| {
+            if let Some(client) = clients.get_mut(self.id) {
+                client.db = self.db;
+                client.user = self.user.clone();
+                client.subscriptions = subscriptions;
+                client.pattern_subscriptions = pattern_subscriptions;
+                client.multi = multi;
+                client.query_buffer = query_buffer;
+            }
+        });
+    }
+
+    pub(crate) fn is_killed(&self) -> bool {
+        with_clients(|clients| clients.is_killed(self.id))
+    }
+
+    // client ID | INFO | GETNAME
+    // client SETNAME <name>
+    // client NO-EVICT <ON | OFF>
+    // client LIST [TYPE <type>] [ID <id> [id ...]]
+    // client KILL <addr> | client KILL [ID <id>] [ADDR <addr>] [LADDR <addr>] [USER <user>] [TYPE <type>] [SKIPME <yes | no>]
+    pub(crate) fn respond_client<T: Write>(&mut self, stream: &mut T, args: &[String]) {
+        let result = self.client(args);
+        response_helper::send_result_response(stream, result);
+    }
+
+    fn client(&mut self, args: &[String]) -> Result<RESPOutput, String> {
+        let subcommand = args.first().ok_or_else(|| wrong_number_of_arguments("client"))?.to_uppercase();
+        let arity_ok = match subcommand.as_str() {
+            "ID" | "INFO" | "GETNAME" => args.len() == 1,
+            "SETNAME" | "NO-EVICT" => args.len() == 2,
+            "LIST" => true,
+            "KILL" => args.len() >= 2,
+            _ => return Err(format!("ERR unknown subcommand '{}'. Try CLIENT HELP.", args[0])),
+        };
+        if !arity_ok {
+            return Err(format!(
+                "ERR wrong number of arguments for 'client|{}' command",
+                subcommand.to_lowercase()
+            ));
+        }
+
+        let args = &args[1..];
+        match subcommand.as_str() {
+            "ID" => Ok(RESPOutput::Integer(self.id as i64)),
+            "INFO" => Ok(RESPOutput::VerbatimString(
+                String::from("txt"),
+                with_clients(|clients| {
+                    clients.get(self.id).map(|client| format!("{}\n", client.describe())).unwrap_or_default()
+                }),
+            )),
+            "GETNAME" => Ok(with_clients(|clients| {
+                match clients.get(self.id).and_then(|client| client.name.clone()) {
+                    Some(name) => RESPOutput::BulkString(name),
+                    None => RESPOutput::Null,
+                }
+            })),
+            "SETNAME" => self.client_setname(&args[0]),
+            "NO-EVICT" => self.client_no_evict(&args[0]),
+            "LIST" => client_list(args),
+            _ => self.client_kill(args),
+        }
+    }
+
+    // An empty name removes the name
+    pub(crate) fn client_setname(&self, name: &str) -> Result<RESPOutput, String> {
+        if name.chars().any(|c| !('!'..='~').contains(&c)) {
+            return Err(String::from(
+                "ERR Client names cannot contain spaces, newlines or special characters.",
+            ));
+        }
+
+        let name = if name.is_empty() { None } else { Some(String::from(name)) };
+        with_clients(|clients| {
+            if let Some(client) = clients.get_mut(self.id) {
+                client.name = name;
+            }
+        });
+        Ok(RESPOutput::SimpleString(String::from("OK")))
+    }
+
+    fn client_no_evict(&self, mode: &str) -> Result<RESPOutput, String> {
+        let no_evict = match mode.to_lowercase().as_str() {
+            "on" => true,
+            "off" => false,
+            _ => return Err(String::from(SYNTAX_ERROR)),
+        };
+        with_clients(|clients| {
+            if let Some(client) = clients.get_mut(self.id) {
+                client.no_evict = no_evict;
+            }
+        });
+        Ok(RESPOutput::SimpleString(String::from("OK")))
+    }
+
+    fn client_kill(&mut self, args: &[String]) -> Result<RESPOutput, String> {
+        // The old form kills a single client by address, including this one
+        if let [addr] = args {
+            let filter = ClientFilter {
+                addr: Some(addr.clone()),
+                ..ClientFilter::default()
+            };
+            return match self.kill_clients(&filter, false) {
+                0 => Err(String::from("ERR No such client")),
+                _ => Ok(RESPOutput::SimpleString(String::from("OK"))),
+            };
+        }
+
+        if args.len() % 2 != 0 {
+            return Err(String::from(SYNTAX_ERROR));
+        }
+        let mut filter = ClientFilter::default();
+        let mut skip_me = true;
+        for pair in args.chunks(2) {
+            let value = &pair[1];
+            match pair[0].to_uppercase().as_str() {
+                "ID" => filter.ids = Some(vec![parse_client_id(value)?]),
+                "ADDR" => filter.addr = Some(value.clone()),
+                "LADDR" => filter.laddr = Some(value.clone()),
+                "USER" => filter.user = Some(parse_user(value)?),
+                "TYPE" => filter.client_type = Some(parse_client_type(value)?),
+                "SKIPME" => {
+                    skip_me = match value.to_lowercase().as_str() {
+                        "yes" => true,
+                        "no" => false,
+                        _ => return Err(String::from(SYNTAX_ERROR)),
+                    }
+                }
+                _ => return Err(String::from(SYNTAX_ERROR)),
+            }
+        }
+
+        let killed = self.kill_clients(&filter, skip_me);
+        Ok(RESPOutput::Integer(killed as i64))
+    }
+
+    // This connection is closed after replying, while others are closed right away
+    fn kill_clients(&mut self, filter: &ClientFilter, skip_me: bool) -> usize {
+        let ids: Vec<ClientId> = with_clients(|clients| {
+            clients
+                .clients()
+                .filter(|client| filter.matches(client))
+                .filter(|client| !(skip_me && client.id == self.id))
+                .map(|client| client.id)
+                .collect()
+        });
+
+        for id in ids.iter() {
+            if *id == self.id {
+                self.closing = true;
+            } else {
+                with_clients(|clients| clients.kill(*id));
+            }
+        }
+        ids.len()
+    }
+}
+
+fn client_list(args: &[String]) -> Result<RESPOutput, String> {
+    let mut filter = ClientFilter::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.to_uppercase().as_str() {
+            "TYPE" => {
+                let client_type = args.next().ok_or_else(|| String::from(SYNTAX_ERROR))?;
+                filter.client_type = Some(parse_client_type(client_type)?);
+            }
+            // Every remaining argument is an id
+            "ID" => {
+                let ids = args.by_ref().map(|id| parse_client_id(id)).collect::<Result<Vec<ClientId>, String>>()?;
+                if ids.is_empty() {
+                    return Err(String::from(SYNTAX_ERROR));
+                }
+                filter.ids = Some(ids);
+            }
+            _ => return Err(String::from(SYNTAX_ERROR)),
+        }
+    }
+
+    let list: String = with_clients(|clients| {
+        clients
+            .clients()
+            .filter(|client| filter.matches(client))
+            .map(|client| format!("{}\n", client.describe()))
+            .collect()
+    });
+    Ok(RESPOutput::VerbatimString(String::from("txt"), list))
+}
+
+fn parse_client_id(id: &str) -> Result<ClientId, String> {
+    id.parse()
+        .ok()
+        .filter(|id| *id > 0)
+        .ok_or_else(|| String::from("ERR client-id should be greater than 0"))
+}
+
+fn parse_client_type(client_type: &str) -> Result<String, String> {
+    let client_type = client_type.to_lowercase();
+    match client_type.as_str() {
+        "normal" | "pubsub" | "master" | "replica" | "slave" => Ok(client_type),
+        _ => Err(format!("ERR Unknown client type '{}'", client_type)),
+    }
+}
+
+fn parse_user(user: &str) -> Result<String, String> {
+    if with_acl(|acl| acl.get_user(user).is_none()) {
+        return Err(format!("ERR No such user '{}'", user));
+    }
+    Ok(String::from(user))
+}
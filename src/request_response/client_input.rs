@@ -1,13 +1,52 @@
 use std::borrow::Borrow;
-use std::io::{Error, ErrorKind, Read, Write};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
 
+use crate::acl::acl::{with_acl, DEFAULT_USER};
+use crate::client::registry::{with_clients, ClientId};
 use crate::request_response::{command::Command, parsed_command::ParsedCommand, response_helper};
+use crate::request_response::command_args::wrong_number_of_arguments;
+use crate::request_response::connection_handler::NOAUTH_ERROR;
+use crate::request_response::pubsub_handler::Subscription;
+use crate::request_response::response_helper::Protocol;
+use crate::request_response::transaction_handler::Transaction;
+use crate::store::command_lock::with_command_lock;
 use crate::store::redis::{RedisStore, Store};
 use crate::store::redis_operation::SetOptionalArgs;
-use crate::parser::parser::{Parser, ParseResult, ParseError, RESPOutput};
+use crate::config::config::with_config;
+use crate::parser::parser::{Parser, ParseError, ParseLimits, RESPOutput};
+use crate::parser::scanner::ValueScanner;
+use crate::pubsub::keyspace_events::{NOTIFY_GENERIC, NOTIFY_STRING};
 
 pub struct ClientInput {
+    // Id in the client registry
+    pub(crate) id: ClientId,
     input: Vec<u8>,
+    // How much of the next request is in the input
+    scanner: ValueScanner,
+    // Set once the connection subscribes to a channel or pattern
+    pub(crate) subscription: Option<Subscription>,
+    // Taken by the thread that writes published messages once the connection subscribes
+    pub(crate) message_writer: Option<Box<dyn Write + Send>>,
+    // Held while a command is answered so that messages are not written in the middle of its reply,
+    // and holds the protocol that messages are encoded with
+    pub(crate) writing: Arc<Mutex<Protocol>>,
+    // Set between MULTI and EXEC or DISCARD
+    pub(crate) transaction: Option<Transaction>,
+    // Blocking commands do not block while EXEC executes them
+    pub(crate) executing_transaction: bool,
+    // Keys watched by WATCH, with their databases and versions at that time
+    pub(crate) watched_keys: Vec<(usize, String, u64)>,
+    // Database selected by SELECT
+    pub(crate) db: usize,
+    // Cleared while the default user has a password and the connection has not sent a valid AUTH
+    pub(crate) authenticated: bool,
+    // ACL user that commands are run as
+    pub(crate) user: String,
+    // Set by QUIT, so that the connection is closed after replying
+    pub(crate) closing: bool,
+    // Protocol of the replies, chosen by HELLO
+    pub(crate) protocol: Protocol,
 }
 
 pub trait HandleClientInput {
@@ -16,7 +55,7 @@ pub trait HandleClientInput {
         buffer: &[u8],
     ) -> Result<RESPOutput, ParseError>;
 
-    fn respond<T: Write>(&self, stream: &mut T, parsed: ParsedCommand);
+    fn respond<T: Write>(&mut self, stream: &mut T, parsed: ParsedCommand);
     fn respond_error<T: Write>(&self, stream: &mut T, error: &str);
 
     fn reset(&mut self);
@@ -27,66 +66,31 @@ impl HandleClientInput for ClientInput {
         &mut self,
         buffer: &[u8],
     ) -> Result<RESPOutput, ParseError> {
-        self.append_input(&buffer);
-        let parsed = Parser::parse_resp(self.get_input());
-        match parsed {
-            Ok(res) => Ok(res.0),
-            Err(e) => Err(e),
-        }
-    }
-
-    fn respond<T: Write>(&self, stream: &mut T, parsed: ParsedCommand) {
-        let args = parsed.args();
-        let command = parsed.command();
-
-        if command.is_none() {
-            return response_helper::send_error_response(stream, "Unrecognised command");
-        }
-
-        let command_unwrapped = command.as_ref().unwrap();
-
-        if command_unwrapped == &Command::PING {
-            response_helper::send_pong_response(stream);
-        } else if command_unwrapped == &Command::ECHO {
-            let mut result = String::from("");
-
-            for arg in args.iter() {
-                let str: &str = arg.borrow();
-                result.push_str(str);
-            }
-            response_helper::send_bulk_string_response(stream, Some(&result));
-        } else if command_unwrapped == &Command::GET {
-            let key_expired_for_get = self.get_key_and_expiry(args);
-            if key_expired_for_get.is_none() {
-                response_helper::send_bulk_string_response(stream, None);
-                return;
-            }
-
-            let KeyValueExpiry {
-                key,
-                value,
-                is_expired,
-            } = key_expired_for_get.unwrap();
-
-            if is_expired {
-                self.delete_expired_keys(vec![&key]);
-                response_helper::send_bulk_string_response(stream, None);
-            } else {
-                let value = value.as_ref().map(|v| &**v);
-                response_helper::send_bulk_string_response(stream, value);
+        self.append_input(buffer);
+        let limits = ParseLimits {
+            max_bulk_len: with_config(|config| config.proto_max_bulk_len),
+            ..ParseLimits::default()
+        };
+        // RESP requests are parsed once all of a request has arrived, rather than again after every read.
+        // Inline ones are only parsed again until the end of their line, which is short
+        let length = match self.input.first() {
+            Some(b'+' | b'-' | b'$' | b':' | b'*') => {
+                self.scanner.scan(&self.input, &limits).ok_or(ParseError::IncompleteInput)?
             }
+            _ => self.input.len(),
+        };
+
+        // The command is removed from the input, leaving the ones that were pipelined after it
+        let (parsed, remaining) = Parser::parse_request(&self.input[..length], &limits)?;
+        let consumed = length - remaining.len();
+        self.input.drain(..consumed);
+        Ok(parsed)
+    }
 
-        } else if command_unwrapped == &Command::SET {
-            let store = &mut RedisStore::get_store();
-
-            // set <key> <value> [ex seconds | px milliseconds]
-            let key = args.get(0).unwrap();
-            let value = args.get(1).unwrap();
-            let optional_args = self.determine_set_optional_args(args);
-
-            store.set(key, value, &optional_args);
-            response_helper::send_bulk_string_response(stream, Some("OK"));
-        }
+    fn respond<T: Write>(&mut self, stream: &mut T, parsed: ParsedCommand) {
+        // Replies are encoded with the protocol chosen by HELLO
+        let protocol = self.protocol;
+        response_helper::with_protocol(protocol, || self.respond_command(stream, parsed));
     }
 
     fn respond_error<T: Write>(&self, stream: &mut T, error: &str) {
@@ -95,13 +99,199 @@ impl HandleClientInput for ClientInput {
 
     fn reset(&mut self) {
         self.input = Vec::new();
+        self.scanner = ValueScanner::default();
+    }
+}
+
+impl Default for ClientInput {
+    fn default() -> Self {
+        ClientInput::new()
+    }
+}
+
+impl Drop for ClientInput {
+    fn drop(&mut self) {
+        self.leave_pubsub();
+        self.unwatch_all();
+        with_clients(|clients| clients.unregister(self.id));
     }
 }
 
 impl ClientInput {
     pub fn new() -> ClientInput {
         ClientInput {
+            id: with_clients(|clients| clients.register()),
             input: Vec::new(),
+            scanner: ValueScanner::default(),
+            subscription: None,
+            message_writer: None,
+            writing: Arc::new(Mutex::new(Protocol::Resp2)),
+            transaction: None,
+            executing_transaction: false,
+            watched_keys: Vec::new(),
+            db: 0,
+            authenticated: with_acl(|acl| acl.is_default_user_open()),
+            user: String::from(DEFAULT_USER),
+            closing: false,
+            protocol: Protocol::Resp2,
+        }
+    }
+
+    pub fn id(&self) -> ClientId {
+        self.id
+    }
+
+    // The database selected by the connection
+    pub(crate) fn store(&self) -> &'static mut RedisStore {
+        RedisStore::get_db(self.db)
+    }
+
+    fn respond_command<T: Write>(&mut self, stream: &mut T, parsed: ParsedCommand) {
+        let command = match parsed.command() {
+            Some(command) => command,
+            None => {
+                self.flag_transaction();
+                return response_helper::send_error_response(stream, "Unrecognised command");
+            }
+        };
+        let args = parsed.args();
+
+        // Checked before anything else, so that commands never run with missing arguments
+        if !command.accepts_arg_count(args.len()) {
+            self.flag_transaction();
+            return response_helper::send_error_response(stream, &wrong_number_of_arguments(&command.name()));
+        }
+
+        // Connections of deleted users and killed clients are closed, like redis
+        if !self.user_exists() || self.is_killed() {
+            self.closing = true;
+            return;
+        }
+
+        if !self.is_authenticated() && !ClientInput::is_allowed_without_auth(command) {
+            self.flag_transaction();
+            return response_helper::send_error_response(stream, NOAUTH_ERROR);
+        }
+
+        if let Err(e) = self.check_permissions(command, args) {
+            self.flag_transaction();
+            return response_helper::send_error_response(stream, &e);
+        }
+
+        // RESP3 connections can run any command while subscribed, since messages are pushes
+        if self.is_subscribed() && self.protocol == Protocol::Resp2 {
+            if !ClientInput::is_allowed_when_subscribed(command) {
+                return self.respond_not_allowed_when_subscribed(stream, command);
+            }
+            if let Command::PING = command {
+                return self.respond_subscribed_ping(stream, args);
+            }
+        }
+
+        if self.is_in_transaction() && !ClientInput::is_transaction_command(command) {
+            return self.queue_command(stream, parsed);
+        }
+
+        // The reply is written once the lock is released, so a slow client does not block the others
+        let mut reply: Vec<u8> = Vec::new();
+        with_command_lock(|| self.execute(&mut reply, command, args));
+        response_helper::send_encoded_response(stream, &reply);
+    }
+
+    // Executes a command against the store, while the caller holds the command lock
+    fn execute<T: Write>(&mut self, stream: &mut T, command: &Command, args: &[String]) {
+        match command {
+            Command::PING => response_helper::send_pong_response(stream),
+            Command::ECHO => {
+                let mut result = String::from("");
+
+                for arg in args.iter() {
+                    let str: &str = arg.borrow();
+                    result.push_str(str);
+                }
+                response_helper::send_bulk_string_response(stream, Some(&result));
+            }
+            Command::GET => {
+                let key_expired_for_get = self.get_key_and_expiry(args);
+                if key_expired_for_get.is_none() {
+                    response_helper::send_bulk_string_response(stream, None);
+                    return;
+                }
+
+                let KeyValueExpiry {
+                    key,
+                    value,
+                    is_expired,
+                } = key_expired_for_get.unwrap();
+
+                if is_expired {
+                    self.delete_expired_keys(vec![&key]);
+                    response_helper::send_bulk_string_response(stream, None);
+                } else {
+                    let value = value.as_deref();
+                    response_helper::send_bulk_string_response(stream, value);
+                }
+            }
+            Command::SET => {
+                let store = self.store();
+
+                // set <key> <value> [ex seconds | px milliseconds]
+                let key = args.first().unwrap();
+                let value = args.get(1).unwrap();
+                let optional_args = self.determine_set_optional_args(args);
+
+                store.set(key, value, &optional_args);
+                store.notify_keyspace_event(NOTIFY_STRING, "set", key);
+                if optional_args.is_some() {
+                    store.notify_keyspace_event(NOTIFY_GENERIC, "expire", key);
+                }
+                response_helper::send_bulk_string_response(stream, Some("OK"));
+            }
+            Command::XADD => self.respond_xadd(stream, args),
+            Command::XRANGE => self.respond_xrange(stream, args),
+            Command::XREVRANGE => self.respond_xrevrange(stream, args),
+            Command::XLEN => self.respond_xlen(stream, args),
+            Command::XDEL => self.respond_xdel(stream, args),
+            Command::XTRIM => self.respond_xtrim(stream, args),
+            Command::XREAD => self.respond_xread(stream, args),
+            Command::XGROUP => self.respond_xgroup(stream, args),
+            Command::XREADGROUP => self.respond_xreadgroup(stream, args),
+            Command::XACK => self.respond_xack(stream, args),
+            Command::XPENDING => self.respond_xpending(stream, args),
+            Command::XCLAIM => self.respond_xclaim(stream, args),
+            Command::XAUTOCLAIM => self.respond_xautoclaim(stream, args),
+            Command::XINFO => self.respond_xinfo(stream, args),
+            Command::PFADD => self.respond_pfadd(stream, args),
+            Command::PFCOUNT => self.respond_pfcount(stream, args),
+            Command::PFMERGE => self.respond_pfmerge(stream, args),
+            Command::GEOADD => self.respond_geoadd(stream, args),
+            Command::GEODIST => self.respond_geodist(stream, args),
+            Command::GEOPOS => self.respond_geopos(stream, args),
+            Command::GEOHASH => self.respond_geohash(stream, args),
+            Command::GEOSEARCH => self.respond_geosearch(stream, args),
+            Command::GEOSEARCHSTORE => self.respond_geosearchstore(stream, args),
+            Command::SUBSCRIBE => self.respond_subscribe(stream, args),
+            Command::UNSUBSCRIBE => self.respond_unsubscribe(stream, args),
+            Command::PSUBSCRIBE => self.respond_psubscribe(stream, args),
+            Command::PUNSUBSCRIBE => self.respond_punsubscribe(stream, args),
+            Command::PUBLISH => self.respond_publish(stream, args),
+            Command::PUBSUB => self.respond_pubsub(stream, args),
+            Command::CONFIG => self.respond_config(stream, args),
+            Command::MULTI => self.respond_multi(stream),
+            Command::EXEC => self.respond_exec(stream),
+            Command::DISCARD => self.respond_discard(stream),
+            Command::WATCH => self.respond_watch(stream, args),
+            Command::UNWATCH => self.respond_unwatch(stream),
+            Command::SELECT => self.respond_select(stream, args),
+            Command::MOVE => self.respond_move(stream, args),
+            Command::SWAPDB => self.respond_swapdb(stream, args),
+            Command::FLUSHDB => self.respond_flushdb(stream, args),
+            Command::FLUSHALL => self.respond_flushall(stream, args),
+            Command::AUTH => self.respond_auth(stream, args),
+            Command::QUIT => self.respond_quit(stream),
+            Command::HELLO => self.respond_hello(stream, args),
+            Command::ACL => self.respond_acl(stream, args),
+            Command::CLIENT => self.respond_client(stream, args),
         }
     }
 
@@ -113,7 +303,7 @@ impl ClientInput {
         self.input.extend_from_slice(input);
     }
 
-    fn determine_set_optional_args(&self, args: &Vec<String>) -> Option<SetOptionalArgs> {
+    fn determine_set_optional_args(&self, args: &[String]) -> Option<SetOptionalArgs> {
         let mut optional_args: Option<SetOptionalArgs> = None;
 
         if args.len() != 4 {
@@ -148,16 +338,26 @@ impl ClientInput {
     }
 
     fn delete_expired_keys(&self, keys: Vec<&str>) {
-        let store = &mut RedisStore::get_store();
-        store.delete(keys);
+        let store = self.store();
+        for key in keys {
+            store.delete_expired(key);
+        }
+    }
+
+    // Lazily deletes the key if it has expired, so that commands see it as not present
+    pub(crate) fn expire_if_needed(&self, key: &str) {
+        let store = self.store();
+        if store.is_key_expired(key) {
+            self.delete_expired_keys(vec![key]);
+        }
     }
 
     fn get_key_and_expiry(
         &self,
-        args: &Vec<String>,
+        args: &[String],
     ) -> Option<KeyValueExpiry> {
-        let store = RedisStore::get_store();
-        let key = args.get(0).unwrap();
+        let store = self.store();
+        let key = args.first().unwrap();
         let value = store.get(key.as_str());
 
         let is_expired = store.is_key_expired(key);
@@ -205,7 +405,7 @@ mod tests {
 
     #[test]
     fn determine_set_optional_args_return_some_when_expiry_args_are_present() {
-        let mut client_input = ClientInput::new();
+        let client_input = ClientInput::new();
         let args = vec![
             String::from("hello"),
             String::from("world"),
@@ -223,7 +423,7 @@ mod tests {
 
     #[test]
     fn determine_set_optional_args_return_none_when_expiry_args_are_not_present() {
-        let mut client_input = ClientInput::new();
+        let client_input = ClientInput::new();
         let input = vec![
             vec![String::from("hello"), String::from("world")],
             vec![
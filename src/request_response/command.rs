@@ -4,26 +4,392 @@ pub enum Command {
     ECHO,
     GET,
     SET,
+    XADD,
+    XRANGE,
+    XREVRANGE,
+    XLEN,
+    XDEL,
+    XTRIM,
+    XREAD,
+    XGROUP,
+    XREADGROUP,
+    XACK,
+    XPENDING,
+    XCLAIM,
+    XAUTOCLAIM,
+    XINFO,
+    PFADD,
+    PFCOUNT,
+    PFMERGE,
+    GEOADD,
+    GEODIST,
+    GEOPOS,
+    GEOHASH,
+    GEOSEARCH,
+    GEOSEARCHSTORE,
+    SUBSCRIBE,
+    UNSUBSCRIBE,
+    PSUBSCRIBE,
+    PUNSUBSCRIBE,
+    PUBLISH,
+    PUBSUB,
+    CONFIG,
+    MULTI,
+    EXEC,
+    DISCARD,
+    WATCH,
+    UNWATCH,
+    SELECT,
+    MOVE,
+    SWAPDB,
+    FLUSHDB,
+    FLUSHALL,
+    AUTH,
+    QUIT,
+    HELLO,
+    ACL,
+    CLIENT,
+}
+
+// Every command, so that ACL rules can be applied to all of them
+pub const ALL_COMMANDS: [Command; 49] = [
+    Command::PING,
+    Command::ECHO,
+    Command::GET,
+    Command::SET,
+    Command::XADD,
+    Command::XRANGE,
+    Command::XREVRANGE,
+    Command::XLEN,
+    Command::XDEL,
+    Command::XTRIM,
+    Command::XREAD,
+    Command::XGROUP,
+    Command::XREADGROUP,
+    Command::XACK,
+    Command::XPENDING,
+    Command::XCLAIM,
+    Command::XAUTOCLAIM,
+    Command::XINFO,
+    Command::PFADD,
+    Command::PFCOUNT,
+    Command::PFMERGE,
+    Command::GEOADD,
+    Command::GEODIST,
+    Command::GEOPOS,
+    Command::GEOHASH,
+    Command::GEOSEARCH,
+    Command::GEOSEARCHSTORE,
+    Command::SUBSCRIBE,
+    Command::UNSUBSCRIBE,
+    Command::PSUBSCRIBE,
+    Command::PUNSUBSCRIBE,
+    Command::PUBLISH,
+    Command::PUBSUB,
+    Command::CONFIG,
+    Command::MULTI,
+    Command::EXEC,
+    Command::DISCARD,
+    Command::WATCH,
+    Command::UNWATCH,
+    Command::SELECT,
+    Command::MOVE,
+    Command::SWAPDB,
+    Command::FLUSHDB,
+    Command::FLUSHALL,
+    Command::AUTH,
+    Command::QUIT,
+    Command::HELLO,
+    Command::ACL,
+    Command::CLIENT,
+];
+
+// ACL categories, in the order that redis lists them
+pub const CATEGORIES: [&str; 21] = [
+    "keyspace",
+    "read",
+    "write",
+    "set",
+    "sortedset",
+    "list",
+    "hash",
+    "string",
+    "bitmap",
+    "hyperloglog",
+    "geo",
+    "stream",
+    "pubsub",
+    "admin",
+    "fast",
+    "slow",
+    "blocking",
+    "dangerous",
+    "connection",
+    "transaction",
+    "scripting",
+];
+
+// How a command uses a key, which ACL key patterns must allow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyAccess {
+    pub read: bool,
+    pub write: bool,
+}
+
+impl KeyAccess {
+    pub const READ: KeyAccess = KeyAccess { read: true, write: false };
+    pub const WRITE: KeyAccess = KeyAccess { read: false, write: true };
+    pub const READ_WRITE: KeyAccess = KeyAccess { read: true, write: true };
 }
 
 impl Command {
     pub fn from(str: &str) -> Option<Command> {
-        let mut command: Option<Command> = None;
-
-        if str.to_lowercase() == "echo" {
-            command = Some(Command::ECHO);
-        } else if str.to_lowercase() == "ping" {
-            command = Some(Command::PING);
-        } else if str.to_lowercase() == "get" {
-            command = Some(Command::GET);
-        } else if str.to_lowercase() == "set" {
-            command = Some(Command::SET);
+        let command = match str.to_lowercase().as_str() {
+            "echo" => Command::ECHO,
+            "ping" => Command::PING,
+            "get" => Command::GET,
+            "set" => Command::SET,
+            "xadd" => Command::XADD,
+            "xrange" => Command::XRANGE,
+            "xrevrange" => Command::XREVRANGE,
+            "xlen" => Command::XLEN,
+            "xdel" => Command::XDEL,
+            "xtrim" => Command::XTRIM,
+            "xread" => Command::XREAD,
+            "xgroup" => Command::XGROUP,
+            "xreadgroup" => Command::XREADGROUP,
+            "xack" => Command::XACK,
+            "xpending" => Command::XPENDING,
+            "xclaim" => Command::XCLAIM,
+            "xautoclaim" => Command::XAUTOCLAIM,
+            "xinfo" => Command::XINFO,
+            "pfadd" => Command::PFADD,
+            "pfcount" => Command::PFCOUNT,
+            "pfmerge" => Command::PFMERGE,
+            "geoadd" => Command::GEOADD,
+            "geodist" => Command::GEODIST,
+            "geopos" => Command::GEOPOS,
+            "geohash" => Command::GEOHASH,
+            "geosearch" => Command::GEOSEARCH,
+            "geosearchstore" => Command::GEOSEARCHSTORE,
+            "subscribe" => Command::SUBSCRIBE,
+            "unsubscribe" => Command::UNSUBSCRIBE,
+            "psubscribe" => Command::PSUBSCRIBE,
+            "punsubscribe" => Command::PUNSUBSCRIBE,
+            "publish" => Command::PUBLISH,
+            "pubsub" => Command::PUBSUB,
+            "config" => Command::CONFIG,
+            "multi" => Command::MULTI,
+            "exec" => Command::EXEC,
+            "discard" => Command::DISCARD,
+            "watch" => Command::WATCH,
+            "unwatch" => Command::UNWATCH,
+            "select" => Command::SELECT,
+            "move" => Command::MOVE,
+            "swapdb" => Command::SWAPDB,
+            "flushdb" => Command::FLUSHDB,
+            "flushall" => Command::FLUSHALL,
+            "auth" => Command::AUTH,
+            "quit" => Command::QUIT,
+            "hello" => Command::HELLO,
+            "acl" => Command::ACL,
+            "client" => Command::CLIENT,
+            _ => return None,
+        };
+
+        Some(command)
+    }
+
+    /// The lowercase name of the command, as used in error messages
+    pub fn name(&self) -> String {
+        format!("{:?}", self).to_lowercase()
+    }
+
+    /// Number of arguments including the command name, like redis. A negative arity is the
+    /// minimum number of arguments
+    pub fn arity(&self) -> i64 {
+        match self {
+            Command::PING => -1,
+            Command::ECHO => -2,
+            Command::GET => 2,
+            Command::SET => -3,
+            Command::XADD => -5,
+            Command::XRANGE => -4,
+            Command::XREVRANGE => -4,
+            Command::XLEN => 2,
+            Command::XDEL => -3,
+            Command::XTRIM => -4,
+            Command::XREAD => -4,
+            Command::XGROUP => -2,
+            Command::XREADGROUP => -7,
+            Command::XACK => -4,
+            Command::XPENDING => -3,
+            Command::XCLAIM => -6,
+            Command::XAUTOCLAIM => -6,
+            Command::XINFO => -2,
+            Command::PFADD => -2,
+            Command::PFCOUNT => -2,
+            Command::PFMERGE => -2,
+            Command::GEOADD => -5,
+            Command::GEODIST => -4,
+            Command::GEOPOS => -2,
+            Command::GEOHASH => -2,
+            Command::GEOSEARCH => -7,
+            Command::GEOSEARCHSTORE => -8,
+            Command::SUBSCRIBE => -2,
+            Command::UNSUBSCRIBE => -1,
+            Command::PSUBSCRIBE => -2,
+            Command::PUNSUBSCRIBE => -1,
+            Command::PUBLISH => 3,
+            Command::PUBSUB => -2,
+            Command::CONFIG => -2,
+            Command::MULTI => 1,
+            Command::EXEC => 1,
+            Command::DISCARD => 1,
+            Command::WATCH => -2,
+            Command::UNWATCH => 1,
+            Command::SELECT => 2,
+            Command::MOVE => 3,
+            Command::SWAPDB => 3,
+            Command::FLUSHDB => -1,
+            Command::FLUSHALL => -1,
+            Command::AUTH => -2,
+            Command::QUIT => -1,
+            Command::HELLO => -1,
+            Command::ACL => -2,
+            Command::CLIENT => -2,
         }
+    }
+
+    /// Whether the first argument is a subcommand, e.g. CONFIG GET
+    pub fn has_subcommands(&self) -> bool {
+        matches!(
+            self,
+            Command::XGROUP | Command::XINFO | Command::PUBSUB | Command::CONFIG | Command::ACL | Command::CLIENT
+        )
+    }
+
+    /// ACL categories of the command, following the redis command table
+    pub fn categories(&self) -> &'static [&'static str] {
+        match self {
+            Command::PING | Command::ECHO | Command::SELECT | Command::AUTH | Command::QUIT | Command::HELLO => {
+                &["fast", "connection"]
+            }
+            Command::GET => &["read", "string", "fast"],
+            Command::SET => &["write", "string", "slow"],
+            Command::XADD | Command::XDEL | Command::XACK | Command::XCLAIM | Command::XAUTOCLAIM => {
+                &["write", "stream", "fast"]
+            }
+            Command::XTRIM | Command::XGROUP => &["write", "stream", "slow"],
+            Command::XLEN => &["read", "stream", "fast"],
+            Command::XRANGE | Command::XREVRANGE | Command::XPENDING | Command::XINFO => &["read", "stream", "slow"],
+            Command::XREAD => &["read", "stream", "slow", "blocking"],
+            Command::XREADGROUP => &["write", "stream", "slow", "blocking"],
+            Command::PFADD => &["write", "hyperloglog", "fast"],
+            Command::PFCOUNT => &["read", "hyperloglog", "slow"],
+            Command::PFMERGE => &["write", "hyperloglog", "slow"],
+            Command::GEOADD | Command::GEOSEARCHSTORE => &["write", "geo", "slow"],
+            Command::GEODIST | Command::GEOPOS | Command::GEOHASH | Command::GEOSEARCH => &["read", "geo", "slow"],
+            Command::SUBSCRIBE
+            | Command::UNSUBSCRIBE
+            | Command::PSUBSCRIBE
+            | Command::PUNSUBSCRIBE
+            | Command::PUBSUB => &["pubsub", "slow"],
+            Command::PUBLISH => &["pubsub", "fast"],
+            Command::CONFIG | Command::ACL => &["admin", "slow", "dangerous"],
+            Command::CLIENT => &["admin", "slow", "dangerous", "connection"],
+            Command::MULTI | Command::DISCARD | Command::WATCH | Command::UNWATCH => &["fast", "transaction"],
+            Command::EXEC => &["slow", "transaction"],
+            Command::MOVE => &["keyspace", "write", "fast"],
+            Command::SWAPDB => &["keyspace", "write", "fast", "dangerous"],
+            Command::FLUSHDB | Command::FLUSHALL => &["keyspace", "write", "slow", "dangerous"],
+        }
+    }
+
+    /// Keys in the arguments, excluding the command name, and how the command uses them
+    pub fn key_args<'a>(&self, args: &'a [String]) -> Vec<(&'a str, KeyAccess)> {
+        let keys_with = |keys: &'a [String], access: KeyAccess| -> Vec<(&'a str, KeyAccess)> {
+            keys.iter().map(|key| (key.as_str(), access)).collect()
+        };
+        let first = |access: KeyAccess| keys_with(args.get(..1).unwrap_or_default(), access);
 
-        command
+        match self {
+            Command::GET
+            | Command::XRANGE
+            | Command::XREVRANGE
+            | Command::XLEN
+            | Command::XPENDING
+            | Command::GEODIST
+            | Command::GEOPOS
+            | Command::GEOHASH
+            | Command::GEOSEARCH => first(KeyAccess::READ),
+            Command::SET => first(KeyAccess::WRITE),
+            Command::XADD
+            | Command::XDEL
+            | Command::XTRIM
+            | Command::XACK
+            | Command::XCLAIM
+            | Command::XAUTOCLAIM
+            | Command::PFADD
+            | Command::GEOADD
+            | Command::MOVE => first(KeyAccess::READ_WRITE),
+            Command::PFCOUNT | Command::WATCH => keys_with(args, KeyAccess::READ),
+            Command::PFMERGE => {
+                let mut keys = first(KeyAccess::READ_WRITE);
+                keys.extend(keys_with(args.get(1..).unwrap_or_default(), KeyAccess::READ));
+                keys
+            }
+            Command::GEOSEARCHSTORE => {
+                let mut keys = first(KeyAccess::WRITE);
+                keys.extend(keys_with(args.get(1..2).unwrap_or_default(), KeyAccess::READ));
+                keys
+            }
+            // The key follows the subcommand, except for HELP
+            Command::XGROUP | Command::XINFO => {
+                let access = if *self == Command::XGROUP { KeyAccess::READ_WRITE } else { KeyAccess::READ };
+                keys_with(args.get(1..2).unwrap_or_default(), access)
+            }
+            // The first half of the arguments after STREAMS are keys, the rest are IDs. Options
+            // follow GROUP group consumer for XREADGROUP
+            Command::XREAD | Command::XREADGROUP => {
+                let (access, options) =
+                    if *self == Command::XREAD { (KeyAccess::READ, 0) } else { (KeyAccess::READ_WRITE, 3) };
+                match streams_option_index(args, options) {
+                    Some(index) => {
+                        let streams = &args[index + 1..];
+                        keys_with(&streams[..streams.len() / 2], access)
+                    }
+                    None => Vec::new(),
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether the number of arguments, excluding the command name, satisfies the arity
+    pub fn accepts_arg_count(&self, count: usize) -> bool {
+        let arity = self.arity();
+        let count = count as i64 + 1;
+        if arity < 0 {
+            count >= -arity
+        } else {
+            count == arity
+        }
     }
 }
 
+// Index of the STREAMS option of XREAD and XREADGROUP, skipping other options and their values
+// like the handlers do, so that a value that happens to be STREAMS is not taken for the option
+fn streams_option_index(args: &[String], mut index: usize) -> Option<usize> {
+    loop {
+        match args.get(index)?.to_uppercase().as_str() {
+            "COUNT" | "BLOCK" => index += 2,
+            "NOACK" => index += 1,
+            "STREAMS" => return Some(index),
+            _ => return None,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -62,4 +428,167 @@ mod tests {
         assert!(result.is_some());
         assert_eq!(result.unwrap(), Command::SET);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn return_stream_commands_case_insensitively() {
+        let input = ["XADD", "xrange", "XRevRange", "xlen", "xdel", "xtrim", "XREAD"];
+        let expected = [
+            Command::XADD,
+            Command::XRANGE,
+            Command::XREVRANGE,
+            Command::XLEN,
+            Command::XDEL,
+            Command::XTRIM,
+            Command::XREAD,
+        ];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Command::from(inp);
+            assert_eq!(result.unwrap(), expected[index]);
+        }
+    }
+
+    #[test]
+    fn return_stream_group_commands_case_insensitively() {
+        let input = ["xgroup", "XREADGROUP", "xack", "XPending", "xclaim", "xautoclaim", "XINFO"];
+        let expected = [
+            Command::XGROUP,
+            Command::XREADGROUP,
+            Command::XACK,
+            Command::XPENDING,
+            Command::XCLAIM,
+            Command::XAUTOCLAIM,
+            Command::XINFO,
+        ];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Command::from(inp);
+            assert_eq!(result.unwrap(), expected[index]);
+        }
+    }
+
+    #[test]
+    fn return_hyperloglog_commands_case_insensitively() {
+        let input = ["PFADD", "pfcount", "PfMerge"];
+        let expected = [Command::PFADD, Command::PFCOUNT, Command::PFMERGE];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Command::from(inp);
+            assert_eq!(result.unwrap(), expected[index]);
+        }
+    }
+
+    #[test]
+    fn return_geo_commands_case_insensitively() {
+        let input = ["GEOADD", "geodist", "GeoPos", "geohash", "GEOSEARCH", "geosearchstore"];
+        let expected = [
+            Command::GEOADD,
+            Command::GEODIST,
+            Command::GEOPOS,
+            Command::GEOHASH,
+            Command::GEOSEARCH,
+            Command::GEOSEARCHSTORE,
+        ];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Command::from(inp);
+            assert_eq!(result.unwrap(), expected[index]);
+        }
+    }
+
+    #[test]
+    fn return_pubsub_commands_case_insensitively() {
+        let input = ["SUBSCRIBE", "unsubscribe", "PSubscribe", "punsubscribe", "PUBLISH", "pubsub"];
+        let expected = [
+            Command::SUBSCRIBE,
+            Command::UNSUBSCRIBE,
+            Command::PSUBSCRIBE,
+            Command::PUNSUBSCRIBE,
+            Command::PUBLISH,
+            Command::PUBSUB,
+        ];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Command::from(inp);
+            assert_eq!(result.unwrap(), expected[index]);
+        }
+    }
+
+    #[test]
+    fn return_config_command() {
+        let result = Command::from("CONFIG");
+        assert!(result.is_some());
+        assert_eq!(result.unwrap(), Command::CONFIG);
+    }
+
+    #[test]
+    fn return_transaction_commands_case_insensitively() {
+        let input = ["MULTI", "exec", "Discard", "watch", "UNWATCH"];
+        let expected = [Command::MULTI, Command::EXEC, Command::DISCARD, Command::WATCH, Command::UNWATCH];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Command::from(inp);
+            assert_eq!(result.unwrap(), expected[index]);
+        }
+    }
+
+    #[test]
+    fn return_db_commands_case_insensitively() {
+        let input = ["SELECT", "move", "SwapDb", "flushdb", "FLUSHALL"];
+        let expected = [Command::SELECT, Command::MOVE, Command::SWAPDB, Command::FLUSHDB, Command::FLUSHALL];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Command::from(inp);
+            assert_eq!(result.unwrap(), expected[index]);
+        }
+    }
+
+    #[test]
+    fn return_connection_commands_case_insensitively() {
+        let input = ["AUTH", "quit", "Hello", "Acl", "client"];
+        let expected = [Command::AUTH, Command::QUIT, Command::HELLO, Command::ACL, Command::CLIENT];
+
+        for (index, inp) in input.iter().enumerate() {
+            let result = Command::from(inp);
+            assert_eq!(result.unwrap(), expected[index]);
+        }
+    }
+
+    #[test]
+    fn all_commands_can_be_parsed_from_their_names() {
+        for command in ALL_COMMANDS.iter() {
+            assert_eq!(Command::from(&command.name()).as_ref(), Some(command));
+            for category in command.categories() {
+                assert!(CATEGORIES.contains(category));
+            }
+        }
+    }
+
+    #[test]
+    fn key_args_returns_keys_with_their_access() {
+        let args: Vec<String> = ["COUNT", "1", "STREAMS", "a", "b", "0", "0"].iter().map(|arg| String::from(*arg)).collect();
+        assert_eq!(Command::XREAD.key_args(&args), vec![("a", KeyAccess::READ), ("b", KeyAccess::READ)]);
+
+        let args: Vec<String> = ["GROUP", "streams", "c", "COUNT", "1", "STREAMS", "a", ">"].iter().map(|arg| String::from(*arg)).collect();
+        assert_eq!(Command::XREADGROUP.key_args(&args), vec![("a", KeyAccess::READ_WRITE)]);
+
+        let args: Vec<String> = ["dest", "src", "FROMLONLAT"].iter().map(|arg| String::from(*arg)).collect();
+        assert_eq!(
+            Command::GEOSEARCHSTORE.key_args(&args),
+            vec![("dest", KeyAccess::WRITE), ("src", KeyAccess::READ)]
+        );
+        assert!(Command::GET.key_args(&[]).is_empty());
+        assert!(Command::PUBLISH.key_args(&args).is_empty());
+    }
+
+    #[test]
+    fn accepts_arg_count_follows_arity() {
+        assert!(Command::GET.accepts_arg_count(1));
+        assert!(!Command::GET.accepts_arg_count(2));
+        assert!(Command::SET.accepts_arg_count(2));
+        assert!(Command::SET.accepts_arg_count(4));
+        assert!(!Command::SET.accepts_arg_count(1));
+        assert!(Command::PING.accepts_arg_count(0));
+        assert_eq!(Command::GEOSEARCHSTORE.name(), "geosearchstore");
+    }
+}
@@ -0,0 +1,63 @@
+// Helpers to validate command arguments, returning the error replies that redis uses
+
+pub const SYNTAX_ERROR: &str = "ERR syntax error";
+pub const NOT_AN_INTEGER_ERROR: &str = "ERR value is not an integer or out of range";
+pub const NOT_A_FLOAT_ERROR: &str = "ERR value is not a valid float";
+
+pub fn wrong_number_of_arguments(command: &str) -> String {
+    format!("ERR wrong number of arguments for '{}' command", command.to_lowercase())
+}
+
+pub fn parse_integer_arg(arg: &str) -> Result<i64, String> {
+    arg.parse().map_err(|_| String::from(NOT_AN_INTEGER_ERROR))
+}
+
+// NaN is not a valid float
+pub fn parse_float_arg(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(num) if !num.is_nan() => Ok(num),
+        _ => Err(String::from(NOT_A_FLOAT_ERROR)),
+    }
+}
+
+// Negative values are clamped to 0
+pub fn parse_count_arg(arg: &str) -> Result<u64, String> {
+    parse_integer_arg(arg).map(|num| num.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_integer_arg_success() {
+        assert_eq!(parse_integer_arg("10"), Ok(10));
+        assert_eq!(parse_integer_arg("-10"), Ok(-10));
+    }
+
+    #[test]
+    fn parse_integer_arg_error() {
+        for input in ["", "1.5", "ten", "99999999999999999999"] {
+            assert_eq!(parse_integer_arg(input), Err(String::from(NOT_AN_INTEGER_ERROR)));
+        }
+    }
+
+    #[test]
+    fn parse_float_arg_success() {
+        assert_eq!(parse_float_arg("1.5"), Ok(1.5));
+        assert_eq!(parse_float_arg("-10"), Ok(-10.0));
+    }
+
+    #[test]
+    fn parse_float_arg_error() {
+        for input in ["", "ten", "nan"] {
+            assert_eq!(parse_float_arg(input), Err(String::from(NOT_A_FLOAT_ERROR)));
+        }
+    }
+
+    #[test]
+    fn parse_count_arg_clamps_negative_values() {
+        assert_eq!(parse_count_arg("-5"), Ok(0));
+        assert_eq!(parse_count_arg("5"), Ok(5));
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::Write;
+
+use crate::acl::acl::with_acl;
+use crate::config::config::{with_config, IMMUTABLE_PARAMETERS, PARAMETERS};
+use crate::parser::parser::RESPOutput;
+use crate::request_response::client_input::ClientInput;
+use crate::request_response::command_args::wrong_number_of_arguments;
+use crate::request_response::response_helper;
+use crate::util::glob::glob_match;
+
+impl ClientInput {
+    // config GET <parameter> [parameter ...]
+    // config SET <parameter> <value> [parameter value ...]
+    pub(crate) fn respond_config<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.config(args));
+    }
+
+    fn config(&self, args: &[String]) -> Result<RESPOutput, String> {
+        let subcommand = args.first().ok_or_else(|| wrong_number_of_arguments("config"))?.to_uppercase();
+        let arity_ok = match subcommand.as_str() {
+            "GET" => args.len() >= 2,
+            "SET" => args.len() >= 3 && args.len() % 2 == 1,
+            _ => return Err(format!("ERR unknown subcommand '{}'. Try CONFIG HELP.", args[0])),
+        };
+        if !arity_ok {
+            return Err(format!(
+                "ERR wrong number of arguments for 'config|{}' command",
+                subcommand.to_lowercase()
+            ));
+        }
+
+        match subcommand.as_str() {
+            "GET" => Ok(self.config_get(&args[1..])),
+            _ => self.config_set(&args[1..]),
+        }
+    }
+
+    // Parameters are matched case-insensitively against each glob pattern
+    fn config_get(&self, patterns: &[String]) -> RESPOutput {
+        let names = PARAMETERS.iter().filter(|name| {
+            patterns
+                .iter()
+                .any(|pattern| glob_match(&pattern.to_lowercase(), name))
+        });
+
+        with_config(|config| {
+            RESPOutput::Map(
+                names
+                    .map(|name| {
+                        let value = config.get(name).unwrap_or_default();
+                        (RESPOutput::BulkString(String::from(*name)), RESPOutput::BulkString(value))
+                    })
+                    .collect(),
+            )
+        })
+    }
+
+    // Either every parameter is set, or none of them if any is invalid
+    fn config_set(&self, pairs: &[String]) -> Result<RESPOutput, String> {
+        let requirepass = with_config(|config| {
+            let mut updated = config.clone();
+            for pair in pairs.chunks(2) {
+                let (name, value) = (&pair[0], &pair[1]);
+                if IMMUTABLE_PARAMETERS.contains(&name.to_lowercase().as_str()) {
+                    return Err(format!(
+                        "ERR CONFIG SET failed (possibly related to argument '{}') - can't set immutable config",
+                        name.to_lowercase()
+                    ));
+                }
+                updated.set(name, value).map_err(|e| {
+                    if config.get(name).is_none() {
+                        format!("ERR Unknown option or number of arguments for CONFIG SET - '{}'", name)
+                    } else {
+                        format!(
+                            "ERR CONFIG SET failed (possibly related to argument '{}') - {}",
+                            name.to_lowercase(),
+                            e
+                        )
+                    }
+                })?;
+            }
+
+            let requirepass = (updated.requirepass != config.requirepass).then(|| updated.requirepass.clone());
+            *config = updated;
+            Ok(requirepass)
+        })?;
+
+        // requirepass is the password of the default user
+        if let Some(requirepass) = requirepass {
+            with_acl(|acl| acl.set_default_password(&requirepass));
+        }
+        Ok(RESPOutput::SimpleString(String::from("OK")))
+    }
+}
@@ -0,0 +1,126 @@
+use std::io::Write;
+
+use crate::acl::acl::{with_acl, Denial, DEFAULT_USER};
+use crate::parser::parser::RESPOutput;
+use crate::request_response::client_input::ClientInput;
+use crate::request_response::command::Command;
+use crate::request_response::command_args::SYNTAX_ERROR;
+use crate::request_response::response_helper::{self, Protocol};
+
+pub const NOAUTH_ERROR: &str = "NOAUTH Authentication required.";
+const HELLO_NOAUTH_ERROR: &str = "NOAUTH HELLO must be called with the client already authenticated, otherwise the HELLO <proto> AUTH <user> <pass> option can be used to authenticate the client and select the RESP protocol version at the same time";
+
+impl ClientInput {
+    /// Connections are authenticated by AUTH, or while the default user needs no password
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticated || with_acl(|acl| acl.is_default_user_open())
+    }
+
+    /// Set once QUIT is replied to, so that the connection can be closed
+    pub fn is_closing(&self) -> bool {
+        self.closing
+    }
+
+    // Commands that can be used before authenticating
+    pub(crate) fn is_allowed_without_auth(command: &Command) -> bool {
+        matches!(command, Command::AUTH | Command::QUIT | Command::HELLO)
+    }
+
+    // auth [username] <password>
+    pub(crate) fn respond_auth<T: Write>(&mut self, stream: &mut T, args: &[String]) {
+        let result = self.auth(args);
+        response_helper::send_result_response(stream, result);
+    }
+
+    // quit
+    pub(crate) fn respond_quit<T: Write>(&mut self, stream: &mut T) {
+        self.closing = true;
+        response_helper::send_simple_string_response(stream, "OK");
+    }
+
+    // hello [protover [AUTH username password] [SETNAME clientname]]
+    pub(crate) fn respond_hello<T: Write>(&mut self, stream: &mut T, args: &[String]) {
+        let result = self.hello(args);
+        // The reply already uses the protocol that was switched to
+        response_helper::with_protocol(self.protocol, || response_helper::send_result_response(stream, result));
+    }
+
+    fn hello(&mut self, args: &[String]) -> Result<RESPOutput, String> {
+        let protocol = match args.first() {
+            Some(version) => {
+                let version: i64 = version
+                    .parse()
+                    .map_err(|_| String::from("ERR Protocol version is not an integer or out of range"))?;
+                Protocol::from_version(version).ok_or_else(|| String::from("NOPROTO unsupported protocol version"))?
+            }
+            None => self.protocol,
+        };
+
+        let mut credentials: Option<&[String]> = None;
+        let mut name: Option<&String> = None;
+        let mut options = args.iter().enumerate().skip(1);
+        while let Some((index, option)) = options.next() {
+            match option.to_uppercase().as_str() {
+                "AUTH" if index + 2 < args.len() => {
+                    credentials = Some(&args[index + 1..index + 3]);
+                    options.nth(1);
+                }
+                "SETNAME" if index + 1 < args.len() => {
+                    name = Some(&args[index + 1]);
+                    options.next();
+                }
+                _ => return Err(format!("ERR Syntax error in HELLO option '{}'", option)),
+            }
+        }
+
+        match credentials {
+            Some(credentials) => {
+                self.auth(credentials)?;
+            }
+            None if !self.is_authenticated() => return Err(String::from(HELLO_NOAUTH_ERROR)),
+            None => {}
+        }
+        if let Some(name) = name {
+            self.client_setname(name)?;
+        }
+        self.protocol = protocol;
+
+        let field = |name: &str| RESPOutput::BulkString(String::from(name));
+        Ok(RESPOutput::Map(vec![
+            (field("server"), field("redis")),
+            (field("version"), field(env!("CARGO_PKG_VERSION"))),
+            (field("proto"), RESPOutput::Integer(protocol.version())),
+            (field("id"), RESPOutput::Integer(self.id as i64)),
+            (field("mode"), field("standalone")),
+            (field("role"), field("master")),
+            (field("modules"), RESPOutput::Array(Vec::new())),
+        ]))
+    }
+
+    fn auth(&mut self, args: &[String]) -> Result<RESPOutput, String> {
+        let (username, password) = match args {
+            [password] => {
+                if with_acl(|acl| acl.get_user(DEFAULT_USER).map_or(false, |user| user.is_nopass())) {
+                    return Err(String::from(
+                        "ERR AUTH <password> called without any password configured for the default user. Are you sure your configuration is correct?",
+                    ));
+                }
+                (DEFAULT_USER, password)
+            }
+            [username, password] => (username.as_str(), password),
+            _ => return Err(String::from(SYNTAX_ERROR)),
+        };
+
+        let context = self.acl_log_context();
+        with_acl(|acl| {
+            if !acl.authenticate(username, password) {
+                acl.add_log_entry(&Denial::Auth, context, "AUTH", username);
+                return Err(Denial::Auth.error(username, &Command::AUTH));
+            }
+
+            self.user = String::from(username);
+            self.authenticated = true;
+            Ok(RESPOutput::SimpleString(String::from("OK")))
+        })
+    }
+}
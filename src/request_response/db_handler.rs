@@ -0,0 +1,126 @@
+use std::io::Write;
+
+use crate::parser::parser::RESPOutput;
+use crate::request_response::client_input::ClientInput;
+use crate::request_response::command_args::{parse_integer_arg, SYNTAX_ERROR};
+use crate::request_response::response_helper;
+use crate::store::redis::{RedisStore, Store};
+use crate::store::redis_stream::notify_stream_added;
+
+const OUT_OF_RANGE_ERROR: &str = "ERR DB index is out of range";
+
+impl ClientInput {
+    // select <index>
+    pub(crate) fn respond_select<T: Write>(&mut self, stream: &mut T, args: &[String]) {
+        let result = self.select(args);
+        response_helper::send_result_response(stream, result);
+    }
+
+    // move <key> <db>
+    pub(crate) fn respond_move<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.move_key(args));
+    }
+
+    // swapdb <index1> <index2>
+    pub(crate) fn respond_swapdb<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.swapdb(args));
+    }
+
+    // flushdb [ASYNC | SYNC]
+    pub(crate) fn respond_flushdb<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.flushdb(args));
+    }
+
+    // flushall [ASYNC | SYNC]
+    pub(crate) fn respond_flushall<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.flushall(args));
+    }
+
+    fn select(&mut self, args: &[String]) -> Result<RESPOutput, String> {
+        self.db = parse_db_index(&args[0])?;
+        Ok(RESPOutput::SimpleString(String::from("OK")))
+    }
+
+    fn move_key(&self, args: &[String]) -> Result<RESPOutput, String> {
+        let key = &args[0];
+        let db = parse_db_index(&args[1])?;
+        if db == self.db {
+            return Err(String::from("ERR source and destination objects are the same"));
+        }
+
+        for store in [self.store(), RedisStore::get_db(db)] {
+            if store.is_key_expired(key) {
+                store.delete_expired(key);
+            }
+        }
+        let moved = RedisStore::move_key(key, self.db, db);
+        Ok(RESPOutput::Integer(moved as i64))
+    }
+
+    fn swapdb(&self, args: &[String]) -> Result<RESPOutput, String> {
+        let first = parse_integer_arg(&args[0]).map_err(|_| String::from("ERR invalid first DB index"))?;
+        let second = parse_integer_arg(&args[1]).map_err(|_| String::from("ERR invalid second DB index"))?;
+        let first = db_index_in_range(first)?;
+        let second = db_index_in_range(second)?;
+
+        if first != second {
+            RedisStore::swap_databases(first, second);
+            // Clients blocked on streams of either database may now have entries to read
+            notify_stream_added();
+        }
+        Ok(RESPOutput::SimpleString(String::from("OK")))
+    }
+
+    fn flushdb(&self, args: &[String]) -> Result<RESPOutput, String> {
+        let asynchronously = parse_flush_mode(args)?;
+        self.store().flush(asynchronously);
+        Ok(RESPOutput::SimpleString(String::from("OK")))
+    }
+
+    fn flushall(&self, args: &[String]) -> Result<RESPOutput, String> {
+        let asynchronously = parse_flush_mode(args)?;
+        for index in 0..RedisStore::database_count() {
+            RedisStore::get_db(index).flush(asynchronously);
+        }
+        Ok(RESPOutput::SimpleString(String::from("OK")))
+    }
+}
+
+fn parse_db_index(arg: &str) -> Result<usize, String> {
+    db_index_in_range(parse_integer_arg(arg)?)
+}
+
+fn db_index_in_range(index: i64) -> Result<usize, String> {
+    if index < 0 || index as usize >= RedisStore::database_count() {
+        return Err(String::from(OUT_OF_RANGE_ERROR));
+    }
+    Ok(index as usize)
+}
+
+// Returns whether keys are freed asynchronously
+fn parse_flush_mode(args: &[String]) -> Result<bool, String> {
+    match args {
+        [] => Ok(false),
+        [mode] if mode.eq_ignore_ascii_case("sync") => Ok(false),
+        [mode] if mode.eq_ignore_ascii_case("async") => Ok(true),
+        _ => Err(String::from(SYNTAX_ERROR)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| String::from(*arg)).collect()
+    }
+
+    #[test]
+    fn parse_flush_mode_accepts_sync_and_async() {
+        assert_eq!(parse_flush_mode(&to_args(&[])), Ok(false));
+        assert_eq!(parse_flush_mode(&to_args(&["SYNC"])), Ok(false));
+        assert_eq!(parse_flush_mode(&to_args(&["async"])), Ok(true));
+        assert_eq!(parse_flush_mode(&to_args(&["now"])), Err(String::from(SYNTAX_ERROR)));
+        assert_eq!(parse_flush_mode(&to_args(&["sync", "async"])), Err(String::from(SYNTAX_ERROR)));
+    }
+}
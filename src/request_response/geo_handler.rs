@@ -0,0 +1,547 @@
+use std::io::Write;
+
+use crate::parser::parser::RESPOutput;
+use crate::pubsub::keyspace_events::NOTIFY_ZSET;
+use crate::request_response::client_input::ClientInput;
+use crate::request_response::command_args::{
+    parse_float_arg, parse_integer_arg, wrong_number_of_arguments, SYNTAX_ERROR,
+};
+use crate::request_response::response_helper;
+use crate::store::redis::Store;
+use crate::store::redis_geo::{
+    areas_by_shape, decode_score, distance, distance_if_in_shape, encode, geohash_string, is_valid_coordinate,
+    GeoShape, GEO_STEP_MAX,
+};
+use crate::store::redis_sorted_set::{total_cmp, SortedSet};
+
+const UNSUPPORTED_UNIT_ERROR: &str = "ERR unsupported unit provided. please use M, KM, FT, MI";
+
+#[derive(Debug, PartialEq)]
+enum GeoSearchFrom {
+    Member(String),
+    LonLat(f64, f64),
+}
+
+#[derive(Debug, PartialEq)]
+enum GeoSort {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, PartialEq)]
+struct GeoSearchArgs {
+    from: GeoSearchFrom,
+    // Sizes are in meters
+    shape: GeoShape,
+    // Meters per unit of the shape, which is also the unit of distances in replies
+    unit: f64,
+    sort: Option<GeoSort>,
+    count: Option<usize>,
+    // Stops searching once `count` members are found, instead of returning the nearest ones
+    any: bool,
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+    // GEOSEARCHSTORE only, stores distances instead of geohashes as scores
+    store_dist: bool,
+}
+
+struct GeoPoint {
+    member: String,
+    score: u64,
+    // In meters
+    distance: f64,
+    longitude: f64,
+    latitude: f64,
+}
+
+impl ClientInput {
+    // geoadd <key> [NX|XX] [CH] longitude latitude member [longitude latitude member ...]
+    pub(crate) fn respond_geoadd<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.geoadd(args));
+    }
+
+    // geodist <key> <member1> <member2> [M|KM|FT|MI]
+    pub(crate) fn respond_geodist<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.geodist(args));
+    }
+
+    // geopos <key> [member ...]
+    pub(crate) fn respond_geopos<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.geopos(args));
+    }
+
+    // geohash <key> [member ...]
+    pub(crate) fn respond_geohash<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.geohash(args));
+    }
+
+    // geosearch <key> FROMMEMBER member|FROMLONLAT longitude latitude
+    //     BYRADIUS radius M|KM|FT|MI|BYBOX width height M|KM|FT|MI
+    //     [ASC|DESC] [COUNT count [ANY]] [WITHCOORD] [WITHDIST] [WITHHASH]
+    pub(crate) fn respond_geosearch<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.geosearch(args));
+    }
+
+    // geosearchstore <destination> <source> FROMMEMBER member|FROMLONLAT longitude latitude
+    //     BYRADIUS radius M|KM|FT|MI|BYBOX width height M|KM|FT|MI
+    //     [ASC|DESC] [COUNT count [ANY]] [STOREDIST]
+    pub(crate) fn respond_geosearchstore<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.geosearchstore(args));
+    }
+
+    fn geoadd(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() < 4 {
+            return Err(wrong_number_of_arguments("geoadd"));
+        }
+
+        let key = &args[0];
+        let mut nx = false;
+        let mut xx = false;
+        let mut ch = false;
+        let mut index = 1;
+        while index < args.len() {
+            match args[index].to_uppercase().as_str() {
+                "NX" => nx = true,
+                "XX" => xx = true,
+                "CH" => ch = true,
+                _ => break,
+            }
+            index += 1;
+        }
+        let positions = &args[index..];
+        if nx && xx {
+            return Err(String::from("ERR XX and NX options at the same time are not compatible"));
+        }
+        if positions.is_empty() || positions.len() % 3 != 0 {
+            return Err(String::from(SYNTAX_ERROR));
+        }
+
+        // Every position is validated before any member is added
+        let mut members: Vec<(&str, f64)> = Vec::new();
+        for position in positions.chunks(3) {
+            let longitude = parse_float_arg(&position[0])?;
+            let latitude = parse_float_arg(&position[1])?;
+            if !is_valid_coordinate(longitude, latitude) {
+                return Err(format!(
+                    "ERR invalid longitude,latitude pair {:.6},{:.6}",
+                    longitude, latitude
+                ));
+            }
+            let score = encode(longitude, latitude, GEO_STEP_MAX).score();
+            members.push((&position[2], score as f64));
+        }
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let sorted_set = match store.get_sorted_set_mut(key, !xx).map_err(|e| e.message())? {
+            Some(sorted_set) => sorted_set,
+            None => return Ok(RESPOutput::Integer(0)),
+        };
+
+        let mut changed = 0;
+        let mut modified = false;
+        for (member, score) in members {
+            let previous = sorted_set.score(member);
+            if (nx && previous.is_some()) || (xx && previous.is_none()) {
+                continue;
+            }
+            sorted_set.insert(member, score);
+            match previous {
+                None => changed += 1,
+                Some(previous) if ch && previous != score => changed += 1,
+                Some(_) => {}
+            }
+            modified |= previous != Some(score);
+        }
+
+        // Like redis, GEOADD is published as the ZADD that it is implemented with
+        if modified {
+            store.notify_keyspace_event(NOTIFY_ZSET, "zadd", key);
+        }
+        Ok(RESPOutput::Integer(changed))
+    }
+
+    fn geodist(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() < 3 {
+            return Err(wrong_number_of_arguments("geodist"));
+        }
+        if args.len() > 4 {
+            return Err(String::from(SYNTAX_ERROR));
+        }
+
+        let key = &args[0];
+        let unit = match args.get(3) {
+            Some(unit) => parse_unit(unit)?,
+            None => 1.0,
+        };
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let sorted_set = match store.get_sorted_set(key).map_err(|e| e.message())? {
+            Some(sorted_set) => sorted_set,
+            None => return Ok(RESPOutput::Null),
+        };
+        match (member_position(sorted_set, &args[1]), member_position(sorted_set, &args[2])) {
+            (Some((lon1, lat1)), Some((lon2, lat2))) => {
+                let distance = distance(lon1, lat1, lon2, lat2);
+                Ok(RESPOutput::BulkString(format_distance(distance, unit)))
+            }
+            _ => Ok(RESPOutput::Null),
+        }
+    }
+
+    fn geopos(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.is_empty() {
+            return Err(wrong_number_of_arguments("geopos"));
+        }
+
+        let key = &args[0];
+        self.expire_if_needed(key);
+        let store = self.store();
+        let sorted_set = store.get_sorted_set(key).map_err(|e| e.message())?;
+
+        let positions = args[1..]
+            .iter()
+            .map(|member| match sorted_set.and_then(|sorted_set| member_position(sorted_set, member)) {
+                Some((longitude, latitude)) => format_coordinates(longitude, latitude),
+                None => RESPOutput::NullArray,
+            })
+            .collect();
+        Ok(RESPOutput::Array(positions))
+    }
+
+    fn geohash(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.is_empty() {
+            return Err(wrong_number_of_arguments("geohash"));
+        }
+
+        let key = &args[0];
+        self.expire_if_needed(key);
+        let store = self.store();
+        let sorted_set = store.get_sorted_set(key).map_err(|e| e.message())?;
+
+        let hashes = args[1..]
+            .iter()
+            .map(|member| match sorted_set.and_then(|sorted_set| sorted_set.score(member)) {
+                Some(score) => RESPOutput::BulkString(geohash_string(score as u64)),
+                None => RESPOutput::Null,
+            })
+            .collect();
+        Ok(RESPOutput::Array(hashes))
+    }
+
+    fn geosearch(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() < 6 {
+            return Err(wrong_number_of_arguments("geosearch"));
+        }
+
+        let key = &args[0];
+        let search_args = parse_geo_search_args(&args[1..], false)?;
+        let points = self.search_points(key, &search_args)?;
+
+        let with_any = search_args.with_dist || search_args.with_hash || search_args.with_coord;
+        let reply = points
+            .into_iter()
+            .map(|point| {
+                if !with_any {
+                    return RESPOutput::BulkString(point.member);
+                }
+                let mut item = vec![RESPOutput::BulkString(point.member)];
+                if search_args.with_dist {
+                    item.push(RESPOutput::BulkString(format_distance(point.distance, search_args.unit)));
+                }
+                if search_args.with_hash {
+                    item.push(RESPOutput::Integer(point.score as i64));
+                }
+                if search_args.with_coord {
+                    item.push(format_coordinates(point.longitude, point.latitude));
+                }
+                RESPOutput::Array(item)
+            })
+            .collect();
+        Ok(RESPOutput::Array(reply))
+    }
+
+    fn geosearchstore(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() < 7 {
+            return Err(wrong_number_of_arguments("geosearchstore"));
+        }
+
+        let dest_key = &args[0];
+        let key = &args[1];
+        let search_args = parse_geo_search_args(&args[2..], true)?;
+        let points = self.search_points(key, &search_args)?;
+
+        let store = self.store();
+        if points.is_empty() {
+            store.delete(vec![dest_key]);
+            return Ok(RESPOutput::Integer(0));
+        }
+
+        let mut sorted_set = SortedSet::new();
+        for point in points.iter() {
+            let score = if search_args.store_dist {
+                point.distance / search_args.unit
+            } else {
+                point.score as f64
+            };
+            sorted_set.insert(&point.member, score);
+        }
+        store.set_sorted_set(dest_key, sorted_set);
+        store.notify_keyspace_event(NOTIFY_ZSET, "geosearchstore", dest_key);
+
+        Ok(RESPOutput::Integer(points.len() as i64))
+    }
+
+    // Returns the members within the shape, sorted and limited as requested
+    fn search_points(&self, key: &str, search_args: &GeoSearchArgs) -> Result<Vec<GeoPoint>, String> {
+        self.expire_if_needed(key);
+        let store = self.store();
+        let sorted_set = store.get_sorted_set(key).map_err(|e| e.message())?;
+
+        let center = match &search_args.from {
+            GeoSearchFrom::LonLat(longitude, latitude) => (*longitude, *latitude),
+            GeoSearchFrom::Member(member) => sorted_set
+                .and_then(|sorted_set| member_position(sorted_set, member))
+                .ok_or_else(|| String::from("ERR could not decode requested zset member"))?,
+        };
+        let sorted_set = match sorted_set {
+            Some(sorted_set) => sorted_set,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut points: Vec<GeoPoint> = Vec::new();
+        'areas: for area in areas_by_shape(center, &search_args.shape) {
+            let (min, max) = area.score_range();
+            for (member, score) in sorted_set.range_by_score(min as f64, max as f64) {
+                let score = score as u64;
+                let (longitude, latitude) = decode_score(score);
+                if let Some(distance) = distance_if_in_shape(center, &search_args.shape, (longitude, latitude)) {
+                    points.push(GeoPoint {
+                        member: String::from(member),
+                        score,
+                        distance,
+                        longitude,
+                        latitude,
+                    });
+                    if search_args.any && Some(points.len()) == search_args.count {
+                        break 'areas;
+                    }
+                }
+            }
+        }
+
+        match search_args.sort {
+            Some(GeoSort::Asc) => points.sort_by(|a, b| total_cmp(a.distance, b.distance)),
+            Some(GeoSort::Desc) => points.sort_by(|a, b| total_cmp(b.distance, a.distance)),
+            None => {}
+        }
+        if let Some(count) = search_args.count {
+            points.truncate(count);
+        }
+        Ok(points)
+    }
+}
+
+// Parses the arguments after the key(s) of GEOSEARCH and GEOSEARCHSTORE
+fn parse_geo_search_args(args: &[String], store: bool) -> Result<GeoSearchArgs, String> {
+    let mut from: Option<GeoSearchFrom> = None;
+    let mut shape: Option<(GeoShape, f64)> = None;
+    let mut sort: Option<GeoSort> = None;
+    let mut count: Option<usize> = None;
+    let mut any = false;
+    let mut with_coord = false;
+    let mut with_dist = false;
+    let mut with_hash = false;
+    let mut store_dist = false;
+    let mut index = 0;
+
+    let from_error = "ERR exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH";
+    let by_error = "ERR exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH";
+
+    while index < args.len() {
+        let remaining = args.len() - index - 1;
+        match args[index].to_uppercase().as_str() {
+            "FROMMEMBER" if remaining >= 1 => {
+                if from.is_some() {
+                    return Err(String::from(from_error));
+                }
+                from = Some(GeoSearchFrom::Member(args[index + 1].clone()));
+                index += 1;
+            }
+            "FROMLONLAT" if remaining >= 2 => {
+                if from.is_some() {
+                    return Err(String::from(from_error));
+                }
+                let longitude = parse_float_arg(&args[index + 1])?;
+                let latitude = parse_float_arg(&args[index + 2])?;
+                if !is_valid_coordinate(longitude, latitude) {
+                    return Err(format!(
+                        "ERR invalid longitude,latitude pair {:.6},{:.6}",
+                        longitude, latitude
+                    ));
+                }
+                from = Some(GeoSearchFrom::LonLat(longitude, latitude));
+                index += 2;
+            }
+            "BYRADIUS" if remaining >= 2 => {
+                if shape.is_some() {
+                    return Err(String::from(by_error));
+                }
+                let radius = parse_float_arg(&args[index + 1])?;
+                if radius < 0.0 {
+                    return Err(String::from("ERR radius cannot be negative"));
+                }
+                let unit = parse_unit(&args[index + 2])?;
+                shape = Some((GeoShape::Radius(radius * unit), unit));
+                index += 2;
+            }
+            "BYBOX" if remaining >= 3 => {
+                if shape.is_some() {
+                    return Err(String::from(by_error));
+                }
+                let width = parse_float_arg(&args[index + 1])?;
+                let height = parse_float_arg(&args[index + 2])?;
+                if width < 0.0 || height < 0.0 {
+                    return Err(String::from("ERR height or width cannot be negative"));
+                }
+                let unit = parse_unit(&args[index + 3])?;
+                shape = Some((GeoShape::Box(width * unit, height * unit), unit));
+                index += 3;
+            }
+            "ASC" => sort = Some(GeoSort::Asc),
+            "DESC" => sort = Some(GeoSort::Desc),
+            "COUNT" if remaining >= 1 => {
+                let value = parse_integer_arg(&args[index + 1])?;
+                if value <= 0 {
+                    return Err(String::from("ERR COUNT must be > 0"));
+                }
+                count = Some(value as usize);
+                index += 1;
+            }
+            "ANY" => any = true,
+            "WITHCOORD" if !store => with_coord = true,
+            "WITHDIST" if !store => with_dist = true,
+            "WITHHASH" if !store => with_hash = true,
+            "STOREDIST" if store => store_dist = true,
+            _ => return Err(String::from(SYNTAX_ERROR)),
+        }
+        index += 1;
+    }
+
+    let from = from.ok_or_else(|| String::from(from_error))?;
+    let (shape, unit) = shape.ok_or_else(|| String::from(by_error))?;
+    if any && count.is_none() {
+        return Err(String::from("ERR the ANY argument requires COUNT argument"));
+    }
+    // The nearest members are returned when only COUNT is given
+    if count.is_some() && !any && sort.is_none() {
+        sort = Some(GeoSort::Asc);
+    }
+
+    Ok(GeoSearchArgs {
+        from,
+        shape,
+        unit,
+        sort,
+        count,
+        any,
+        with_coord,
+        with_dist,
+        with_hash,
+        store_dist,
+    })
+}
+
+// Returns the number of meters per unit
+fn parse_unit(arg: &str) -> Result<f64, String> {
+    match arg.to_lowercase().as_str() {
+        "m" => Ok(1.0),
+        "km" => Ok(1000.0),
+        "ft" => Ok(0.3048),
+        "mi" => Ok(1609.34),
+        _ => Err(String::from(UNSUPPORTED_UNIT_ERROR)),
+    }
+}
+
+fn member_position(sorted_set: &SortedSet, member: &str) -> Option<(f64, f64)> {
+    sorted_set.score(member).map(|score| decode_score(score as u64))
+}
+
+fn format_distance(meters: f64, unit: f64) -> String {
+    format!("{:.4}", meters / unit)
+}
+
+fn format_coordinates(longitude: f64, latitude: f64) -> RESPOutput {
+    RESPOutput::Array(vec![
+        RESPOutput::BulkString(format_coordinate(longitude)),
+        RESPOutput::BulkString(format_coordinate(latitude)),
+    ])
+}
+
+// Formats with 17 decimal places like the human readable long doubles of redis, without trailing zeros
+fn format_coordinate(value: f64) -> String {
+    let formatted = format!("{:.17}", value);
+    String::from(formatted.trim_end_matches('0').trim_end_matches('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| String::from(*arg)).collect()
+    }
+
+    #[test]
+    fn format_coordinate_uses_17_decimal_places() {
+        assert_eq!(format_coordinate(13.361389338970184), "13.36138933897018433");
+        assert_eq!(format_coordinate(-0.5), "-0.5");
+        assert_eq!(format_coordinate(0.0), "0");
+        assert_eq!(format_coordinate(180.0), "180");
+    }
+
+    #[test]
+    fn parse_geo_search_args_success() {
+        let args = to_args(&["FROMLONLAT", "15", "37", "BYBOX", "400", "300", "km", "COUNT", "2", "WITHDIST"]);
+        assert_eq!(
+            parse_geo_search_args(&args, false),
+            Ok(GeoSearchArgs {
+                from: GeoSearchFrom::LonLat(15.0, 37.0),
+                shape: GeoShape::Box(400000.0, 300000.0),
+                unit: 1000.0,
+                sort: Some(GeoSort::Asc),
+                count: Some(2),
+                any: false,
+                with_coord: false,
+                with_dist: true,
+                with_hash: false,
+                store_dist: false,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_geo_search_args_error() {
+        let input = [
+            (vec!["BYRADIUS", "10", "m"], false),
+            (vec!["FROMMEMBER", "a"], false),
+            (vec!["FROMMEMBER", "a", "BYRADIUS", "10", "m", "ANY"], false),
+            (vec!["FROMMEMBER", "a", "BYRADIUS", "10", "yd"], false),
+            (vec!["FROMMEMBER", "a", "BYRADIUS", "10", "m", "WITHDIST"], true),
+        ];
+        let expected = [
+            "ERR exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH",
+            "ERR exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH",
+            "ERR the ANY argument requires COUNT argument",
+            UNSUPPORTED_UNIT_ERROR,
+            SYNTAX_ERROR,
+        ];
+
+        for (index, (args, store)) in input.iter().enumerate() {
+            let result = parse_geo_search_args(&to_args(args), *store);
+            assert_eq!(result, Err(String::from(expected[index])));
+        }
+    }
+}
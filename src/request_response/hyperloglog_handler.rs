@@ -0,0 +1,139 @@
+use std::io::Write;
+
+use crate::parser::parser::RESPOutput;
+use crate::pubsub::keyspace_events::NOTIFY_STRING;
+use crate::request_response::client_input::ClientInput;
+use crate::request_response::command_args::wrong_number_of_arguments;
+use crate::request_response::response_helper;
+use crate::store::redis::Store;
+use crate::store::redis_hyperloglog::{
+    count_registers, merge_registers, HllError, HyperLogLog, HLL_REGISTERS, HLL_SPARSE_MAX_BYTES,
+};
+
+impl ClientInput {
+    // pfadd <key> [element ...]
+    pub(crate) fn respond_pfadd<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.pfadd(args));
+    }
+
+    // pfcount <key> [key ...]
+    pub(crate) fn respond_pfcount<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.pfcount(args));
+    }
+
+    // pfmerge <destkey> [sourcekey ...]
+    pub(crate) fn respond_pfmerge<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.pfmerge(args));
+    }
+
+    fn pfadd(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.is_empty() {
+            return Err(wrong_number_of_arguments("pfadd"));
+        }
+
+        let key = &args[0];
+        let elements: Vec<&str> = args[1..].iter().map(|element| element.as_str()).collect();
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let changed = match store.get_string_mut(key).map_err(|e| e.message())? {
+            Some(value) => {
+                let mut hll = HyperLogLog::parse(value).map_err(hll_error)?;
+                let changed = hll.add(&elements, HLL_SPARSE_MAX_BYTES).map_err(hll_error)?;
+                if changed {
+                    *value = hll.encode();
+                }
+                changed
+            }
+            // Creating the key counts as a change even without elements
+            None => {
+                let mut hll = HyperLogLog::new();
+                hll.add(&elements, HLL_SPARSE_MAX_BYTES).map_err(hll_error)?;
+                store.set(key, &hll.encode(), &None);
+                true
+            }
+        };
+        if changed {
+            store.notify_keyspace_event(NOTIFY_STRING, "pfadd", key);
+        }
+
+        Ok(RESPOutput::Integer(changed as i64))
+    }
+
+    fn pfcount(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.is_empty() {
+            return Err(wrong_number_of_arguments("pfcount"));
+        }
+
+        let store = self.store();
+        // The cardinality of a single key is cached in the value
+        if args.len() == 1 {
+            let key = &args[0];
+            self.expire_if_needed(key);
+            let value = match store.get_string_mut(key).map_err(|e| e.message())? {
+                Some(value) => value,
+                None => return Ok(RESPOutput::Integer(0)),
+            };
+
+            let mut hll = HyperLogLog::parse(value).map_err(hll_error)?;
+            let cardinality = hll.count().map_err(hll_error)?;
+            *value = hll.encode();
+            return Ok(RESPOutput::Integer(cardinality as i64));
+        }
+
+        let mut registers = vec![0; HLL_REGISTERS];
+        for key in args {
+            self.expire_if_needed(key);
+            if let Some(hll) = self.get_hll(key)? {
+                merge_registers(&mut registers, &hll.registers().map_err(hll_error)?);
+            }
+        }
+        Ok(RESPOutput::Integer(count_registers(&registers) as i64))
+    }
+
+    fn pfmerge(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.is_empty() {
+            return Err(wrong_number_of_arguments("pfmerge"));
+        }
+
+        // The destination is part of the union if it exists
+        let mut registers = vec![0; HLL_REGISTERS];
+        let mut all_sparse = true;
+        for key in args {
+            self.expire_if_needed(key);
+            if let Some(hll) = self.get_hll(key)? {
+                merge_registers(&mut registers, &hll.registers().map_err(hll_error)?);
+                all_sparse &= hll.is_sparse();
+            }
+        }
+
+        // The result is only kept sparse if every input is sparse
+        let sparse_max_bytes = if all_sparse { HLL_SPARSE_MAX_BYTES } else { 0 };
+        let merged = HyperLogLog::from_registers(&registers, sparse_max_bytes).encode();
+
+        let dest_key = &args[0];
+        let store = self.store();
+        match store.get_string_mut(dest_key).map_err(|e| e.message())? {
+            Some(value) => *value = merged,
+            None => {
+                store.set(dest_key, &merged, &None);
+            }
+        }
+        // Redis publishes the merge as a PFADD to the destination
+        store.notify_keyspace_event(NOTIFY_STRING, "pfadd", dest_key);
+
+        Ok(RESPOutput::SimpleString(String::from("OK")))
+    }
+
+    fn get_hll(&self, key: &str) -> Result<Option<HyperLogLog>, String> {
+        let store = self.store();
+        match store.get_string_mut(key).map_err(|e| e.message())? {
+            Some(value) => HyperLogLog::parse(value).map(Some).map_err(hll_error),
+            None => Ok(None),
+        }
+    }
+}
+
+fn hll_error(e: HllError) -> String {
+    String::from(e.message())
+}
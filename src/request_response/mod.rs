@@ -1,4 +1,16 @@
 pub mod response_helper;
+pub mod acl_handler;
+pub mod client_handler;
 pub mod client_input;
 pub mod command;
+pub mod command_args;
+pub mod config_handler;
+pub mod connection_handler;
+pub mod db_handler;
+pub mod geo_handler;
+pub mod hyperloglog_handler;
 pub mod parsed_command;
+pub mod pubsub_handler;
+pub mod stream_group_handler;
+pub mod stream_handler;
+pub mod transaction_handler;
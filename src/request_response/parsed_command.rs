@@ -10,6 +10,12 @@ pub struct ParsedCommand {
     pub args: Vec<String>,
 }
 
+impl Default for ParsedCommand {
+    fn default() -> Self {
+        ParsedCommand::new()
+    }
+}
+
 impl ParsedCommand {
     pub fn new() -> ParsedCommand {
         ParsedCommand {
@@ -0,0 +1,348 @@
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::parser::parser::RESPOutput;
+use crate::pubsub::pubsub::{with_pubsub, SubscriberId};
+use crate::request_response::client_input::ClientInput;
+use crate::request_response::client_input::HandleClientInput;
+use crate::request_response::command::Command;
+use crate::request_response::command_args::wrong_number_of_arguments;
+use crate::request_response::parsed_command::ParsedCommand;
+use crate::request_response::response_helper::{self, Protocol};
+
+// Channels and patterns that a connection is subscribed to, and where their messages arrive
+// unless a thread writes them out
+#[derive(Debug)]
+pub(crate) struct Subscription {
+    id: SubscriberId,
+    receiver: Option<Receiver<RESPOutput>>,
+    channels: BTreeSet<String>,
+    patterns: BTreeSet<String>,
+}
+
+impl Subscription {
+    fn count(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+}
+
+impl ClientInput {
+    /// A connection is in subscribed mode while it has at least one channel or pattern subscription
+    pub fn is_subscribed(&self) -> bool {
+        self.subscription.as_ref().map_or(false, |subscription| subscription.count() > 0)
+    }
+
+    /// Writes out the messages published to the subscriptions of this connection since the last call
+    pub fn write_pending_messages<T: Write>(&self, stream: &mut T) {
+        let receiver = match self.subscription.as_ref().and_then(|subscription| subscription.receiver.as_ref()) {
+            Some(receiver) => receiver,
+            None => return,
+        };
+
+        let messages: Vec<RESPOutput> = receiver.try_iter().collect();
+        if !messages.is_empty() {
+            response_helper::with_protocol(self.protocol, || {
+                response_helper::send_resp_outputs_response(stream, &messages)
+            });
+        }
+    }
+
+    /// Answers the command without messages of the subscriptions being written in between
+    pub(crate) fn respond_before_messages<T: Write>(&mut self, stream: &mut T, parsed: ParsedCommand) {
+        let writing = Arc::clone(&self.writing);
+        let mut protocol = writing.lock().unwrap_or_else(|e| e.into_inner());
+        self.respond(stream, parsed);
+        *protocol = self.protocol;
+    }
+
+    // Only commands that manage subscriptions can be used in subscribed mode
+    pub(crate) fn is_allowed_when_subscribed(command: &Command) -> bool {
+        matches!(
+            command,
+            Command::SUBSCRIBE
+                | Command::UNSUBSCRIBE
+                | Command::PSUBSCRIBE
+                | Command::PUNSUBSCRIBE
+                | Command::PING
+                | Command::QUIT
+        )
+    }
+
+    pub(crate) fn respond_not_allowed_when_subscribed<T: Write>(&self, stream: &mut T, command: &Command) {
+        let error = format!(
+            "ERR Can't execute '{}': only (P)SUBSCRIBE / (P)UNSUBSCRIBE / PING / QUIT are allowed in this context",
+            command.name()
+        );
+        response_helper::send_error_response(stream, &error);
+    }
+
+    // ping [message], which replies with a push in subscribed mode
+    pub(crate) fn respond_subscribed_ping<T: Write>(&self, stream: &mut T, args: &[String]) {
+        let message = args.first().map_or("", |message| message.as_str());
+        let output = RESPOutput::Array(vec![
+            RESPOutput::BulkString(String::from("pong")),
+            RESPOutput::BulkString(String::from(message)),
+        ]);
+        response_helper::send_resp_output_response(stream, &output);
+    }
+
+    // subscribe <channel> [channel ...]
+    pub(crate) fn respond_subscribe<T: Write>(&mut self, stream: &mut T, args: &[String]) {
+        match self.subscribe(args, false) {
+            Ok(outputs) => response_helper::send_resp_outputs_response(stream, &outputs),
+            Err(e) => response_helper::send_error_response(stream, &e),
+        }
+    }
+
+    // psubscribe <pattern> [pattern ...]
+    pub(crate) fn respond_psubscribe<T: Write>(&mut self, stream: &mut T, args: &[String]) {
+        match self.subscribe(args, true) {
+            Ok(outputs) => response_helper::send_resp_outputs_response(stream, &outputs),
+            Err(e) => response_helper::send_error_response(stream, &e),
+        }
+    }
+
+    // unsubscribe [channel ...]
+    pub(crate) fn respond_unsubscribe<T: Write>(&mut self, stream: &mut T, args: &[String]) {
+        let outputs = self.unsubscribe(args, false);
+        response_helper::send_resp_outputs_response(stream, &outputs);
+    }
+
+    // punsubscribe [pattern ...]
+    pub(crate) fn respond_punsubscribe<T: Write>(&mut self, stream: &mut T, args: &[String]) {
+        let outputs = self.unsubscribe(args, true);
+        response_helper::send_resp_outputs_response(stream, &outputs);
+    }
+
+    // publish <channel> <message>
+    pub(crate) fn respond_publish<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.publish(args));
+    }
+
+    // pubsub channels [pattern] | numsub [channel ...] | numpat
+    pub(crate) fn respond_pubsub<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.pubsub(args));
+    }
+
+    // Numbers of channels and patterns that the connection is subscribed to
+    pub(crate) fn subscription_counts(&self) -> (usize, usize) {
+        self.subscription
+            .as_ref()
+            .map_or((0, 0), |subscription| (subscription.channels.len(), subscription.patterns.len()))
+    }
+
+    /// Removes the subscriptions of this connection, i.e. when it is closed
+    pub(crate) fn leave_pubsub(&mut self) {
+        if let Some(subscription) = self.subscription.take() {
+            with_pubsub(|pubsub| pubsub.unregister(subscription.id));
+        }
+    }
+
+    fn subscribe(&mut self, args: &[String], is_pattern: bool) -> Result<Vec<RESPOutput>, String> {
+        let kind = if is_pattern { "psubscribe" } else { "subscribe" };
+        if args.is_empty() {
+            return Err(wrong_number_of_arguments(kind));
+        }
+
+        if self.subscription.is_none() {
+            let (id, receiver) = with_pubsub(|pubsub| pubsub.register(self.id()));
+            // Messages wait in the channel for write_pending_messages if the connection cannot be written
+            // from another thread
+            let receiver = match self.message_writer.take() {
+                Some(writer) => {
+                    spawn_message_writer(writer, receiver, Arc::clone(&self.writing));
+                    None
+                }
+                None => Some(receiver),
+            };
+            self.subscription = Some(Subscription {
+                id,
+                receiver,
+                channels: BTreeSet::new(),
+                patterns: BTreeSet::new(),
+            });
+        }
+        let subscription = self.subscription.as_mut().unwrap();
+
+        let mut outputs = Vec::new();
+        for name in args {
+            let added = if is_pattern {
+                subscription.patterns.insert(name.clone())
+            } else {
+                subscription.channels.insert(name.clone())
+            };
+            if added {
+                with_pubsub(|pubsub| {
+                    if is_pattern {
+                        pubsub.psubscribe(subscription.id, name)
+                    } else {
+                        pubsub.subscribe(subscription.id, name)
+                    }
+                });
+            }
+            outputs.push(subscription_reply(kind, Some(name), subscription.count()));
+        }
+        Ok(outputs)
+    }
+
+    fn unsubscribe(&mut self, args: &[String], is_pattern: bool) -> Vec<RESPOutput> {
+        let kind = if is_pattern { "punsubscribe" } else { "unsubscribe" };
+        let subscription = match self.subscription.as_mut() {
+            Some(subscription) => subscription,
+            None if args.is_empty() => return vec![subscription_reply(kind, None, 0)],
+            None => return args.iter().map(|name| subscription_reply(kind, Some(name), 0)).collect(),
+        };
+
+        // Without arguments, unsubscribes from everything that it is subscribed to
+        let names: Vec<String> = if !args.is_empty() {
+            args.to_vec()
+        } else if is_pattern {
+            subscription.patterns.iter().cloned().collect()
+        } else {
+            subscription.channels.iter().cloned().collect()
+        };
+        if names.is_empty() {
+            return vec![subscription_reply(kind, None, subscription.count())];
+        }
+
+        let mut outputs = Vec::new();
+        for name in names.iter() {
+            let removed = if is_pattern {
+                subscription.patterns.remove(name)
+            } else {
+                subscription.channels.remove(name)
+            };
+            if removed {
+                with_pubsub(|pubsub| {
+                    if is_pattern {
+                        pubsub.punsubscribe(subscription.id, name)
+                    } else {
+                        pubsub.unsubscribe(subscription.id, name)
+                    }
+                });
+            }
+            outputs.push(subscription_reply(kind, Some(name), subscription.count()));
+        }
+        outputs
+    }
+
+    fn publish(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() != 2 {
+            return Err(wrong_number_of_arguments("publish"));
+        }
+
+        let receivers = with_pubsub(|pubsub| pubsub.publish(&args[0], &args[1]));
+        Ok(RESPOutput::Integer(receivers as i64))
+    }
+
+    fn pubsub(&self, args: &[String]) -> Result<RESPOutput, String> {
+        let subcommand = args.first().ok_or_else(|| wrong_number_of_arguments("pubsub"))?.to_uppercase();
+        let arity_ok = match subcommand.as_str() {
+            "CHANNELS" => args.len() <= 2,
+            "NUMSUB" => true,
+            "NUMPAT" => args.len() == 1,
+            _ => return Err(format!("ERR unknown subcommand '{}'. Try PUBSUB HELP.", args[0])),
+        };
+        if !arity_ok {
+            return Err(format!(
+                "ERR wrong number of arguments for 'pubsub|{}' command",
+                subcommand.to_lowercase()
+            ));
+        }
+
+        with_pubsub(|pubsub| {
+            let output = match subcommand.as_str() {
+                "CHANNELS" => RESPOutput::Array(
+                    pubsub
+                        .active_channels(args.get(1).map(|pattern| pattern.as_str()))
+                        .into_iter()
+                        .map(|channel| RESPOutput::BulkString(String::from(channel)))
+                        .collect(),
+                ),
+                "NUMSUB" => RESPOutput::Array(
+                    args[1..]
+                        .iter()
+                        .flat_map(|channel| {
+                            vec![
+                                RESPOutput::BulkString(channel.clone()),
+                                RESPOutput::Integer(pubsub.num_subscribers(channel) as i64),
+                            ]
+                        })
+                        .collect(),
+                ),
+                _ => RESPOutput::Integer(pubsub.num_patterns() as i64),
+            };
+            Ok(output)
+        })
+    }
+}
+
+// Writes messages as soon as they are published, so that subscribed connections wait for them without polling.
+// The thread ends once the subscriber is unregistered, which drops the sending end of its channel
+fn spawn_message_writer(mut writer: Box<dyn Write + Send>, receiver: Receiver<RESPOutput>, writing: Arc<Mutex<Protocol>>) {
+    thread::spawn(move || {
+        while let Ok(message) = receiver.recv() {
+            let mut messages = vec![message];
+            messages.extend(receiver.try_iter());
+
+            let protocol = writing.lock().unwrap_or_else(|e| e.into_inner());
+            response_helper::with_protocol(*protocol, || {
+                response_helper::send_resp_outputs_response(&mut writer, &messages)
+            });
+        }
+    });
+}
+
+// Confirms a change of subscription, with the number of subscriptions of the connection after it.
+// It is a push in RESP3, like the messages that follow it
+fn subscription_reply(kind: &str, name: Option<&String>, count: usize) -> RESPOutput {
+    RESPOutput::Push(vec![
+        RESPOutput::BulkString(String::from(kind)),
+        name.map_or(RESPOutput::Null, |name| RESPOutput::BulkString(name.clone())),
+        RESPOutput::Integer(count as i64),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribe_and_unsubscribe_track_the_subscription_count() {
+        let mut client_input = ClientInput::new();
+        let channels = vec![String::from("first"), String::from("second")];
+
+        let outputs = client_input.subscribe(&channels, false).unwrap();
+        assert_eq!(outputs[1], subscription_reply("subscribe", Some(&channels[1]), 2));
+        let outputs = client_input.subscribe(&[String::from("news.*")], true).unwrap();
+        assert_eq!(outputs[0], subscription_reply("psubscribe", Some(&String::from("news.*")), 3));
+        assert!(client_input.is_subscribed());
+
+        let outputs = client_input.unsubscribe(&[], false);
+        assert_eq!(
+            outputs,
+            vec![
+                subscription_reply("unsubscribe", Some(&channels[0]), 2),
+                subscription_reply("unsubscribe", Some(&channels[1]), 1),
+            ]
+        );
+        let outputs = client_input.unsubscribe(&[], true);
+        assert_eq!(outputs[0], subscription_reply("punsubscribe", Some(&String::from("news.*")), 0));
+        assert!(!client_input.is_subscribed());
+
+        assert_eq!(client_input.unsubscribe(&[], false), vec![subscription_reply("unsubscribe", None, 0)]);
+    }
+
+    #[test]
+    fn subscribe_requires_a_channel() {
+        let mut client_input = ClientInput::new();
+        assert_eq!(
+            client_input.subscribe(&[], false),
+            Err(String::from("ERR wrong number of arguments for 'subscribe' command"))
+        );
+        assert!(!client_input.is_subscribed());
+    }
+}
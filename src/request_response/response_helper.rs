@@ -1,14 +1,59 @@
+use std::cell::Cell;
 use std::io::Write;
 
-pub fn send_bulk_string_response<T: Write>(stream: &mut T, data: Option<&str>) {
-    let response: String;
-    if data.is_some() {
-        let str = data.unwrap();
-        response = format!("${}\r\n{}\r\n", str.len(), str);
-    } else {
-        response = String::from("$-1\r\n");
+use crate::parser::parser::RESPOutput;
+
+// Version of the protocol that replies are encoded with, chosen by the connection with HELLO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Resp2,
+    Resp3,
+}
+
+impl Protocol {
+    pub fn from_version(version: i64) -> Option<Protocol> {
+        match version {
+            2 => Some(Protocol::Resp2),
+            3 => Some(Protocol::Resp3),
+            _ => None,
+        }
     }
 
+    pub fn version(&self) -> i64 {
+        match self {
+            Protocol::Resp2 => 2,
+            Protocol::Resp3 => 3,
+        }
+    }
+}
+
+thread_local! {
+    // Protocol of the connection that is being replied to. Each connection has its own thread, but
+    // tests reply to several connections from one thread, so it is set around every reply
+    static PROTOCOL: Cell<Protocol> = Cell::new(Protocol::Resp2);
+}
+
+/// Encodes the replies sent by `f` with the protocol of the connection
+pub fn with_protocol<F, R>(protocol: Protocol, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let previous = PROTOCOL.with(|current| current.replace(protocol));
+    let result = f();
+    PROTOCOL.with(|current| current.set(previous));
+    result
+}
+
+pub fn protocol() -> Protocol {
+    PROTOCOL.with(|current| current.get())
+}
+
+pub fn send_bulk_string_response<T: Write>(stream: &mut T, data: Option<&str>) {
+    let response = match data {
+        Some(str) => format!("${}\r\n{}\r\n", str.len(), str),
+        None => format_resp_output(&RESPOutput::Null),
+    };
+
     match stream.write(response.as_bytes()) {
         Ok(t) => {
             println!("Wrote {} bytes to output", t);
@@ -49,3 +94,125 @@ pub fn format_simple_string_response(res: &str) -> String {
     format!("+{}\r\n", res)
 }
 pub fn format_error_response(res: &str) -> String { format!("-{}\r\n", res) }
+
+pub fn send_resp_output_response<T: Write>(stream: &mut T, output: &RESPOutput) {
+    write_response(stream, format_resp_output(output).as_bytes());
+}
+
+// Writes several replies at once, i.e. one confirmation for each channel of SUBSCRIBE
+pub fn send_resp_outputs_response<T: Write>(stream: &mut T, outputs: &[RESPOutput]) {
+    let response: String = outputs.iter().map(format_resp_output).collect();
+    write_response(stream, response.as_bytes());
+}
+
+// Writes replies that are already encoded, i.e. ones encoded while the command lock was held
+pub fn send_encoded_response<T: Write>(stream: &mut T, reply: &[u8]) {
+    if !reply.is_empty() {
+        write_response(stream, reply);
+    }
+}
+
+// Replies with an array of replies that are already encoded, i.e. the replies of the commands of EXEC
+pub fn send_encoded_array_response<T: Write>(stream: &mut T, replies: &[Vec<u8>]) {
+    let mut response = format!("*{}\r\n", replies.len()).into_bytes();
+    for reply in replies {
+        response.extend_from_slice(reply);
+    }
+    write_response(stream, &response);
+}
+
+pub fn send_integer_response<T: Write>(stream: &mut T, num: i64) {
+    send_resp_output_response(stream, &RESPOutput::Integer(num));
+}
+
+// Used by commands that reply with a null array instead of a null bulk string, i.e. XREAD. RESP3
+// has a single null
+pub fn send_null_array_response<T: Write>(stream: &mut T) {
+    match protocol() {
+        Protocol::Resp2 => write_response(stream, b"*-1\r\n"),
+        Protocol::Resp3 => write_response(stream, b"_\r\n"),
+    }
+}
+
+pub fn format_resp_output(output: &RESPOutput) -> String {
+    encode(output, protocol())
+}
+
+// RESP2 has no RESP3 types, so they are encoded like redis does, e.g. maps as arrays of
+// alternating keys and values, and booleans as 1 or 0
+fn encode(output: &RESPOutput, protocol: Protocol) -> String {
+    let is_resp3 = protocol == Protocol::Resp3;
+    match output {
+        RESPOutput::SimpleString(str) => format_simple_string_response(str),
+        RESPOutput::Error(str) => format_error_response(str),
+        RESPOutput::BulkString(str) => format!("${}\r\n{}\r\n", str.len(), str),
+        RESPOutput::Integer(num) => format!(":{}\r\n", num),
+        RESPOutput::Array(arr) => encode_aggregate('*', arr.len(), arr, protocol),
+        RESPOutput::Null | RESPOutput::NullArray if is_resp3 => String::from("_\r\n"),
+        RESPOutput::Null => String::from("$-1\r\n"),
+        RESPOutput::NullArray => String::from("*-1\r\n"),
+        RESPOutput::Map(pairs) => {
+            let elements: Vec<RESPOutput> = pairs.iter().flat_map(|(key, value)| [key.clone(), value.clone()]).collect();
+            match is_resp3 {
+                true => encode_aggregate('%', pairs.len(), &elements, protocol),
+                false => encode_aggregate('*', elements.len(), &elements, protocol),
+            }
+        }
+        RESPOutput::Set(set) if is_resp3 => encode_aggregate('~', set.len(), set, protocol),
+        RESPOutput::Push(push) if is_resp3 => encode_aggregate('>', push.len(), push, protocol),
+        RESPOutput::Set(arr) | RESPOutput::Push(arr) => encode_aggregate('*', arr.len(), arr, protocol),
+        RESPOutput::Double(num) if is_resp3 => format!(",{}\r\n", format_double(*num)),
+        RESPOutput::Double(num) => encode(&RESPOutput::BulkString(format_double(*num)), protocol),
+        RESPOutput::Boolean(bool) if is_resp3 => format!("#{}\r\n", if *bool { 't' } else { 'f' }),
+        RESPOutput::Boolean(bool) => encode(&RESPOutput::Integer(*bool as i64), protocol),
+        RESPOutput::BigNumber(num) if is_resp3 => format!("({}\r\n", num),
+        RESPOutput::BigNumber(num) => encode(&RESPOutput::BulkString(num.clone()), protocol),
+        RESPOutput::VerbatimString(format, text) if is_resp3 => {
+            format!("={}\r\n{}:{}\r\n", format.len() + 1 + text.len(), format, text)
+        }
+        RESPOutput::VerbatimString(_, text) => encode(&RESPOutput::BulkString(text.clone()), protocol),
+        RESPOutput::Attribute(pairs, reply) if is_resp3 => {
+            let elements: Vec<RESPOutput> = pairs.iter().flat_map(|(key, value)| [key.clone(), value.clone()]).collect();
+            encode_aggregate('|', pairs.len(), &elements, protocol) + &encode(reply, protocol)
+        }
+        RESPOutput::Attribute(_, reply) => encode(reply, protocol),
+    }
+}
+
+fn encode_aggregate(symbol: char, len: usize, elements: &[RESPOutput], protocol: Protocol) -> String {
+    let mut response = format!("{}{}\r\n", symbol, len);
+    for element in elements {
+        response.push_str(&encode(element, protocol));
+    }
+    response
+}
+
+// Infinities and NaN are spelt like redis
+fn format_double(num: f64) -> String {
+    if num.is_nan() {
+        String::from("nan")
+    } else if num.is_infinite() {
+        String::from(if num > 0.0 { "inf" } else { "-inf" })
+    } else {
+        num.to_string()
+    }
+}
+
+fn write_response<T: Write>(stream: &mut T, response: &[u8]) {
+    match stream.write_all(response) {
+        Ok(_) => {
+            println!("Wrote {} bytes to output", response.len());
+        },
+        Err(e) => {
+            println!("unable to write to response: {}", e);
+        }
+    }
+}
+
+// Replies with the output of a command, or its error message
+pub fn send_result_response<T: Write>(stream: &mut T, result: Result<RESPOutput, String>) {
+    match result {
+        Ok(output) => send_resp_output_response(stream, &output),
+        Err(e) => send_error_response(stream, &e),
+    }
+}
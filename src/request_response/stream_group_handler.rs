@@ -0,0 +1,718 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+
+use crate::parser::parser::RESPOutput;
+use crate::pubsub::keyspace_events::NOTIFY_STREAM;
+use crate::request_response::client_input::ClientInput;
+use crate::request_response::command_args::{
+    parse_count_arg, parse_integer_arg, wrong_number_of_arguments, SYNTAX_ERROR,
+};
+use crate::request_response::response_helper;
+use crate::request_response::stream_handler::{
+    format_stream_entry, parse_range_end, parse_range_start, stream_error,
+};
+use crate::store::redis::Store;
+use crate::store::redis_operation::{StreamAutoClaimArgs, StreamClaimArgs};
+use crate::store::redis_stream::{stream_version, wait_for_stream_added, Stream, StreamError, StreamId};
+
+// Id that XREADGROUP uses to read entries that were never delivered to the group
+const NEW_ENTRIES_ID: &str = ">";
+
+enum GroupReadId {
+    New,
+    Pending(StreamId),
+}
+
+impl ClientInput {
+    // xgroup CREATE <key> <group> <id|$> [MKSTREAM] [ENTRIESREAD entries-read]
+    // xgroup SETID <key> <group> <id|$> [ENTRIESREAD entries-read]
+    // xgroup DESTROY <key> <group>
+    // xgroup CREATECONSUMER <key> <group> <consumer>
+    // xgroup DELCONSUMER <key> <group> <consumer>
+    pub(crate) fn respond_xgroup<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xgroup(args));
+    }
+
+    // xreadgroup GROUP <group> <consumer> [COUNT count] [BLOCK milliseconds] [NOACK] STREAMS key [key ...] id [id ...]
+    pub(crate) fn respond_xreadgroup<T: Write>(&self, stream: &mut T, args: &[String]) {
+        match self.xreadgroup(args) {
+            Ok(Some(output)) => response_helper::send_resp_output_response(stream, &output),
+            Ok(None) => response_helper::send_null_array_response(stream),
+            Err(e) => response_helper::send_error_response(stream, &e),
+        }
+    }
+
+    // xack <key> <group> <id> [id ...]
+    pub(crate) fn respond_xack<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xack(args));
+    }
+
+    // xpending <key> <group> [[IDLE min-idle-time] <start> <end> <count> [consumer]]
+    pub(crate) fn respond_xpending<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xpending(args));
+    }
+
+    // xclaim <key> <group> <consumer> <min-idle-time> <id> [id ...] [IDLE ms] [TIME unix-time-milliseconds]
+    //     [RETRYCOUNT count] [FORCE] [JUSTID] [LASTID lastid]
+    pub(crate) fn respond_xclaim<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xclaim(args));
+    }
+
+    // xautoclaim <key> <group> <consumer> <min-idle-time> <start> [COUNT count] [JUSTID]
+    pub(crate) fn respond_xautoclaim<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xautoclaim(args));
+    }
+
+    // xinfo STREAM <key>
+    // xinfo GROUPS <key>
+    // xinfo CONSUMERS <key> <group>
+    pub(crate) fn respond_xinfo<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xinfo(args));
+    }
+
+    fn xgroup(&self, args: &[String]) -> Result<RESPOutput, String> {
+        let subcommand = args.first().ok_or_else(|| wrong_number_of_arguments("xgroup"))?.to_uppercase();
+        let arity_ok = match subcommand.as_str() {
+            "CREATE" => (4..=7).contains(&args.len()),
+            "SETID" => args.len() == 4 || args.len() == 6,
+            "DESTROY" => args.len() == 3,
+            "CREATECONSUMER" | "DELCONSUMER" => args.len() == 4,
+            _ => return Err(format!("ERR unknown subcommand '{}'. Try XGROUP HELP.", args[0])),
+        };
+        if !arity_ok {
+            return Err(format!(
+                "ERR wrong number of arguments for 'xgroup|{}' command",
+                subcommand.to_lowercase()
+            ));
+        }
+
+        let key = &args[1];
+        let group_name = &args[2];
+
+        let mut mkstream = false;
+        let mut entries_read: Option<u64> = None;
+        let mut index = 4;
+        while index < args.len() {
+            match args[index].to_uppercase().as_str() {
+                "MKSTREAM" if subcommand == "CREATE" => {
+                    mkstream = true;
+                    index += 1;
+                }
+                "ENTRIESREAD" if index + 1 < args.len() => {
+                    let value = parse_integer_arg(&args[index + 1])?;
+                    if value < 0 {
+                        return Err(String::from("ERR value for ENTRIESREAD must be positive or -1"));
+                    }
+                    entries_read = Some(value as u64);
+                    index += 2;
+                }
+                _ => return Err(String::from(SYNTAX_ERROR)),
+            }
+        }
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let redis_stream = match store.get_stream_mut(key, mkstream).map_err(|e| e.message())? {
+            Some(redis_stream) => redis_stream,
+            None => {
+                return Err(String::from(
+                    "ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.",
+                ))
+            }
+        };
+
+        // Events are only published when the subcommand changes the stream
+        let (output, event) = match subcommand.as_str() {
+            "CREATE" => {
+                let last_id = parse_group_last_id(redis_stream, &args[3])?;
+                if !redis_stream.create_group(group_name, last_id, entries_read) {
+                    return Err(String::from("BUSYGROUP Consumer Group name already exists"));
+                }
+                (RESPOutput::SimpleString(String::from("OK")), Some("xgroup-create"))
+            }
+            "SETID" => {
+                let last_id = parse_group_last_id(redis_stream, &args[3])?;
+                let group = redis_stream
+                    .group_mut(group_name)
+                    .ok_or_else(|| no_such_group(key, group_name))?;
+                group.last_id = last_id;
+                group.entries_read = entries_read;
+                (RESPOutput::SimpleString(String::from("OK")), Some("xgroup-setid"))
+            }
+            "DESTROY" => {
+                let destroyed = redis_stream.destroy_group(group_name);
+                (RESPOutput::Integer(destroyed as i64), Some("xgroup-destroy").filter(|_| destroyed))
+            }
+            "CREATECONSUMER" => {
+                let group = redis_stream
+                    .group_mut(group_name)
+                    .ok_or_else(|| no_such_group(key, group_name))?;
+                let created = group.create_consumer(&args[3], Utc::now().timestamp_millis());
+                (RESPOutput::Integer(created as i64), Some("xgroup-createconsumer").filter(|_| created))
+            }
+            _ => {
+                let group = redis_stream
+                    .group_mut(group_name)
+                    .ok_or_else(|| no_such_group(key, group_name))?;
+                let deleted = group.delete_consumer(&args[3]);
+                let output = RESPOutput::Integer(deleted.unwrap_or(0) as i64);
+                (output, Some("xgroup-delconsumer").filter(|_| deleted.is_some()))
+            }
+        };
+
+        if let Some(event) = event {
+            store.notify_keyspace_event(NOTIFY_STREAM, event, key);
+        }
+        Ok(output)
+    }
+
+    // Returns None if there is nothing to read before the block timeout
+    fn xreadgroup(&self, args: &[String]) -> Result<Option<RESPOutput>, String> {
+        if args.len() < 6 {
+            return Err(wrong_number_of_arguments("xreadgroup"));
+        }
+        if args[0].to_uppercase() != "GROUP" {
+            return Err(String::from("ERR Missing GROUP option for XREADGROUP"));
+        }
+        let group_name = &args[1];
+        let consumer = &args[2];
+
+        let mut count: Option<usize> = None;
+        let mut block: Option<Duration> = None;
+        let mut no_ack = false;
+        let mut index = 3;
+
+        loop {
+            let option = args.get(index).ok_or(SYNTAX_ERROR)?;
+            match option.to_uppercase().as_str() {
+                "COUNT" => {
+                    let value = args.get(index + 1).ok_or(SYNTAX_ERROR)?;
+                    count = Some(parse_count_arg(value)? as usize).filter(|count| *count > 0);
+                    index += 2;
+                }
+                "BLOCK" => {
+                    let value = args.get(index + 1).ok_or(SYNTAX_ERROR)?;
+                    let timeout = parse_integer_arg(value)?;
+                    if timeout < 0 {
+                        return Err(String::from("ERR timeout is negative"));
+                    }
+                    block = Some(Duration::from_millis(timeout as u64));
+                    index += 2;
+                }
+                "NOACK" => {
+                    no_ack = true;
+                    index += 1;
+                }
+                "STREAMS" => {
+                    index += 1;
+                    break;
+                }
+                _ => return Err(String::from(SYNTAX_ERROR)),
+            }
+        }
+
+        let streams = &args[index..];
+        if streams.is_empty() || streams.len() % 2 != 0 {
+            return Err(String::from(
+                "ERR Unbalanced 'xreadgroup' list of streams: for each stream key an ID or '>' must be specified.",
+            ));
+        }
+        let (keys, ids) = streams.split_at(streams.len() / 2);
+
+        let mut read_ids: Vec<GroupReadId> = Vec::new();
+        for id in ids {
+            let read_id = match id.as_str() {
+                NEW_ENTRIES_ID => GroupReadId::New,
+                "$" => return Err(String::from("ERR The $ ID is meaningless in the context of XREADGROUP")),
+                _ => GroupReadId::Pending(StreamId::parse(id, 0).map_err(stream_error)?),
+            };
+            read_ids.push(read_id);
+        }
+
+        // BLOCK 0 blocks forever
+        let deadline = block.filter(|timeout| !timeout.is_zero()).map(|timeout| Instant::now() + timeout);
+        loop {
+            let version = stream_version();
+            let result = self.read_groups(group_name, consumer, keys, &read_ids, count, no_ack)?;
+            if !result.is_empty() {
+                return Ok(Some(RESPOutput::Array(result)));
+            }
+
+            // Like redis, blocking reads do not block inside a transaction
+            if block.is_none() || self.executing_transaction || !wait_for_stream_added(version, deadline) {
+                return Ok(None);
+            }
+        }
+    }
+
+    // Reading pending entries always replies for the stream, while reading new entries only
+    // replies for streams that have any
+    fn read_groups(
+        &self,
+        group_name: &str,
+        consumer: &str,
+        keys: &[String],
+        read_ids: &[GroupReadId],
+        count: Option<usize>,
+        no_ack: bool,
+    ) -> Result<Vec<RESPOutput>, String> {
+        let mut result: Vec<RESPOutput> = Vec::new();
+        let now = Utc::now().timestamp_millis();
+
+        for (key, read_id) in keys.iter().zip(read_ids) {
+            self.expire_if_needed(key);
+            let store = self.store();
+            let redis_stream = store.get_stream_mut(key, false).map_err(|e| e.message())?;
+            let redis_stream = match redis_stream {
+                Some(redis_stream) if redis_stream.group(group_name).is_some() => redis_stream,
+                _ => {
+                    return Err(format!(
+                        "NOGROUP No such key '{}' or consumer group '{}' in XREADGROUP with GROUP option",
+                        key, group_name
+                    ))
+                }
+            };
+
+            let entries: Vec<RESPOutput> = match read_id {
+                GroupReadId::New => redis_stream
+                    .read_group(group_name, consumer, count, no_ack, now)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(id, fields)| format_stream_entry(id, fields))
+                    .collect(),
+                GroupReadId::Pending(after) => redis_stream
+                    .read_group_pending(group_name, consumer, *after, count, now)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(id, fields)| match fields {
+                        Some(fields) => format_stream_entry(id, fields),
+                        // The entry is deleted from the stream but still pending
+                        None => RESPOutput::Array(vec![RESPOutput::BulkString(id.to_string()), RESPOutput::Null]),
+                    })
+                    .collect(),
+            };
+            if entries.is_empty() && matches!(read_id, GroupReadId::New) {
+                continue;
+            }
+            result.push(RESPOutput::Array(vec![
+                RESPOutput::BulkString(key.clone()),
+                RESPOutput::Array(entries),
+            ]));
+        }
+        Ok(result)
+    }
+
+    fn xack(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() < 3 {
+            return Err(wrong_number_of_arguments("xack"));
+        }
+
+        let key = &args[0];
+        let group_name = &args[1];
+        let ids = parse_stream_ids(&args[2..])?;
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let acknowledged = match store.get_stream_mut(key, false).map_err(|e| e.message())? {
+            Some(redis_stream) => match redis_stream.group_mut(group_name) {
+                Some(group) => group.ack(&ids),
+                None => 0,
+            },
+            None => 0,
+        };
+
+        Ok(RESPOutput::Integer(acknowledged as i64))
+    }
+
+    fn xpending(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() < 2 {
+            return Err(wrong_number_of_arguments("xpending"));
+        }
+
+        let key = &args[0];
+        let group_name = &args[1];
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let redis_stream = store.get_stream(key).map_err(|e| e.message())?;
+        let group = redis_stream
+            .and_then(|redis_stream| redis_stream.group(group_name))
+            .ok_or_else(|| no_such_key_or_group(key, group_name))?;
+
+        // Summary form
+        if args.len() == 2 {
+            let (first, last) = match (group.pending.keys().next(), group.pending.keys().next_back()) {
+                (Some(first), Some(last)) => (first, last),
+                _ => {
+                    return Ok(RESPOutput::Array(vec![
+                        RESPOutput::Integer(0),
+                        RESPOutput::Null,
+                        RESPOutput::Null,
+                        RESPOutput::Null,
+                    ]))
+                }
+            };
+
+            let consumers: Vec<RESPOutput> = group
+                .consumers
+                .iter()
+                .filter(|(_, consumer)| !consumer.pending.is_empty())
+                .map(|(name, consumer)| {
+                    RESPOutput::Array(vec![
+                        RESPOutput::BulkString(name.clone()),
+                        RESPOutput::BulkString(consumer.pending.len().to_string()),
+                    ])
+                })
+                .collect();
+
+            return Ok(RESPOutput::Array(vec![
+                RESPOutput::Integer(group.pending.len() as i64),
+                RESPOutput::BulkString(first.to_string()),
+                RESPOutput::BulkString(last.to_string()),
+                RESPOutput::Array(consumers),
+            ]));
+        }
+
+        // Extended form
+        let mut index = 2;
+        let mut min_idle_time = 0;
+        if args[index].to_uppercase() == "IDLE" {
+            let value = args.get(index + 1).ok_or(SYNTAX_ERROR)?;
+            min_idle_time = parse_integer_arg(value)?;
+            index += 2;
+        }
+        let range_args = &args[index..];
+        if range_args.len() != 3 && range_args.len() != 4 {
+            return Err(String::from(SYNTAX_ERROR));
+        }
+        let start = parse_range_start(&range_args[0])?;
+        let end = parse_range_end(&range_args[1])?;
+        let count = parse_count_arg(&range_args[2])? as usize;
+        let consumer = range_args.get(3);
+
+        let now = Utc::now().timestamp_millis();
+        let mut result: Vec<RESPOutput> = Vec::new();
+        if start <= end {
+            for (id, pending) in group.pending.range(start..=end) {
+                if result.len() >= count {
+                    break;
+                }
+                if consumer.map_or(false, |consumer| *consumer != pending.consumer) {
+                    continue;
+                }
+                let idle = now - pending.delivery_time;
+                if idle < min_idle_time {
+                    continue;
+                }
+                result.push(RESPOutput::Array(vec![
+                    RESPOutput::BulkString(id.to_string()),
+                    RESPOutput::BulkString(pending.consumer.clone()),
+                    RESPOutput::Integer(idle),
+                    RESPOutput::Integer(pending.delivery_count as i64),
+                ]));
+            }
+        }
+
+        Ok(RESPOutput::Array(result))
+    }
+
+    fn xclaim(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() < 5 {
+            return Err(wrong_number_of_arguments("xclaim"));
+        }
+
+        let key = &args[0];
+        let group_name = &args[1];
+        let consumer = &args[2];
+        let min_idle_time = parse_min_idle_time(&args[3])?;
+
+        // IDs are followed by the options, the first argument that is not an ID ends them
+        let mut index = 4;
+        let mut ids: Vec<StreamId> = Vec::new();
+        while let Some(Ok(id)) = args.get(index).map(|arg| StreamId::parse(arg, 0)) {
+            ids.push(id);
+            index += 1;
+        }
+        if ids.is_empty() {
+            return Err(stream_error(StreamError::InvalidId));
+        }
+
+        let now = Utc::now().timestamp_millis();
+        let mut claim_args = StreamClaimArgs {
+            min_idle_time,
+            delivery_time: None,
+            retry_count: None,
+            force: false,
+            just_id: false,
+            last_id: None,
+        };
+        while index < args.len() {
+            let option = args[index].to_uppercase();
+            match option.as_str() {
+                "FORCE" => claim_args.force = true,
+                "JUSTID" => claim_args.just_id = true,
+                "IDLE" | "TIME" | "RETRYCOUNT" | "LASTID" => {
+                    let value = args.get(index + 1).ok_or(SYNTAX_ERROR)?;
+                    match option.as_str() {
+                        "IDLE" => claim_args.delivery_time = Some(now - parse_integer_arg(value)?),
+                        "TIME" => claim_args.delivery_time = Some(parse_integer_arg(value)?),
+                        "RETRYCOUNT" => claim_args.retry_count = Some(parse_count_arg(value)?),
+                        _ => claim_args.last_id = Some(StreamId::parse(value, 0).map_err(stream_error)?),
+                    }
+                    index += 1;
+                }
+                _ => return Err(format!("ERR Unrecognized XCLAIM option '{}'", args[index])),
+            }
+            index += 1;
+        }
+        // Delivery time in the future is not allowed
+        claim_args.delivery_time = claim_args.delivery_time.map(|time| time.min(now));
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let redis_stream = store.get_stream_mut(key, false).map_err(|e| e.message())?;
+        let redis_stream = redis_stream.ok_or_else(|| no_such_key_or_group(key, group_name))?;
+        let claimed = redis_stream
+            .claim(group_name, consumer, &ids, &claim_args, now)
+            .ok_or_else(|| no_such_key_or_group(key, group_name))?;
+
+        Ok(RESPOutput::Array(format_claimed_entries(
+            redis_stream,
+            &claimed,
+            claim_args.just_id,
+        )))
+    }
+
+    fn xautoclaim(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() < 5 {
+            return Err(wrong_number_of_arguments("xautoclaim"));
+        }
+
+        let key = &args[0];
+        let group_name = &args[1];
+        let consumer = &args[2];
+        let min_idle_time = parse_min_idle_time(&args[3])?;
+        let start = parse_range_start(&args[4])?;
+
+        let mut count = 100;
+        let mut just_id = false;
+        let mut index = 5;
+        while index < args.len() {
+            match args[index].to_uppercase().as_str() {
+                "COUNT" => {
+                    let value = args.get(index + 1).ok_or(SYNTAX_ERROR)?;
+                    let value = parse_integer_arg(value)?;
+                    if value < 1 {
+                        return Err(String::from("ERR COUNT must be > 0"));
+                    }
+                    count = value as usize;
+                    index += 2;
+                }
+                "JUSTID" => {
+                    just_id = true;
+                    index += 1;
+                }
+                _ => return Err(String::from(SYNTAX_ERROR)),
+            }
+        }
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let redis_stream = store.get_stream_mut(key, false).map_err(|e| e.message())?;
+        let redis_stream = redis_stream.ok_or_else(|| no_such_key_or_group(key, group_name))?;
+        let now = Utc::now().timestamp_millis();
+        let auto_claim_args = StreamAutoClaimArgs {
+            min_idle_time,
+            start,
+            count,
+            just_id,
+        };
+        let auto_claim = redis_stream
+            .auto_claim(group_name, consumer, &auto_claim_args, now)
+            .ok_or_else(|| no_such_key_or_group(key, group_name))?;
+
+        Ok(RESPOutput::Array(vec![
+            RESPOutput::BulkString(auto_claim.next_id.to_string()),
+            RESPOutput::Array(format_claimed_entries(redis_stream, &auto_claim.claimed, just_id)),
+            RESPOutput::Array(
+                auto_claim
+                    .deleted
+                    .iter()
+                    .map(|id| RESPOutput::BulkString(id.to_string()))
+                    .collect(),
+            ),
+        ]))
+    }
+
+    fn xinfo(&self, args: &[String]) -> Result<RESPOutput, String> {
+        let subcommand = args.first().ok_or_else(|| wrong_number_of_arguments("xinfo"))?.to_uppercase();
+        let arity_ok = match subcommand.as_str() {
+            "STREAM" | "GROUPS" => args.len() == 2,
+            "CONSUMERS" => args.len() == 3,
+            _ => return Err(format!("ERR unknown subcommand '{}'. Try XINFO HELP.", args[0])),
+        };
+        if !arity_ok {
+            return Err(format!(
+                "ERR wrong number of arguments for 'xinfo|{}' command",
+                subcommand.to_lowercase()
+            ));
+        }
+
+        let key = &args[1];
+        self.expire_if_needed(key);
+        let store = self.store();
+        let redis_stream = store
+            .get_stream(key)
+            .map_err(|e| e.message())?
+            .ok_or_else(|| String::from("ERR no such key"))?;
+        let now = Utc::now().timestamp_millis();
+
+        match subcommand.as_str() {
+            "STREAM" => {
+                let format_entry = |entry: Option<(&StreamId, &_)>| match entry {
+                    Some((id, fields)) => format_stream_entry(id, fields),
+                    None => RESPOutput::Null,
+                };
+                let first_id = redis_stream.first_entry().map(|(id, _)| *id).unwrap_or(StreamId::MIN);
+
+                Ok(format_map(vec![
+                    ("length", RESPOutput::Integer(redis_stream.len() as i64)),
+                    ("last-generated-id", RESPOutput::BulkString(redis_stream.last_id().to_string())),
+                    (
+                        "max-deleted-entry-id",
+                        RESPOutput::BulkString(redis_stream.max_deleted_entry_id().to_string()),
+                    ),
+                    ("entries-added", RESPOutput::Integer(redis_stream.entries_added() as i64)),
+                    ("recorded-first-entry-id", RESPOutput::BulkString(first_id.to_string())),
+                    ("groups", RESPOutput::Integer(redis_stream.groups().len() as i64)),
+                    ("first-entry", format_entry(redis_stream.first_entry())),
+                    ("last-entry", format_entry(redis_stream.last_entry())),
+                ]))
+            }
+            "GROUPS" => {
+                let groups = redis_stream
+                    .groups()
+                    .iter()
+                    .map(|(name, group)| {
+                        let optional_integer = |value: Option<u64>| match value {
+                            Some(value) => RESPOutput::Integer(value as i64),
+                            None => RESPOutput::Null,
+                        };
+                        format_map(vec![
+                            ("name", RESPOutput::BulkString(name.clone())),
+                            ("consumers", RESPOutput::Integer(group.consumers.len() as i64)),
+                            ("pending", RESPOutput::Integer(group.pending.len() as i64)),
+                            ("last-delivered-id", RESPOutput::BulkString(group.last_id.to_string())),
+                            ("entries-read", optional_integer(group.entries_read)),
+                            ("lag", optional_integer(redis_stream.group_lag(group))),
+                        ])
+                    })
+                    .collect();
+                Ok(RESPOutput::Array(groups))
+            }
+            _ => {
+                let group_name = &args[2];
+                let group = redis_stream
+                    .group(group_name)
+                    .ok_or_else(|| no_such_group(key, group_name))?;
+                let consumers = group
+                    .consumers
+                    .iter()
+                    .map(|(name, consumer)| {
+                        // -1 if the consumer never read or claimed anything
+                        let inactive = consumer.active_time.map(|time| now - time).unwrap_or(-1);
+                        format_map(vec![
+                            ("name", RESPOutput::BulkString(name.clone())),
+                            ("pending", RESPOutput::Integer(consumer.pending.len() as i64)),
+                            ("idle", RESPOutput::Integer(now - consumer.seen_time)),
+                            ("inactive", RESPOutput::Integer(inactive)),
+                        ])
+                    })
+                    .collect();
+                Ok(RESPOutput::Array(consumers))
+            }
+        }
+    }
+}
+
+fn no_such_group(key: &str, group_name: &str) -> String {
+    format!("NOGROUP No such consumer group '{}' for key name '{}'", group_name, key)
+}
+
+fn no_such_key_or_group(key: &str, group_name: &str) -> String {
+    format!("NOGROUP No such key '{}' or consumer group '{}'", key, group_name)
+}
+
+// `$` is the last ID of the stream
+fn parse_group_last_id(redis_stream: &Stream, arg: &str) -> Result<StreamId, String> {
+    match arg {
+        "$" => Ok(redis_stream.last_id()),
+        _ => StreamId::parse(arg, 0).map_err(stream_error),
+    }
+}
+
+fn parse_stream_ids(args: &[String]) -> Result<Vec<StreamId>, String> {
+    args.iter()
+        .map(|id| StreamId::parse(id, 0))
+        .collect::<Result<Vec<StreamId>, StreamError>>()
+        .map_err(stream_error)
+}
+
+// Negative values are clamped to 0
+fn parse_min_idle_time(arg: &str) -> Result<i64, String> {
+    parse_integer_arg(arg)
+        .map(|num| num.max(0))
+        .map_err(|_| String::from("ERR Invalid min-idle-time argument for XCLAIM"))
+}
+
+fn format_claimed_entries(redis_stream: &Stream, ids: &[StreamId], just_id: bool) -> Vec<RESPOutput> {
+    ids.iter()
+        .filter_map(|id| {
+            if just_id {
+                return Some(RESPOutput::BulkString(id.to_string()));
+            }
+            redis_stream.get(id).map(|fields| format_stream_entry(id, fields))
+        })
+        .collect()
+}
+
+// Replies that are maps, which RESP2 flattens into arrays of alternating keys and values
+fn format_map(pairs: Vec<(&str, RESPOutput)>) -> RESPOutput {
+    RESPOutput::Map(
+        pairs
+            .into_iter()
+            .map(|(key, value)| (RESPOutput::BulkString(String::from(key)), value))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_response::response_helper::Protocol;
+
+    #[test]
+    fn parse_min_idle_time_clamps_negative_values() {
+        assert_eq!(parse_min_idle_time("-10"), Ok(0));
+        assert_eq!(parse_min_idle_time("10"), Ok(10));
+        assert_eq!(
+            parse_min_idle_time("ten"),
+            Err(String::from("ERR Invalid min-idle-time argument for XCLAIM"))
+        );
+    }
+
+    #[test]
+    fn format_map_is_flattened_in_resp2() {
+        let output = format_map(vec![("length", RESPOutput::Integer(1)), ("groups", RESPOutput::Integer(0))]);
+        assert_eq!(
+            response_helper::format_resp_output(&output),
+            "*4\r\n$6\r\nlength\r\n:1\r\n$6\r\ngroups\r\n:0\r\n"
+        );
+        assert_eq!(
+            response_helper::with_protocol(Protocol::Resp3, || response_helper::format_resp_output(&output)),
+            "%2\r\n$6\r\nlength\r\n:1\r\n$6\r\ngroups\r\n:0\r\n"
+        );
+    }
+}
@@ -0,0 +1,470 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crate::parser::parser::RESPOutput;
+use crate::pubsub::keyspace_events::NOTIFY_STREAM;
+use crate::request_response::client_input::ClientInput;
+use crate::request_response::command_args::{
+    parse_count_arg, parse_integer_arg, wrong_number_of_arguments, SYNTAX_ERROR,
+};
+use crate::request_response::response_helper;
+use crate::store::redis::Store;
+use crate::store::redis_operation::{StreamTrimArgs, StreamTrimStrategy};
+use crate::store::redis_stream::{
+    notify_stream_added, stream_version, wait_for_stream_added, StreamError, StreamFields, StreamId,
+    StreamIdSpec,
+};
+
+impl ClientInput {
+    // xadd <key> [NOMKSTREAM] [MAXLEN|MINID [=|~] threshold [LIMIT count]] <*|id> field value [field value ...]
+    pub(crate) fn respond_xadd<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xadd(args));
+    }
+
+    // xrange <key> <start> <end> [COUNT count]
+    pub(crate) fn respond_xrange<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xrange(args, false));
+    }
+
+    // xrevrange <key> <end> <start> [COUNT count]
+    pub(crate) fn respond_xrevrange<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xrange(args, true));
+    }
+
+    // xlen <key>
+    pub(crate) fn respond_xlen<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xlen(args));
+    }
+
+    // xdel <key> <id> [id ...]
+    pub(crate) fn respond_xdel<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xdel(args));
+    }
+
+    // xtrim <key> MAXLEN|MINID [=|~] threshold [LIMIT count]
+    pub(crate) fn respond_xtrim<T: Write>(&self, stream: &mut T, args: &[String]) {
+        response_helper::send_result_response(stream, self.xtrim(args));
+    }
+
+    // xread [COUNT count] [BLOCK milliseconds] STREAMS key [key ...] id [id ...]
+    pub(crate) fn respond_xread<T: Write>(&self, stream: &mut T, args: &[String]) {
+        match self.xread(args) {
+            Ok(Some(output)) => response_helper::send_resp_output_response(stream, &output),
+            Ok(None) => response_helper::send_null_array_response(stream),
+            Err(e) => response_helper::send_error_response(stream, &e),
+        }
+    }
+
+    fn xadd(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() < 4 {
+            return Err(wrong_number_of_arguments("xadd"));
+        }
+
+        let key = &args[0];
+        let mut no_mkstream = false;
+        let mut trim_args: Option<StreamTrimArgs> = None;
+        let mut index = 1;
+
+        while index < args.len() {
+            match args[index].to_uppercase().as_str() {
+                "NOMKSTREAM" => {
+                    no_mkstream = true;
+                    index += 1;
+                }
+                "MAXLEN" | "MINID" => {
+                    let (parsed, next_index) = parse_stream_trim_args(args, index)?;
+                    trim_args = Some(parsed);
+                    index = next_index;
+                }
+                _ => break,
+            }
+        }
+
+        let id_spec = StreamIdSpec::parse(args.get(index).ok_or(SYNTAX_ERROR)?).map_err(stream_error)?;
+        if id_spec == StreamIdSpec::Explicit(StreamId::MIN) {
+            return Err(stream_error(StreamError::IdIsZero));
+        }
+
+        let field_values = &args[index + 1..];
+        if field_values.is_empty() || field_values.len() % 2 != 0 {
+            return Err(wrong_number_of_arguments("xadd"));
+        }
+        let fields: StreamFields = field_values
+            .chunks(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect();
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let redis_stream = match store.get_stream_mut(key, !no_mkstream).map_err(|e| e.message())? {
+            Some(redis_stream) => redis_stream,
+            None => return Ok(RESPOutput::Null),
+        };
+
+        let id = redis_stream.add(&id_spec, fields).map_err(stream_error)?;
+        let evicted = trim_args.map_or(0, |trim_args| redis_stream.trim(&trim_args));
+        notify_stream_added();
+
+        store.notify_keyspace_event(NOTIFY_STREAM, "xadd", key);
+        if evicted > 0 {
+            store.notify_keyspace_event(NOTIFY_STREAM, "xtrim", key);
+        }
+
+        Ok(RESPOutput::BulkString(id.to_string()))
+    }
+
+    fn xrange(&self, args: &[String], rev: bool) -> Result<RESPOutput, String> {
+        let command = if rev { "xrevrange" } else { "xrange" };
+        if args.len() != 3 && args.len() != 5 {
+            return Err(wrong_number_of_arguments(command));
+        }
+
+        let key = &args[0];
+        // xrevrange takes the end of the range before the start
+        let (start, end) = if rev { (&args[2], &args[1]) } else { (&args[1], &args[2]) };
+        let start = parse_range_start(start)?;
+        let end = parse_range_end(end)?;
+
+        let mut count: Option<usize> = None;
+        if args.len() == 5 {
+            if args[3].to_uppercase() != "COUNT" {
+                return Err(String::from(SYNTAX_ERROR));
+            }
+            count = Some(parse_count_arg(&args[4])? as usize);
+        }
+        if count == Some(0) {
+            return Ok(RESPOutput::Array(Vec::new()));
+        }
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let entries = match store.get_stream(key).map_err(|e| e.message())? {
+            Some(redis_stream) => redis_stream
+                .range(start, end, count, rev)
+                .into_iter()
+                .map(|(id, fields)| format_stream_entry(id, fields))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Ok(RESPOutput::Array(entries))
+    }
+
+    fn xlen(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() != 1 {
+            return Err(wrong_number_of_arguments("xlen"));
+        }
+
+        let key = &args[0];
+        self.expire_if_needed(key);
+        let store = self.store();
+        let len = match store.get_stream(key).map_err(|e| e.message())? {
+            Some(redis_stream) => redis_stream.len(),
+            None => 0,
+        };
+
+        Ok(RESPOutput::Integer(len as i64))
+    }
+
+    fn xdel(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() < 2 {
+            return Err(wrong_number_of_arguments("xdel"));
+        }
+
+        let key = &args[0];
+        let ids = args[1..]
+            .iter()
+            .map(|id| StreamId::parse(id, 0))
+            .collect::<Result<Vec<StreamId>, StreamError>>()
+            .map_err(stream_error)?;
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let deleted = match store.get_stream_mut(key, false).map_err(|e| e.message())? {
+            Some(redis_stream) => redis_stream.delete(&ids),
+            None => 0,
+        };
+        if deleted > 0 {
+            store.notify_keyspace_event(NOTIFY_STREAM, "xdel", key);
+        }
+
+        Ok(RESPOutput::Integer(deleted as i64))
+    }
+
+    fn xtrim(&self, args: &[String]) -> Result<RESPOutput, String> {
+        if args.len() < 3 {
+            return Err(wrong_number_of_arguments("xtrim"));
+        }
+
+        let key = &args[0];
+        let (trim_args, next_index) = match args[1].to_uppercase().as_str() {
+            "MAXLEN" | "MINID" => parse_stream_trim_args(args, 1)?,
+            _ => return Err(String::from(SYNTAX_ERROR)),
+        };
+        if next_index != args.len() {
+            return Err(String::from(SYNTAX_ERROR));
+        }
+
+        self.expire_if_needed(key);
+        let store = self.store();
+        let evicted = match store.get_stream_mut(key, false).map_err(|e| e.message())? {
+            Some(redis_stream) => redis_stream.trim(&trim_args),
+            None => 0,
+        };
+        if evicted > 0 {
+            store.notify_keyspace_event(NOTIFY_STREAM, "xtrim", key);
+        }
+
+        Ok(RESPOutput::Integer(evicted as i64))
+    }
+
+    // Returns None if there is nothing to read before the block timeout
+    fn xread(&self, args: &[String]) -> Result<Option<RESPOutput>, String> {
+        let mut count: Option<usize> = None;
+        let mut block: Option<Duration> = None;
+        let mut index = 0;
+
+        loop {
+            let option = args.get(index).ok_or_else(|| wrong_number_of_arguments("xread"))?;
+            match option.to_uppercase().as_str() {
+                "COUNT" => {
+                    let value = args.get(index + 1).ok_or(SYNTAX_ERROR)?;
+                    count = Some(parse_count_arg(value)? as usize).filter(|count| *count > 0);
+                    index += 2;
+                }
+                "BLOCK" => {
+                    let value = args.get(index + 1).ok_or(SYNTAX_ERROR)?;
+                    let timeout = parse_integer_arg(value)?;
+                    if timeout < 0 {
+                        return Err(String::from("ERR timeout is negative"));
+                    }
+                    block = Some(Duration::from_millis(timeout as u64));
+                    index += 2;
+                }
+                "STREAMS" => {
+                    index += 1;
+                    break;
+                }
+                _ => return Err(String::from(SYNTAX_ERROR)),
+            }
+        }
+
+        let streams = &args[index..];
+        if streams.is_empty() || streams.len() % 2 != 0 {
+            return Err(String::from(
+                "ERR Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.",
+            ));
+        }
+        let (keys, ids) = streams.split_at(streams.len() / 2);
+
+        // `$` is resolved once so that blocking reads only return entries added after this call
+        let mut last_ids: Vec<StreamId> = Vec::new();
+        for (key, id) in keys.iter().zip(ids) {
+            let last_id = if id == "$" {
+                self.expire_if_needed(key);
+                let store = self.store();
+                match store.get_stream(key).map_err(|e| e.message())? {
+                    Some(redis_stream) => redis_stream.last_id(),
+                    None => StreamId::MIN,
+                }
+            } else {
+                StreamId::parse(id, 0).map_err(stream_error)?
+            };
+            last_ids.push(last_id);
+        }
+
+        // BLOCK 0 blocks forever
+        let deadline = block.filter(|timeout| !timeout.is_zero()).map(|timeout| Instant::now() + timeout);
+        loop {
+            let version = stream_version();
+            let result = self.read_streams(keys, &last_ids, count)?;
+            if !result.is_empty() {
+                return Ok(Some(RESPOutput::Array(result)));
+            }
+
+            // Like redis, blocking reads do not block inside a transaction
+            if block.is_none() || self.executing_transaction || !wait_for_stream_added(version, deadline) {
+                return Ok(None);
+            }
+        }
+    }
+
+    // Returns entries after the given IDs for each stream that has any
+    fn read_streams(
+        &self,
+        keys: &[String],
+        last_ids: &[StreamId],
+        count: Option<usize>,
+    ) -> Result<Vec<RESPOutput>, String> {
+        let mut result: Vec<RESPOutput> = Vec::new();
+
+        for (key, last_id) in keys.iter().zip(last_ids) {
+            self.expire_if_needed(key);
+            let store = self.store();
+            let redis_stream = match store.get_stream(key).map_err(|e| e.message())? {
+                Some(redis_stream) => redis_stream,
+                None => continue,
+            };
+            let start = match last_id.next() {
+                Some(start) => start,
+                None => continue,
+            };
+
+            let entries: Vec<RESPOutput> = redis_stream
+                .range(start, StreamId::MAX, count, false)
+                .into_iter()
+                .map(|(id, fields)| format_stream_entry(id, fields))
+                .collect();
+            if !entries.is_empty() {
+                result.push(RESPOutput::Array(vec![
+                    RESPOutput::BulkString(key.clone()),
+                    RESPOutput::Array(entries),
+                ]));
+            }
+        }
+        Ok(result)
+    }
+}
+
+pub(crate) fn stream_error(e: StreamError) -> String {
+    String::from(e.message())
+}
+
+// Parses MAXLEN|MINID [=|~] threshold [LIMIT count] starting at index, and returns the index after it
+fn parse_stream_trim_args(args: &[String], index: usize) -> Result<(StreamTrimArgs, usize), String> {
+    let strategy = args[index].to_uppercase();
+    let mut index = index + 1;
+
+    let mut approximate = false;
+    match args.get(index).map(|arg| arg.as_str()) {
+        Some("~") => {
+            approximate = true;
+            index += 1;
+        }
+        Some("=") => index += 1,
+        _ => {}
+    }
+
+    let threshold = args.get(index).ok_or(SYNTAX_ERROR)?;
+    let strategy = if strategy == "MAXLEN" {
+        let max_len = parse_integer_arg(threshold)?;
+        if max_len < 0 {
+            return Err(String::from("ERR The MAXLEN argument must be >= 0."));
+        }
+        StreamTrimStrategy::MaxLen(max_len as u64)
+    } else {
+        StreamTrimStrategy::MinId(StreamId::parse(threshold, 0).map_err(stream_error)?)
+    };
+    index += 1;
+
+    let mut limit: Option<u64> = None;
+    if args.get(index).map(|arg| arg.to_uppercase()) == Some(String::from("LIMIT")) {
+        let value = args.get(index + 1).ok_or(SYNTAX_ERROR)?;
+        let value = parse_integer_arg(value)?;
+        if value < 0 {
+            return Err(String::from("ERR The LIMIT argument must be >= 0."));
+        }
+        if !approximate {
+            return Err(String::from("ERR syntax error, LIMIT cannot be used without the special ~ option"));
+        }
+        limit = Some(value as u64);
+        index += 2;
+    }
+
+    Ok((StreamTrimArgs { strategy, approximate, limit }, index))
+}
+
+// `-` is the smallest ID, `(` excludes the ID, and a missing sequence number defaults to 0
+pub(crate) fn parse_range_start(arg: &str) -> Result<StreamId, String> {
+    match arg {
+        "-" => Ok(StreamId::MIN),
+        "+" => Ok(StreamId::MAX),
+        _ => match arg.strip_prefix('(') {
+            Some(id) => StreamId::parse(id, 0)
+                .map_err(stream_error)?
+                .next()
+                .ok_or_else(|| String::from("ERR invalid start ID for the interval")),
+            None => StreamId::parse(arg, 0).map_err(stream_error),
+        },
+    }
+}
+
+// `+` is the greatest ID, `(` excludes the ID, and a missing sequence number includes the whole millisecond
+pub(crate) fn parse_range_end(arg: &str) -> Result<StreamId, String> {
+    match arg {
+        "-" => Ok(StreamId::MIN),
+        "+" => Ok(StreamId::MAX),
+        _ => match arg.strip_prefix('(') {
+            Some(id) => StreamId::parse(id, u64::MAX)
+                .map_err(stream_error)?
+                .prev()
+                .ok_or_else(|| String::from("ERR invalid end ID for the interval")),
+            None => StreamId::parse(arg, u64::MAX).map_err(stream_error),
+        },
+    }
+}
+
+pub(crate) fn format_stream_entry(id: &StreamId, fields: &StreamFields) -> RESPOutput {
+    let mut field_values: Vec<RESPOutput> = Vec::new();
+    for (field, value) in fields {
+        field_values.push(RESPOutput::BulkString(field.clone()));
+        field_values.push(RESPOutput::BulkString(value.clone()));
+    }
+    RESPOutput::Array(vec![RESPOutput::BulkString(id.to_string()), RESPOutput::Array(field_values)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| String::from(*arg)).collect()
+    }
+
+    #[test]
+    fn parse_stream_trim_args_success() {
+        let args = to_args(&["MAXLEN", "~", "10", "LIMIT", "5", "*"]);
+        let (trim_args, index) = parse_stream_trim_args(&args, 0).unwrap();
+        assert_eq!(
+            trim_args,
+            StreamTrimArgs {
+                strategy: StreamTrimStrategy::MaxLen(10),
+                approximate: true,
+                limit: Some(5),
+            }
+        );
+        assert_eq!(index, 5);
+
+        let args = to_args(&["key", "MINID", "5-1"]);
+        let (trim_args, index) = parse_stream_trim_args(&args, 1).unwrap();
+        assert_eq!(trim_args.strategy, StreamTrimStrategy::MinId(StreamId::new(5, 1)));
+        assert!(!trim_args.approximate);
+        assert_eq!(index, 3);
+    }
+
+    #[test]
+    fn parse_stream_trim_args_error() {
+        let input = [
+            to_args(&["MAXLEN", "-1"]),
+            to_args(&["MAXLEN", "=", "10", "LIMIT", "5"]),
+            to_args(&["MINID", "abc"]),
+            to_args(&["MAXLEN", "~"]),
+        ];
+
+        for inp in input.iter() {
+            assert!(parse_stream_trim_args(inp, 0).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_range_bounds() {
+        assert_eq!(parse_range_start("-"), Ok(StreamId::MIN));
+        assert_eq!(parse_range_start("5"), Ok(StreamId::new(5, 0)));
+        assert_eq!(parse_range_start("(5-1"), Ok(StreamId::new(5, 2)));
+        assert_eq!(parse_range_end("+"), Ok(StreamId::MAX));
+        assert_eq!(parse_range_end("5"), Ok(StreamId::new(5, u64::MAX)));
+        assert_eq!(parse_range_end("(5-0"), Ok(StreamId::new(4, u64::MAX)));
+        assert!(parse_range_start("(18446744073709551615-18446744073709551615").is_err());
+        assert!(parse_range_end("(0-0").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use std::io::Write;
+
+use crate::request_response::client_input::{ClientInput, HandleClientInput};
+use crate::request_response::command::Command;
+use crate::request_response::command_args::wrong_number_of_arguments;
+use crate::request_response::parsed_command::ParsedCommand;
+use crate::request_response::response_helper;
+use crate::store::command_lock::with_command_lock;
+use crate::store::redis::{RedisStore, Store};
+
+// Commands queued after MULTI, which are executed by EXEC
+#[derive(Debug, Default)]
+pub(crate) struct Transaction {
+    commands: Vec<ParsedCommand>,
+    // Set when a command is rejected while queueing, so that EXEC discards the transaction
+    has_errors: bool,
+}
+
+impl ClientInput {
+    pub fn is_in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    // Number of commands queued in the transaction, if there is one
+    pub(crate) fn queued_command_count(&self) -> Option<usize> {
+        self.transaction.as_ref().map(|transaction| transaction.commands.len())
+    }
+
+    // Commands that are executed immediately instead of being queued in a transaction
+    pub(crate) fn is_transaction_command(command: &Command) -> bool {
+        matches!(
+            command,
+            Command::MULTI | Command::EXEC | Command::DISCARD | Command::WATCH | Command::QUIT
+        )
+    }
+
+    // Makes EXEC fail, i.e. when an unknown command is sent in a transaction
+    pub(crate) fn flag_transaction(&mut self) {
+        if let Some(transaction) = self.transaction.as_mut() {
+            transaction.has_errors = true;
+        }
+    }
+
+    // Only the number of arguments is checked before queueing, like redis, which the caller does
+    pub(crate) fn queue_command<T: Write>(&mut self, stream: &mut T, parsed: ParsedCommand) {
+        if let Some(transaction) = self.transaction.as_mut() {
+            transaction.commands.push(parsed);
+        }
+        response_helper::send_simple_string_response(stream, "QUEUED");
+    }
+
+    // multi
+    pub(crate) fn respond_multi<T: Write>(&mut self, stream: &mut T) {
+        if self.is_in_transaction() {
+            return response_helper::send_error_response(stream, "ERR MULTI calls can not be nested");
+        }
+
+        self.transaction = Some(Transaction::default());
+        response_helper::send_simple_string_response(stream, "OK");
+    }
+
+    // discard
+    pub(crate) fn respond_discard<T: Write>(&mut self, stream: &mut T) {
+        if self.transaction.take().is_none() {
+            return response_helper::send_error_response(stream, "ERR DISCARD without MULTI");
+        }
+        self.unwatch_all();
+        response_helper::send_simple_string_response(stream, "OK");
+    }
+
+    // watch <key> [key ...]
+    pub(crate) fn respond_watch<T: Write>(&mut self, stream: &mut T, args: &[String]) {
+        if self.is_in_transaction() {
+            return response_helper::send_error_response(stream, "ERR WATCH inside MULTI is not allowed");
+        }
+        if args.is_empty() {
+            return response_helper::send_error_response(stream, &wrong_number_of_arguments("watch"));
+        }
+
+        for key in args {
+            if self.watched_keys.iter().any(|(db, watched, _)| *db == self.db && watched == key) {
+                continue;
+            }
+            self.expire_if_needed(key);
+            let version = self.store().watch(key);
+            self.watched_keys.push((self.db, key.clone(), version));
+        }
+        response_helper::send_simple_string_response(stream, "OK");
+    }
+
+    // unwatch
+    pub(crate) fn respond_unwatch<T: Write>(&mut self, stream: &mut T) {
+        self.unwatch_all();
+        response_helper::send_simple_string_response(stream, "OK");
+    }
+
+    // exec
+    pub(crate) fn respond_exec<T: Write>(&mut self, stream: &mut T) {
+        let transaction = match self.transaction.take() {
+            Some(transaction) => transaction,
+            None => return response_helper::send_error_response(stream, "ERR EXEC without MULTI"),
+        };
+        // Keys are only watched until the next EXEC, whether it executes the transaction or not
+        let watched_keys = std::mem::take(&mut self.watched_keys);
+
+        if transaction.has_errors {
+            ClientInput::unwatch_keys(&watched_keys);
+            return response_helper::send_error_response(
+                stream,
+                "EXECABORT Transaction discarded because of previous errors.",
+            );
+        }
+
+        // Replies are encoded while the command lock is held and written once it is released
+        let replies = self.execute_transaction(transaction, &watched_keys);
+        ClientInput::unwatch_keys(&watched_keys);
+        match replies {
+            Some(replies) => response_helper::send_encoded_array_response(stream, &replies),
+            None => response_helper::send_null_array_response(stream),
+        }
+    }
+
+    // Stops watching every key, i.e. when the connection is closed
+    pub(crate) fn unwatch_all(&mut self) {
+        let watched_keys = std::mem::take(&mut self.watched_keys);
+        ClientInput::unwatch_keys(&watched_keys);
+    }
+
+    // Databases only keep versions of keys while some connection watches them
+    fn unwatch_keys(watched_keys: &[(usize, String, u64)]) {
+        if watched_keys.is_empty() {
+            return;
+        }
+        with_command_lock(|| {
+            for (db, key, _) in watched_keys {
+                // The databases may have been reset since, i.e. by tests
+                if *db < RedisStore::database_count() {
+                    RedisStore::get_db(*db).unwatch(key);
+                }
+            }
+        })
+    }
+
+    // Keys that expired since WATCH count as modified, so they are expired before comparing
+    fn is_any_key_modified(&self, watched_keys: &[(usize, String, u64)]) -> bool {
+        watched_keys.iter().any(|(db, key, version)| {
+            let store = RedisStore::get_db(*db);
+            if store.is_key_expired(key) {
+                store.delete_expired(key);
+            }
+            store.key_version(key) != *version
+        })
+    }
+
+    // Commands of other clients cannot run in between, since the command lock is held throughout.
+    // Nothing is executed if a watched key was modified
+    fn execute_transaction(
+        &mut self,
+        transaction: Transaction,
+        watched_keys: &[(usize, String, u64)],
+    ) -> Option<Vec<Vec<u8>>> {
+        with_command_lock(|| {
+            if self.is_any_key_modified(watched_keys) {
+                return None;
+            }
+            self.executing_transaction = true;
+            let replies = transaction
+                .commands
+                .into_iter()
+                .map(|parsed| {
+                    let mut reply: Vec<u8> = Vec::new();
+                    self.respond(&mut reply, parsed);
+                    reply
+                })
+                .collect();
+            self.executing_transaction = false;
+            Some(replies)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed_command(command: Command, args: &[&str]) -> ParsedCommand {
+        ParsedCommand {
+            command: Some(command),
+            args: args.iter().map(|arg| String::from(*arg)).collect(),
+        }
+    }
+
+    #[test]
+    fn wrong_number_of_arguments_flags_transaction() {
+        let mut client_input = ClientInput::new();
+        let mut stream: Vec<u8> = Vec::new();
+        client_input.respond_multi(&mut stream);
+
+        let mut stream: Vec<u8> = Vec::new();
+        client_input.respond(&mut stream, parsed_command(Command::ECHO, &["hello"]));
+        assert_eq!(stream, b"+QUEUED\r\n");
+        assert!(!client_input.transaction.as_ref().unwrap().has_errors);
+
+        let mut stream: Vec<u8> = Vec::new();
+        client_input.respond(&mut stream, parsed_command(Command::GET, &[]));
+        assert_eq!(stream, b"-ERR wrong number of arguments for 'get' command\r\n");
+
+        let transaction = client_input.transaction.as_ref().unwrap();
+        assert!(transaction.has_errors);
+        assert_eq!(transaction.commands.len(), 1);
+    }
+}
@@ -0,0 +1,92 @@
+use std::io;
+use std::net::TcpStream;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+// Socket options from the C headers, since std does not expose keepalive. The manifest can't take
+// a dependency such as socket2, so only Linux, whose values are stable, is supported
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::os::raw::{c_int, c_void};
+
+    pub const SOL_SOCKET: c_int = 1;
+    pub const SO_KEEPALIVE: c_int = 9;
+    pub const IPPROTO_TCP: c_int = 6;
+    pub const TCP_KEEPIDLE: c_int = 4;
+    pub const TCP_KEEPINTVL: c_int = 5;
+    pub const TCP_KEEPCNT: c_int = 6;
+
+    extern "C" {
+        pub fn setsockopt(socket: c_int, level: c_int, name: c_int, value: *const c_void, len: u32) -> c_int;
+        #[cfg(test)]
+        pub fn getsockopt(socket: c_int, level: c_int, name: c_int, value: *mut c_void, len: *mut u32) -> c_int;
+    }
+}
+
+// Number of unanswered probes after which the connection is closed, like redis
+#[cfg(target_os = "linux")]
+const KEEPALIVE_PROBES: i32 = 3;
+
+/// Sends keepalive probes after `interval` without traffic, so that dead peers are detected.
+/// Other platforms keep their default behaviour
+#[cfg(target_os = "linux")]
+pub fn set_keepalive(stream: &TcpStream, interval: Option<Duration>) -> io::Result<()> {
+    let socket = stream.as_raw_fd();
+    let interval = match interval {
+        Some(interval) => interval.as_secs().clamp(1, i32::MAX as u64) as i32,
+        None => return set_option(socket, sys::SOL_SOCKET, sys::SO_KEEPALIVE, 0),
+    };
+
+    set_option(socket, sys::SOL_SOCKET, sys::SO_KEEPALIVE, 1)?;
+    set_option(socket, sys::IPPROTO_TCP, sys::TCP_KEEPIDLE, interval)?;
+    // Probes are sent faster than the first one, so that a dead peer is closed after about twice the interval
+    set_option(socket, sys::IPPROTO_TCP, sys::TCP_KEEPINTVL, (interval / KEEPALIVE_PROBES).max(1))?;
+    set_option(socket, sys::IPPROTO_TCP, sys::TCP_KEEPCNT, KEEPALIVE_PROBES)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_keepalive(_stream: &TcpStream, _interval: Option<Duration>) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_option(socket: i32, level: i32, name: i32, value: i32) -> io::Result<()> {
+    let size = std::mem::size_of::<i32>() as u32;
+    let result = unsafe { sys::setsockopt(socket, level, name, &value as *const i32 as *const _, size) };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn get_option(stream: &TcpStream, level: i32, name: i32) -> i32 {
+        let mut value: i32 = 0;
+        let mut size = std::mem::size_of::<i32>() as u32;
+        let result = unsafe {
+            sys::getsockopt(stream.as_raw_fd(), level, name, &mut value as *mut i32 as *mut _, &mut size)
+        };
+        assert_eq!(result, 0);
+        value
+    }
+
+    #[test]
+    fn keepalive_is_enabled_with_the_interval() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+        set_keepalive(&stream, Some(Duration::from_secs(300))).unwrap();
+        assert_eq!(get_option(&stream, sys::SOL_SOCKET, sys::SO_KEEPALIVE), 1);
+        assert_eq!(get_option(&stream, sys::IPPROTO_TCP, sys::TCP_KEEPIDLE), 300);
+        assert_eq!(get_option(&stream, sys::IPPROTO_TCP, sys::TCP_KEEPINTVL), 100);
+        assert_eq!(get_option(&stream, sys::IPPROTO_TCP, sys::TCP_KEEPCNT), 3);
+
+        set_keepalive(&stream, None).unwrap();
+        assert_eq!(get_option(&stream, sys::SOL_SOCKET, sys::SO_KEEPALIVE), 0);
+    }
+}
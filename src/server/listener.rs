@@ -0,0 +1,159 @@
+use std::io::ErrorKind;
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+#[cfg(unix)]
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+
+use crate::config::config::Config;
+use crate::handle_connection;
+
+// A socket that connections are accepted on
+#[derive(Debug)]
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    /// The address that the listener is bound to, e.g. to connect to a port chosen by the OS
+    pub fn address(&self) -> String {
+        match self {
+            Listener::Tcp(listener) => listener
+                .local_addr()
+                .map_or_else(|e| e.to_string(), |address| address.to_string()),
+            #[cfg(unix)]
+            Listener::Unix(listener) => listener
+                .local_addr()
+                .ok()
+                .and_then(|address| address.as_pathname().map(|path| path.display().to_string()))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Accepts connections in a new thread, and handles each of them in its own thread
+    pub fn spawn(self) -> JoinHandle<()> {
+        self.spawn_until(Arc::new(AtomicBool::new(false)))
+    }
+
+    /// Like spawn, until `stop` is set. The thread waits for connections, so it only ends once
+    /// another one arrives, e.g. one made to wake it up
+    pub fn spawn_until(self, stop: Arc<AtomicBool>) -> JoinHandle<()> {
+        thread::spawn(move || match self {
+            Listener::Tcp(listener) => {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    match stream {
+                        Ok(stream) => {
+                            // Replies are written as soon as they are ready, like redis, instead of
+                            // waiting for the ack of the previous one when commands are pipelined
+                            if let Err(e) = stream.set_nodelay(true) {
+                                println!("Unable to set TCP_NODELAY: {}", e);
+                            }
+                            thread::spawn(move || handle_connection(stream));
+                        }
+                        Err(e) => println!("Unable to accept connection: {}", e),
+                    }
+                }
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener) => {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    match stream {
+                        Ok(stream) => {
+                            thread::spawn(move || handle_connection(stream));
+                        }
+                        Err(e) => println!("Unable to accept connection: {}", e),
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Binds every address in `bind` on `port`, and the Unix socket if configured
+pub fn bind_listeners(config: &Config) -> Result<Vec<Listener>, String> {
+    let port = if config.port != 0 { Some(config.port) } else { None };
+    bind_listeners_on(config, port)
+}
+
+// Like bind_listeners, with TCP on `port` instead, or not at all if None. Port 0 lets the OS
+// choose a free port for the first address, which the others then share
+pub(crate) fn bind_listeners_on(config: &Config, port: Option<u16>) -> Result<Vec<Listener>, String> {
+    let mut listeners = Vec::new();
+
+    if let Some(mut port) = port {
+        for address in config.bind.iter() {
+            // Addresses prefixed with `-` are skipped if they are not available, like redis
+            let (address, is_optional) = match address.strip_prefix('-') {
+                Some(address) => (address, true),
+                None => (address.as_str(), false),
+            };
+            let host = match address {
+                "*" => "0.0.0.0",
+                "::*" => "::",
+                _ => address,
+            };
+
+            match TcpListener::bind((host, port)) {
+                Ok(listener) => {
+                    if port == 0 {
+                        port = listener.local_addr().map_or(0, |address| address.port());
+                    }
+                    listeners.push(Listener::Tcp(listener));
+                }
+                Err(e) if is_optional && e.kind() == ErrorKind::AddrNotAvailable => {
+                    println!("Skipping unavailable address {}", address);
+                }
+                Err(e) => {
+                    return Err(format!(
+                        "Could not create server TCP listening socket {}:{}: {}",
+                        address, port, e
+                    ))
+                }
+            }
+        }
+    }
+
+    if !config.unixsocket.is_empty() {
+        listeners.push(bind_unix_socket(config)?);
+    }
+
+    if listeners.is_empty() {
+        return Err(String::from("Configured to not listen anywhere, exiting."));
+    }
+    Ok(listeners)
+}
+
+#[cfg(unix)]
+fn bind_unix_socket(config: &Config) -> Result<Listener, String> {
+    let path = &config.unixsocket;
+    let error = |e: std::io::Error| format!("Failed opening Unix socket {}: {}", path, e);
+
+    // A socket file left behind by a previous run would make binding fail
+    if fs::metadata(path).is_ok() {
+        fs::remove_file(path).map_err(error)?;
+    }
+    let listener = UnixListener::bind(path).map_err(error)?;
+    if config.unixsocketperm != 0 {
+        fs::set_permissions(path, fs::Permissions::from_mode(config.unixsocketperm)).map_err(error)?;
+    }
+    Ok(Listener::Unix(listener))
+}
+
+#[cfg(not(unix))]
+fn bind_unix_socket(_config: &Config) -> Result<Listener, String> {
+    Err(String::from("Unix sockets are not supported on this platform"))
+}
@@ -0,0 +1,4 @@
+pub mod keepalive;
+pub mod listener;
+#[allow(clippy::module_inception)]
+pub mod server;
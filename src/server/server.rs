@@ -0,0 +1,236 @@
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crate::acl::acl::{with_acl, Acl};
+use crate::client::registry::with_clients;
+use crate::config::config::{with_config, Config};
+use crate::pubsub::pubsub::with_pubsub;
+use crate::server::listener::{bind_listeners_on, Listener};
+use crate::store::redis::RedisStore;
+
+// Set while a server runs. The configuration, ACL users, store and clients are those of the process,
+// so a second server would replace those of the first
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Configures a server before it starts, e.g. on a free port for a test:
+/// ```no_run
+/// use redis_starter_rust::client::connection::RedisClient;
+/// use redis_starter_rust::server::server::Server;
+///
+/// let server = Server::builder().port(0).start().unwrap();
+/// let mut client = RedisClient::connect(server.address()).unwrap();
+/// assert_eq!(client.ping(), Ok(String::from("PONG")));
+/// server.shutdown();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServerBuilder {
+    config: Config,
+    // TCP port, where 0 lets the OS choose a free one, or None to only listen on the Unix socket
+    port: Option<u16>,
+}
+
+impl ServerBuilder {
+    /// Replaces the configuration, including the port unless `port` is called after it
+    pub fn config(mut self, config: Config) -> ServerBuilder {
+        self.port = if config.port != 0 { Some(config.port) } else { None };
+        self.config = config;
+        self
+    }
+
+    /// Port of every bind address. Unlike the port parameter, 0 picks a free port instead of
+    /// disabling TCP
+    pub fn port(mut self, port: u16) -> ServerBuilder {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn bind(mut self, addresses: &[&str]) -> ServerBuilder {
+        self.config.bind = addresses.iter().map(|address| String::from(*address)).collect();
+        self
+    }
+
+    pub fn unixsocket(mut self, path: &str) -> ServerBuilder {
+        self.config.unixsocket = String::from(path);
+        self
+    }
+
+    /// Binds the listeners and accepts connections in the background. The configuration, ACL
+    /// users, store and clients are those of the process, so only one server runs in a process at
+    /// a time, and starting another fails until it is shut down. Each server starts from its own
+    /// configuration with empty databases
+    pub fn start(self) -> Result<Server, String> {
+        if RUNNING.swap(true, Ordering::SeqCst) {
+            return Err(String::from("ERR another server is already running in this process"));
+        }
+        let result = self.start_listeners();
+        if result.is_err() {
+            RUNNING.store(false, Ordering::SeqCst);
+        }
+        result
+    }
+
+    fn start_listeners(self) -> Result<Server, String> {
+        let mut config = self.config;
+        let listeners = bind_listeners_on(&config, self.port)?;
+
+        // CONFIG GET port shows the port that the OS chose
+        if let Some(Listener::Tcp(listener)) = listeners.first() {
+            config.port = listener.local_addr().map_or(0, |address| address.port());
+        }
+        // Nothing is kept from a server that ran before this one in the process
+        with_config(|global| *global = config.clone());
+        with_acl(|acl| *acl = Acl::new());
+        with_pubsub(|pubsub| pubsub.clear());
+        with_clients(|clients| clients.clear());
+        RedisStore::replace_databases();
+
+        // Users in the ACL file take precedence over requirepass
+        with_acl(|acl| acl.set_default_password(&config.requirepass));
+        if !config.aclfile.is_empty() {
+            with_acl(|acl| acl.load_file(&config.aclfile))?;
+        }
+
+        let handle = ServerHandle {
+            stop: Arc::new(AtomicBool::new(false)),
+            addresses: Arc::new(listeners.iter().map(Listener::address).collect()),
+            unixsocket: config.unixsocket.clone(),
+        };
+        let threads = listeners
+            .into_iter()
+            .map(|listener| listener.spawn_until(Arc::clone(&handle.stop)))
+            .collect();
+        Ok(Server { handle, threads })
+    }
+}
+
+/// A server that accepts connections in background threads until it is shut down or dropped
+#[derive(Debug)]
+pub struct Server {
+    handle: ServerHandle,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Server {
+    /// Listens on 127.0.0.1:6379 unless configured otherwise
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder {
+            config: Config::new(),
+            port: Some(Config::new().port),
+        }
+    }
+
+    /// Address of the first listener, e.g. 127.0.0.1:43121, or the path of the Unix socket
+    pub fn address(&self) -> String {
+        self.handle.addresses[0].clone()
+    }
+
+    pub fn addresses(&self) -> &[String] {
+        &self.handle.addresses
+    }
+
+    /// A handle that shuts the server down from another thread
+    pub fn shutdown_handle(&self) -> ServerHandle {
+        self.handle.clone()
+    }
+
+    /// Stops accepting connections, closes the ones that it accepted, and waits for the listeners
+    pub fn shutdown(mut self) {
+        self.handle.shutdown();
+        self.wait_for_listeners();
+    }
+
+    /// Waits until the server is shut down with its handle
+    pub fn wait(mut self) {
+        self.wait_for_listeners();
+    }
+
+    fn wait_for_listeners(&mut self) {
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.handle.shutdown();
+    }
+}
+
+/// Shuts down a server, e.g. from a thread other than the one that owns it
+#[derive(Debug, Clone)]
+pub struct ServerHandle {
+    stop: Arc<AtomicBool>,
+    addresses: Arc<Vec<String>>,
+    unixsocket: String,
+}
+
+impl ServerHandle {
+    pub fn is_shutdown(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+
+    /// Does nothing if the server was already shut down
+    pub fn shutdown(&self) {
+        if self.stop.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        // Listeners wait for a connection before they see that they should stop
+        for address in self.addresses.iter() {
+            self.wake_up(address);
+        }
+
+        // Only the connections that this server accepted are closed
+        let ports: Vec<u16> = self.addresses.iter().filter_map(|address| port(address)).collect();
+        let unix_address = format!("{}:0", self.unixsocket);
+        with_clients(|clients| {
+            let ids: Vec<_> = clients
+                .clients()
+                .filter(|client| match client.is_unix_socket {
+                    true => !self.unixsocket.is_empty() && client.laddr == unix_address,
+                    false => port(&client.laddr).map_or(false, |port| ports.contains(&port)),
+                })
+                .map(|client| client.id)
+                .collect();
+            for id in ids {
+                clients.kill(id);
+            }
+        });
+
+        if !self.unixsocket.is_empty() {
+            let _ = fs::remove_file(&self.unixsocket);
+        }
+        RUNNING.store(false, Ordering::SeqCst);
+    }
+
+    fn wake_up(&self, address: &str) {
+        match address.parse::<SocketAddr>() {
+            // A listener on every interface is reached on the loopback interface
+            Ok(mut address) => {
+                if address.ip().is_unspecified() {
+                    address.set_ip(match address.ip() {
+                        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+                    });
+                }
+                let _ = TcpStream::connect(address);
+            }
+            #[cfg(unix)]
+            Err(_) => {
+                let _ = UnixStream::connect(address);
+            }
+            #[cfg(not(unix))]
+            Err(_) => {}
+        }
+    }
+}
+
+fn port(address: &str) -> Option<u16> {
+    address.parse::<SocketAddr>().ok().map(|address| address.port())
+}
@@ -0,0 +1,78 @@
+use std::cell::RefCell;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::util::global::Global;
+
+// Commands of all connections are executed one at a time while holding this lock,
+// so that a transaction is applied without commands of other clients in between
+static COMMAND_LOCK: Global<Mutex<()>> = Global::new();
+
+thread_local! {
+    // The guard of the connection thread that is executing a command
+    static GUARD: RefCell<Option<MutexGuard<'static, ()>>> = RefCell::new(None);
+}
+
+fn command_lock() -> &'static Mutex<()> {
+    COMMAND_LOCK.get_or_init(|| Mutex::new(()))
+}
+
+/// Runs the function while holding the command lock. Nested calls, i.e. for the commands
+/// of EXEC, run within the lock that is already held
+pub fn with_command_lock<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    if GUARD.with(|guard| guard.borrow().is_some()) {
+        return f();
+    }
+
+    let guard = command_lock().lock().unwrap_or_else(|e| e.into_inner());
+    GUARD.with(|cell| *cell.borrow_mut() = Some(guard));
+    let result = f();
+    GUARD.with(|cell| cell.borrow_mut().take());
+    result
+}
+
+/// Runs the function with the command lock released if it is held, i.e. while a blocking
+/// command waits so that other clients can execute commands in the meantime
+pub fn without_command_lock<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let guard = GUARD.with(|cell| cell.borrow_mut().take());
+    let was_held = guard.is_some();
+    drop(guard);
+
+    let result = f();
+    if was_held {
+        let guard = command_lock().lock().unwrap_or_else(|e| e.into_inner());
+        GUARD.with(|cell| *cell.borrow_mut() = Some(guard));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn with_command_lock_is_reentrant() {
+        let result = with_command_lock(|| with_command_lock(|| 1) + 1);
+        assert_eq!(result, 2);
+    }
+
+    #[test]
+    fn without_command_lock_lets_other_threads_execute() {
+        with_command_lock(|| {
+            let (sender, receiver) = channel();
+            let handle = thread::spawn(move || with_command_lock(|| sender.send(()).unwrap()));
+
+            assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+            without_command_lock(|| receiver.recv_timeout(Duration::from_secs(5)).unwrap());
+            handle.join().unwrap();
+        });
+    }
+}
@@ -0,0 +1,449 @@
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::store::redis::{RedisStore, Store, StoreError};
+use crate::store::redis_data_structure::DataType;
+use crate::store::redis_operation::SetOptionalArgs;
+use crate::util::glob::glob_match;
+
+pub type DbResult<T> = Result<T, StoreError>;
+
+/// A store of keys with the data types and expiry of the server, used directly in the process
+/// instead of through commands. Every Db has its own keys, which the server and other Dbs don't
+/// see, and it publishes no keyspace events:
+/// ```
+/// use std::time::Duration;
+/// use redis_starter_rust::store::db::Db;
+///
+/// let mut db = Db::new();
+/// db.set_with_ttl("session", "token", Duration::from_secs(60)).unwrap();
+/// assert_eq!(db.get("session"), Ok(Some(String::from("token"))));
+///
+/// db.rpush("queue", &["first", "second"]).unwrap();
+/// assert_eq!(db.lpop("queue"), Ok(Some(String::from("first"))));
+/// assert!(db.get("queue").is_err());
+/// ```
+#[derive(Debug)]
+pub struct Db {
+    store: RedisStore,
+}
+
+impl Default for Db {
+    fn default() -> Self {
+        Db::new()
+    }
+}
+
+impl Db {
+    pub fn new() -> Db {
+        Db {
+            store: RedisStore::new_embedded(),
+        }
+    }
+
+    /// Returns None if key is not present, or an error if key holds another data type
+    pub fn get(&mut self, key: &str) -> DbResult<Option<String>> {
+        self.delete_if_expired(key);
+        match self.store.get_data(key) {
+            None => Ok(None),
+            Some(DataType::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    /// Like SET, replaces the key whatever data type it holds, and removes its expiry
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.store.set(key, value, &None);
+    }
+
+    /// Same as set, but the key expires after `ttl`, which has to be at least a millisecond
+    pub fn set_with_ttl(&mut self, key: &str, value: &str, ttl: Duration) -> DbResult<()> {
+        let args = SetOptionalArgs {
+            expire_in_ms: Some(Db::ttl_millis(ttl)?),
+        };
+        self.store.set(key, value, &Some(args));
+        Ok(())
+    }
+
+    pub fn exists(&mut self, key: &str) -> bool {
+        self.delete_if_expired(key);
+        self.store.get_data(key).is_some()
+    }
+
+    /// Returns whether the key was present
+    pub fn delete(&mut self, key: &str) -> bool {
+        self.delete_if_expired(key);
+        self.store.delete(vec![key]) == 1
+    }
+
+    /// Name of the data type of the key, e.g. "list", or None if key is not present
+    pub fn key_type(&mut self, key: &str) -> Option<&'static str> {
+        self.delete_if_expired(key);
+        self.store.get_data(key).map(DataType::name)
+    }
+
+    /// Time left before the key expires, or None if key is not present or never expires
+    pub fn ttl(&mut self, key: &str) -> Option<Duration> {
+        self.delete_if_expired(key);
+        let expire_at = self.store.expire_at(key)?;
+        Some((expire_at - Utc::now()).to_std().unwrap_or_default())
+    }
+
+    /// Makes the key expire after `ttl`, which has to be at least a millisecond. Returns false if
+    /// key is not present
+    pub fn expire(&mut self, key: &str, ttl: Duration) -> DbResult<bool> {
+        let ttl = chrono::Duration::milliseconds(Db::ttl_millis(ttl)? as i64);
+        self.delete_if_expired(key);
+        Ok(self.store.set_expire_at(key, Utc::now().checked_add_signed(ttl)))
+    }
+
+    /// Removes the expiry of the key. Returns false if key is not present or never expires
+    pub fn persist(&mut self, key: &str) -> bool {
+        self.delete_if_expired(key);
+        self.store.expire_at(key).is_some() && self.store.set_expire_at(key, None)
+    }
+
+    /// Number of keys that have not expired
+    pub fn len(&mut self) -> usize {
+        self.delete_expired_keys();
+        self.store.len()
+    }
+
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Keys that match the glob-style pattern, like KEYS, in no particular order
+    pub fn keys(&mut self, pattern: &str) -> Vec<String> {
+        self.delete_expired_keys();
+        self.store
+            .iter()
+            .filter(|(key, _)| glob_match(pattern, key))
+            .map(|(key, _)| String::from(key))
+            .collect()
+    }
+
+    /// Iterates over keys that have not expired and their data, in no particular order
+    pub fn iter(&mut self) -> impl Iterator<Item = (&str, &DataType)> {
+        self.delete_expired_keys();
+        self.store.iter()
+    }
+
+    /// Removes every key
+    pub fn flush(&mut self) {
+        self.store.flush(false);
+    }
+
+    /// Pushes the elements one after the other at the head of the list. Returns the length of
+    /// the list
+    pub fn lpush(&mut self, key: &str, elements: &[&str]) -> DbResult<usize> {
+        self.delete_if_expired(key);
+        let list = self.store.get_list_mut(key, true)?.unwrap();
+        for element in elements {
+            list.push_front(element);
+        }
+        let len = list.len();
+        self.delete_if_empty(key);
+        Ok(len)
+    }
+
+    /// Pushes the elements at the tail of the list. Returns the length of the list
+    pub fn rpush(&mut self, key: &str, elements: &[&str]) -> DbResult<usize> {
+        self.delete_if_expired(key);
+        let list = self.store.get_list_mut(key, true)?.unwrap();
+        for element in elements {
+            list.push_back(element);
+        }
+        let len = list.len();
+        self.delete_if_empty(key);
+        Ok(len)
+    }
+
+    pub fn lpop(&mut self, key: &str) -> DbResult<Option<String>> {
+        self.delete_if_expired(key);
+        let element = match self.store.get_list_mut(key, false)? {
+            None => return Ok(None),
+            Some(list) => list.pop_front(),
+        };
+        self.delete_if_empty(key);
+        Ok(element)
+    }
+
+    pub fn rpop(&mut self, key: &str) -> DbResult<Option<String>> {
+        self.delete_if_expired(key);
+        let element = match self.store.get_list_mut(key, false)? {
+            None => return Ok(None),
+            Some(list) => list.pop_back(),
+        };
+        self.delete_if_empty(key);
+        Ok(element)
+    }
+
+    /// Elements from start to stop inclusive, where negative indexes count from the end
+    pub fn lrange(&mut self, key: &str, start: i64, stop: i64) -> DbResult<Vec<String>> {
+        self.delete_if_expired(key);
+        Ok(match self.store.get_list(key)? {
+            None => vec![],
+            Some(list) => list.range(start, stop).map(String::from).collect(),
+        })
+    }
+
+    pub fn llen(&mut self, key: &str) -> DbResult<usize> {
+        self.delete_if_expired(key);
+        Ok(self.store.get_list(key)?.map_or(0, |list| list.len()))
+    }
+
+    /// Returns whether the field is new, rather than given another value
+    pub fn hset(&mut self, key: &str, field: &str, value: &str) -> DbResult<bool> {
+        self.delete_if_expired(key);
+        Ok(self.store.get_hash_mut(key, true)?.unwrap().insert(field, value))
+    }
+
+    pub fn hget(&mut self, key: &str, field: &str) -> DbResult<Option<String>> {
+        self.delete_if_expired(key);
+        Ok(self
+            .store
+            .get_hash(key)?
+            .and_then(|hash| hash.get(field))
+            .map(String::from))
+    }
+
+    /// Returns whether the field was present
+    pub fn hdel(&mut self, key: &str, field: &str) -> DbResult<bool> {
+        self.delete_if_expired(key);
+        let removed = match self.store.get_hash_mut(key, false)? {
+            None => return Ok(false),
+            Some(hash) => hash.remove(field).is_some(),
+        };
+        self.delete_if_empty(key);
+        Ok(removed)
+    }
+
+    /// Fields and their values in no particular order
+    pub fn hgetall(&mut self, key: &str) -> DbResult<Vec<(String, String)>> {
+        self.delete_if_expired(key);
+        Ok(match self.store.get_hash(key)? {
+            None => vec![],
+            Some(hash) => hash
+                .iter()
+                .map(|(field, value)| (String::from(field), String::from(value)))
+                .collect(),
+        })
+    }
+
+    pub fn hlen(&mut self, key: &str) -> DbResult<usize> {
+        self.delete_if_expired(key);
+        Ok(self.store.get_hash(key)?.map_or(0, |hash| hash.len()))
+    }
+
+    /// Returns the number of members that are new
+    pub fn sadd(&mut self, key: &str, members: &[&str]) -> DbResult<usize> {
+        self.delete_if_expired(key);
+        let set = self.store.get_set_mut(key, true)?.unwrap();
+        let added = members.iter().filter(|member| set.insert(member)).count();
+        self.delete_if_empty(key);
+        Ok(added)
+    }
+
+    /// Returns the number of members that were present
+    pub fn srem(&mut self, key: &str, members: &[&str]) -> DbResult<usize> {
+        self.delete_if_expired(key);
+        let removed = match self.store.get_set_mut(key, false)? {
+            None => return Ok(0),
+            Some(set) => members.iter().filter(|member| set.remove(member)).count(),
+        };
+        self.delete_if_empty(key);
+        Ok(removed)
+    }
+
+    pub fn sismember(&mut self, key: &str, member: &str) -> DbResult<bool> {
+        self.delete_if_expired(key);
+        Ok(self.store.get_set(key)?.map_or(false, |set| set.contains(member)))
+    }
+
+    /// Members in no particular order
+    pub fn smembers(&mut self, key: &str) -> DbResult<Vec<String>> {
+        self.delete_if_expired(key);
+        Ok(match self.store.get_set(key)? {
+            None => vec![],
+            Some(set) => set.iter().map(String::from).collect(),
+        })
+    }
+
+    pub fn scard(&mut self, key: &str) -> DbResult<usize> {
+        self.delete_if_expired(key);
+        Ok(self.store.get_set(key)?.map_or(0, |set| set.len()))
+    }
+
+    // Keys expire lazily, when they are next accessed
+    fn delete_if_expired(&mut self, key: &str) {
+        if self.store.expire_at(key).map_or(false, |expire_at| expire_at < Utc::now()) {
+            self.store.delete_expired(key);
+        }
+    }
+
+    fn delete_expired_keys(&mut self) {
+        let now = Utc::now();
+        let expired: Vec<String> = self
+            .store
+            .iter()
+            .map(|(key, _)| String::from(key))
+            .filter(|key| self.store.expire_at(key).map_or(false, |expire_at| expire_at < now))
+            .collect();
+        for key in expired {
+            self.store.delete_expired(&key);
+        }
+    }
+
+    // Like redis, lists, hashes and sets are deleted once their last element is removed
+    fn delete_if_empty(&mut self, key: &str) {
+        let is_empty = match self.store.get_data(key) {
+            Some(DataType::List(list)) => list.is_empty(),
+            Some(DataType::Hash(hash)) => hash.is_empty(),
+            Some(DataType::Set(set)) => set.is_empty(),
+            _ => false,
+        };
+        if is_empty {
+            self.store.delete(vec![key]);
+        }
+    }
+
+    // Whole milliseconds of a TTL, which the store keeps expiry in. A TTL that rounds down to
+    // nothing, or whose expiry cannot be represented, is rejected rather than truncated
+    fn ttl_millis(ttl: Duration) -> DbResult<u64> {
+        let millis = match i64::try_from(ttl.as_millis()) {
+            Ok(millis) if millis > 0 => millis,
+            _ => return Err(StoreError::InvalidExpireTime),
+        };
+        match Utc::now().checked_add_signed(chrono::Duration::milliseconds(millis)) {
+            Some(_) => Ok(millis as u64),
+            None => Err(StoreError::InvalidExpireTime),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn strings_are_set_with_and_without_ttl() {
+        let mut db = Db::new();
+        assert_eq!(db.get("key"), Ok(None));
+
+        assert_eq!(db.set_with_ttl("key", "value", Duration::from_secs(60)), Ok(()));
+        assert_eq!(db.get("key"), Ok(Some(String::from("value"))));
+        let ttl = db.ttl("key").unwrap();
+        assert!(ttl > Duration::from_secs(59) && ttl <= Duration::from_secs(60));
+
+        db.set("key", "other");
+        assert_eq!(db.get("key"), Ok(Some(String::from("other"))));
+        assert_eq!(db.ttl("key"), None);
+        assert_eq!(db.key_type("key"), Some("string"));
+        assert!(db.delete("key"));
+        assert!(!db.delete("key"));
+    }
+
+    #[test]
+    fn ttls_that_cannot_be_kept_are_rejected() {
+        let mut db = Db::new();
+        for ttl in [Duration::from_secs(0), Duration::from_micros(999), Duration::from_secs(u64::MAX)] {
+            assert_eq!(db.set_with_ttl("key", "value", ttl), Err(StoreError::InvalidExpireTime));
+        }
+        assert_eq!(db.get("key"), Ok(None));
+
+        db.set("key", "value");
+        assert_eq!(db.expire("key", Duration::from_secs(u64::MAX)), Err(StoreError::InvalidExpireTime));
+        assert_eq!(db.expire("key", Duration::from_secs(0)), Err(StoreError::InvalidExpireTime));
+        assert_eq!(db.ttl("key"), None);
+        assert_eq!(db.get("key"), Ok(Some(String::from("value"))));
+    }
+
+    #[test]
+    fn keys_expire_when_accessed() {
+        let mut db = Db::new();
+        db.set_with_ttl("short", "value", Duration::from_millis(20)).unwrap();
+        db.set("long", "value");
+        assert_eq!(db.expire("long", Duration::from_millis(20)), Ok(true));
+        assert!(db.persist("long"));
+        assert!(!db.persist("long"));
+        assert_eq!(db.expire("missing", Duration::from_secs(1)), Ok(false));
+        assert_eq!(db.len(), 2);
+
+        thread::sleep(Duration::from_millis(30));
+        assert!(!db.exists("short"));
+        assert_eq!(db.get("short"), Ok(None));
+        assert_eq!(db.keys("*"), vec![String::from("long")]);
+        assert_eq!(db.len(), 1);
+    }
+
+    #[test]
+    fn lists_are_pushed_and_popped_at_both_ends() {
+        let mut db = Db::new();
+        assert_eq!(db.rpush("list", &["b", "c"]), Ok(2));
+        assert_eq!(db.lpush("list", &["a", "z"]), Ok(4));
+        assert_eq!(db.lrange("list", 0, -1), Ok(vec!["z", "a", "b", "c"].into_iter().map(String::from).collect()));
+        assert_eq!(db.lrange("list", -2, 10), Ok(vec![String::from("b"), String::from("c")]));
+        assert_eq!(db.lrange("list", 3, 1), Ok(vec![]));
+        assert_eq!(db.rpush("empty", &[]), Ok(0));
+        assert!(!db.exists("empty"));
+
+        assert_eq!(db.lpop("list"), Ok(Some(String::from("z"))));
+        assert_eq!(db.rpop("list"), Ok(Some(String::from("c"))));
+        assert_eq!(db.llen("list"), Ok(2));
+        db.lpop("list").unwrap();
+        db.lpop("list").unwrap();
+        assert_eq!(db.lpop("list"), Ok(None));
+        assert!(!db.exists("list"));
+    }
+
+    #[test]
+    fn hashes_and_sets_are_deleted_once_empty() {
+        let mut db = Db::new();
+        assert_eq!(db.hset("hash", "field", "one"), Ok(true));
+        assert_eq!(db.hset("hash", "field", "two"), Ok(false));
+        assert_eq!(db.hget("hash", "field"), Ok(Some(String::from("two"))));
+        assert_eq!(db.hgetall("hash"), Ok(vec![(String::from("field"), String::from("two"))]));
+        assert_eq!(db.hlen("hash"), Ok(1));
+        assert_eq!(db.hdel("hash", "field"), Ok(true));
+        assert_eq!(db.key_type("hash"), None);
+
+        assert_eq!(db.sadd("set", &["a", "b", "a"]), Ok(2));
+        assert_eq!(db.sismember("set", "a"), Ok(true));
+        assert_eq!(db.scard("set"), Ok(2));
+        assert_eq!(db.srem("set", &["a", "c"]), Ok(1));
+        assert_eq!(db.smembers("set"), Ok(vec![String::from("b")]));
+        assert_eq!(db.srem("set", &["b"]), Ok(1));
+        assert!(db.is_empty());
+    }
+
+    #[test]
+    fn operations_on_another_data_type_are_errors() {
+        let mut db = Db::new();
+        db.set("string", "value");
+        db.sadd("set", &["member"]).unwrap();
+
+        assert_eq!(db.lpush("string", &["a"]), Err(StoreError::WrongType));
+        assert_eq!(db.hget("string", "field"), Err(StoreError::WrongType));
+        assert_eq!(db.sismember("string", "a"), Err(StoreError::WrongType));
+        assert_eq!(db.get("set"), Err(StoreError::WrongType));
+        assert_eq!(db.get("string"), Ok(Some(String::from("value"))));
+    }
+
+    #[test]
+    fn dbs_are_independent_of_each_other_and_of_the_server() {
+        let mut first = Db::new();
+        let mut second = Db::new();
+        first.set("key", "first");
+        second.set("key", "second");
+
+        assert_eq!(first.get("key"), Ok(Some(String::from("first"))));
+        assert_eq!(second.get("key"), Ok(Some(String::from("second"))));
+        first.flush();
+        assert!(first.is_empty());
+        assert_eq!(second.iter().count(), 1);
+    }
+}
@@ -1,3 +1,10 @@
+pub mod command_lock;
+pub mod db;
 pub mod redis;
 pub mod redis_data_structure;
+pub mod redis_geo;
+pub mod redis_hyperloglog;
 pub mod redis_operation;
+pub mod redis_sorted_set;
+pub mod redis_stream;
+pub mod redis_stream_group;
@@ -1,27 +1,75 @@
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use std::collections::HashMap;
+use std::ptr::addr_of_mut;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(feature = "init_redis_test"))]
 use std::sync::Once;
+use std::thread;
 
-use serial_test::serial;
-
-use crate::store::redis_data_structure::{DataType, DateTimeMeta, DateTimeMetaBuilder};
+use crate::config::config::with_config;
+use crate::pubsub::keyspace_events::{self, NOTIFY_EXPIRED, NOTIFY_GENERIC, NOTIFY_NEW};
+use crate::store::command_lock::with_command_lock;
+use crate::store::redis_data_structure::{DataType, DateTimeMeta, DateTimeMetaBuilder, Hash, List, Set};
 use crate::store::redis_operation::SetOptionalArgs;
-
-static mut STORE: Option<RedisStore> = None;
+use crate::store::redis_sorted_set::SortedSet;
+use crate::store::redis_stream::Stream;
+
+// The numbered databases, of which there are as many as the `databases` config
+static mut STORE: Option<Vec<RedisStore>> = None;
+// Versions are unique across databases, so that they still differ after SWAPDB
+static LAST_VERSION: AtomicU64 = AtomicU64::new(0);
+#[cfg(not(feature = "init_redis_test"))]
 static INIT: Once = Once::new();
 static mut INIT_COUNT: u8 = 0;
 
+#[derive(Debug, PartialEq)]
+pub enum StoreError {
+    WrongType,
+    // A TTL of less than a millisecond, or one that ends after the latest time that can be represented
+    InvalidExpireTime,
+}
+
+impl StoreError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            StoreError::WrongType => "WRONGTYPE Operation against a key holding the wrong kind of value",
+            StoreError::InvalidExpireTime => "ERR invalid expire time",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct RedisStore {
+    // Index of the database
+    id: usize,
     data: HashMap<String, DataType>,
     date_time: HashMap<String, DateTimeMeta>,
+    // Version of each watched key, which WATCH compares against. Only keys that connections
+    // watch are versioned, and versions are kept after a key is deleted until nobody watches it,
+    // so that deleting and re-creating a key is detected
+    watched: HashMap<String, WatchedKey>,
+    // A store of Db rather than a database of the server, which publishes no keyspace events
+    embedded: bool,
+}
+
+#[derive(Debug, Default)]
+struct WatchedKey {
+    version: u64,
+    // Number of connections that watch the key
+    watchers: usize,
 }
 
 pub trait Store {
     fn initialise();
 
+    /// Returns the first database, which connections use until they SELECT another one
     fn get_store() -> &'static mut RedisStore;
 
+    /// Returns the database with the index, which must be less than database_count
+    fn get_db(index: usize) -> &'static mut RedisStore;
+
+    fn database_count() -> usize;
+
     // https://redis.io/commands/get
     fn get(&self, key: &str) -> Option<&str>;
 
@@ -31,18 +79,60 @@ pub trait Store {
 
     fn is_key_expired(&self, key: &str) -> bool;
 
+    /// Returns the version of the key, which changes whenever the key is written, deleted or expires.
+    /// Only watched keys are versioned, so other keys, and watched keys that have not been modified
+    /// since, have version 0
+    fn key_version(&self, key: &str) -> u64;
+
     /// Returns number of key that are deleted
     fn delete(&mut self, keys: Vec<&str>) -> u64;
+
+    /// Same as delete, but for a key that has expired. Returns whether the key is deleted
+    fn delete_expired(&mut self, key: &str) -> bool;
+
+    /// Returns None if key is not present, or an error if key holds another data type.
+    /// Unlike set, modifying the string keeps the expiry of the key
+    fn get_string_mut(&mut self, key: &str) -> Result<Option<&mut String>, StoreError>;
+
+    /// Returns None if key is not present, or an error if key holds another data type
+    fn get_stream(&self, key: &str) -> Result<Option<&Stream>, StoreError>;
+
+    /// Same as get_stream, but creates an empty stream first if `create` is set and key is not present
+    fn get_stream_mut(&mut self, key: &str, create: bool) -> Result<Option<&mut Stream>, StoreError>;
+
+    /// Returns None if key is not present, or an error if key holds another data type
+    fn get_list(&self, key: &str) -> Result<Option<&List>, StoreError>;
+
+    /// Same as get_list, but creates an empty list first if `create` is set and key is not present
+    fn get_list_mut(&mut self, key: &str, create: bool) -> Result<Option<&mut List>, StoreError>;
+
+    /// Returns None if key is not present, or an error if key holds another data type
+    fn get_hash(&self, key: &str) -> Result<Option<&Hash>, StoreError>;
+
+    /// Same as get_hash, but creates an empty hash first if `create` is set and key is not present
+    fn get_hash_mut(&mut self, key: &str, create: bool) -> Result<Option<&mut Hash>, StoreError>;
+
+    /// Returns None if key is not present, or an error if key holds another data type
+    fn get_set(&self, key: &str) -> Result<Option<&Set>, StoreError>;
+
+    /// Same as get_set, but creates an empty set first if `create` is set and key is not present
+    fn get_set_mut(&mut self, key: &str, create: bool) -> Result<Option<&mut Set>, StoreError>;
+
+    /// Returns None if key is not present, or an error if key holds another data type
+    fn get_sorted_set(&self, key: &str) -> Result<Option<&SortedSet>, StoreError>;
+
+    /// Same as get_sorted_set, but creates an empty sorted set first if `create` is set and key is not present
+    fn get_sorted_set_mut(&mut self, key: &str, create: bool) -> Result<Option<&mut SortedSet>, StoreError>;
+
+    /// Like set, replaces the key whatever data type it holds, but without expiry
+    fn set_sorted_set(&mut self, key: &str, sorted_set: SortedSet);
 }
 
 impl Store for RedisStore {
     #[cfg(not(feature = "init_redis_test"))]
     fn initialise() {
         INIT.call_once(|| unsafe {
-            STORE = Some(RedisStore {
-                data: HashMap::new(),
-                date_time: HashMap::new(),
-            });
+            STORE = Some(RedisStore::new_databases());
             INIT_COUNT += 1;
             println!("Store is initialised.");
         });
@@ -54,33 +144,26 @@ impl Store for RedisStore {
     #[cfg(feature = "init_redis_test")]
     fn initialise() {
         unsafe {
-            STORE = Some(RedisStore {
-                data: HashMap::new(),
-                date_time: HashMap::new(),
-            });
+            STORE = Some(RedisStore::new_databases());
             INIT_COUNT += 1;
         }
         println!("Store is initialised in test mode.");
     }
 
     fn get_store() -> &'static mut RedisStore {
-        unsafe {
-            if STORE.is_none() {
-                panic!("Store is not initialised.");
-            }
-
-            STORE.as_mut().unwrap()
-        }
+        RedisStore::get_db(0)
     }
 
-    fn get(&self, key: &str) -> Option<&str> {
-        let value = self.data.get(key);
+    fn get_db(index: usize) -> &'static mut RedisStore {
+        &mut RedisStore::databases()[index]
+    }
 
-        if value.is_none() {
-            return None;
-        }
+    fn database_count() -> usize {
+        RedisStore::databases().len()
+    }
 
-        let data_unwrapped = value.unwrap();
+    fn get(&self, key: &str) -> Option<&str> {
+        let data_unwrapped = self.data.get(key)?;
 
         if let DataType::String(string) = data_unwrapped {
             return Some(string);
@@ -95,6 +178,10 @@ impl Store for RedisStore {
         let insert_data_result = self
             .data
             .insert(key_string, DataType::String(String::from(value)));
+        self.touch(key);
+        if insert_data_result.is_none() {
+            self.notify_keyspace_event(NOTIFY_NEW, "new", key);
+        }
 
         let now = Utc::now();
         let mut date_time_meta_builder = DateTimeMetaBuilder::new(now);
@@ -112,10 +199,7 @@ impl Store for RedisStore {
             return insert_data_result;
         }
 
-        let expire_in_option = set_args.expire_in_ms.as_ref();
-
-        if expire_in_option.is_some() {
-            let duration = expire_in_option.unwrap();
+        if let Some(duration) = set_args.expire_in_ms.as_ref() {
             let expire_at = now.checked_add_signed(Duration::milliseconds(*duration as i64));
             date_time_meta_builder = date_time_meta_builder.expire_at(expire_at);
         }
@@ -145,31 +229,372 @@ impl Store for RedisStore {
         date_time_meta.expire_at.unwrap() < now
     }
 
+    fn key_version(&self, key: &str) -> u64 {
+        self.watched.get(key).map_or(0, |watched| watched.version)
+    }
+
     fn delete(&mut self, keys: Vec<&str>) -> u64 {
         let mut delete_count = 0;
 
         for key in keys {
             if self.data.remove(key).is_some() {
                 self.date_time.remove(key);
+                self.touch(key);
                 delete_count += 1;
-                println!("Key {} is removed.", key);
+                if !self.embedded {
+                    println!("Key {} is removed.", key);
+                }
+                self.notify_keyspace_event(NOTIFY_GENERIC, "del", key);
             }
         }
         delete_count
     }
+
+    fn delete_expired(&mut self, key: &str) -> bool {
+        if self.data.remove(key).is_none() {
+            return false;
+        }
+
+        self.date_time.remove(key);
+        self.touch(key);
+        if !self.embedded {
+            println!("Key {} has expired.", key);
+        }
+        self.notify_keyspace_event(NOTIFY_EXPIRED, "expired", key);
+        true
+    }
+
+    fn get_string_mut(&mut self, key: &str) -> Result<Option<&mut String>, StoreError> {
+        if let Some(DataType::String(_)) = self.data.get(key) {
+            self.touch(key);
+        }
+        match self.data.get_mut(key) {
+            None => Ok(None),
+            Some(DataType::String(string)) => Ok(Some(string)),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    fn get_stream(&self, key: &str) -> Result<Option<&Stream>, StoreError> {
+        match self.data.get(key) {
+            None => Ok(None),
+            Some(DataType::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    fn get_stream_mut(&mut self, key: &str, create: bool) -> Result<Option<&mut Stream>, StoreError> {
+        if create && !self.data.contains_key(key) {
+            self.insert_data(key, DataType::Stream(Stream::new()));
+        }
+        if let Some(DataType::Stream(_)) = self.data.get(key) {
+            self.touch(key);
+        }
+
+        match self.data.get_mut(key) {
+            None => Ok(None),
+            Some(DataType::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    fn get_list(&self, key: &str) -> Result<Option<&List>, StoreError> {
+        match self.data.get(key) {
+            None => Ok(None),
+            Some(DataType::List(list)) => Ok(Some(list)),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    fn get_list_mut(&mut self, key: &str, create: bool) -> Result<Option<&mut List>, StoreError> {
+        if create && !self.data.contains_key(key) {
+            self.insert_data(key, DataType::List(List::new()));
+        }
+        if let Some(DataType::List(_)) = self.data.get(key) {
+            self.touch(key);
+        }
+
+        match self.data.get_mut(key) {
+            None => Ok(None),
+            Some(DataType::List(list)) => Ok(Some(list)),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    fn get_hash(&self, key: &str) -> Result<Option<&Hash>, StoreError> {
+        match self.data.get(key) {
+            None => Ok(None),
+            Some(DataType::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    fn get_hash_mut(&mut self, key: &str, create: bool) -> Result<Option<&mut Hash>, StoreError> {
+        if create && !self.data.contains_key(key) {
+            self.insert_data(key, DataType::Hash(Hash::new()));
+        }
+        if let Some(DataType::Hash(_)) = self.data.get(key) {
+            self.touch(key);
+        }
+
+        match self.data.get_mut(key) {
+            None => Ok(None),
+            Some(DataType::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    fn get_set(&self, key: &str) -> Result<Option<&Set>, StoreError> {
+        match self.data.get(key) {
+            None => Ok(None),
+            Some(DataType::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    fn get_set_mut(&mut self, key: &str, create: bool) -> Result<Option<&mut Set>, StoreError> {
+        if create && !self.data.contains_key(key) {
+            self.insert_data(key, DataType::Set(Set::new()));
+        }
+        if let Some(DataType::Set(_)) = self.data.get(key) {
+            self.touch(key);
+        }
+
+        match self.data.get_mut(key) {
+            None => Ok(None),
+            Some(DataType::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    fn get_sorted_set(&self, key: &str) -> Result<Option<&SortedSet>, StoreError> {
+        match self.data.get(key) {
+            None => Ok(None),
+            Some(DataType::SortedSet(sorted_set)) => Ok(Some(sorted_set)),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    fn get_sorted_set_mut(&mut self, key: &str, create: bool) -> Result<Option<&mut SortedSet>, StoreError> {
+        if create && !self.data.contains_key(key) {
+            self.insert_data(key, DataType::SortedSet(SortedSet::new()));
+        }
+        if let Some(DataType::SortedSet(_)) = self.data.get(key) {
+            self.touch(key);
+        }
+
+        match self.data.get_mut(key) {
+            None => Ok(None),
+            Some(DataType::SortedSet(sorted_set)) => Ok(Some(sorted_set)),
+            Some(_) => Err(StoreError::WrongType),
+        }
+    }
+
+    fn set_sorted_set(&mut self, key: &str, sorted_set: SortedSet) {
+        if self.data.contains_key(key) {
+            self.data.insert(String::from(key), DataType::SortedSet(sorted_set));
+            self.date_time
+                .insert(String::from(key), DateTimeMetaBuilder::new(Utc::now()).build());
+            self.touch(key);
+        } else {
+            self.insert_data(key, DataType::SortedSet(sorted_set));
+        }
+    }
 }
 
 impl RedisStore {
+    fn new(id: usize) -> RedisStore {
+        RedisStore {
+            id,
+            data: HashMap::new(),
+            date_time: HashMap::new(),
+            watched: HashMap::new(),
+            embedded: false,
+        }
+    }
+
+    /// A store that is not one of the numbered databases, e.g. the store of a Db
+    pub(crate) fn new_embedded() -> RedisStore {
+        RedisStore {
+            embedded: true,
+            ..RedisStore::new(0)
+        }
+    }
+
+    fn new_databases() -> Vec<RedisStore> {
+        let count = with_config(|config| config.databases);
+        (0..count).map(RedisStore::new).collect()
+    }
+
+    fn databases() -> &'static mut Vec<RedisStore> {
+        unsafe {
+            match (*addr_of_mut!(STORE)).as_mut() {
+                Some(databases) => databases,
+                None => panic!("Store is not initialised."),
+            }
+        }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the data of the key whatever its type, or None if key is not present
+    pub fn get_data(&self, key: &str) -> Option<&DataType> {
+        self.data.get(key)
+    }
+
+    /// Iterates over keys and their data in no particular order, including keys that have
+    /// expired but are not deleted yet
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DataType)> {
+        self.data.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Returns when the key expires, or None if the key is not present or never expires
+    pub fn expire_at(&self, key: &str) -> Option<DateTime<Utc>> {
+        self.date_time.get(key)?.expire_at
+    }
+
+    /// Sets when the key expires, or removes its expiry if `expire_at` is None.
+    /// Returns false if the key is not present
+    pub fn set_expire_at(&mut self, key: &str, expire_at: Option<DateTime<Utc>>) -> bool {
+        match self.date_time.get_mut(key) {
+            None => false,
+            Some(date_time_meta) => {
+                date_time_meta.expire_at = expire_at;
+                self.touch(key);
+                true
+            }
+        }
+    }
+
+    /// Starts versioning the key for a connection that watches it. Returns its current version
+    pub(crate) fn watch(&mut self, key: &str) -> u64 {
+        let watched = self.watched.entry(String::from(key)).or_default();
+        watched.watchers += 1;
+        watched.version
+    }
+
+    /// Stops versioning the key once no connection watches it
+    pub(crate) fn unwatch(&mut self, key: &str) {
+        if let Some(watched) = self.watched.get_mut(key) {
+            watched.watchers -= 1;
+            if watched.watchers == 0 {
+                self.watched.remove(key);
+            }
+        }
+    }
+
+    // Gives the key a new version if it is watched, since it is about to be modified
+    fn touch(&mut self, key: &str) {
+        if let Some(watched) = self.watched.get_mut(key) {
+            watched.version = LAST_VERSION.fetch_add(1, Ordering::Relaxed) + 1;
+        }
+    }
+
+    /// Removes every key of the database. Keys are freed in another thread if `asynchronously` is set
+    pub fn flush(&mut self, asynchronously: bool) {
+        let data = &self.data;
+        for (_, watched) in self.watched.iter_mut().filter(|(key, _)| data.contains_key(key.as_str())) {
+            watched.version = LAST_VERSION.fetch_add(1, Ordering::Relaxed) + 1;
+        }
+
+        let data = std::mem::take(&mut self.data);
+        let date_time = std::mem::take(&mut self.date_time);
+        if asynchronously {
+            thread::spawn(move || drop((data, date_time)));
+        }
+    }
+
+    /// Swaps the keys of two databases, so that connections of one see the keys of the other
+    pub fn swap_databases(first: usize, second: usize) {
+        let databases = RedisStore::databases();
+        databases.swap(first, second);
+        databases[first].id = first;
+        databases[second].id = second;
+
+        // Connections watch keys of a database index, so watched keys stay with the index and
+        // count as modified, like redis
+        let (left, right) = databases.split_at_mut(first.max(second));
+        let (one, other) = (&mut left[first.min(second)], &mut right[0]);
+        std::mem::swap(&mut one.watched, &mut other.watched);
+        for watched in one.watched.values_mut().chain(other.watched.values_mut()) {
+            watched.version = LAST_VERSION.fetch_add(1, Ordering::Relaxed) + 1;
+        }
+    }
+
+    /// Moves the key to another database along with its expiry. Returns false if the key
+    /// is not present, or if the other database already has the key
+    pub fn move_key(key: &str, from: usize, to: usize) -> bool {
+        let databases = RedisStore::databases();
+        let (source, destination) = if from < to {
+            let (left, right) = databases.split_at_mut(to);
+            (&mut left[from], &mut right[0])
+        } else {
+            let (left, right) = databases.split_at_mut(from);
+            (&mut right[0], &mut left[to])
+        };
+
+        if !source.data.contains_key(key) || destination.data.contains_key(key) {
+            return false;
+        }
+
+        let value = source.data.remove(key).unwrap();
+        let date_time = source.date_time.remove(key);
+        source.touch(key);
+        destination.data.insert(String::from(key), value);
+        if let Some(date_time) = date_time {
+            destination.date_time.insert(String::from(key), date_time);
+        }
+        destination.touch(key);
+
+        source.notify_keyspace_event(NOTIFY_GENERIC, "move_from", key);
+        destination.notify_keyspace_event(NOTIFY_GENERIC, "move_to", key);
+        true
+    }
+
+    // Inserts data of any type without expiry
+    fn insert_data(&mut self, key: &str, value: DataType) {
+        self.data.insert(String::from(key), value);
+        self.touch(key);
+        self.date_time
+            .insert(String::from(key), DateTimeMetaBuilder::new(Utc::now()).build());
+        self.notify_keyspace_event(NOTIFY_NEW, "new", key);
+    }
+
+    /// Publishes a keyspace event for a key of this store, i.e. after a command modifies it
+    pub fn notify_keyspace_event(&self, class: u32, event: &str, key: &str) {
+        if self.embedded {
+            return;
+        }
+        keyspace_events::notify_keyspace_event(class, event, key, self.id);
+    }
+
+    /// Replaces the databases with empty ones, as many as the `databases` config, even if they
+    /// were already initialised, i.e. so that a server does not keep the keys of one before it
+    pub fn replace_databases() {
+        with_command_lock(|| unsafe {
+            STORE = Some(RedisStore::new_databases());
+            INIT_COUNT += 1;
+        });
+        println!("Store is initialised.");
+    }
+
     pub fn reset() {
         unsafe {
-            if STORE.is_none() {
+            if (*addr_of_mut!(STORE)).is_none() {
                 println!("Store is already None.");
                 return;
             }
 
-            let store = STORE.as_mut().unwrap();
-            store.data = HashMap::new();
-            store.date_time = HashMap::new();
             STORE = None;
             INIT_COUNT = 0;
 
@@ -181,12 +606,12 @@ impl RedisStore {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::ops::Deref;
+    use serial_test::serial;
     use std::thread;
 
     fn with_reset_redis<F>(test: F)
     where
-        F: FnOnce() -> (),
+        F: FnOnce(),
     {
         test();
         RedisStore::reset();
@@ -212,12 +637,11 @@ mod tests {
                 let key = "key";
                 let value = "value";
 
-                let set_result = store.set(key, value, &None);
+                store.set(key, value, &None);
             }
 
             {
                 let store = RedisStore::get_store();
-                let key = "key";
                 let result = store.get("key");
                 assert_eq!(result.unwrap(), "value");
             }
@@ -351,4 +775,60 @@ mod tests {
             }
         })
     }
+
+    #[test]
+    #[serial]
+    fn key_version_changes_when_key_is_modified() {
+        with_reset_redis(|| {
+            RedisStore::initialise();
+
+            let store = RedisStore::get_store();
+            assert_eq!(store.watch("key"), 0);
+
+            store.set("key", "value", &None);
+            let set_version = store.key_version("key");
+            assert!(set_version > 0);
+
+            store.get("key");
+            assert_eq!(store.key_version("key"), set_version);
+            assert!(store.get_stream_mut("key", false).is_err());
+            assert_eq!(store.key_version("key"), set_version);
+
+            store.get_string_mut("key").unwrap();
+            let modified_version = store.key_version("key");
+            assert!(modified_version > set_version);
+
+            store.delete(vec!["key"]);
+            assert!(store.key_version("key") > modified_version);
+        })
+    }
+
+    #[test]
+    #[serial]
+    fn only_watched_keys_are_versioned() {
+        with_reset_redis(|| {
+            RedisStore::initialise();
+
+            let store = RedisStore::get_store();
+            for i in 0..100 {
+                let key = format!("key{}", i);
+                store.set(&key, "value", &None);
+                store.delete(vec![&key]);
+            }
+            store.set("key", "value", &None);
+            store.flush(false);
+            assert!(store.watched.is_empty());
+
+            store.watch("key");
+            store.watch("key");
+            store.set("key", "value", &None);
+            assert!(store.key_version("key") > 0);
+
+            store.unwatch("key");
+            assert_eq!(store.watched.len(), 1);
+            store.unwatch("key");
+            assert!(store.watched.is_empty());
+            assert_eq!(store.key_version("key"), 0);
+        })
+    }
 }
@@ -1,33 +1,159 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use chrono::{DateTime, Utc};
 
+use crate::store::redis_sorted_set::SortedSet;
+use crate::store::redis_stream::Stream;
+
 #[derive(Debug)]
 pub enum DataType {
     String(String),
-    List(LinkedList),
+    List(List),
+    Hash(Hash),
+    Set(Set),
+    Stream(Stream),
+    SortedSet(SortedSet),
 }
 
-#[derive(Debug)]
-pub struct LinkedList {
-    head: LinkedListNode,
+impl DataType {
+    /// Name of the data type as redis gives it, e.g. "zset" for a sorted set
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataType::String(_) => "string",
+            DataType::List(_) => "list",
+            DataType::Hash(_) => "hash",
+            DataType::Set(_) => "set",
+            DataType::Stream(_) => "stream",
+            DataType::SortedSet(_) => "zset",
+        }
+    }
 }
 
-#[derive(Debug)]
-pub struct LinkedListNode {
-    data: String,
-    next: Option<Box<LinkedList>>,
+// Elements in insertion order, which are pushed and popped at both ends
+#[derive(Debug, Default)]
+pub struct List {
+    data: VecDeque<String>,
 }
 
-#[derive(Debug)]
-pub struct Set {
-    data: HashMap<String, String>,
+impl List {
+    pub fn new() -> List {
+        List { data: VecDeque::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn push_front(&mut self, element: &str) {
+        self.data.push_front(String::from(element));
+    }
+
+    pub fn push_back(&mut self, element: &str) {
+        self.data.push_back(String::from(element));
+    }
+
+    pub fn pop_front(&mut self) -> Option<String> {
+        self.data.pop_front()
+    }
+
+    pub fn pop_back(&mut self) -> Option<String> {
+        self.data.pop_back()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.data.iter().map(|element| element.as_str())
+    }
+
+    /// Iterates over elements from start to stop inclusive, where negative indexes count from
+    /// the end, like LRANGE
+    pub fn range(&self, start: i64, stop: i64) -> impl Iterator<Item = &str> {
+        let len = self.data.len() as i64;
+        let start = if start < 0 { (len + start).max(0) } else { start };
+        let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+        let count = if start > stop { 0 } else { stop - start + 1 };
+        self.iter().skip(start as usize).take(count as usize)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Hash {
     data: HashMap<String, String>,
 }
 
+impl Hash {
+    pub fn new() -> Hash {
+        Hash { data: HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.data.get(field).map(|value| value.as_str())
+    }
+
+    /// Returns whether the field is new, rather than given another value
+    pub fn insert(&mut self, field: &str, value: &str) -> bool {
+        self.data.insert(String::from(field), String::from(value)).is_none()
+    }
+
+    /// Returns the value of the field that is removed
+    pub fn remove(&mut self, field: &str) -> Option<String> {
+        self.data.remove(field)
+    }
+
+    /// Iterates over fields and their values in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.data.iter().map(|(field, value)| (field.as_str(), value.as_str()))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Set {
+    data: HashSet<String>,
+}
+
+impl Set {
+    pub fn new() -> Set {
+        Set { data: HashSet::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn contains(&self, member: &str) -> bool {
+        self.data.contains(member)
+    }
+
+    /// Returns whether the member is new
+    pub fn insert(&mut self, member: &str) -> bool {
+        self.data.insert(String::from(member))
+    }
+
+    /// Returns whether the member was present
+    pub fn remove(&mut self, member: &str) -> bool {
+        self.data.remove(member)
+    }
+
+    /// Iterates over members in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.data.iter().map(|member| member.as_str())
+    }
+}
+
 #[derive(Debug)]
 pub struct DateTimeMeta {
     pub created_at: DateTime<Utc>,
@@ -62,3 +188,25 @@ impl DateTimeMetaBuilder {
 
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_range_counts_negative_indexes_from_the_end() {
+        let mut list = List::new();
+        for element in ["a", "b", "c", "d"] {
+            list.push_back(element);
+        }
+
+        let range = |start, stop| list.range(start, stop).collect::<Vec<&str>>();
+        assert_eq!(range(0, -1), vec!["a", "b", "c", "d"]);
+        assert_eq!(range(1, 2), vec!["b", "c"]);
+        assert_eq!(range(-2, 100), vec!["c", "d"]);
+        assert_eq!(range(-100, 0), vec!["a"]);
+        assert!(range(2, 1).is_empty());
+        assert!(range(4, 10).is_empty());
+        assert!(List::new().range(0, -1).next().is_none());
+    }
+}
@@ -0,0 +1,422 @@
+// Geohash as implemented by redis, so that scores of geo members are the same.
+// https://github.com/redis/redis/blob/unstable/src/geohash.c
+// https://github.com/redis/redis/blob/unstable/src/geohash_helper.c
+//
+// A position is encoded as 52 bits that interleave 26 bits of latitude (even bits) and 26 bits of
+// longitude (odd bits). Latitudes are limited to the range of the web mercator projection
+
+pub const GEO_STEP_MAX: u8 = 26;
+pub const GEO_LAT_MIN: f64 = -85.051_128_78;
+pub const GEO_LAT_MAX: f64 = 85.051_128_78;
+pub const GEO_LONG_MIN: f64 = -180.0;
+pub const GEO_LONG_MAX: f64 = 180.0;
+
+const EARTH_RADIUS_IN_METERS: f64 = 6_372_797.560_856;
+const MERCATOR_MAX: f64 = 20_037_726.37;
+const GEOHASH_ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoHashBits {
+    pub bits: u64,
+    pub step: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoArea {
+    pub longitude: GeoRange,
+    pub latitude: GeoRange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoShape {
+    // Radius in meters
+    Radius(f64),
+    // Width and height in meters
+    Box(f64, f64),
+}
+
+const LONG_RANGE: GeoRange = GeoRange {
+    min: GEO_LONG_MIN,
+    max: GEO_LONG_MAX,
+};
+const LAT_RANGE: GeoRange = GeoRange {
+    min: GEO_LAT_MIN,
+    max: GEO_LAT_MAX,
+};
+
+impl GeoHashBits {
+    /// Returns the 52 bits score of the hash at full precision
+    pub fn score(&self) -> u64 {
+        self.bits << (2 * (GEO_STEP_MAX - self.step))
+    }
+
+    /// Returns the scores of the members within the area, in [min, max)
+    pub fn score_range(&self) -> (u64, u64) {
+        let shift = 2 * (GEO_STEP_MAX - self.step);
+        (self.bits << shift, (self.bits + 1) << shift)
+    }
+
+    fn move_x(&self, direction: i8) -> GeoHashBits {
+        let x = self.bits & 0xaaaa_aaaa_aaaa_aaaa;
+        let y = self.bits & 0x5555_5555_5555_5555;
+        let zz = 0x5555_5555_5555_5555u64 >> (64 - self.step as u32 * 2);
+        let x = if direction > 0 {
+            x.wrapping_add(zz + 1)
+        } else {
+            (x | zz).wrapping_sub(zz + 1)
+        };
+        let x = x & (0xaaaa_aaaa_aaaa_aaaau64 >> (64 - self.step as u32 * 2));
+        GeoHashBits {
+            bits: x | y,
+            step: self.step,
+        }
+    }
+
+    fn move_y(&self, direction: i8) -> GeoHashBits {
+        let x = self.bits & 0xaaaa_aaaa_aaaa_aaaa;
+        let y = self.bits & 0x5555_5555_5555_5555;
+        let zz = 0xaaaa_aaaa_aaaa_aaaau64 >> (64 - self.step as u32 * 2);
+        let y = if direction > 0 {
+            y.wrapping_add(zz + 1)
+        } else {
+            (y | zz).wrapping_sub(zz + 1)
+        };
+        let y = y & (0x5555_5555_5555_5555u64 >> (64 - self.step as u32 * 2));
+        GeoHashBits {
+            bits: x | y,
+            step: self.step,
+        }
+    }
+
+    /// Returns the 8 areas around this area: N, S, E, W, NE, NW, SE, SW
+    fn neighbors(&self) -> [GeoHashBits; 8] {
+        let north = self.move_y(1);
+        let south = self.move_y(-1);
+        [
+            north,
+            south,
+            self.move_x(1),
+            self.move_x(-1),
+            north.move_x(1),
+            north.move_x(-1),
+            south.move_x(1),
+            south.move_x(-1),
+        ]
+    }
+}
+
+/// Returns false if the coordinates cannot be indexed
+pub fn is_valid_coordinate(longitude: f64, latitude: f64) -> bool {
+    (GEO_LONG_MIN..=GEO_LONG_MAX).contains(&longitude) && (GEO_LAT_MIN..=GEO_LAT_MAX).contains(&latitude)
+}
+
+pub fn encode(longitude: f64, latitude: f64, step: u8) -> GeoHashBits {
+    encode_with_ranges(&LONG_RANGE, &LAT_RANGE, longitude, latitude, step)
+}
+
+fn encode_with_ranges(
+    long_range: &GeoRange,
+    lat_range: &GeoRange,
+    longitude: f64,
+    latitude: f64,
+    step: u8,
+) -> GeoHashBits {
+    let lat_offset = (latitude - lat_range.min) / (lat_range.max - lat_range.min);
+    let long_offset = (longitude - long_range.min) / (long_range.max - long_range.min);
+    let lat_offset = (lat_offset * (1u64 << step) as f64) as u32;
+    let long_offset = (long_offset * (1u64 << step) as f64) as u32;
+    GeoHashBits {
+        bits: interleave(lat_offset, long_offset),
+        step,
+    }
+}
+
+pub fn decode(hash: GeoHashBits) -> GeoArea {
+    let (lat_offset, long_offset) = deinterleave(hash.bits);
+    let cells = (1u64 << hash.step) as f64;
+    let lat_scale = LAT_RANGE.max - LAT_RANGE.min;
+    let long_scale = LONG_RANGE.max - LONG_RANGE.min;
+    GeoArea {
+        latitude: GeoRange {
+            min: LAT_RANGE.min + (lat_offset as f64 / cells) * lat_scale,
+            max: LAT_RANGE.min + ((lat_offset as f64 + 1.0) / cells) * lat_scale,
+        },
+        longitude: GeoRange {
+            min: LONG_RANGE.min + (long_offset as f64 / cells) * long_scale,
+            max: LONG_RANGE.min + ((long_offset as f64 + 1.0) / cells) * long_scale,
+        },
+    }
+}
+
+/// Returns the longitude and latitude at the center of the area of a score
+pub fn decode_score(score: u64) -> (f64, f64) {
+    let area = decode(GeoHashBits {
+        bits: score,
+        step: GEO_STEP_MAX,
+    });
+    let longitude = ((area.longitude.min + area.longitude.max) / 2.0).clamp(GEO_LONG_MIN, GEO_LONG_MAX);
+    let latitude = ((area.latitude.min + area.latitude.max) / 2.0).clamp(GEO_LAT_MIN, GEO_LAT_MAX);
+    (longitude, latitude)
+}
+
+/// Returns the standard 11 characters geohash, which uses [-90, 90] as the latitude range
+pub fn geohash_string(score: u64) -> String {
+    let (longitude, latitude) = decode_score(score);
+    let lat_range = GeoRange { min: -90.0, max: 90.0 };
+    let bits = encode_with_ranges(&LONG_RANGE, &lat_range, longitude, latitude, GEO_STEP_MAX).bits;
+
+    (0..11)
+        .map(|index| {
+            // The 11th character only has 2 bits left, which are taken as zeros
+            let char_index = if index == 10 {
+                0
+            } else {
+                (bits >> (52 - (index + 1) * 5)) & 0x1f
+            };
+            GEOHASH_ALPHABET[char_index as usize] as char
+        })
+        .collect()
+}
+
+/// Great circle distance in meters with the haversine formula
+pub fn distance(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let lat1r = lat1.to_radians();
+    let lat2r = lat2.to_radians();
+    let u = ((lat2r - lat1r) / 2.0).sin();
+    let v = ((lon2.to_radians() - lon1.to_radians()) / 2.0).sin();
+    let a = u * u + lat1r.cos() * lat2r.cos() * v * v;
+    2.0 * EARTH_RADIUS_IN_METERS * a.sqrt().asin()
+}
+
+/// Returns the distance in meters from the center to the point if it is within the shape
+pub fn distance_if_in_shape(center: (f64, f64), shape: &GeoShape, point: (f64, f64)) -> Option<f64> {
+    match shape {
+        GeoShape::Radius(radius) => {
+            let distance = distance(center.0, center.1, point.0, point.1);
+            if distance > *radius {
+                return None;
+            }
+            Some(distance)
+        }
+        GeoShape::Box(width, height) => {
+            // Latitude distance is cheaper to compute, so it is checked first
+            let lat_distance = EARTH_RADIUS_IN_METERS * (point.1.to_radians() - center.1.to_radians()).abs();
+            if lat_distance > height / 2.0 {
+                return None;
+            }
+            let long_distance = distance(point.0, point.1, center.0, point.1);
+            if long_distance > width / 2.0 {
+                return None;
+            }
+            Some(distance(center.0, center.1, point.0, point.1))
+        }
+    }
+}
+
+/// Returns the areas to scan to find every point within the shape: the area of the center and its
+/// neighbors, leaving out neighbors that cannot contain any point
+pub fn areas_by_shape(center: (f64, f64), shape: &GeoShape) -> Vec<GeoHashBits> {
+    let (longitude, latitude) = center;
+    let (min_lon, min_lat, max_lon, max_lat) = bounding_box(center, shape);
+
+    // The distance from the center to the corners for boxes
+    let radius = match shape {
+        GeoShape::Radius(radius) => *radius,
+        GeoShape::Box(width, height) => ((width / 2.0).powi(2) + (height / 2.0).powi(2)).sqrt(),
+    };
+    let mut step = estimate_steps_by_radius(radius, latitude);
+    let mut hash = encode(longitude, latitude, step);
+    let mut neighbors = hash.neighbors();
+    let mut area = decode(hash);
+
+    // The estimated step may not be small enough when the search area is near the edge of the
+    // area of the center, in which case the neighbors do not cover all of it
+    let (north, south) = (decode(neighbors[0]), decode(neighbors[1]));
+    let (east, west) = (decode(neighbors[2]), decode(neighbors[3]));
+    let decrease_step = north.latitude.max < max_lat
+        || south.latitude.min > min_lat
+        || east.longitude.max < max_lon
+        || west.longitude.min > min_lon;
+    if step > 1 && decrease_step {
+        step -= 1;
+        hash = encode(longitude, latitude, step);
+        neighbors = hash.neighbors();
+        area = decode(hash);
+    }
+
+    // Indexes into the neighbors: N, S, E, W, NE, NW, SE, SW
+    let mut excluded = [false; 8];
+    if step >= 2 {
+        if area.latitude.min < min_lat {
+            for index in [1, 6, 7] {
+                excluded[index] = true;
+            }
+        }
+        if area.latitude.max > max_lat {
+            for index in [0, 4, 5] {
+                excluded[index] = true;
+            }
+        }
+        if area.longitude.min < min_lon {
+            for index in [3, 5, 7] {
+                excluded[index] = true;
+            }
+        }
+        if area.longitude.max > max_lon {
+            for index in [2, 4, 6] {
+                excluded[index] = true;
+            }
+        }
+    }
+
+    let mut areas = vec![hash];
+    for (index, neighbor) in neighbors.iter().enumerate() {
+        // Small areas near the poles may have the same neighbors more than once
+        if !excluded[index] && !areas.contains(neighbor) {
+            areas.push(*neighbor);
+        }
+    }
+    areas
+}
+
+// Returns the minimum longitude, minimum latitude, maximum longitude and maximum latitude
+fn bounding_box(center: (f64, f64), shape: &GeoShape) -> (f64, f64, f64, f64) {
+    let (longitude, latitude) = center;
+    let (width, height) = match shape {
+        GeoShape::Radius(radius) => (*radius, *radius),
+        GeoShape::Box(width, height) => (width / 2.0, height / 2.0),
+    };
+
+    let lat_delta = (height / EARTH_RADIUS_IN_METERS).to_degrees();
+    let long_delta_top = (width / EARTH_RADIUS_IN_METERS / (latitude + lat_delta).to_radians().cos()).to_degrees();
+    let long_delta_bottom =
+        (width / EARTH_RADIUS_IN_METERS / (latitude - lat_delta).to_radians().cos()).to_degrees();
+    // The widest part of the box is the edge closest to the equator
+    let long_delta = if latitude < 0.0 { long_delta_bottom } else { long_delta_top };
+    (
+        longitude - long_delta,
+        latitude - lat_delta,
+        longitude + long_delta,
+        latitude + lat_delta,
+    )
+}
+
+fn estimate_steps_by_radius(mut radius: f64, latitude: f64) -> u8 {
+    if radius == 0.0 {
+        return GEO_STEP_MAX;
+    }
+    let mut step: i32 = 1;
+    while radius < MERCATOR_MAX {
+        radius *= 2.0;
+        step += 1;
+    }
+    // Makes sure the radius is included in most of the base cases
+    step -= 2;
+
+    // Areas are narrower towards the poles
+    if !(-66.0..=66.0).contains(&latitude) {
+        step -= 1;
+        if !(-80.0..=80.0).contains(&latitude) {
+            step -= 1;
+        }
+    }
+    step.clamp(1, GEO_STEP_MAX as i32) as u8
+}
+
+// Interleaves the bits of x and y, with x in the even bits and y in the odd bits
+// https://graphics.stanford.edu/~seander/bithacks.html#InterleaveBMN
+fn interleave(x: u32, y: u32) -> u64 {
+    fn spread(value: u32) -> u64 {
+        let mut value = value as u64;
+        value = (value | (value << 16)) & 0x0000_ffff_0000_ffff;
+        value = (value | (value << 8)) & 0x00ff_00ff_00ff_00ff;
+        value = (value | (value << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+        value = (value | (value << 2)) & 0x3333_3333_3333_3333;
+        (value | (value << 1)) & 0x5555_5555_5555_5555
+    }
+    spread(x) | (spread(y) << 1)
+}
+
+// Reverses interleave, returning the even bits and the odd bits
+fn deinterleave(bits: u64) -> (u32, u32) {
+    fn squash(value: u64) -> u32 {
+        let mut value = value & 0x5555_5555_5555_5555;
+        value = (value | (value >> 1)) & 0x3333_3333_3333_3333;
+        value = (value | (value >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+        value = (value | (value >> 4)) & 0x00ff_00ff_00ff_00ff;
+        value = (value | (value >> 8)) & 0x0000_ffff_0000_ffff;
+        ((value | (value >> 16)) & 0x0000_0000_ffff_ffff) as u32
+    }
+    (squash(bits), squash(bits >> 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Palermo and Catania, the examples of the redis documentation
+    const PALERMO: (f64, f64) = (13.361389, 38.115556);
+    const CATANIA: (f64, f64) = (15.087269, 37.502669);
+
+    #[test]
+    fn encode_matches_redis_scores() {
+        assert_eq!(encode(PALERMO.0, PALERMO.1, GEO_STEP_MAX).score(), 3479099956230698);
+        assert_eq!(encode(CATANIA.0, CATANIA.1, GEO_STEP_MAX).score(), 3479447370796909);
+    }
+
+    #[test]
+    fn decode_score_returns_center_of_area() {
+        let (longitude, latitude) = decode_score(3479099956230698);
+        assert!((longitude - PALERMO.0).abs() < 0.00001);
+        assert!((latitude - PALERMO.1).abs() < 0.00001);
+    }
+
+    #[test]
+    fn geohash_string_matches_redis() {
+        assert_eq!(geohash_string(3479099956230698), "sqc8b49rny0");
+        assert_eq!(geohash_string(3479447370796909), "sqdtr74hyu0");
+    }
+
+    #[test]
+    fn distance_matches_redis() {
+        // Redis measures between the positions decoded from the scores
+        let (lon1, lat1) = decode_score(3479099956230698);
+        let (lon2, lat2) = decode_score(3479447370796909);
+        let distance = distance(lon1, lat1, lon2, lat2);
+        assert_eq!(format!("{:.4}", distance), "166274.1516");
+    }
+
+    #[test]
+    fn interleave_round_trips() {
+        assert_eq!(deinterleave(interleave(0x3ff_ffff, 0x155_5555)), (0x3ff_ffff, 0x155_5555));
+    }
+
+    #[test]
+    fn areas_by_shape_contain_points_within_radius() {
+        let center = (15.0, 37.0);
+        let shape = GeoShape::Radius(200_000.0);
+        let areas = areas_by_shape(center, &shape);
+
+        for point in [PALERMO, CATANIA] {
+            let score = encode(point.0, point.1, GEO_STEP_MAX).score();
+            assert!(distance_if_in_shape(center, &shape, point).is_some());
+            assert!(areas.iter().any(|area| {
+                let (min, max) = area.score_range();
+                (min..max).contains(&score)
+            }));
+        }
+    }
+
+    #[test]
+    fn distance_if_in_shape_checks_box() {
+        let shape = GeoShape::Box(400_000.0, 400_000.0);
+        assert!(distance_if_in_shape((15.0, 37.0), &shape, CATANIA).is_some());
+        assert!(distance_if_in_shape((15.0, 37.0), &shape, (20.0, 37.0)).is_none());
+    }
+}
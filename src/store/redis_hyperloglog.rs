@@ -0,0 +1,432 @@
+// HyperLogLog with the header, registers and hash function of redis, so values can be copied between
+// keys of this server with GET and SET.
+// https://github.com/redis/redis/blob/unstable/src/hyperloglog.c
+//
+// A value starts with a 16 bytes header: "HYLL", the encoding, 3 unused bytes and the cached cardinality
+// in little endian, whose most significant bit is set when the cache is stale. The header is followed by
+// either 16384 registers of 6 bits each (dense), or run length encoded registers (sparse).
+//
+// Values are stored as strings where every char holds a byte, since string values are not binary safe.
+// Bytes from 0x80 are sent as two bytes of UTF-8, so the format is not compatible with upstream redis
+
+use std::convert::TryFrom;
+
+// Bits of the hash used to select a register
+const HLL_P: u32 = 14;
+// Bits of the hash used to count trailing zeros
+const HLL_Q: u32 = 64 - HLL_P;
+pub const HLL_REGISTERS: usize = 1 << HLL_P;
+const HLL_BITS: usize = 6;
+const HLL_REGISTER_MAX: u8 = (1 << HLL_BITS) - 1;
+const HLL_HDR_SIZE: usize = 16;
+const HLL_DENSE_SIZE: usize = HLL_HDR_SIZE + (HLL_REGISTERS * HLL_BITS + 7) / 8;
+const HLL_ALPHA_INF: f64 = 0.721_347_520_444_481_7;
+const HLL_MAGIC: &[u8] = b"HYLL";
+const HLL_DENSE: u8 = 0;
+const HLL_SPARSE: u8 = 1;
+const HLL_CACHE_STALE: u8 = 1 << 7;
+
+// Sparse values are promoted to dense once they are larger than this, same as `hll-sparse-max-bytes`
+pub const HLL_SPARSE_MAX_BYTES: usize = 3000;
+// Sparse opcodes
+const HLL_SPARSE_ZERO_MAX_LEN: usize = 64;
+const HLL_SPARSE_XZERO_MAX_LEN: usize = 16384;
+const HLL_SPARSE_VAL_MAX_VALUE: u8 = 32;
+const HLL_SPARSE_VAL_MAX_LEN: usize = 4;
+
+const HASH_SEED: u64 = 0xadc8_3b19;
+
+#[derive(Debug, PartialEq)]
+pub enum HllError {
+    // The string does not start with a valid header
+    InvalidValue,
+    // The registers cannot be decoded
+    Corrupted,
+}
+
+impl HllError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            HllError::InvalidValue => "WRONGTYPE Key is not a valid HyperLogLog string value.",
+            HllError::Corrupted => "INVALIDOBJ Corrupted HLL object detected",
+        }
+    }
+}
+
+pub type Registers = Vec<u8>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperLogLog {
+    bytes: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog::new()
+    }
+}
+
+impl HyperLogLog {
+    /// Creates an empty HyperLogLog in the sparse encoding
+    pub fn new() -> HyperLogLog {
+        let mut hll = HyperLogLog::from_registers(&vec![0; HLL_REGISTERS], HLL_SPARSE_MAX_BYTES);
+        // The cardinality of an empty value is known to be 0
+        hll.bytes[15] &= !HLL_CACHE_STALE;
+        hll
+    }
+
+    /// Encodes the registers as sparse if they fit within `sparse_max_bytes`, or dense otherwise.
+    /// The cached cardinality is stale
+    pub fn from_registers(registers: &[u8], sparse_max_bytes: usize) -> HyperLogLog {
+        let mut bytes = header(HLL_SPARSE);
+        match encode_sparse(registers) {
+            Some(sparse) if sparse.len() + HLL_HDR_SIZE <= sparse_max_bytes => bytes.extend(sparse),
+            _ => {
+                bytes = header(HLL_DENSE);
+                bytes.extend(encode_dense(registers));
+            }
+        }
+        bytes[15] |= HLL_CACHE_STALE;
+        HyperLogLog { bytes }
+    }
+
+    /// Parses a string value, where every char holds a single byte
+    pub fn parse(value: &str) -> Result<HyperLogLog, HllError> {
+        let bytes = value
+            .chars()
+            .map(|c| u8::try_from(c).map_err(|_| HllError::InvalidValue))
+            .collect::<Result<Vec<u8>, HllError>>()?;
+
+        if bytes.len() < HLL_HDR_SIZE || &bytes[..4] != HLL_MAGIC {
+            return Err(HllError::InvalidValue);
+        }
+        match bytes[4] {
+            HLL_DENSE if bytes.len() != HLL_DENSE_SIZE => Err(HllError::InvalidValue),
+            HLL_DENSE | HLL_SPARSE => Ok(HyperLogLog { bytes }),
+            _ => Err(HllError::InvalidValue),
+        }
+    }
+
+    /// Formats the value as a string, where every char holds a single byte
+    pub fn encode(&self) -> String {
+        self.bytes.iter().map(|byte| *byte as char).collect()
+    }
+
+    pub fn is_sparse(&self) -> bool {
+        self.bytes[4] == HLL_SPARSE
+    }
+
+    pub fn registers(&self) -> Result<Registers, HllError> {
+        let data = &self.bytes[HLL_HDR_SIZE..];
+        if self.is_sparse() {
+            decode_sparse(data)
+        } else {
+            Ok((0..HLL_REGISTERS).map(|index| get_dense_register(data, index)).collect())
+        }
+    }
+
+    /// Adds the elements, and returns whether any register is changed
+    pub fn add(&mut self, elements: &[&str], sparse_max_bytes: usize) -> Result<bool, HllError> {
+        let mut registers = self.registers()?;
+        let mut changed = false;
+        for element in elements {
+            let (index, count) = pattern_len(element.as_bytes());
+            if count > registers[index] {
+                registers[index] = count;
+                changed = true;
+            }
+        }
+
+        if changed {
+            // Dense values are never converted back to sparse
+            let sparse_max_bytes = if self.is_sparse() { sparse_max_bytes } else { 0 };
+            self.bytes = HyperLogLog::from_registers(&registers, sparse_max_bytes).bytes;
+        }
+        Ok(changed)
+    }
+
+    /// Returns the estimated cardinality, using the cached cardinality when it is up to date
+    pub fn count(&mut self) -> Result<u64, HllError> {
+        if self.bytes[15] & HLL_CACHE_STALE == 0 {
+            let mut cached = [0; 8];
+            cached.copy_from_slice(&self.bytes[8..HLL_HDR_SIZE]);
+            return Ok(u64::from_le_bytes(cached));
+        }
+
+        let cardinality = count_registers(&self.registers()?);
+        self.bytes[8..HLL_HDR_SIZE].copy_from_slice(&cardinality.to_le_bytes());
+        Ok(cardinality)
+    }
+}
+
+/// Sets every register to the greater of itself and the other register
+pub fn merge_registers(registers: &mut [u8], other: &[u8]) {
+    for (register, other) in registers.iter_mut().zip(other) {
+        *register = (*register).max(*other);
+    }
+}
+
+/// Estimates the cardinality with the improved estimator of Otmar Ertl, as redis does
+/// https://arxiv.org/abs/1702.01284
+pub fn count_registers(registers: &[u8]) -> u64 {
+    let m = HLL_REGISTERS as f64;
+    let mut histogram = [0u32; HLL_Q as usize + 2];
+    for register in registers {
+        histogram[*register as usize] += 1;
+    }
+
+    let mut z = m * tau((m - histogram[HLL_Q as usize + 1] as f64) / m);
+    for count in histogram[1..=HLL_Q as usize].iter().rev() {
+        z += *count as f64;
+        z *= 0.5;
+    }
+    z += m * sigma(histogram[0] as f64 / m);
+
+    (HLL_ALPHA_INF * m * m / z).round() as u64
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if previous == z {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if previous == z {
+            return z / 3.0;
+        }
+    }
+}
+
+// Returns the register of the element, and the number of trailing zeros of the rest of the hash plus 1
+fn pattern_len(element: &[u8]) -> (usize, u8) {
+    let hash = murmur_hash64a(element, HASH_SEED);
+    let index = (hash & (HLL_REGISTERS as u64 - 1)) as usize;
+    // Caps the count at Q + 1 when the rest of the hash is all zeros
+    let hash = (hash >> HLL_P) | (1 << HLL_Q);
+    (index, hash.trailing_zeros() as u8 + 1)
+}
+
+// MurmurHash2, 64 bit version, reading blocks in little endian like redis does
+fn murmur_hash64a(data: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+
+    let mut h = seed ^ (data.len() as u64).wrapping_mul(M);
+    let mut blocks = data.chunks_exact(8);
+    for block in &mut blocks {
+        let mut k = u64::from_le_bytes([
+            block[0], block[1], block[2], block[3], block[4], block[5], block[6], block[7],
+        ]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        for (index, byte) in tail.iter().enumerate() {
+            h ^= (*byte as u64) << (8 * index);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+fn header(encoding: u8) -> Vec<u8> {
+    let mut header = HLL_MAGIC.to_vec();
+    header.extend([encoding, 0, 0, 0]);
+    header.extend([0; 8]);
+    header
+}
+
+// Registers are packed from the least significant bit of each byte
+fn get_dense_register(data: &[u8], index: usize) -> u8 {
+    let byte = index * HLL_BITS / 8;
+    let first_bit = index * HLL_BITS % 8;
+    let b0 = data[byte] as u16;
+    let b1 = data.get(byte + 1).cloned().unwrap_or(0) as u16;
+    (((b0 >> first_bit) | (b1 << (8 - first_bit))) & HLL_REGISTER_MAX as u16) as u8
+}
+
+fn encode_dense(registers: &[u8]) -> Vec<u8> {
+    let mut data = vec![0u8; HLL_DENSE_SIZE - HLL_HDR_SIZE];
+    for (index, register) in registers.iter().enumerate() {
+        let byte = index * HLL_BITS / 8;
+        let first_bit = index * HLL_BITS % 8;
+        let value = (*register as u16) << first_bit;
+        data[byte] |= value as u8;
+        if let Some(next) = data.get_mut(byte + 1) {
+            *next |= (value >> 8) as u8;
+        }
+    }
+    data
+}
+
+// ZERO:  00xxxxxx          run of 1 to 64 zero registers
+// XZERO: 01xxxxxx yyyyyyyy run of 1 to 16384 zero registers
+// VAL:   1vvvvvxx          run of 1 to 4 registers with value 1 to 32
+fn decode_sparse(data: &[u8]) -> Result<Registers, HllError> {
+    let mut registers: Registers = Vec::with_capacity(HLL_REGISTERS);
+    let mut bytes = data.iter();
+
+    while let Some(opcode) = bytes.next() {
+        let (value, len) = if opcode & 0x80 != 0 {
+            (((opcode >> 2) & 0x1f) + 1, (opcode & 0x03) as usize + 1)
+        } else if opcode & 0x40 != 0 {
+            let next = bytes.next().ok_or(HllError::Corrupted)?;
+            (0, ((((opcode & 0x3f) as usize) << 8) | *next as usize) + 1)
+        } else {
+            (0, (opcode & 0x3f) as usize + 1)
+        };
+
+        if registers.len() + len > HLL_REGISTERS {
+            return Err(HllError::Corrupted);
+        }
+        registers.extend(std::iter::repeat(value).take(len));
+    }
+
+    if registers.len() != HLL_REGISTERS {
+        return Err(HllError::Corrupted);
+    }
+    Ok(registers)
+}
+
+// Returns None if a register cannot be represented in the sparse encoding
+fn encode_sparse(registers: &[u8]) -> Option<Vec<u8>> {
+    let mut data: Vec<u8> = Vec::new();
+    let mut index = 0;
+
+    while index < registers.len() {
+        let value = registers[index];
+        if value > HLL_SPARSE_VAL_MAX_VALUE {
+            return None;
+        }
+        let run = registers[index..].iter().take_while(|register| **register == value).count();
+        index += run;
+
+        let mut remaining = run;
+        while remaining > 0 {
+            if value == 0 && remaining > HLL_SPARSE_ZERO_MAX_LEN {
+                let len = remaining.min(HLL_SPARSE_XZERO_MAX_LEN);
+                data.push(0x40 | ((len - 1) >> 8) as u8);
+                data.push(((len - 1) & 0xff) as u8);
+                remaining -= len;
+            } else if value == 0 {
+                data.push((remaining - 1) as u8);
+                remaining = 0;
+            } else {
+                let len = remaining.min(HLL_SPARSE_VAL_MAX_LEN);
+                data.push(0x80 | ((value - 1) << 2) | (len - 1) as u8);
+                remaining -= len;
+            }
+        }
+    }
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(prefix: &str, count: usize) -> Vec<String> {
+        (0..count).map(|index| format!("{}:{}", prefix, index)).collect()
+    }
+
+    fn add_all(hll: &mut HyperLogLog, elements: &[String]) {
+        let elements: Vec<&str> = elements.iter().map(|element| element.as_str()).collect();
+        hll.add(&elements, HLL_SPARSE_MAX_BYTES).unwrap();
+    }
+
+    #[test]
+    fn new_is_an_empty_sparse_value() {
+        let mut hll = HyperLogLog::new();
+        assert_eq!(hll.encode(), "HYLL\u{1}\0\0\0\0\0\0\0\0\0\0\0\u{7f}\u{ff}");
+        assert!(hll.is_sparse());
+        assert_eq!(hll.count(), Ok(0));
+    }
+
+    #[test]
+    fn parse_rejects_invalid_values() {
+        assert_eq!(HyperLogLog::parse("hello"), Err(HllError::InvalidValue));
+        assert_eq!(HyperLogLog::parse("HYLL\u{2}\0\0\0\0\0\0\0\0\0\0\0"), Err(HllError::InvalidValue));
+        assert_eq!(HyperLogLog::parse("HYLL\0\0\0\0\0\0\0\0\0\0\0\0"), Err(HllError::InvalidValue));
+
+        let corrupted = HyperLogLog::parse("HYLL\u{1}\0\0\0\0\0\0\0\0\0\0\0\u{7f}").unwrap();
+        assert_eq!(corrupted.registers(), Err(HllError::Corrupted));
+    }
+
+    #[test]
+    fn add_reports_whether_registers_changed() {
+        let mut hll = HyperLogLog::new();
+        assert_eq!(hll.add(&["a", "b"], HLL_SPARSE_MAX_BYTES), Ok(true));
+        assert_eq!(hll.add(&["a"], HLL_SPARSE_MAX_BYTES), Ok(false));
+        assert_eq!(hll.count(), Ok(2));
+    }
+
+    #[test]
+    fn sparse_is_promoted_to_dense() {
+        let mut hll = HyperLogLog::new();
+        add_all(&mut hll, &elements("small", 100));
+        assert!(hll.is_sparse());
+
+        add_all(&mut hll, &elements("large", 5000));
+        assert!(!hll.is_sparse());
+        assert_eq!(hll.encode().chars().count(), HLL_DENSE_SIZE);
+
+        let parsed = HyperLogLog::parse(&hll.encode()).unwrap();
+        assert_eq!(parsed.registers(), hll.registers());
+    }
+
+    #[test]
+    fn count_is_within_standard_error() {
+        for cardinality in [1000, 10000, 100000] {
+            let mut hll = HyperLogLog::new();
+            add_all(&mut hll, &elements("element", cardinality));
+
+            let estimate = hll.count().unwrap() as f64;
+            let error = (estimate - cardinality as f64).abs() / cardinality as f64;
+            // 0.81% is the standard error, allow for a few standard deviations
+            assert!(error < 0.03, "estimate {} for {}", estimate, cardinality);
+        }
+    }
+
+    #[test]
+    fn merge_registers_takes_the_union() {
+        let mut first = HyperLogLog::new();
+        let mut second = HyperLogLog::new();
+        add_all(&mut first, &elements("element", 600));
+        add_all(&mut second, &elements("element", 1000));
+
+        let mut registers = first.registers().unwrap();
+        merge_registers(&mut registers, &second.registers().unwrap());
+        assert_eq!(registers, second.registers().unwrap());
+    }
+}
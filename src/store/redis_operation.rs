@@ -1,4 +1,46 @@
+use crate::store::redis_stream::StreamId;
+
 #[derive(Debug)]
 pub struct SetOptionalArgs {
     pub expire_in_ms: Option<u64>
-}
\ No newline at end of file
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StreamTrimStrategy {
+    // MAXLEN: evict entries while the stream is longer than this
+    MaxLen(u64),
+    // MINID: evict entries with IDs lower than this
+    MinId(StreamId),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StreamTrimArgs {
+    pub strategy: StreamTrimStrategy,
+    // `~` was given, so whole nodes are evicted only
+    pub approximate: bool,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StreamClaimArgs {
+    // Entries that were delivered more recently than this are not claimed
+    pub min_idle_time: i64,
+    // Unix time in milliseconds to set as the last delivery, now if not given
+    pub delivery_time: Option<i64>,
+    pub retry_count: Option<u64>,
+    // Creates the pending entry if it does not exist yet
+    pub force: bool,
+    // Does not increment the delivery count, and replies with IDs only
+    pub just_id: bool,
+    pub last_id: Option<StreamId>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StreamAutoClaimArgs {
+    pub min_idle_time: i64,
+    // Where to start scanning the pending entries list
+    pub start: StreamId,
+    // Maximum number of entries to claim
+    pub count: usize,
+    pub just_id: bool,
+}
@@ -0,0 +1,147 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+
+// Scores are ordered with `total_cmp` so they can be used as keys of the B-tree
+#[derive(Debug, Clone, Copy)]
+struct Score(f64);
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        total_cmp(self.0, other.0)
+    }
+}
+
+/// Orders every float, including NaN and negative zero, like f64::total_cmp, which is newer than
+/// the Rust version of the project
+pub fn total_cmp(a: f64, b: f64) -> Ordering {
+    // Flipping the bits other than the sign of negative numbers makes their order that of integers
+    let key = |value: f64| {
+        let bits = value.to_bits() as i64;
+        bits ^ (((bits >> 63) as u64) >> 1) as i64
+    };
+    key(a).cmp(&key(b))
+}
+
+// Members are ordered by score, then lexicographically, like redis.
+// The map gives the score of a member, and the set gives the order
+#[derive(Debug, Default)]
+pub struct SortedSet {
+    scores: HashMap<String, f64>,
+    ordered: BTreeSet<(Score, String)>,
+}
+
+impl SortedSet {
+    pub fn new() -> SortedSet {
+        SortedSet {
+            scores: HashMap::new(),
+            ordered: BTreeSet::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn score(&self, member: &str) -> Option<f64> {
+        self.scores.get(member).cloned()
+    }
+
+    /// Sets the score of the member, and returns its previous score
+    pub fn insert(&mut self, member: &str, score: f64) -> Option<f64> {
+        let previous = self.remove(member);
+        self.scores.insert(String::from(member), score);
+        self.ordered.insert((Score(score), String::from(member)));
+        previous
+    }
+
+    /// Returns the score of the member that is removed
+    pub fn remove(&mut self, member: &str) -> Option<f64> {
+        let score = self.scores.remove(member)?;
+        self.ordered.remove(&(Score(score), String::from(member)));
+        Some(score)
+    }
+
+    /// Iterates over members in ascending order of score
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.ordered.iter().map(|(score, member)| (member.as_str(), score.0))
+    }
+
+    /// Iterates over members with scores in [min, max)
+    pub fn range_by_score(&self, min: f64, max: f64) -> impl Iterator<Item = (&str, f64)> {
+        let start = Bound::Included((Score(min), String::new()));
+        let end = Bound::Excluded((Score(max), String::new()));
+        self.ordered
+            .range((start, end))
+            .map(|(score, member)| (member.as_str(), score.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_orders_members_by_score_then_member() {
+        let mut set = SortedSet::new();
+        assert_eq!(set.insert("b", 2.0), None);
+        set.insert("c", 1.0);
+        set.insert("a", 2.0);
+        assert_eq!(set.insert("c", 3.0), Some(1.0));
+
+        let members: Vec<(&str, f64)> = set.iter().collect();
+        assert_eq!(members, vec![("a", 2.0), ("b", 2.0), ("c", 3.0)]);
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn total_cmp_orders_every_float() {
+        let mut values = [f64::NAN, 1.5, f64::INFINITY, 0.0, -0.0, -2.0, f64::NEG_INFINITY];
+        values.sort_by(|a, b| total_cmp(*a, *b));
+        let bits: Vec<u64> = values.iter().map(|value| value.to_bits()).collect();
+        let expected: Vec<u64> = [f64::NEG_INFINITY, -2.0, -0.0, 0.0, 1.5, f64::INFINITY, f64::NAN]
+            .iter()
+            .map(|value| value.to_bits())
+            .collect();
+        assert_eq!(bits, expected);
+    }
+
+    #[test]
+    fn remove_deletes_member() {
+        let mut set = SortedSet::new();
+        set.insert("a", 1.0);
+        assert_eq!(set.remove("a"), Some(1.0));
+        assert_eq!(set.remove("a"), None);
+        assert!(set.is_empty());
+        assert_eq!(set.iter().count(), 0);
+    }
+
+    #[test]
+    fn range_by_score_excludes_max() {
+        let mut set = SortedSet::new();
+        for (member, score) in [("a", 1.0), ("b", 2.0), ("c", 3.0)] {
+            set.insert(member, score);
+        }
+
+        let members: Vec<&str> = set.range_by_score(2.0, 3.0).map(|(member, _)| member).collect();
+        assert_eq!(members, vec!["b"]);
+    }
+}
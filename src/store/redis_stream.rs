@@ -0,0 +1,880 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+
+use crate::store::command_lock::without_command_lock;
+use crate::store::redis_operation::{StreamAutoClaimArgs, StreamClaimArgs, StreamTrimArgs, StreamTrimStrategy};
+use crate::store::redis_stream_group::ConsumerGroup;
+use crate::util::global::Global;
+
+// Entries are grouped into nodes of this size when trimming with `~`, mirroring
+// the default `stream-node-max-entries` of redis
+pub const STREAM_NODE_MAX_ENTRIES: u64 = 100;
+
+// Incremented on every XADD so that blocked XREAD clients know when to look again
+static STREAM_VERSION: Global<Mutex<u64>> = Global::new();
+static STREAM_ADDED: Global<Condvar> = Global::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+pub type StreamFields = Vec<(String, String)>;
+
+/// Result of XAUTOCLAIM
+#[derive(Debug, PartialEq)]
+pub struct StreamAutoClaim {
+    // Where the next scan should start, 0-0 if the whole pending entries list was scanned
+    pub next_id: StreamId,
+    pub claimed: Vec<StreamId>,
+    // Pending entries that no longer exist in the stream, which are removed from the pending entries list
+    pub deleted: Vec<StreamId>,
+}
+
+/// How the ID of a new entry is specified in XADD
+#[derive(Debug, PartialEq)]
+pub enum StreamIdSpec {
+    // *
+    Auto,
+    // <ms>-*
+    AutoSequence(u64),
+    // <ms>-<seq> or <ms>
+    Explicit(StreamId),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StreamError {
+    InvalidId,
+    IdIsZero,
+    IdTooSmall,
+    IdOverflow,
+}
+
+impl StreamError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            StreamError::InvalidId => "ERR Invalid stream ID specified as stream command argument",
+            StreamError::IdIsZero => "ERR The ID specified in XADD must be greater than 0-0",
+            StreamError::IdTooSmall => {
+                "ERR The ID specified in XADD is equal or smaller than the target stream top item"
+            }
+            StreamError::IdOverflow => "ERR The stream has exhausted the last possible ID, unable to add more items",
+        }
+    }
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId { ms: u64::MAX, seq: u64::MAX };
+
+    pub fn new(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+
+    /// Parses `<ms>-<seq>`, or `<ms>` in which case the sequence is `missing_seq`
+    pub fn parse(input: &str, missing_seq: u64) -> Result<StreamId, StreamError> {
+        let (ms, seq) = match input.split_once('-') {
+            Some((ms, seq)) => (ms, Some(seq)),
+            None => (input, None),
+        };
+
+        let ms = ms.parse().map_err(|_| StreamError::InvalidId)?;
+        let seq = match seq {
+            Some(seq) => seq.parse().map_err(|_| StreamError::InvalidId)?,
+            None => missing_seq,
+        };
+        Ok(StreamId { ms, seq })
+    }
+
+    /// Returns the smallest ID that is greater than this ID
+    pub fn next(&self) -> Option<StreamId> {
+        if self.seq < u64::MAX {
+            Some(StreamId::new(self.ms, self.seq + 1))
+        } else if self.ms < u64::MAX {
+            Some(StreamId::new(self.ms + 1, 0))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the greatest ID that is smaller than this ID
+    pub fn prev(&self) -> Option<StreamId> {
+        if self.seq > 0 {
+            Some(StreamId::new(self.ms, self.seq - 1))
+        } else if self.ms > 0 {
+            Some(StreamId::new(self.ms - 1, u64::MAX))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+impl StreamIdSpec {
+    pub fn parse(input: &str) -> Result<StreamIdSpec, StreamError> {
+        if input == "*" {
+            return Ok(StreamIdSpec::Auto);
+        }
+        if let Some(ms) = input.strip_suffix("-*") {
+            let ms = ms.parse().map_err(|_| StreamError::InvalidId)?;
+            return Ok(StreamIdSpec::AutoSequence(ms));
+        }
+        StreamId::parse(input, 0).map(StreamIdSpec::Explicit)
+    }
+}
+
+// Entries are kept in a B-tree ordered by ID, which gives us cheap range queries
+// and trimming from the head of the stream
+#[derive(Debug)]
+pub struct Stream {
+    entries: BTreeMap<StreamId, StreamFields>,
+    last_id: StreamId,
+    max_deleted_entry_id: StreamId,
+    entries_added: u64,
+    groups: BTreeMap<String, ConsumerGroup>,
+}
+
+impl Default for Stream {
+    fn default() -> Self {
+        Stream::new()
+    }
+}
+
+impl Stream {
+    pub fn new() -> Stream {
+        Stream {
+            entries: BTreeMap::new(),
+            last_id: StreamId::MIN,
+            max_deleted_entry_id: StreamId::MIN,
+            entries_added: 0,
+            groups: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    pub fn max_deleted_entry_id(&self) -> StreamId {
+        self.max_deleted_entry_id
+    }
+
+    pub fn entries_added(&self) -> u64 {
+        self.entries_added
+    }
+
+    pub fn first_entry(&self) -> Option<(&StreamId, &StreamFields)> {
+        self.entries.iter().next()
+    }
+
+    pub fn last_entry(&self) -> Option<(&StreamId, &StreamFields)> {
+        self.entries.iter().next_back()
+    }
+
+    pub fn get(&self, id: &StreamId) -> Option<&StreamFields> {
+        self.entries.get(id)
+    }
+
+    /// Appends an entry and returns its ID. The ID must be greater than every ID
+    /// that has ever been added to the stream
+    pub fn add(&mut self, spec: &StreamIdSpec, fields: StreamFields) -> Result<StreamId, StreamError> {
+        let id = self.next_id(spec)?;
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        self.entries_added += 1;
+        Ok(id)
+    }
+
+    fn next_id(&self, spec: &StreamIdSpec) -> Result<StreamId, StreamError> {
+        let last = self.last_id;
+        match spec {
+            StreamIdSpec::Auto => {
+                let now = Utc::now().timestamp_millis() as u64;
+                if now > last.ms {
+                    Ok(StreamId::new(now, 0))
+                } else {
+                    last.next().ok_or(StreamError::IdOverflow)
+                }
+            }
+            StreamIdSpec::AutoSequence(ms) => {
+                if *ms > last.ms {
+                    // 0-0 is never a valid ID, so a stream starting at ms 0 starts at sequence 1
+                    Ok(StreamId::new(*ms, if *ms == 0 { 1 } else { 0 }))
+                } else if *ms == last.ms {
+                    if last.seq == u64::MAX {
+                        return Err(StreamError::IdTooSmall);
+                    }
+                    Ok(StreamId::new(*ms, last.seq + 1))
+                } else {
+                    Err(StreamError::IdTooSmall)
+                }
+            }
+            StreamIdSpec::Explicit(id) => {
+                if *id == StreamId::MIN {
+                    Err(StreamError::IdIsZero)
+                } else if *id <= last {
+                    Err(StreamError::IdTooSmall)
+                } else {
+                    Ok(*id)
+                }
+            }
+        }
+    }
+
+    /// Returns number of entries that are deleted
+    pub fn delete(&mut self, ids: &[StreamId]) -> u64 {
+        let mut delete_count = 0;
+        for id in ids {
+            if self.entries.remove(id).is_some() {
+                delete_count += 1;
+                if *id > self.max_deleted_entry_id {
+                    self.max_deleted_entry_id = *id;
+                }
+            }
+        }
+        delete_count
+    }
+
+    /// Returns entries between start and end inclusive, in reverse order if `rev` is set
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+        rev: bool,
+    ) -> Vec<(&StreamId, &StreamFields)> {
+        if start > end {
+            return Vec::new();
+        }
+        let limit = count.unwrap_or(usize::MAX);
+        let range = self.entries.range(start..=end);
+        if rev {
+            range.rev().take(limit).collect()
+        } else {
+            range.take(limit).collect()
+        }
+    }
+
+    /// Returns number of entries that are evicted
+    pub fn trim(&mut self, args: &StreamTrimArgs) -> u64 {
+        // With `~`, only whole nodes are evicted, and by default at most 100 nodes at a time
+        let node_size = if args.approximate { STREAM_NODE_MAX_ENTRIES } else { 1 };
+        let limit = match args.limit {
+            Some(0) => u64::MAX,
+            Some(limit) => limit,
+            None if args.approximate => STREAM_NODE_MAX_ENTRIES * 100,
+            None => u64::MAX,
+        };
+
+        let mut evicted = 0;
+        loop {
+            let node: Vec<StreamId> = self.entries.keys().take(node_size as usize).cloned().collect();
+            if node.len() as u64 != node_size || evicted + node_size > limit {
+                break;
+            }
+
+            let can_evict = match &args.strategy {
+                StreamTrimStrategy::MaxLen(max_len) => self.entries.len() as u64 - node_size >= *max_len,
+                StreamTrimStrategy::MinId(min_id) => node[node.len() - 1] < *min_id,
+            };
+            if !can_evict {
+                break;
+            }
+
+            for id in node {
+                self.entries.remove(&id);
+            }
+            evicted += node_size;
+        }
+        evicted
+    }
+}
+
+impl Stream {
+    pub fn groups(&self) -> &BTreeMap<String, ConsumerGroup> {
+        &self.groups
+    }
+
+    pub fn group(&self, name: &str) -> Option<&ConsumerGroup> {
+        self.groups.get(name)
+    }
+
+    pub fn group_mut(&mut self, name: &str) -> Option<&mut ConsumerGroup> {
+        self.groups.get_mut(name)
+    }
+
+    /// Returns false if the group already exists
+    pub fn create_group(&mut self, name: &str, last_id: StreamId, entries_read: Option<u64>) -> bool {
+        if self.groups.contains_key(name) {
+            return false;
+        }
+        self.groups
+            .insert(String::from(name), ConsumerGroup::new(last_id, entries_read));
+        true
+    }
+
+    /// Returns false if the group does not exist
+    pub fn destroy_group(&mut self, name: &str) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    /// Delivers entries that were never delivered to the group before, adding them to the
+    /// consumer's pending entries unless `no_ack` is set. Returns None if the group does not exist
+    pub fn read_group(
+        &mut self,
+        group_name: &str,
+        consumer: &str,
+        count: Option<usize>,
+        no_ack: bool,
+        now: i64,
+    ) -> Option<Vec<(StreamId, StreamFields)>> {
+        let last_id = self.groups.get(group_name)?.last_id;
+        let entries: Vec<(StreamId, StreamFields)> = match last_id.next() {
+            Some(start) => self
+                .range(start, StreamId::MAX, count, false)
+                .into_iter()
+                .map(|(id, fields)| (*id, fields.clone()))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        for (id, _) in entries.iter() {
+            let has_tombstones = self.range_has_tombstones(*id);
+            let estimated_entries_read = self.estimate_entries_read(id);
+            let entries_added = self.entries_added;
+
+            let group = self.groups.get_mut(group_name)?;
+            group.entries_read = match group.entries_read {
+                // Without deletions ahead, the counter can simply be incremented
+                Some(entries_read) if !has_tombstones => Some(entries_read + 1),
+                _ if entries_added > 0 => estimated_entries_read,
+                entries_read => entries_read,
+            };
+            group.last_id = *id;
+        }
+
+        let group = self.groups.get_mut(group_name)?;
+        let consumer_state = group.touch_consumer(consumer, now);
+        if !entries.is_empty() {
+            consumer_state.active_time = Some(now);
+        }
+        if !no_ack {
+            for (id, _) in entries.iter() {
+                group.deliver(*id, consumer, now, 1);
+            }
+        }
+        Some(entries)
+    }
+
+    /// Delivers the consumer's pending entries with IDs greater than `after` again. Entries that are
+    /// deleted from the stream are returned without fields. Returns None if the group does not exist
+    pub fn read_group_pending(
+        &mut self,
+        group_name: &str,
+        consumer: &str,
+        after: StreamId,
+        count: Option<usize>,
+        now: i64,
+    ) -> Option<Vec<(StreamId, Option<StreamFields>)>> {
+        let group = self.groups.get_mut(group_name)?;
+        let consumer_state = group.touch_consumer(consumer, now);
+        let ids: Vec<StreamId> = match after.next() {
+            Some(start) => consumer_state
+                .pending
+                .range(start..)
+                .take(count.unwrap_or(usize::MAX))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut result = Vec::new();
+        for id in ids {
+            let fields = self.entries.get(&id).cloned();
+            if fields.is_some() {
+                if let Some(entry) = group.pending.get_mut(&id) {
+                    entry.delivery_time = now;
+                    entry.delivery_count += 1;
+                }
+            }
+            result.push((id, fields));
+        }
+        Some(result)
+    }
+
+    /// Transfers pending entries that are idle for long enough to the consumer, and returns their IDs.
+    /// Returns None if the group does not exist
+    pub fn claim(
+        &mut self,
+        group_name: &str,
+        consumer: &str,
+        ids: &[StreamId],
+        args: &StreamClaimArgs,
+        now: i64,
+    ) -> Option<Vec<StreamId>> {
+        let group = self.groups.get_mut(group_name)?;
+        if let Some(last_id) = args.last_id {
+            if last_id > group.last_id {
+                group.last_id = last_id;
+            }
+        }
+        group.touch_consumer(consumer, now);
+
+        let delivery_time = args.delivery_time.unwrap_or(now);
+        let mut claimed = Vec::new();
+        for id in ids {
+            // Entries that are deleted from the stream cannot be claimed anymore
+            if !self.entries.contains_key(id) {
+                group.remove_pending(id);
+                continue;
+            }
+
+            let pending = match group.pending.get(id) {
+                Some(pending) => pending,
+                None if args.force => {
+                    group.deliver(*id, consumer, now, 1);
+                    &group.pending[id]
+                }
+                None => continue,
+            };
+            if now - pending.delivery_time < args.min_idle_time {
+                continue;
+            }
+
+            let delivery_count = match args.retry_count {
+                Some(retry_count) => retry_count,
+                None if args.just_id => pending.delivery_count,
+                None => pending.delivery_count + 1,
+            };
+            group.deliver(*id, consumer, delivery_time, delivery_count);
+            group.touch_consumer(consumer, now).active_time = Some(now);
+            claimed.push(*id);
+        }
+        Some(claimed)
+    }
+
+    /// Scans the pending entries list from `start`, claiming up to `count` entries that are idle for
+    /// at least `min_idle_time`. Returns None if the group does not exist
+    pub fn auto_claim(
+        &mut self,
+        group_name: &str,
+        consumer: &str,
+        args: &StreamAutoClaimArgs,
+        now: i64,
+    ) -> Option<StreamAutoClaim> {
+        let StreamAutoClaimArgs {
+            min_idle_time,
+            start,
+            count,
+            just_id,
+        } = *args;
+        let group = self.groups.get_mut(group_name)?;
+        group.touch_consumer(consumer, now);
+
+        // Bounds the amount of work done when most entries are not idle for long enough
+        let mut attempts = count.saturating_mul(10);
+        let mut remaining = count;
+        let mut claimed = Vec::new();
+        let mut deleted = Vec::new();
+        let mut next_id = StreamId::MIN;
+
+        let ids: Vec<StreamId> = group.pending.range(start..).map(|(id, _)| *id).collect();
+        let mut ids = ids.into_iter().peekable();
+        while attempts > 0 && remaining > 0 {
+            let id = match ids.next() {
+                Some(id) => id,
+                None => break,
+            };
+            attempts -= 1;
+
+            if !self.entries.contains_key(&id) {
+                group.remove_pending(&id);
+                deleted.push(id);
+                continue;
+            }
+
+            let pending = &group.pending[&id];
+            if now - pending.delivery_time < min_idle_time {
+                continue;
+            }
+
+            let delivery_count = if just_id { pending.delivery_count } else { pending.delivery_count + 1 };
+            group.deliver(id, consumer, now, delivery_count);
+            group.touch_consumer(consumer, now).active_time = Some(now);
+            claimed.push(id);
+            remaining -= 1;
+        }
+        if let Some(id) = ids.peek() {
+            next_id = *id;
+        }
+
+        Some(StreamAutoClaim {
+            next_id,
+            claimed,
+            deleted,
+        })
+    }
+
+    /// Number of entries that are not delivered to the group yet, None if it cannot be known
+    /// because entries were deleted
+    pub fn group_lag(&self, group: &ConsumerGroup) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+
+        let entries_read = match group.entries_read {
+            Some(entries_read) if !self.range_has_tombstones(group.last_id) => Some(entries_read),
+            _ => self.estimate_entries_read(&group.last_id),
+        };
+        entries_read.map(|entries_read| self.entries_added.saturating_sub(entries_read))
+    }
+
+    // Number of entries that were ever added up to and including the ID, which is only known when
+    // no entries were deleted after it
+    fn estimate_entries_read(&self, id: &StreamId) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+        if self.entries.is_empty() && *id <= self.last_id {
+            return Some(self.entries_added);
+        }
+        if *id == self.last_id {
+            return Some(self.entries_added);
+        }
+        if *id > self.last_id {
+            return None;
+        }
+
+        let first_id = self.first_entry().map(|(id, _)| *id).unwrap_or(StreamId::MIN);
+        if self.max_deleted_entry_id == StreamId::MIN || self.max_deleted_entry_id < first_id {
+            if *id < first_id {
+                return Some(self.entries_added - self.entries.len() as u64);
+            } else if *id == first_id {
+                return Some(self.entries_added - self.entries.len() as u64 + 1);
+            }
+        }
+        None
+    }
+
+    // Whether any entry from `start` onwards was deleted
+    fn range_has_tombstones(&self, start: StreamId) -> bool {
+        if self.entries.is_empty() || self.max_deleted_entry_id == StreamId::MIN {
+            return false;
+        }
+        self.max_deleted_entry_id >= start && self.max_deleted_entry_id <= self.last_id
+    }
+}
+
+fn stream_version_lock() -> &'static Mutex<u64> {
+    STREAM_VERSION.get_or_init(|| Mutex::new(0))
+}
+
+fn stream_added() -> &'static Condvar {
+    STREAM_ADDED.get_or_init(Condvar::new)
+}
+
+pub fn stream_version() -> u64 {
+    *stream_version_lock().lock().unwrap()
+}
+
+/// Wakes up all clients that are blocked on XREAD
+pub fn notify_stream_added() {
+    let mut version = stream_version_lock().lock().unwrap();
+    *version += 1;
+    stream_added().notify_all();
+}
+
+/// Blocks until an entry is added to any stream after `seen_version`, or until the deadline.
+/// Returns false if the deadline has passed
+pub fn wait_for_stream_added(seen_version: u64, deadline: Option<Instant>) -> bool {
+    // Other clients need to execute commands to add entries
+    without_command_lock(|| {
+        let mut version = stream_version_lock().lock().unwrap();
+        while *version == seen_version {
+            match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    version = stream_added().wait_timeout(version, deadline - now).unwrap().0;
+                }
+                None => {
+                    version = stream_added().wait_timeout(version, Duration::from_secs(1)).unwrap().0;
+                }
+            }
+        }
+        true
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> StreamFields {
+        vec![(String::from("field"), String::from("value"))]
+    }
+
+    fn stream_with_ids(ids: &[(u64, u64)]) -> Stream {
+        let mut stream = Stream::new();
+        for (ms, seq) in ids {
+            stream
+                .add(&StreamIdSpec::Explicit(StreamId::new(*ms, *seq)), fields())
+                .unwrap();
+        }
+        stream
+    }
+
+    #[test]
+    fn parse_stream_id_success() {
+        assert_eq!(StreamId::parse("1-2", 0), Ok(StreamId::new(1, 2)));
+        assert_eq!(StreamId::parse("5", 0), Ok(StreamId::new(5, 0)));
+        assert_eq!(StreamId::parse("5", u64::MAX), Ok(StreamId::new(5, u64::MAX)));
+    }
+
+    #[test]
+    fn parse_stream_id_error() {
+        for input in ["", "a-1", "1-a", "-1", "1-2-3"] {
+            assert_eq!(StreamId::parse(input, 0), Err(StreamError::InvalidId));
+        }
+    }
+
+    #[test]
+    fn parse_stream_id_spec_success() {
+        assert_eq!(StreamIdSpec::parse("*"), Ok(StreamIdSpec::Auto));
+        assert_eq!(StreamIdSpec::parse("5-*"), Ok(StreamIdSpec::AutoSequence(5)));
+        assert_eq!(StreamIdSpec::parse("5-1"), Ok(StreamIdSpec::Explicit(StreamId::new(5, 1))));
+    }
+
+    #[test]
+    fn add_generates_increasing_ids() {
+        let mut stream = Stream::new();
+        let first = stream.add(&StreamIdSpec::Auto, fields()).unwrap();
+        let second = stream.add(&StreamIdSpec::Auto, fields()).unwrap();
+        assert!(second > first);
+
+        let third = stream.add(&StreamIdSpec::AutoSequence(second.ms), fields()).unwrap();
+        assert_eq!(third, StreamId::new(second.ms, second.seq + 1));
+        assert_eq!(stream.len(), 3);
+        assert_eq!(stream.last_id(), third);
+    }
+
+    #[test]
+    fn add_partial_id_starting_at_zero_skips_zero_zero() {
+        let mut stream = Stream::new();
+        let id = stream.add(&StreamIdSpec::AutoSequence(0), fields()).unwrap();
+        assert_eq!(id, StreamId::new(0, 1));
+    }
+
+    #[test]
+    fn add_rejects_invalid_ids() {
+        let mut stream = stream_with_ids(&[(5, 5)]);
+
+        let result = stream.add(&StreamIdSpec::Explicit(StreamId::MIN), fields());
+        assert_eq!(result, Err(StreamError::IdIsZero));
+
+        let result = stream.add(&StreamIdSpec::Explicit(StreamId::new(5, 5)), fields());
+        assert_eq!(result, Err(StreamError::IdTooSmall));
+
+        let result = stream.add(&StreamIdSpec::AutoSequence(4), fields());
+        assert_eq!(result, Err(StreamError::IdTooSmall));
+
+        assert_eq!(stream.len(), 1);
+    }
+
+    #[test]
+    fn range_returns_entries_in_order() {
+        let stream = stream_with_ids(&[(1, 0), (2, 0), (3, 0), (4, 0)]);
+
+        let ids: Vec<StreamId> = stream
+            .range(StreamId::new(2, 0), StreamId::MAX, None, false)
+            .into_iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(ids, vec![StreamId::new(2, 0), StreamId::new(3, 0), StreamId::new(4, 0)]);
+
+        let ids: Vec<StreamId> = stream
+            .range(StreamId::MIN, StreamId::MAX, Some(2), true)
+            .into_iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(ids, vec![StreamId::new(4, 0), StreamId::new(3, 0)]);
+
+        assert!(stream.range(StreamId::new(3, 0), StreamId::new(2, 0), None, false).is_empty());
+    }
+
+    #[test]
+    fn delete_tracks_max_deleted_entry_id() {
+        let mut stream = stream_with_ids(&[(1, 0), (2, 0), (3, 0)]);
+        let deleted = stream.delete(&[StreamId::new(2, 0), StreamId::new(9, 0)]);
+
+        assert_eq!(deleted, 1);
+        assert_eq!(stream.len(), 2);
+        assert_eq!(stream.max_deleted_entry_id(), StreamId::new(2, 0));
+        assert_eq!(stream.entries_added(), 3);
+    }
+
+    #[test]
+    fn trim_exact() {
+        let mut stream = stream_with_ids(&[(1, 0), (2, 0), (3, 0), (4, 0)]);
+        let evicted = stream.trim(&StreamTrimArgs {
+            strategy: StreamTrimStrategy::MaxLen(2),
+            approximate: false,
+            limit: None,
+        });
+        assert_eq!(evicted, 2);
+        assert_eq!(*stream.first_entry().unwrap().0, StreamId::new(3, 0));
+
+        let evicted = stream.trim(&StreamTrimArgs {
+            strategy: StreamTrimStrategy::MinId(StreamId::new(4, 0)),
+            approximate: false,
+            limit: None,
+        });
+        assert_eq!(evicted, 1);
+        assert_eq!(stream.len(), 1);
+    }
+
+    #[test]
+    fn trim_approximate_only_evicts_whole_nodes() {
+        let ids: Vec<(u64, u64)> = (1..=250).map(|ms| (ms, 0)).collect();
+        let mut stream = stream_with_ids(&ids);
+
+        let evicted = stream.trim(&StreamTrimArgs {
+            strategy: StreamTrimStrategy::MaxLen(120),
+            approximate: true,
+            limit: None,
+        });
+        assert_eq!(evicted, 100);
+        assert_eq!(stream.len(), 150);
+
+        let evicted = stream.trim(&StreamTrimArgs {
+            strategy: StreamTrimStrategy::MinId(StreamId::new(240, 0)),
+            approximate: true,
+            limit: None,
+        });
+        assert_eq!(evicted, 100);
+        assert_eq!(stream.len(), 50);
+    }
+
+    #[test]
+    fn read_group_delivers_new_entries_once() {
+        let mut stream = stream_with_ids(&[(1, 0), (2, 0), (3, 0)]);
+        assert!(stream.create_group("group", StreamId::MIN, Some(0)));
+        assert!(!stream.create_group("group", StreamId::MIN, Some(0)));
+
+        let entries = stream.read_group("group", "alice", Some(2), false, 10).unwrap();
+        assert_eq!(entries.len(), 2);
+        let entries = stream.read_group("group", "bob", None, false, 10).unwrap();
+        assert_eq!(entries[0].0, StreamId::new(3, 0));
+        assert!(stream.read_group("group", "bob", None, false, 10).unwrap().is_empty());
+
+        let group = stream.group("group").unwrap();
+        assert_eq!(group.last_id, StreamId::new(3, 0));
+        assert_eq!(group.entries_read, Some(3));
+        assert_eq!(group.pending.len(), 3);
+        assert_eq!(stream.group_lag(group), Some(0));
+        assert!(stream.read_group("missing", "alice", None, false, 10).is_none());
+    }
+
+    #[test]
+    fn read_group_pending_returns_deleted_entries_without_fields() {
+        let mut stream = stream_with_ids(&[(1, 0), (2, 0)]);
+        stream.create_group("group", StreamId::MIN, None);
+        stream.read_group("group", "alice", None, false, 10);
+        stream.delete(&[StreamId::new(1, 0)]);
+
+        let entries = stream
+            .read_group_pending("group", "alice", StreamId::MIN, None, 20)
+            .unwrap();
+        assert_eq!(entries, vec![(StreamId::new(1, 0), None), (StreamId::new(2, 0), Some(fields()))]);
+        assert_eq!(stream.group("group").unwrap().pending[&StreamId::new(2, 0)].delivery_count, 2);
+    }
+
+    #[test]
+    fn group_lag_is_unknown_with_deleted_entries_ahead() {
+        let mut stream = stream_with_ids(&[(1, 0), (2, 0), (3, 0)]);
+        stream.create_group("group", StreamId::MIN, Some(0));
+        assert_eq!(stream.group_lag(stream.group("group").unwrap()), Some(3));
+
+        stream.delete(&[StreamId::new(2, 0)]);
+        assert_eq!(stream.group_lag(stream.group("group").unwrap()), None);
+
+        stream.read_group("group", "alice", None, false, 10);
+        assert_eq!(stream.group_lag(stream.group("group").unwrap()), Some(0));
+    }
+
+    #[test]
+    fn claim_only_transfers_idle_entries() {
+        let mut stream = stream_with_ids(&[(1, 0), (2, 0)]);
+        stream.create_group("group", StreamId::MIN, None);
+        stream.read_group("group", "alice", Some(1), false, 0);
+        stream.read_group("group", "alice", Some(1), false, 50);
+        let args = StreamClaimArgs {
+            min_idle_time: 80,
+            delivery_time: None,
+            retry_count: None,
+            force: false,
+            just_id: false,
+            last_id: None,
+        };
+
+        let claimed = stream
+            .claim("group", "bob", &[StreamId::new(1, 0), StreamId::new(2, 0)], &args, 100)
+            .unwrap();
+        assert_eq!(claimed, vec![StreamId::new(1, 0)]);
+        let group = stream.group("group").unwrap();
+        assert_eq!(group.pending[&StreamId::new(1, 0)].consumer, "bob");
+        assert_eq!(group.pending[&StreamId::new(1, 0)].delivery_count, 2);
+        assert_eq!(group.pending[&StreamId::new(2, 0)].consumer, "alice");
+    }
+
+    #[test]
+    fn auto_claim_removes_deleted_entries() {
+        let mut stream = stream_with_ids(&[(1, 0), (2, 0), (3, 0)]);
+        stream.create_group("group", StreamId::MIN, None);
+        stream.read_group("group", "alice", None, false, 0);
+        stream.delete(&[StreamId::new(1, 0)]);
+
+        let auto_claim = stream
+            .auto_claim(
+                "group",
+                "bob",
+                &StreamAutoClaimArgs {
+                    min_idle_time: 0,
+                    start: StreamId::MIN,
+                    count: 1,
+                    just_id: false,
+                },
+                10,
+            )
+            .unwrap();
+        assert_eq!(
+            auto_claim,
+            StreamAutoClaim {
+                next_id: StreamId::new(3, 0),
+                claimed: vec![StreamId::new(2, 0)],
+                deleted: vec![StreamId::new(1, 0)],
+            }
+        );
+        assert_eq!(stream.group("group").unwrap().pending.len(), 2);
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::store::redis_stream::StreamId;
+
+/// An entry that is delivered to a consumer but not acknowledged yet
+#[derive(Debug, PartialEq)]
+pub struct PendingEntry {
+    pub consumer: String,
+    // Unix time in milliseconds of the last delivery
+    pub delivery_time: i64,
+    pub delivery_count: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Consumer {
+    // Unix time in milliseconds of the last interaction, i.e. XREADGROUP, XCLAIM
+    pub seen_time: i64,
+    // Unix time in milliseconds of the last successful read or claim
+    pub active_time: Option<i64>,
+    pub pending: BTreeSet<StreamId>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConsumerGroup {
+    pub last_id: StreamId,
+    // Number of entries the group has read, None if it cannot be known because of deletions
+    pub entries_read: Option<u64>,
+    // Pending entries list (PEL) of the whole group
+    pub pending: BTreeMap<StreamId, PendingEntry>,
+    pub consumers: BTreeMap<String, Consumer>,
+}
+
+impl Consumer {
+    pub fn new(now: i64) -> Consumer {
+        Consumer {
+            seen_time: now,
+            active_time: None,
+            pending: BTreeSet::new(),
+        }
+    }
+}
+
+impl ConsumerGroup {
+    pub fn new(last_id: StreamId, entries_read: Option<u64>) -> ConsumerGroup {
+        ConsumerGroup {
+            last_id,
+            entries_read,
+            pending: BTreeMap::new(),
+            consumers: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the consumer, creating it if it does not exist. Its seen time is updated either way
+    pub fn touch_consumer(&mut self, name: &str, now: i64) -> &mut Consumer {
+        let consumer = self
+            .consumers
+            .entry(String::from(name))
+            .or_insert_with(|| Consumer::new(now));
+        consumer.seen_time = now;
+        consumer
+    }
+
+    /// Returns false if the consumer already exists
+    pub fn create_consumer(&mut self, name: &str, now: i64) -> bool {
+        if self.consumers.contains_key(name) {
+            return false;
+        }
+        self.consumers.insert(String::from(name), Consumer::new(now));
+        true
+    }
+
+    /// Returns the number of pending entries the consumer had, which are discarded with it
+    pub fn delete_consumer(&mut self, name: &str) -> Option<u64> {
+        let consumer = self.consumers.remove(name)?;
+        for id in consumer.pending.iter() {
+            self.pending.remove(id);
+        }
+        Some(consumer.pending.len() as u64)
+    }
+
+    /// Delivers the entry to the consumer, taking it over from any consumer it was delivered to before
+    pub fn deliver(&mut self, id: StreamId, consumer: &str, delivery_time: i64, delivery_count: u64) {
+        if let Some(previous) = self.pending.get(&id) {
+            if let Some(previous_consumer) = self.consumers.get_mut(&previous.consumer) {
+                previous_consumer.pending.remove(&id);
+            }
+        }
+
+        self.pending.insert(
+            id,
+            PendingEntry {
+                consumer: String::from(consumer),
+                delivery_time,
+                delivery_count,
+            },
+        );
+        if let Some(consumer) = self.consumers.get_mut(consumer) {
+            consumer.pending.insert(id);
+        }
+    }
+
+    /// Removes the entry from the pending entries list, returns false if it is not pending
+    pub fn remove_pending(&mut self, id: &StreamId) -> bool {
+        match self.pending.remove(id) {
+            Some(entry) => {
+                if let Some(consumer) = self.consumers.get_mut(&entry.consumer) {
+                    consumer.pending.remove(id);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns number of entries that are acknowledged
+    pub fn ack(&mut self, ids: &[StreamId]) -> u64 {
+        ids.iter().filter(|id| self.remove_pending(id)).count() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deliver_moves_entry_between_consumers() {
+        let mut group = ConsumerGroup::new(StreamId::MIN, None);
+        group.touch_consumer("alice", 0);
+        group.touch_consumer("bob", 0);
+        let id = StreamId::new(1, 0);
+
+        group.deliver(id, "alice", 10, 1);
+        assert!(group.consumers["alice"].pending.contains(&id));
+
+        group.deliver(id, "bob", 20, 2);
+        assert!(group.consumers["alice"].pending.is_empty());
+        assert!(group.consumers["bob"].pending.contains(&id));
+        assert_eq!(
+            group.pending[&id],
+            PendingEntry {
+                consumer: String::from("bob"),
+                delivery_time: 20,
+                delivery_count: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn ack_removes_pending_entries() {
+        let mut group = ConsumerGroup::new(StreamId::MIN, None);
+        group.touch_consumer("alice", 0);
+        group.deliver(StreamId::new(1, 0), "alice", 10, 1);
+        group.deliver(StreamId::new(2, 0), "alice", 10, 1);
+
+        assert_eq!(group.ack(&[StreamId::new(1, 0), StreamId::new(3, 0)]), 1);
+        assert_eq!(group.pending.len(), 1);
+        assert_eq!(group.consumers["alice"].pending.len(), 1);
+    }
+
+    #[test]
+    fn delete_consumer_discards_its_pending_entries() {
+        let mut group = ConsumerGroup::new(StreamId::MIN, None);
+        assert!(group.create_consumer("alice", 0));
+        assert!(!group.create_consumer("alice", 0));
+        group.deliver(StreamId::new(1, 0), "alice", 10, 1);
+
+        assert_eq!(group.delete_consumer("alice"), Some(1));
+        assert!(group.pending.is_empty());
+        assert_eq!(group.delete_consumer("alice"), None);
+    }
+}
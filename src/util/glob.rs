@@ -0,0 +1,122 @@
+// Glob-style matching used by pattern subscriptions, following redis' stringmatchlen:
+// `*` matches any sequence, `?` matches one character, `[...]` matches a set or range
+// (negated with `^`), and `\` escapes the next character
+pub fn glob_match(pattern: &str, string: &str) -> bool {
+    match_bytes(pattern.as_bytes(), string.as_bytes())
+}
+
+fn match_bytes(mut pattern: &[u8], mut string: &[u8]) -> bool {
+    while !pattern.is_empty() {
+        match pattern[0] {
+            b'*' => {
+                while pattern.len() > 1 && pattern[1] == b'*' {
+                    pattern = &pattern[1..];
+                }
+                if pattern.len() == 1 {
+                    return true;
+                }
+                for start in 0..=string.len() {
+                    if match_bytes(&pattern[1..], &string[start..]) {
+                        return true;
+                    }
+                }
+                return false;
+            }
+            b'?' => {
+                if string.is_empty() {
+                    return false;
+                }
+                string = &string[1..];
+            }
+            b'[' => {
+                if string.is_empty() {
+                    return false;
+                }
+                let (matched, rest) = match_set(&pattern[1..], string[0]);
+                if !matched {
+                    return false;
+                }
+                pattern = rest;
+                string = &string[1..];
+                continue;
+            }
+            b'\\' if pattern.len() > 1 => {
+                if string.is_empty() || pattern[1] != string[0] {
+                    return false;
+                }
+                pattern = &pattern[1..];
+                string = &string[1..];
+            }
+            c => {
+                if string.is_empty() || c != string[0] {
+                    return false;
+                }
+                string = &string[1..];
+            }
+        }
+        pattern = &pattern[1..];
+    }
+    string.is_empty()
+}
+
+// Matches a character against the set that starts after `[`, and returns the pattern after `]`
+fn match_set(mut pattern: &[u8], c: u8) -> (bool, &[u8]) {
+    let negate = pattern.first() == Some(&b'^');
+    if negate {
+        pattern = &pattern[1..];
+    }
+
+    let mut matched = false;
+    loop {
+        match pattern {
+            [] => break,
+            [b']', rest @ ..] => {
+                pattern = rest;
+                break;
+            }
+            [b'\\', escaped, rest @ ..] => {
+                matched |= *escaped == c;
+                pattern = rest;
+            }
+            [start, b'-', end, rest @ ..] if *end != b']' => {
+                let (low, high) = if start <= end { (*start, *end) } else { (*end, *start) };
+                matched |= low <= c && c <= high;
+                pattern = rest;
+            }
+            [first, rest @ ..] => {
+                matched |= *first == c;
+                pattern = rest;
+            }
+        }
+    }
+    (matched != negate, pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_match_wildcards() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("news.*", "news.tech"));
+        assert!(glob_match("news.*", "news."));
+        assert!(!glob_match("news.*", "news"));
+        assert!(glob_match("h?llo", "hello"));
+        assert!(!glob_match("h?llo", "hllo"));
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(!glob_match("a*b*c", "aXXbYY"));
+    }
+
+    #[test]
+    fn glob_match_sets_and_escapes() {
+        assert!(glob_match("h[ae]llo", "hallo"));
+        assert!(!glob_match("h[ae]llo", "hillo"));
+        assert!(glob_match("h[^e]llo", "hallo"));
+        assert!(!glob_match("h[^e]llo", "hello"));
+        assert!(glob_match("h[a-c]llo", "hbllo"));
+        assert!(!glob_match("h[a-c]llo", "hdllo"));
+        assert!(glob_match("h\\*llo", "h*llo"));
+        assert!(!glob_match("h\\*llo", "hello"));
+    }
+}
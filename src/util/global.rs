@@ -0,0 +1,63 @@
+use std::cell::UnsafeCell;
+use std::sync::Once;
+
+// A static that is created the first time it is used, since Mutex::new and Condvar::new cannot be
+// called in a static with the Rust version of codecrafters.yml
+pub struct Global<T> {
+    once: Once,
+    value: UnsafeCell<Option<T>>,
+}
+
+// The value is written once, in call_once, which every thread waits for before reading it
+unsafe impl<T: Send + Sync> Sync for Global<T> {}
+
+impl<T> Default for Global<T> {
+    fn default() -> Self {
+        Global::new()
+    }
+}
+
+impl<T> Global<T> {
+    pub const fn new() -> Global<T> {
+        Global {
+            once: Once::new(),
+            value: UnsafeCell::new(None),
+        }
+    }
+
+    /// Returns the value, which `init` creates if this is the first call
+    pub fn get_or_init<F: FnOnce() -> T>(&self, init: F) -> &T {
+        self.once.call_once(|| unsafe { *self.value.get() = Some(init()) });
+        unsafe { (*self.value.get()).as_ref().unwrap() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static VALUE: Global<String> = Global::new();
+
+    #[test]
+    fn value_is_created_once_for_every_thread() {
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                thread::spawn(|| {
+                    VALUE.get_or_init(|| {
+                        CALLS.fetch_add(1, Ordering::SeqCst);
+                        String::from("value")
+                    })
+                    .len()
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), 5);
+        }
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    }
+}
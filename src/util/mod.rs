@@ -0,0 +1,3 @@
+pub mod glob;
+pub mod global;
+pub mod sha256;
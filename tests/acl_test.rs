@@ -0,0 +1,228 @@
+use std::fs;
+
+use serial_test::serial;
+
+use redis_starter_rust::config::config::with_config;
+use redis_starter_rust::request_response::client_input::ClientInput;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::send_command;
+use mock::common::reset_redis::with_reset_redis;
+
+#[test]
+#[serial]
+fn users_can_only_run_allowed_commands_on_allowed_keys() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut admin = ClientInput::new();
+        let mut client = ClientInput::new();
+
+        assert_eq!(
+            send_command(&mut admin, &["ACL", "SETUSER", "alice", "on", ">secret", "+@read", "+set", "-@dangerous", "~cache:*", "%R~config:*"]),
+            "+OK\r\n"
+        );
+        assert_eq!(send_command(&mut client, &["AUTH", "alice", "secret"]), "+OK\r\n");
+        assert_eq!(send_command(&mut client, &["ACL", "WHOAMI"]), "-NOPERM User alice has no permissions to run the 'acl' command\r\n");
+
+        assert_eq!(send_command(&mut client, &["SET", "cache:1", "value"]), "$2\r\nOK\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "cache:1"]), "$5\r\nvalue\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "config:1"]), "$-1\r\n");
+        assert_eq!(send_command(&mut client, &["SET", "config:1", "value"]), "-NOPERM No permissions to access a key\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "other"]), "-NOPERM No permissions to access a key\r\n");
+        assert_eq!(
+            send_command(&mut client, &["XADD", "cache:2", "*", "field", "value"]),
+            "-NOPERM User alice has no permissions to run the 'xadd' command\r\n"
+        );
+        assert_eq!(send_command(&mut admin, &["ACL", "WHOAMI"]), "$7\r\ndefault\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn stream_keys_are_checked_whatever_the_group_is_named() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut admin = ClientInput::new();
+        let mut client = ClientInput::new();
+
+        send_command(&mut admin, &["XADD", "secret", "1-0", "field", "value"]);
+        assert_eq!(send_command(&mut admin, &["XGROUP", "CREATE", "secret", "streams", "0"]), "+OK\r\n");
+        // Allows the arguments that would be taken for keys if STREAMS was looked for anywhere
+        send_command(&mut admin, &["ACL", "SETUSER", "alice", "on", ">secret", "+@stream", "~c", "~STREAMS"]);
+        assert_eq!(send_command(&mut client, &["AUTH", "alice", "secret"]), "+OK\r\n");
+
+        assert_eq!(
+            send_command(&mut client, &["XREADGROUP", "GROUP", "streams", "c", "STREAMS", "secret", "0"]),
+            "-NOPERM No permissions to access a key\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["XREADGROUP", "GROUP", "g", "c", "COUNT", "streams", "STREAMS", "secret", ">"]),
+            "-NOPERM No permissions to access a key\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn channel_permissions_and_denied_commands_in_transactions() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut admin = ClientInput::new();
+        let mut client = ClientInput::new();
+
+        send_command(&mut admin, &["ACL", "SETUSER", "bob", "on", "nopass", "+@pubsub", "+@transaction", "&news.*"]);
+        send_command(&mut client, &["AUTH", "bob", "anything"]);
+
+        assert_eq!(send_command(&mut client, &["PUBLISH", "news.tech", "hello"]), ":0\r\n");
+        assert_eq!(send_command(&mut client, &["PUBLISH", "sport", "hello"]), "-NOPERM No permissions to access a channel\r\n");
+        assert_eq!(send_command(&mut client, &["PSUBSCRIBE", "news.t*"]), "-NOPERM No permissions to access a channel\r\n");
+
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(
+            send_command(&mut client, &["GET", "hello"]),
+            "-NOPERM User bob has no permissions to run the 'get' command\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["EXEC"]),
+            "-EXECABORT Transaction discarded because of previous errors.\r\n"
+        );
+
+        let log = send_command(&mut admin, &["ACL", "LOG", "1"]);
+        assert!(log.starts_with(
+            "*1\r\n*18\r\n$5\r\ncount\r\n:1\r\n$6\r\nreason\r\n$7\r\ncommand\r\n$7\r\ncontext\r\n$5\r\nmulti\r\n$6\r\nobject\r\n$3\r\nget\r\n$8\r\nusername\r\n$3\r\nbob\r\n"
+        ));
+        assert!(send_command(&mut admin, &["ACL", "LOG"]).starts_with("*3\r\n"));
+        assert_eq!(send_command(&mut admin, &["ACL", "LOG", "RESET"]), "+OK\r\n");
+        assert_eq!(send_command(&mut admin, &["ACL", "LOG"]), "*0\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn failed_auth_is_logged() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut admin = ClientInput::new();
+        let mut client = ClientInput::new();
+
+        send_command(&mut admin, &["ACL", "SETUSER", "carol", ">secret", "+@all"]);
+        // The user is disabled until `on` is applied
+        assert_eq!(
+            send_command(&mut client, &["AUTH", "carol", "secret"]),
+            "-WRONGPASS invalid username-password pair or user is disabled.\r\n"
+        );
+        assert!(send_command(&mut admin, &["ACL", "LOG"]).contains("$6\r\nreason\r\n$4\r\nauth\r\n"));
+    });
+}
+
+#[test]
+#[serial]
+fn getuser_list_and_deluser() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut admin = ClientInput::new();
+        let mut client = ClientInput::new();
+
+        send_command(&mut admin, &["ACL", "SETUSER", "dave", "on", "nopass", "+get", "~app:*"]);
+        assert_eq!(
+            send_command(&mut admin, &["ACL", "GETUSER", "dave"]),
+            "*12\r\n$5\r\nflags\r\n*2\r\n$2\r\non\r\n$6\r\nnopass\r\n$9\r\npasswords\r\n*0\r\n$8\r\ncommands\r\n$10\r\n-@all +get\r\n$4\r\nkeys\r\n$6\r\n~app:*\r\n$8\r\nchannels\r\n$0\r\n\r\n$9\r\nselectors\r\n*0\r\n"
+        );
+        assert_eq!(send_command(&mut admin, &["ACL", "GETUSER", "nobody"]), "$-1\r\n");
+        assert_eq!(
+            send_command(&mut admin, &["ACL", "LIST"]),
+            "*2\r\n$51\r\nuser dave on nopass ~app:* resetchannels -@all +get\r\n$34\r\nuser default on nopass ~* &* +@all\r\n"
+        );
+        assert_eq!(
+            send_command(&mut admin, &["ACL", "SETUSER", "dave", "~other:*", "+nothing"]),
+            "-ERR Error in ACL SETUSER modifier '+nothing': Unknown command or category name in ACL\r\n"
+        );
+
+        send_command(&mut client, &["AUTH", "dave", "anything"]);
+        assert_eq!(send_command(&mut client, &["GET", "app:1"]), "$-1\r\n");
+
+        assert_eq!(send_command(&mut admin, &["ACL", "DELUSER", "dave", "nobody"]), ":1\r\n");
+        assert_eq!(
+            send_command(&mut admin, &["ACL", "DELUSER", "default"]),
+            "-ERR The 'default' user cannot be removed\r\n"
+        );
+        // Connections of deleted users are closed without a reply
+        assert_eq!(send_command(&mut client, &["GET", "app:1"]), "");
+        assert!(client.is_closing());
+    });
+}
+
+#[test]
+#[serial]
+fn acl_cat_lists_categories_and_their_commands() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+
+        assert!(send_command(&mut client, &["ACL", "CAT"]).starts_with("*21\r\n$8\r\nkeyspace\r\n$4\r\nread\r\n"));
+        assert_eq!(
+            send_command(&mut client, &["ACL", "CAT", "hyperloglog"]),
+            "*3\r\n$5\r\npfadd\r\n$7\r\npfcount\r\n$7\r\npfmerge\r\n"
+        );
+        assert_eq!(send_command(&mut client, &["ACL", "CAT", "nothing"]), "-ERR Unknown category 'nothing'\r\n");
+        assert_eq!(
+            send_command(&mut client, &["ACL", "NOTHING"]),
+            "-ERR unknown subcommand 'NOTHING'. Try ACL HELP.\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["ACL", "GETUSER"]),
+            "-ERR wrong number of arguments for 'acl|getuser' command\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn requirepass_sets_the_password_of_the_default_user() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut admin = ClientInput::new();
+
+        send_command(&mut admin, &["CONFIG", "SET", "requirepass", "secret"]);
+        let mut client = ClientInput::new();
+        assert_eq!(send_command(&mut client, &["AUTH", "default", "secret"]), "+OK\r\n");
+        assert!(send_command(&mut admin, &["ACL", "LIST"]).contains(
+            "user default on #2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b ~* &* +@all"
+        ));
+    });
+}
+
+#[test]
+#[serial]
+fn users_are_loaded_from_and_saved_to_the_acl_file() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut admin = ClientInput::new();
+        assert!(send_command(&mut admin, &["ACL", "LOAD"]).starts_with("-ERR This Redis instance is not configured to use an ACL file."));
+
+        let path = std::env::temp_dir().join(format!("redis-acl-test-{}.acl", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        with_config(|config| config.aclfile = path.clone());
+
+        fs::write(&path, "# Users\nuser erin on >secret ~* +@read\n\n").unwrap();
+        assert_eq!(send_command(&mut admin, &["ACL", "LOAD"]), "+OK\r\n");
+        let mut client = ClientInput::new();
+        assert_eq!(send_command(&mut client, &["AUTH", "erin", "secret"]), "+OK\r\n");
+
+        fs::write(&path, "user erin on\nuser erin off\n").unwrap();
+        assert_eq!(
+            send_command(&mut admin, &["ACL", "LOAD"]),
+            format!("-ERR {}:2: Duplicate user 'erin' found\r\n", path)
+        );
+
+        send_command(&mut admin, &["ACL", "SETUSER", "frank", "on", "nopass"]);
+        assert_eq!(send_command(&mut admin, &["ACL", "SAVE"]), "+OK\r\n");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "user default on nopass ~* &* +@all\nuser erin on #2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b ~* resetchannels -@all +@read\nuser frank on nopass resetchannels -@all\n"
+        );
+        fs::remove_file(&path).unwrap();
+    });
+}
@@ -0,0 +1,85 @@
+use std::net::TcpListener;
+use std::thread;
+
+use serial_test::serial;
+
+use redis_starter_rust::handle_connection;
+use redis_starter_rust::request_response::client_input::ClientInput;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::send_command;
+use mock::common::reset_redis::with_reset_redis;
+use mock::common::server::{connect, read_reply, send_socket_command};
+
+#[test]
+#[serial]
+fn commands_require_auth_when_requirepass_is_set() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut admin = ClientInput::new();
+        assert_eq!(send_command(&mut admin, &["CONFIG", "SET", "requirepass", "secret"]), "+OK\r\n");
+
+        // Connections that were open before requirepass was set stay authenticated
+        assert_eq!(send_command(&mut admin, &["PING"]), "+PONG\r\n");
+
+        let mut client = ClientInput::new();
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "-NOAUTH Authentication required.\r\n");
+        assert_eq!(send_command(&mut client, &["MULTI"]), "-NOAUTH Authentication required.\r\n");
+        assert_eq!(
+            send_command(&mut client, &["AUTH", "wrong"]),
+            "-WRONGPASS invalid username-password pair or user is disabled.\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["AUTH", "someone", "secret"]),
+            "-WRONGPASS invalid username-password pair or user is disabled.\r\n"
+        );
+        assert_eq!(send_command(&mut client, &["AUTH", "secret"]), "+OK\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$-1\r\n");
+
+        let mut other = ClientInput::new();
+        assert_eq!(send_command(&mut other, &["AUTH", "default", "secret"]), "+OK\r\n");
+        assert_eq!(send_command(&mut other, &["AUTH", "default", "secret", "extra"]), "-ERR syntax error\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn auth_without_requirepass() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+
+        assert_eq!(
+            send_command(&mut client, &["AUTH", "secret"]),
+            "-ERR AUTH <password> called without any password configured for the default user. Are you sure your configuration is correct?\r\n"
+        );
+        assert_eq!(send_command(&mut client, &["AUTH", "default", "anything"]), "+OK\r\n");
+
+        // Clearing requirepass lets unauthenticated connections run commands
+        send_command(&mut client, &["CONFIG", "SET", "requirepass", "secret"]);
+        let mut other = ClientInput::new();
+        assert_eq!(send_command(&mut other, &["PING"]), "-NOAUTH Authentication required.\r\n");
+        send_command(&mut client, &["CONFIG", "SET", "requirepass", ""]);
+        assert_eq!(send_command(&mut other, &["PING"]), "+PONG\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn quit_closes_the_connection() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let stream = listener.incoming().next().unwrap().unwrap();
+            handle_connection(stream);
+        });
+
+        let mut client = connect(&address);
+        assert_eq!(send_socket_command(&mut client, &["QUIT"]), "+OK\r\n");
+        assert_eq!(read_reply(&mut client), "");
+    });
+}
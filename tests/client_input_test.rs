@@ -3,6 +3,7 @@ use serial_test::serial;
 use std::borrow::Borrow;
 
 use std::thread;
+use std::time::{Duration, Instant};
 
 use redis_starter_rust::request_response::{client_input::ClientInput, command::Command};
 use redis_starter_rust::request_response::client_input::HandleClientInput;
@@ -11,8 +12,9 @@ use redis_starter_rust::request_response::parsed_command::ParsedCommand;
 mod mock;
 use mock::tcp_stream::mock_tcp_stream::MockTcpStream;
 
+use mock::common::mock_input::generate_command_buffer;
 use mock::common::reset_redis::with_reset_redis;
-use redis_starter_rust::parser::parser::RESPOutput;
+use redis_starter_rust::parser::parser::{ParseError, RESPOutput};
 use redis_starter_rust::store::redis::{RedisStore, Store};
 use redis_starter_rust::store::redis_operation::SetOptionalArgs;
 
@@ -36,7 +38,7 @@ fn respond_return_unrecognised_command_if_command_is_none() {
     parsed.set_args(Vec::new());
 
     let mut mock_tcp_stream = MockTcpStream::new();
-    let client_input = ClientInput::new();
+    let mut client_input = ClientInput::new();
     client_input.respond(&mut mock_tcp_stream, parsed);
 
     let written_bytes: &[u8] = mock_tcp_stream.write_buffer.borrow();
@@ -52,7 +54,7 @@ fn respond_return_pong_if_command_is_ping() {
     parsed.set_args(Vec::new());
 
     let mut mock_tcp_stream = MockTcpStream::new();
-    let client_input = ClientInput::new();
+    let mut client_input = ClientInput::new();
     client_input.respond(&mut mock_tcp_stream, parsed);
 
     let written_bytes: &[u8] = mock_tcp_stream.write_buffer.borrow();
@@ -68,7 +70,7 @@ fn respond_return_input_if_command_is_echo() {
     parsed.set_args(vec![String::from("hello "), String::from("world")]);
 
     let mut mock_tcp_stream = MockTcpStream::new();
-    let client_input = ClientInput::new();
+    let mut client_input = ClientInput::new();
     client_input.respond(&mut mock_tcp_stream, parsed);
 
     let written_bytes: &[u8] = mock_tcp_stream.write_buffer.borrow();
@@ -77,6 +79,27 @@ fn respond_return_input_if_command_is_echo() {
     assert_eq!(written_bytes, expected_bytes);
 }
 
+#[test]
+#[serial]
+fn respond_return_error_if_command_has_wrong_number_of_arguments() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        let mut parsed = ParsedCommand::new();
+        parsed.set_command(Some(Command::SET));
+        parsed.set_args(vec![String::from("hello")]);
+
+        let mut mock_tcp_stream = MockTcpStream::new();
+        let mut client_input = ClientInput::new();
+        client_input.respond(&mut mock_tcp_stream, parsed);
+
+        let written_bytes: &[u8] = mock_tcp_stream.write_buffer.borrow();
+        let expected_bytes = "-ERR wrong number of arguments for 'set' command\r\n".as_bytes();
+
+        assert_eq!(written_bytes, expected_bytes);
+    });
+}
+
 #[test]
 #[serial]
 fn respond_return_none_if_command_is_get_and_key_is_not_set() {
@@ -88,7 +111,7 @@ fn respond_return_none_if_command_is_get_and_key_is_not_set() {
         parsed.set_args(vec![String::from("hello")]);
 
         let mut mock_tcp_stream = MockTcpStream::new();
-        let client_input = ClientInput::new();
+        let mut client_input = ClientInput::new();
         client_input.respond(&mut mock_tcp_stream, parsed);
 
         let written_bytes: &[u8] = mock_tcp_stream.write_buffer.borrow();
@@ -116,7 +139,7 @@ fn respond_return_data_if_command_is_get_and_key_is_set_without_expiry() {
         }
 
         let mut mock_tcp_stream = MockTcpStream::new();
-        let client_input = ClientInput::new();
+        let mut client_input = ClientInput::new();
         client_input.respond(&mut mock_tcp_stream, parsed);
 
         let written_bytes: &[u8] = mock_tcp_stream.write_buffer.borrow();
@@ -149,7 +172,7 @@ fn respond_return_data_if_command_is_get_and_key_is_set_with_expiry() {
 
         // Key should not be expired
         let mut mock_tcp_stream = MockTcpStream::new();
-        let client_input = ClientInput::new();
+        let mut client_input = ClientInput::new();
         client_input.respond(&mut mock_tcp_stream, parsed);
 
         let written_bytes: &[u8] = mock_tcp_stream.write_buffer.borrow();
@@ -174,3 +197,31 @@ fn respond_return_data_if_command_is_get_and_key_is_set_with_expiry() {
         assert_eq!(written_bytes, expected_bytes);
     });
 }
+
+// Parses a request of `count` elements that arrives in reads of 1 KiB, like handle_connection does
+fn parse_in_chunks(count: usize) -> Duration {
+    let args: Vec<String> = (0..count).map(|i| i.to_string()).collect();
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    let request = generate_command_buffer(&args);
+
+    let started = Instant::now();
+    let mut client_input = ClientInput::new();
+    let mut chunks = request.chunks(1024).peekable();
+    while let Some(chunk) = chunks.next() {
+        let parsed = client_input.parse_input(chunk);
+        if chunks.peek().is_some() {
+            assert_eq!(parsed, Err(ParseError::IncompleteInput));
+        } else {
+            assert!(matches!(parsed, Ok(RESPOutput::Array(elements)) if elements.len() == count));
+        }
+    }
+    started.elapsed()
+}
+
+#[test]
+fn parse_input_of_a_large_request_takes_linear_time() {
+    // Parsing the input again after every read would take 16 times as long for 4 times the elements
+    let small = parse_in_chunks(20_000).max(Duration::from_millis(10));
+    let large = parse_in_chunks(80_000);
+    assert!(large < small * 8, "{:?} for 20000 elements, {:?} for 80000", small, large);
+}
@@ -0,0 +1,148 @@
+use serial_test::serial;
+
+use redis_starter_rust::config::config::Config;
+use redis_starter_rust::request_response::client_input::ClientInput;
+use redis_starter_rust::server::listener::bind_listeners;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::send_command;
+use mock::common::reset_redis::with_reset_redis;
+use mock::common::server::{connect, free_port, read_reply, send_socket_command};
+
+#[test]
+#[serial]
+fn client_names_and_ids() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+        let id = client.id();
+
+        assert_eq!(send_command(&mut client, &["CLIENT", "ID"]), format!(":{}\r\n", id));
+        assert_eq!(send_command(&mut client, &["CLIENT", "GETNAME"]), "$-1\r\n");
+        assert_eq!(send_command(&mut client, &["CLIENT", "SETNAME", "worker-1"]), "+OK\r\n");
+        assert_eq!(send_command(&mut client, &["CLIENT", "GETNAME"]), "$8\r\nworker-1\r\n");
+        assert_eq!(
+            send_command(&mut client, &["CLIENT", "SETNAME", "worker 1"]),
+            "-ERR Client names cannot contain spaces, newlines or special characters.\r\n"
+        );
+        assert_eq!(send_command(&mut client, &["CLIENT", "SETNAME", ""]), "+OK\r\n");
+        assert_eq!(send_command(&mut client, &["CLIENT", "GETNAME"]), "$-1\r\n");
+
+        assert_eq!(send_command(&mut client, &["CLIENT", "NO-EVICT", "on"]), "+OK\r\n");
+        assert_eq!(send_command(&mut client, &["CLIENT", "NO-EVICT", "maybe"]), "-ERR syntax error\r\n");
+        assert_eq!(
+            send_command(&mut client, &["CLIENT", "NOTHING"]),
+            "-ERR unknown subcommand 'NOTHING'. Try CLIENT HELP.\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["CLIENT", "SETNAME"]),
+            "-ERR wrong number of arguments for 'client|setname' command\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn client_list_and_info_describe_connections() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+        let mut subscriber = ClientInput::new();
+        let mut observer = ClientInput::new();
+
+        send_command(&mut client, &["SELECT", "2"]);
+        send_command(&mut client, &["MULTI"]);
+        send_command(&mut client, &["SET", "hello", "world"]);
+        send_command(&mut subscriber, &["SUBSCRIBE", "news"]);
+
+        let id = client.id().to_string();
+        let list = send_command(&mut observer, &["CLIENT", "LIST", "ID", &id]);
+        assert!(list.contains(&format!("id={} ", id)));
+        assert!(list.contains(" flags=x db=2 sub=0 psub=0 multi=1 "));
+        assert!(list.contains(" user=default cmd=set\n"));
+
+        send_command(&mut client, &["DISCARD"]);
+        let info = send_command(&mut client, &["CLIENT", "INFO"]);
+        assert!(info.contains(" flags=N db=2 sub=0 psub=0 multi=-1 "));
+        assert!(info.contains(" cmd=client|info\n"));
+
+        let list = send_command(&mut client, &["CLIENT", "LIST", "TYPE", "pubsub"]);
+        assert!(list.contains(&format!("id={} ", subscriber.id())));
+        assert!(list.contains(" flags=P db=0 sub=1 psub=0 multi=-1 "));
+        assert!(list.contains(" cmd=subscribe\n"));
+        assert!(!list.contains(&format!("id={} ", client.id())));
+
+        let list = send_command(&mut client, &["CLIENT", "LIST", "ID", &id]);
+        assert_eq!(list.matches("id=").count(), 1);
+        assert!(list.contains(" cmd=client|list\n"));
+
+        assert_eq!(
+            send_command(&mut client, &["CLIENT", "LIST", "TYPE", "other"]),
+            "-ERR Unknown client type 'other'\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["CLIENT", "LIST", "ID", "0"]),
+            "-ERR client-id should be greater than 0\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn client_kill_with_filters() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut admin = ClientInput::new();
+        let mut client = ClientInput::new();
+
+        send_command(&mut admin, &["ACL", "SETUSER", "alice", "on", "nopass", "+@all"]);
+        send_command(&mut client, &["AUTH", "alice", "anything"]);
+
+        assert_eq!(
+            send_command(&mut admin, &["CLIENT", "KILL", "USER", "nobody"]),
+            "-ERR No such user 'nobody'\r\n"
+        );
+        assert_eq!(send_command(&mut admin, &["CLIENT", "KILL", "ID", "1", "SKIPME"]), "-ERR syntax error\r\n");
+        assert_eq!(send_command(&mut admin, &["CLIENT", "KILL", "127.0.0.1:1"]), "-ERR No such client\r\n");
+        assert_eq!(send_command(&mut admin, &["CLIENT", "KILL", "USER", "default"]), ":0\r\n");
+        assert_eq!(send_command(&mut admin, &["CLIENT", "KILL", "USER", "alice"]), ":1\r\n");
+
+        // Killed connections are closed without a reply
+        assert_eq!(send_command(&mut client, &["PING"]), "");
+        assert!(client.is_closing());
+
+        // Connections can kill themselves, and are closed after the reply
+        let id = admin.id().to_string();
+        assert_eq!(send_command(&mut admin, &["CLIENT", "KILL", "ID", &id, "SKIPME", "no"]), ":1\r\n");
+        assert!(admin.is_closing());
+    });
+}
+
+#[test]
+#[serial]
+fn client_kill_closes_the_connection_of_another_client() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut config = Config::new();
+        config.port = free_port();
+        let listeners = bind_listeners(&config).unwrap();
+        let address = listeners[0].address();
+        for listener in listeners {
+            listener.spawn();
+        }
+
+        let mut admin = connect(&address);
+        let mut client = connect(&address);
+        let reply = send_socket_command(&mut client, &["CLIENT", "ID"]);
+        let id = reply.trim_start_matches(':').trim_end();
+
+        let list = send_socket_command(&mut admin, &["CLIENT", "LIST", "ID", id]);
+        assert!(list.contains(&format!("addr={} ", client.local_addr().unwrap())));
+        assert!(list.contains(&format!("laddr={} ", address)));
+
+        assert_eq!(send_socket_command(&mut admin, &["CLIENT", "KILL", "ID", id]), ":1\r\n");
+        assert_eq!(read_reply(&mut client), "");
+    });
+}
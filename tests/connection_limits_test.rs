@@ -0,0 +1,118 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use serial_test::serial;
+
+use redis_starter_rust::config::config::with_config;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::reset_redis::with_reset_redis;
+use mock::common::server::{connect, read_reply, send_socket_command, start_server};
+
+#[test]
+#[serial]
+fn connections_over_maxclients_are_refused() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let server = start_server();
+        let address = server.address();
+        with_config(|config| config.maxclients = 1);
+
+        let mut first = connect(&address);
+        assert_eq!(send_socket_command(&mut first, &["PING"]), "+PONG\r\n");
+
+        let mut second = connect(&address);
+        assert_eq!(read_reply(&mut second), "-ERR max number of clients reached\r\n");
+        assert_eq!(read_reply(&mut second), "");
+
+        assert_eq!(send_socket_command(&mut first, &["PING"]), "+PONG\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn idle_connections_are_closed_after_the_timeout() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let server = start_server();
+        let address = server.address();
+        let mut client = connect(&address);
+        let mut subscriber = connect(&address);
+        assert_eq!(send_socket_command(&mut client, &["CONFIG", "SET", "timeout", "1"]), "+OK\r\n");
+        assert!(send_socket_command(&mut subscriber, &["SUBSCRIBE", "news"]).starts_with("*3\r\n"));
+
+        // The timeout applies once the connection next waits for a command
+        let started = Instant::now();
+        assert_eq!(send_socket_command(&mut client, &["PING"]), "+PONG\r\n");
+        assert_eq!(read_reply(&mut client), "");
+        assert!(started.elapsed() >= Duration::from_secs(1));
+
+        // Subscribed connections stay open while waiting for messages
+        let mut publisher = connect(&address);
+        assert_eq!(send_socket_command(&mut publisher, &["PUBLISH", "news", "hello"]), ":1\r\n");
+        assert_eq!(read_reply(&mut subscriber), "*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn connections_that_never_send_a_command_are_closed_after_the_timeout() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let server = start_server();
+        let address = server.address();
+        with_config(|config| {
+            config.timeout = 1;
+            config.maxclients = 1;
+        });
+
+        let started = Instant::now();
+        let mut silent = connect(&address);
+        assert_eq!(read_reply(&mut silent), "");
+        assert!(started.elapsed() >= Duration::from_secs(1));
+
+        // The closed connection no longer takes up a slot of maxclients
+        let mut client = connect(&address);
+        assert_eq!(send_socket_command(&mut client, &["PING"]), "+PONG\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn malformed_requests_get_a_protocol_error_and_are_closed() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let server = start_server();
+        let address = server.address();
+        let nested = format!("{}$4\r\nPING\r\n", "*1\r\n".repeat(64));
+        let inline = "a".repeat(64 * 1024 + 1);
+        let requests = [
+            ("*1\r\n$abc\r\n", "invalid bulk length"),
+            ("*1\r\n$-5\r\n", "invalid bulk length"),
+            ("*1\r\n$600000000\r\n", "invalid bulk length"),
+            ("*abc\r\n", "invalid multibulk length"),
+            ("*2000000\r\n", "invalid multibulk length"),
+            (nested.as_str(), "too many nested aggregates"),
+            ("SET key \"value\r\n", "unbalanced quotes in request"),
+            (inline.as_str(), "too big inline request"),
+        ];
+
+        for (request, error) in requests.iter() {
+            let mut client = connect(&address);
+            client.write_all(request.as_bytes()).unwrap();
+            assert!(read_reply(&mut client).starts_with(&format!("-ERR Protocol error: {} at offset ", error)));
+            assert_eq!(read_reply(&mut client), "");
+        }
+
+        // Bulk strings are limited by proto-max-bulk-len
+        let mut client = connect(&address);
+        assert_eq!(send_socket_command(&mut client, &["CONFIG", "SET", "proto-max-bulk-len", "1mb"]), "+OK\r\n");
+        client.write_all(b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$1048577\r\n").unwrap();
+        assert_eq!(
+            read_reply(&mut client),
+            "-ERR Protocol error: invalid bulk length at offset 23 (bulk string): expected a length between -1 and 1048576, found \"1048577\"\r\n"
+        );
+    });
+}
@@ -0,0 +1,145 @@
+use serial_test::serial;
+
+use redis_starter_rust::request_response::client_input::ClientInput;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::send_command;
+use mock::common::reset_redis::with_reset_redis;
+
+#[test]
+#[serial]
+fn select_switches_between_databases() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+        let mut other = ClientInput::new();
+
+        send_command(&mut client, &["SET", "hello", "world"]);
+        assert_eq!(send_command(&mut client, &["SELECT", "15"]), "+OK\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$-1\r\n");
+        send_command(&mut client, &["SET", "hello", "there"]);
+
+        // Other connections stay on database 0
+        assert_eq!(send_command(&mut other, &["GET", "hello"]), "$5\r\nworld\r\n");
+
+        assert_eq!(send_command(&mut client, &["SELECT", "16"]), "-ERR DB index is out of range\r\n");
+        assert_eq!(
+            send_command(&mut client, &["SELECT", "one"]),
+            "-ERR value is not an integer or out of range\r\n"
+        );
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$5\r\nthere\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn move_key_to_another_database() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+
+        send_command(&mut client, &["SET", "hello", "world"]);
+        assert_eq!(send_command(&mut client, &["MOVE", "hello", "1"]), ":1\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$-1\r\n");
+        assert_eq!(send_command(&mut client, &["MOVE", "hello", "1"]), ":0\r\n");
+
+        // The key is not moved if the destination already has it
+        send_command(&mut client, &["SET", "hello", "again"]);
+        assert_eq!(send_command(&mut client, &["MOVE", "hello", "1"]), ":0\r\n");
+        send_command(&mut client, &["SELECT", "1"]);
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$5\r\nworld\r\n");
+
+        assert_eq!(
+            send_command(&mut client, &["MOVE", "hello", "1"]),
+            "-ERR source and destination objects are the same\r\n"
+        );
+        assert_eq!(send_command(&mut client, &["MOVE", "hello", "-1"]), "-ERR DB index is out of range\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn swapdb_swaps_keys_for_all_connections() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+        let mut other = ClientInput::new();
+
+        send_command(&mut client, &["SET", "hello", "zero"]);
+        send_command(&mut other, &["SELECT", "2"]);
+        send_command(&mut other, &["SET", "hello", "two"]);
+
+        assert_eq!(send_command(&mut client, &["SWAPDB", "0", "2"]), "+OK\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$3\r\ntwo\r\n");
+        assert_eq!(send_command(&mut other, &["GET", "hello"]), "$4\r\nzero\r\n");
+
+        assert_eq!(send_command(&mut client, &["SWAPDB", "a", "2"]), "-ERR invalid first DB index\r\n");
+        assert_eq!(send_command(&mut client, &["SWAPDB", "0", "b"]), "-ERR invalid second DB index\r\n");
+        assert_eq!(send_command(&mut client, &["SWAPDB", "0", "16"]), "-ERR DB index is out of range\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn flushdb_and_flushall() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+
+        send_command(&mut client, &["SET", "hello", "zero"]);
+        send_command(&mut client, &["SELECT", "1"]);
+        send_command(&mut client, &["SET", "hello", "one"]);
+
+        assert_eq!(send_command(&mut client, &["FLUSHDB", "ASYNC"]), "+OK\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$-1\r\n");
+        send_command(&mut client, &["SELECT", "0"]);
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$4\r\nzero\r\n");
+
+        assert_eq!(send_command(&mut client, &["FLUSHALL", "now"]), "-ERR syntax error\r\n");
+        assert_eq!(send_command(&mut client, &["FLUSHALL", "sync"]), "+OK\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$-1\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn watched_keys_are_modified_by_database_commands() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+        let mut other = ClientInput::new();
+
+        send_command(&mut client, &["SELECT", "3"]);
+        send_command(&mut client, &["SET", "balance", "10"]);
+        send_command(&mut client, &["WATCH", "balance"]);
+        send_command(&mut other, &["FLUSHALL"]);
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*-1\r\n");
+
+        send_command(&mut client, &["SET", "balance", "10"]);
+        send_command(&mut client, &["WATCH", "balance"]);
+        send_command(&mut other, &["SWAPDB", "3", "4"]);
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*-1\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn databases_config_is_immutable() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+
+        assert_eq!(
+            send_command(&mut client, &["CONFIG", "GET", "databases"]),
+            "*2\r\n$9\r\ndatabases\r\n$2\r\n16\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["CONFIG", "SET", "databases", "4"]),
+            "-ERR CONFIG SET failed (possibly related to argument 'databases') - can't set immutable config\r\n"
+        );
+    });
+}
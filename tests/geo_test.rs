@@ -0,0 +1,135 @@
+use serial_test::serial;
+
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::send_command_on_new_connection;
+use mock::common::reset_redis::with_reset_redis;
+
+// Positions from the examples of the redis documentation
+fn add_sicily() {
+    send_command_on_new_connection(&[
+        "GEOADD", "Sicily", "13.361389", "38.115556", "Palermo", "15.087269", "37.502669", "Catania",
+    ]);
+    send_command_on_new_connection(&[
+        "GEOADD", "Sicily", "12.758489", "38.788135", "edge1", "17.241510", "38.788135", "edge2",
+    ]);
+}
+
+#[test]
+#[serial]
+fn geoadd_validates_positions() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        assert_eq!(
+            send_command_on_new_connection(&["GEOADD", "Sicily", "13.361389", "38.115556", "Palermo", "15.087269", "37.502669", "Catania"]),
+            ":2\r\n"
+        );
+        assert_eq!(send_command_on_new_connection(&["GEOADD", "Sicily", "NX", "13", "38", "Palermo"]), ":0\r\n");
+        assert_eq!(send_command_on_new_connection(&["GEOADD", "Sicily", "XX", "CH", "13", "38", "Palermo"]), ":1\r\n");
+        assert_eq!(
+            send_command_on_new_connection(&["GEOADD", "Sicily", "181", "38", "Nowhere"]),
+            "-ERR invalid longitude,latitude pair 181.000000,38.000000\r\n"
+        );
+        assert_eq!(send_command_on_new_connection(&["GEOADD", "Sicily", "13", "38"]), "-ERR wrong number of arguments for 'geoadd' command\r\n");
+        assert_eq!(send_command_on_new_connection(&["GEOADD", "Sicily", "13", "38", "a", "14"]), "-ERR syntax error\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn geodist_geopos_and_geohash() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        add_sicily();
+
+        assert_eq!(send_command_on_new_connection(&["GEODIST", "Sicily", "Palermo", "Catania"]), "$11\r\n166274.1516\r\n");
+        assert_eq!(send_command_on_new_connection(&["GEODIST", "Sicily", "Palermo", "Catania", "km"]), "$8\r\n166.2742\r\n");
+        assert_eq!(send_command_on_new_connection(&["GEODIST", "Sicily", "Palermo", "Catania", "mi"]), "$8\r\n103.3182\r\n");
+        assert_eq!(send_command_on_new_connection(&["GEODIST", "Sicily", "Palermo", "Nowhere"]), "$-1\r\n");
+
+        assert_eq!(
+            send_command_on_new_connection(&["GEOPOS", "Sicily", "Palermo", "Nowhere"]),
+            "*2\r\n*2\r\n$20\r\n13.36138933897018433\r\n$20\r\n38.11555639549629859\r\n*-1\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["GEOHASH", "Sicily", "Palermo", "Catania"]),
+            "*2\r\n$11\r\nsqc8b49rny0\r\n$11\r\nsqdtr74hyu0\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn geosearch_by_radius_and_box() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        add_sicily();
+
+        assert_eq!(
+            send_command_on_new_connection(&["GEOSEARCH", "Sicily", "FROMLONLAT", "15", "37", "BYRADIUS", "200", "km", "ASC"]),
+            "*2\r\n$7\r\nCatania\r\n$7\r\nPalermo\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&[
+                "GEOSEARCH", "Sicily", "FROMLONLAT", "15", "37", "BYBOX", "400", "400", "km", "ASC", "WITHCOORD",
+                "WITHDIST", "WITHHASH",
+            ]),
+            "*4\r\n\
+             *4\r\n$7\r\nCatania\r\n$7\r\n56.4413\r\n:3479447370796909\r\n*2\r\n$20\r\n15.08726745843887329\r\n$20\r\n37.50266842333162032\r\n\
+             *4\r\n$7\r\nPalermo\r\n$8\r\n190.4424\r\n:3479099956230698\r\n*2\r\n$20\r\n13.36138933897018433\r\n$20\r\n38.11555639549629859\r\n\
+             *4\r\n$5\r\nedge2\r\n$8\r\n279.7403\r\n:3481342659049484\r\n*2\r\n$20\r\n17.24151045083999634\r\n$20\r\n38.78813451624225195\r\n\
+             *4\r\n$5\r\nedge1\r\n$8\r\n279.7405\r\n:3479273021651468\r\n*2\r\n$19\r\n12.7584877610206604\r\n$20\r\n38.78813451624225195\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["GEOSEARCH", "Sicily", "FROMMEMBER", "Palermo", "BYRADIUS", "500", "km", "DESC", "COUNT", "1"]),
+            "*1\r\n$5\r\nedge2\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["GEOSEARCH", "Sicily", "FROMMEMBER", "Nowhere", "BYRADIUS", "1", "km"]),
+            "-ERR could not decode requested zset member\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["GEOSEARCH", "missing", "FROMLONLAT", "15", "37", "BYRADIUS", "1", "km"]),
+            "*0\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn geosearchstore_stores_members_or_distances() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        add_sicily();
+
+        assert_eq!(
+            send_command_on_new_connection(&["GEOSEARCHSTORE", "near", "Sicily", "FROMLONLAT", "15", "37", "BYRADIUS", "200", "km"]),
+            ":2\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["GEOPOS", "near", "Catania"]),
+            "*1\r\n*2\r\n$20\r\n15.08726745843887329\r\n$20\r\n37.50266842333162032\r\n"
+        );
+
+        assert_eq!(
+            send_command_on_new_connection(&[
+                "GEOSEARCHSTORE", "near", "Sicily", "FROMLONLAT", "15", "37", "BYRADIUS", "200", "km", "COUNT", "1",
+                "STOREDIST",
+            ]),
+            ":1\r\n"
+        );
+        let store = RedisStore::get_store();
+        let near = store.get_sorted_set("near").unwrap().unwrap();
+        assert_eq!(near.len(), 1);
+        assert_eq!(format!("{:.4}", near.score("Catania").unwrap()), "56.4413");
+
+        assert_eq!(
+            send_command_on_new_connection(&["GEOSEARCHSTORE", "near", "Sicily", "FROMLONLAT", "0", "0", "BYRADIUS", "1", "km"]),
+            ":0\r\n"
+        );
+        assert!(store.get_sorted_set("near").unwrap().is_none());
+    });
+}
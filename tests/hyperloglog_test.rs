@@ -0,0 +1,74 @@
+use serial_test::serial;
+
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::send_command_on_new_connection;
+use mock::common::reset_redis::with_reset_redis;
+
+// Extracts the value of a bulk string reply
+fn bulk_string_value(reply: &str) -> String {
+    let start = reply.find("\r\n").unwrap() + 2;
+    String::from(&reply[start..reply.len() - 2])
+}
+
+#[test]
+#[serial]
+fn pfadd_and_pfcount() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        assert_eq!(send_command_on_new_connection(&["PFADD", "visitors", "alice", "bob", "carol"]), ":1\r\n");
+        assert_eq!(send_command_on_new_connection(&["PFADD", "visitors", "alice"]), ":0\r\n");
+        assert_eq!(send_command_on_new_connection(&["PFCOUNT", "visitors"]), ":3\r\n");
+        assert_eq!(send_command_on_new_connection(&["PFCOUNT", "missing"]), ":0\r\n");
+
+        assert_eq!(send_command_on_new_connection(&["PFADD", "empty"]), ":1\r\n");
+        assert_eq!(send_command_on_new_connection(&["PFADD", "empty"]), ":0\r\n");
+        assert_eq!(send_command_on_new_connection(&["PFCOUNT", "empty"]), ":0\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn pfcount_and_pfmerge_take_the_union() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        send_command_on_new_connection(&["PFADD", "monday", "alice", "bob"]);
+        send_command_on_new_connection(&["PFADD", "tuesday", "bob", "carol", "dave"]);
+        assert_eq!(send_command_on_new_connection(&["PFCOUNT", "monday", "tuesday", "missing"]), ":4\r\n");
+
+        assert_eq!(send_command_on_new_connection(&["PFMERGE", "week", "monday", "tuesday"]), "+OK\r\n");
+        assert_eq!(send_command_on_new_connection(&["PFCOUNT", "week"]), ":4\r\n");
+        assert_eq!(send_command_on_new_connection(&["PFMERGE", "week", "missing"]), "+OK\r\n");
+        assert_eq!(send_command_on_new_connection(&["PFCOUNT", "week"]), ":4\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn hyperloglog_values_are_strings() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        send_command_on_new_connection(&["PFADD", "visitors", "alice", "bob"]);
+        let value = bulk_string_value(&send_command_on_new_connection(&["GET", "visitors"]));
+        assert!(value.starts_with("HYLL"));
+
+        send_command_on_new_connection(&["SET", "copy", &value]);
+        assert_eq!(send_command_on_new_connection(&["PFCOUNT", "copy"]), ":2\r\n");
+
+        send_command_on_new_connection(&["SET", "greeting", "hello"]);
+        assert_eq!(
+            send_command_on_new_connection(&["PFADD", "greeting", "alice"]),
+            "-WRONGTYPE Key is not a valid HyperLogLog string value.\r\n"
+        );
+        send_command_on_new_connection(&["XADD", "events", "*", "field", "value"]);
+        assert_eq!(
+            send_command_on_new_connection(&["PFCOUNT", "events"]),
+            "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+        );
+    });
+}
@@ -0,0 +1,132 @@
+use std::thread;
+use std::time::Duration;
+
+use serial_test::serial;
+
+use redis_starter_rust::request_response::client_input::ClientInput;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::{pending_messages, send_command};
+use mock::common::reset_redis::with_reset_redis;
+
+fn pmessage(pattern: &str, channel: &str, message: &str) -> String {
+    format!(
+        "*4\r\n$8\r\npmessage\r\n${}\r\n{}\r\n${}\r\n{}\r\n${}\r\n{}\r\n",
+        pattern.len(),
+        pattern,
+        channel.len(),
+        channel,
+        message.len(),
+        message
+    )
+}
+
+#[test]
+#[serial]
+fn config_get_and_set() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+
+        assert_eq!(
+            send_command(&mut client, &["CONFIG", "GET", "notify-*"]),
+            "*2\r\n$22\r\nnotify-keyspace-events\r\n$0\r\n\r\n"
+        );
+        assert_eq!(send_command(&mut client, &["CONFIG", "SET", "notify-keyspace-events", "KEA"]), "+OK\r\n");
+        assert_eq!(
+            send_command(&mut client, &["CONFIG", "GET", "NOTIFY-KEYSPACE-EVENTS"]),
+            "*2\r\n$22\r\nnotify-keyspace-events\r\n$3\r\nAKE\r\n"
+        );
+        assert_eq!(send_command(&mut client, &["CONFIG", "GET", "maxmemory"]), "*0\r\n");
+
+        assert_eq!(
+            send_command(&mut client, &["CONFIG", "SET", "notify-keyspace-events", "Kw"]),
+            "-ERR CONFIG SET failed (possibly related to argument 'notify-keyspace-events') - Invalid event class character. Use 'Ag$lshzxeKEtmn'.\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["CONFIG", "SET", "notify-keyspace-events", "", "maxmemory", "1"]),
+            "-ERR Unknown option or number of arguments for CONFIG SET - 'maxmemory'\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["CONFIG", "GET", "notify-keyspace-events"]),
+            "*2\r\n$22\r\nnotify-keyspace-events\r\n$3\r\nAKE\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["CONFIG", "SET", "notify-keyspace-events"]),
+            "-ERR wrong number of arguments for 'config|set' command\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn keyspace_and_keyevent_notifications() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut subscriber = ClientInput::new();
+        let mut client = ClientInput::new();
+
+        send_command(&mut subscriber, &["PSUBSCRIBE", "__key*__:*"]);
+        send_command(&mut client, &["SET", "greeting", "hello"]);
+        assert_eq!(pending_messages(&subscriber), "");
+
+        send_command(&mut client, &["CONFIG", "SET", "notify-keyspace-events", "KE$"]);
+        send_command(&mut client, &["SET", "greeting", "hello"]);
+        assert_eq!(
+            pending_messages(&subscriber),
+            [
+                pmessage("__key*__:*", "__keyspace@0__:greeting", "set"),
+                pmessage("__key*__:*", "__keyevent@0__:set", "greeting"),
+            ]
+            .concat()
+        );
+
+        // Streams are not enabled by '$'
+        send_command(&mut client, &["XADD", "events", "*", "field", "value"]);
+        assert_eq!(pending_messages(&subscriber), "");
+
+        send_command(&mut client, &["CONFIG", "SET", "notify-keyspace-events", "Etzn"]);
+        send_command(&mut client, &["XADD", "events", "MAXLEN", "1", "*", "field", "value"]);
+        send_command(&mut client, &["GEOADD", "places", "13.361389", "38.115556", "Palermo"]);
+        assert_eq!(
+            pending_messages(&subscriber),
+            [
+                pmessage("__key*__:*", "__keyevent@0__:xadd", "events"),
+                pmessage("__key*__:*", "__keyevent@0__:xtrim", "events"),
+                pmessage("__key*__:*", "__keyevent@0__:new", "places"),
+                pmessage("__key*__:*", "__keyevent@0__:zadd", "places"),
+            ]
+            .concat()
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn expired_and_deleted_keys_are_notified() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut subscriber = ClientInput::new();
+        let mut client = ClientInput::new();
+
+        send_command(&mut subscriber, &["SUBSCRIBE", "__keyevent@0__:expired", "__keyevent@0__:del"]);
+        send_command(&mut client, &["CONFIG", "SET", "notify-keyspace-events", "Egx"]);
+
+        send_command(&mut client, &["SET", "session", "token", "PX", "10"]);
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(send_command(&mut client, &["GET", "session"]), "$-1\r\n");
+        assert_eq!(
+            pending_messages(&subscriber),
+            "*3\r\n$7\r\nmessage\r\n$22\r\n__keyevent@0__:expired\r\n$7\r\nsession\r\n"
+        );
+
+        send_command(&mut client, &["GEOADD", "near", "13", "38", "a"]);
+        send_command(&mut client, &["GEOSEARCHSTORE", "near", "missing", "FROMLONLAT", "0", "0", "BYRADIUS", "1", "km"]);
+        assert_eq!(
+            pending_messages(&subscriber),
+            "*3\r\n$7\r\nmessage\r\n$18\r\n__keyevent@0__:del\r\n$4\r\nnear\r\n"
+        );
+    });
+}
@@ -0,0 +1,33 @@
+use std::str::from_utf8;
+
+use redis_starter_rust::handle_connection_helper;
+use redis_starter_rust::request_response::client_input::ClientInput;
+
+use crate::mock::common::mock_input::generate_command_buffer;
+use crate::mock::tcp_stream::mock_tcp_stream::MockTcpStream;
+
+// Sends a command on a connection that keeps its state between commands, e.g. its subscriptions
+pub fn send_command(client_input: &mut ClientInput, args: &[&str]) -> String {
+    let mut mock_tcp_stream = MockTcpStream::new();
+    mock_tcp_stream.read_buffer = generate_command_buffer(args);
+
+    let result = handle_connection_helper(&mut mock_tcp_stream, client_input);
+    assert!(result.is_ok());
+
+    String::from(from_utf8(&mock_tcp_stream.write_buffer).unwrap())
+}
+
+pub fn send_command_on_new_connection(args: &[&str]) -> String {
+    send_command(&mut ClientInput::new(), args)
+}
+
+// Messages published to the subscriptions of the connection since the last call
+pub fn pending_messages(client_input: &ClientInput) -> String {
+    let mut mock_tcp_stream = MockTcpStream::new();
+    client_input.write_pending_messages(&mut mock_tcp_stream);
+    String::from(from_utf8(&mock_tcp_stream.write_buffer).unwrap())
+}
+
+pub fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| String::from(*arg)).collect()
+}
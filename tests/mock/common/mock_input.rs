@@ -39,7 +39,26 @@ pub fn generate_set_buffer_with_expiry(expiry_variant: &str, duration: u32) -> V
     str_to_bytes(&input)
 }
 
+pub fn generate_inline_ping_buffer() -> Vec<u8> {
+    let input = "PING\r\n";
+    str_to_bytes(input)
+}
+
+pub fn generate_inline_set_buffer() -> Vec<u8> {
+    let input = "SET hello \"hello world\\n\"\r\n";
+    str_to_bytes(input)
+}
+
 fn str_to_bytes(str: &str) -> Vec<u8> {
     let input_bytes = str.as_bytes();
     input_bytes.to_vec()
 }
+
+// Encodes any command as an array of bulk strings, i.e. ["XLEN", "stream"]
+pub fn generate_command_buffer(args: &[&str]) -> Vec<u8> {
+    let mut input = format!("*{}\r\n", args.len());
+    for arg in args {
+        input.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
+    }
+    str_to_bytes(&input)
+}
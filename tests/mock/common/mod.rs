@@ -1,2 +1,4 @@
+pub mod commands;
 pub mod mock_input;
-pub mod reset_redis;
\ No newline at end of file
+pub mod reset_redis;
+pub mod server;
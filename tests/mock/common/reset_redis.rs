@@ -1,9 +1,13 @@
+use redis_starter_rust::acl::acl::{with_acl, Acl};
+use redis_starter_rust::config::config::{with_config, Config};
 use redis_starter_rust::store::redis::RedisStore;
 
 pub fn with_reset_redis<F>(test: F)
 where
-    F: FnOnce() -> (),
+    F: FnOnce(),
 {
     test();
     RedisStore::reset();
+    with_config(|config| *config = Config::new());
+    with_acl(|acl| *acl = Acl::new());
 }
@@ -0,0 +1,37 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::from_utf8;
+use std::time::Duration;
+
+use redis_starter_rust::server::server::Server;
+
+use crate::mock::common::mock_input::generate_command_buffer;
+
+// Starts a server on a free port, which is shut down when dropped
+pub fn start_server() -> Server {
+    Server::builder().port(0).start().unwrap()
+}
+
+// A port that is free at the time of calling, since port 0 disables TCP
+pub fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+pub fn connect(address: &str) -> TcpStream {
+    let stream = TcpStream::connect(address).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    stream
+}
+
+// Sends a command on a TCP or Unix socket and returns the reply
+pub fn send_socket_command<S: Read + Write>(stream: &mut S, args: &[&str]) -> String {
+    stream.write_all(&generate_command_buffer(args)).unwrap();
+    read_reply(stream)
+}
+
+// Reads what has arrived of the next replies, which is empty once the server closes the connection
+pub fn read_reply<S: Read>(stream: &mut S) -> String {
+    let mut buffer = [0; 1024];
+    let size = stream.read(&mut buffer).unwrap();
+    String::from(from_utf8(&buffer[..size]).unwrap())
+}
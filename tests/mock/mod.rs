@@ -1,2 +1,4 @@
+#![allow(dead_code)]
+
 pub mod tcp_stream;
 pub mod common;
\ No newline at end of file
@@ -22,7 +22,7 @@ impl Read for MockTcpStreamStreamReadError {
 
 impl Write for MockTcpStreamStreamReadError {
     fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
-        let buffer: Vec<u8> = buf.iter().cloned().collect();
+        let buffer: Vec<u8> = buf.to_vec();
         self.write_buffer = buffer;
         Ok(self.write_buffer.len())
     }
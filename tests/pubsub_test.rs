@@ -0,0 +1,144 @@
+use std::net::TcpListener;
+use std::thread;
+
+use serial_test::serial;
+
+use redis_starter_rust::handle_connection;
+use redis_starter_rust::request_response::client_input::ClientInput;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::{pending_messages, send_command};
+use mock::common::reset_redis::with_reset_redis;
+use mock::common::server::{connect, read_reply, send_socket_command};
+
+#[test]
+#[serial]
+fn publish_fans_out_to_channel_and_pattern_subscribers() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut subscriber = ClientInput::new();
+        let mut pattern_subscriber = ClientInput::new();
+        let mut publisher = ClientInput::new();
+
+        assert_eq!(
+            send_command(&mut subscriber, &["SUBSCRIBE", "news", "weather"]),
+            "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$9\r\nsubscribe\r\n$7\r\nweather\r\n:2\r\n"
+        );
+        assert_eq!(
+            send_command(&mut pattern_subscriber, &["PSUBSCRIBE", "n*"]),
+            "*3\r\n$10\r\npsubscribe\r\n$2\r\nn*\r\n:1\r\n"
+        );
+
+        assert_eq!(send_command(&mut publisher, &["PUBLISH", "news", "hello"]), ":2\r\n");
+        assert_eq!(
+            pending_messages(&subscriber),
+            "*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n"
+        );
+        assert_eq!(
+            pending_messages(&pattern_subscriber),
+            "*4\r\n$8\r\npmessage\r\n$2\r\nn*\r\n$4\r\nnews\r\n$5\r\nhello\r\n"
+        );
+        assert_eq!(pending_messages(&subscriber), "");
+
+        assert_eq!(send_command(&mut publisher, &["PUBLISH", "sport", "goal"]), ":0\r\n");
+        assert_eq!(
+            send_command(&mut subscriber, &["UNSUBSCRIBE", "news"]),
+            "*3\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n:1\r\n"
+        );
+        assert_eq!(send_command(&mut publisher, &["PUBLISH", "news", "again"]), ":1\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn subscribed_mode_only_allows_subscription_commands() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut subscriber = ClientInput::new();
+
+        send_command(&mut subscriber, &["SUBSCRIBE", "news"]);
+        assert_eq!(
+            send_command(&mut subscriber, &["GET", "key"]),
+            "-ERR Can't execute 'get': only (P)SUBSCRIBE / (P)UNSUBSCRIBE / PING / QUIT are allowed in this context\r\n"
+        );
+        assert_eq!(send_command(&mut subscriber, &["PING"]), "*2\r\n$4\r\npong\r\n$0\r\n\r\n");
+
+        assert_eq!(
+            send_command(&mut subscriber, &["UNSUBSCRIBE"]),
+            "*3\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n:0\r\n"
+        );
+        assert_eq!(send_command(&mut subscriber, &["PING"]), "+PONG\r\n");
+        assert_eq!(send_command(&mut subscriber, &["GET", "key"]), "$-1\r\n");
+        assert_eq!(
+            send_command(&mut subscriber, &["PUNSUBSCRIBE"]),
+            "*3\r\n$12\r\npunsubscribe\r\n$-1\r\n:0\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn pubsub_introspection() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut first = ClientInput::new();
+        let mut second = ClientInput::new();
+        let mut other = ClientInput::new();
+
+        send_command(&mut first, &["SUBSCRIBE", "news.tech", "weather"]);
+        send_command(&mut second, &["SUBSCRIBE", "news.tech"]);
+        send_command(&mut second, &["PSUBSCRIBE", "news.*", "sport.*"]);
+
+        assert_eq!(
+            send_command(&mut other, &["PUBSUB", "CHANNELS"]),
+            "*2\r\n$9\r\nnews.tech\r\n$7\r\nweather\r\n"
+        );
+        assert_eq!(send_command(&mut other, &["PUBSUB", "channels", "news.*"]), "*1\r\n$9\r\nnews.tech\r\n");
+        assert_eq!(
+            send_command(&mut other, &["PUBSUB", "NUMSUB", "news.tech", "missing"]),
+            "*4\r\n$9\r\nnews.tech\r\n:2\r\n$7\r\nmissing\r\n:0\r\n"
+        );
+        assert_eq!(send_command(&mut other, &["PUBSUB", "NUMPAT"]), ":2\r\n");
+        assert_eq!(
+            send_command(&mut other, &["PUBSUB", "HELLO"]),
+            "-ERR unknown subcommand 'HELLO'. Try PUBSUB HELP.\r\n"
+        );
+
+        // Subscriptions are removed when the connection is closed
+        drop(second);
+        assert_eq!(send_command(&mut other, &["PUBSUB", "NUMPAT"]), ":0\r\n");
+        assert_eq!(
+            send_command(&mut other, &["PUBSUB", "NUMSUB", "news.tech"]),
+            "*2\r\n$9\r\nnews.tech\r\n:1\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn subscribed_connection_receives_messages_without_sending_commands() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                let stream = stream.unwrap();
+                thread::spawn(move || handle_connection(stream));
+            }
+        });
+
+        let mut subscriber = connect(&address);
+        assert_eq!(
+            send_socket_command(&mut subscriber, &["SUBSCRIBE", "news"]),
+            "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n"
+        );
+
+        let mut publisher = connect(&address);
+        assert_eq!(send_socket_command(&mut publisher, &["PUBLISH", "news", "hello"]), ":1\r\n");
+
+        assert_eq!(read_reply(&mut subscriber), "*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n");
+    });
+}
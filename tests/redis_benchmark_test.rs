@@ -0,0 +1,71 @@
+use serial_test::serial;
+
+use redis_starter_rust::cli::benchmark::{run_benchmark, BenchmarkOptions};
+use redis_starter_rust::client::connection::RedisClient;
+use redis_starter_rust::config::config::with_config;
+use redis_starter_rust::server::server::Server;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::strings;
+use mock::common::reset_redis::with_reset_redis;
+use mock::common::server::start_server;
+
+// Starts a server on a free port, which is shut down when dropped, with options that connect to it
+fn start_server_with_options() -> (Server, BenchmarkOptions) {
+    let server = start_server();
+    let mut options = BenchmarkOptions::new();
+    options.port = with_config(|config| config.port);
+    (server, options)
+}
+
+#[test]
+#[serial]
+fn benchmark_sends_every_request_with_pipelining_and_random_keys() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let (_server, mut options) = start_server_with_options();
+        options.clients = 4;
+        options.requests = 1001;
+        options.pipeline = 16;
+        options.keyspace = 10;
+        options.tests = strings(&["set", "get"]);
+
+        let benchmarks = options.benchmarks();
+        assert_eq!(benchmarks.len(), 2);
+        for (name, commands) in benchmarks.iter() {
+            let report = run_benchmark(&options, name, commands).unwrap();
+            assert_eq!(report.requests, 1001);
+            assert_eq!(report.latencies.len(), 1001);
+            assert_eq!(report.errors, 0);
+            assert!(report.percentile(50.0) <= report.percentile(99.0));
+            assert!(report.requests_per_second() > 0.0);
+        }
+
+        let mut client = RedisClient::connect(options.address()).unwrap();
+        assert_eq!(client.get("key:000000000000"), Ok(Some(String::from("xxx"))));
+        assert_eq!(client.get("key:000000000010"), Ok(None));
+    });
+}
+
+#[test]
+#[serial]
+fn benchmark_counts_error_replies_of_a_command() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let (_server, mut options) = start_server_with_options();
+        options.clients = 2;
+        options.requests = 100;
+        options.command = strings(&["XLEN", "key:__rand_int__"]);
+
+        let mut client = RedisClient::connect(options.address()).unwrap();
+        client.set("key:000000000000", "value").unwrap();
+
+        let (name, commands) = options.benchmarks().remove(0);
+        assert_eq!(name, "XLEN key:__rand_int__");
+        let report = run_benchmark(&options, &name, &commands).unwrap();
+        assert_eq!(report.errors, 100);
+        assert!(report.format(&options).contains("100 error replies"));
+    });
+}
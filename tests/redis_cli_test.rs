@@ -0,0 +1,146 @@
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use serial_test::serial;
+
+use redis_starter_rust::cli::history::History;
+use redis_starter_rust::cli::options::CliOptions;
+use redis_starter_rust::cli::pipe::{mass_insert, PipeSummary};
+use redis_starter_rust::cli::repl::{run_command, Repl};
+use redis_starter_rust::client::connection::encode_command;
+use redis_starter_rust::config::config::with_config;
+use redis_starter_rust::server::server::Server;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::strings;
+use mock::common::reset_redis::with_reset_redis;
+use mock::common::server::start_server;
+
+// Starts a server on a free port, which is shut down when dropped, with options that connect to it
+fn start_server_with_options() -> (Server, CliOptions) {
+    let server = start_server();
+    let mut options = CliOptions::new();
+    options.port = with_config(|config| config.port);
+    (server, options)
+}
+
+#[test]
+#[serial]
+fn prompt_runs_typed_commands_and_formats_their_replies() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let (_server, options) = start_server_with_options();
+        let address = options.address();
+        let mut repl = Repl::new(options, History::new(None));
+
+        let input = [
+            "SET greeting \"hello world\"",
+            "GET greeting",
+            "GET 'missing",
+            "",
+            "2 XADD events * type \"login\"",
+            "XLEN events",
+            "SELECT 1",
+            "GET greeting",
+            "NOSUCHCOMMAND",
+            "quit",
+            "PING",
+        ]
+        .join("\n");
+        let mut output = vec![];
+        repl.run(Cursor::new(input), &mut output).unwrap();
+
+        let prompt = format!("{}> ", address);
+        let expected = [
+            format!("{}\"OK\"", prompt),
+            format!("{}\"hello world\"", prompt),
+            format!("{}Invalid argument(s)", prompt),
+            prompt.clone(),
+        ];
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with(&expected.join("\n")), "{}", output);
+        assert!(output.contains("(integer) 2"));
+        let selected = format!("{}[1]> ", address);
+        assert!(output.ends_with(&format!("{}(nil)\n{}(error) Unrecognised command\n{}", selected, selected, selected)));
+        assert!(!output.contains("PONG"));
+    });
+}
+
+#[test]
+#[serial]
+fn prompt_reconnects_after_the_connection_is_lost() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let (_server, options) = start_server_with_options();
+        let address = options.address();
+        let mut repl = Repl::new(options, History::new(None));
+
+        // The name is lost with the connection that is killed
+        let input = "CLIENT SETNAME first\nCLIENT KILL SKIPME NO\nCLIENT GETNAME\nCLIENT GETNAME\n";
+        let mut output = vec![];
+        repl.run(Cursor::new(input), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let prompt = format!("{}> ", address);
+        assert!(output.contains("(integer) 1"), "{}", output);
+        // Reset or closed, depending on when the reply is read
+        assert!(output.contains(&format!("{}Error: ", prompt)), "{}", output);
+        assert!(output.ends_with(&format!("\nnot connected> (nil)\n{}", prompt)), "{}", output);
+        assert_eq!(repl.prompt(), prompt);
+    });
+}
+
+#[test]
+#[serial]
+fn command_is_repeated_at_intervals() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let (_server, mut options) = start_server_with_options();
+        options.command = strings(&["XADD", "ticks", "*", "n", "1"]);
+        options.repeat = 3;
+        options.interval = Duration::from_millis(50);
+        let mut client = options.connect().unwrap();
+
+        let start = Instant::now();
+        let mut output = vec![];
+        run_command(&mut client, &options, &mut output).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(String::from_utf8(output).unwrap().lines().count(), 3);
+
+        options.command = strings(&["XLEN", "ticks"]);
+        options.repeat = 1;
+        options.raw = Some(true);
+        let mut output = vec![];
+        run_command(&mut client, &options, &mut output).unwrap();
+        assert_eq!(output, b"3\n");
+    });
+}
+
+#[test]
+#[serial]
+fn pipe_sends_raw_commands_and_counts_their_replies() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let (_server, options) = start_server_with_options();
+        let mut client = options.connect().unwrap();
+
+        // More than fits in the buffers of the connection, which is written while it is read
+        let mut input = vec![];
+        for index in 0..20000 {
+            input.extend_from_slice(&encode_command(&["SET", &format!("key:{}", index), &"x".repeat(100)]));
+        }
+        input.extend_from_slice(b"SELECT 100\r\nGET key:19999\r\n");
+
+        let summary = mass_insert(&mut client, Cursor::new(input)).unwrap();
+        assert_eq!(
+            summary,
+            PipeSummary {
+                replies: 20002,
+                errors: vec![String::from("ERR DB index is out of range")],
+            }
+        );
+        assert_eq!(client.get("key:0"), Ok(Some("x".repeat(100))));
+    });
+}
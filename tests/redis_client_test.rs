@@ -0,0 +1,221 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serial_test::serial;
+
+use redis_starter_rust::client::connection::{ClientError, RedisClient};
+use redis_starter_rust::client::pipeline::Pipeline;
+use redis_starter_rust::client::pool::Pool;
+use redis_starter_rust::client::subscription::Message;
+use redis_starter_rust::parser::parser::RESPOutput;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::reset_redis::with_reset_redis;
+use mock::common::server::start_server;
+
+fn connect(address: &str) -> RedisClient {
+    let client = RedisClient::connect(address).unwrap();
+    client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    client
+}
+
+fn bulk(string: &str) -> RESPOutput {
+    RESPOutput::BulkString(String::from(string))
+}
+
+#[test]
+#[serial]
+fn typed_commands_decode_their_replies() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let server = start_server();
+        let address = server.address();
+        let mut client = connect(&address);
+
+        assert_eq!(client.ping(), Ok(String::from("PONG")));
+        assert_eq!(client.echo("hello\r\nworld"), Ok(String::from("hello\r\nworld")));
+        assert_eq!(client.get("key"), Ok(None));
+        assert_eq!(client.set("key", "value"), Ok(()));
+        assert_eq!(client.get("key"), Ok(Some(String::from("value"))));
+        assert_eq!(client.set_with_expiry("short", "lived", Duration::from_millis(50)), Ok(()));
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(client.get("short"), Ok(None));
+
+        let id = client.xadd("events", "1-1", &[("type", "login"), ("user", "ada")]).unwrap();
+        assert_eq!(id, "1-1");
+        client.xadd("events", "*", &[("type", "logout")]).unwrap();
+        assert_eq!(client.xlen("events"), Ok(2));
+        let entries = client.xrange("events", "-", "+").unwrap();
+        assert_eq!(
+            entries[0],
+            (
+                String::from("1-1"),
+                vec![(String::from("type"), String::from("login")), (String::from("user"), String::from("ada"))]
+            )
+        );
+        assert_eq!(client.xdel("events", &["1-1"]), Ok(1));
+
+        assert_eq!(client.pfadd("visitors", &["a", "b", "c"]), Ok(true));
+        assert_eq!(client.pfadd("visitors", &["a"]), Ok(false));
+        assert_eq!(client.pfcount(&["visitors"]), Ok(3));
+
+        assert_eq!(client.geoadd("places", &[(13.361389, 38.115556, "Palermo"), (15.087269, 37.502669, "Catania")]), Ok(2));
+        let distance = client.geodist("places", "Palermo", "Catania").unwrap().unwrap();
+        assert!((distance - 166274.15).abs() < 1.0);
+        assert_eq!(client.geodist("places", "Palermo", "Rome"), Ok(None));
+
+        assert_eq!(client.client_getname(), Ok(None));
+        assert_eq!(client.client_setname("worker"), Ok(()));
+        assert_eq!(client.client_getname(), Ok(Some(String::from("worker"))));
+        assert_eq!(client.config_get("maxclients"), Ok(vec![(String::from("maxclients"), String::from("10000"))]));
+        assert_eq!(client.config_set("timeout", "0"), Ok(()));
+
+        assert_eq!(client.move_key("key", 1), Ok(true));
+        assert_eq!(client.select(1), Ok(()));
+        assert_eq!(client.get("key"), Ok(Some(String::from("value"))));
+
+        assert_eq!(client.multi(), Ok(()));
+        assert_eq!(client.command(&["SET", "key", "changed"]), Ok(RESPOutput::SimpleString(String::from("QUEUED"))));
+        assert_eq!(client.exec(), Ok(Some(vec![bulk("OK")])));
+        assert_eq!(client.flushall(), Ok(()));
+        assert_eq!(client.get("key"), Ok(None));
+    });
+}
+
+#[test]
+#[serial]
+fn errors_are_returned_without_breaking_the_connection() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let server = start_server();
+        let address = server.address();
+        let mut client = connect(&address);
+
+        assert_eq!(client.command(&["NOSUCHCOMMAND"]), Err(ClientError::Server(String::from("Unrecognised command"))));
+        assert_eq!(client.select(100), Err(ClientError::Server(String::from("ERR DB index is out of range"))));
+        client.set("key", "value").unwrap();
+        assert!(matches!(client.xlen("key"), Err(ClientError::Server(error)) if error.starts_with("WRONGTYPE")));
+        assert!(!client.is_broken());
+        assert_eq!(client.ping(), Ok(String::from("PONG")));
+    });
+}
+
+#[test]
+#[serial]
+fn pipelines_return_the_replies_in_order() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let server = start_server();
+        let address = server.address();
+        let mut client = connect(&address);
+
+        // Enough commands that the server reads them in several parts
+        let mut pipeline = Pipeline::new();
+        let keys: Vec<String> = (0..500).map(|index| format!("key:{}", index)).collect();
+        for key in keys.iter() {
+            pipeline.command(&["SET", key, key]);
+        }
+        pipeline.command(&["SELECT", "100"]).command(&["GET", "key:499"]);
+        assert_eq!(pipeline.len(), 502);
+
+        let replies = pipeline.execute(&mut client).unwrap();
+        assert_eq!(replies.len(), 502);
+        assert!(replies[..500].iter().all(|reply| *reply == bulk("OK")));
+        assert_eq!(replies[500], RESPOutput::Error(String::from("ERR DB index is out of range")));
+        assert_eq!(replies[501], bulk("key:499"));
+
+        // Values larger than the buffers of the server and of the client
+        let value = "x".repeat(100 * 1024);
+        assert_eq!(client.set("large", &value), Ok(()));
+        assert_eq!(client.get("large"), Ok(Some(value)));
+    });
+}
+
+#[test]
+#[serial]
+fn subscriptions_receive_published_messages() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let server = start_server();
+        let address = server.address();
+        let mut publisher = connect(&address);
+
+        let mut subscription = connect(&address).subscribe(&["news"]).unwrap();
+        subscription.psubscribe(&["sports.*"]).unwrap();
+        assert_eq!(publisher.publish("news", "hello"), Ok(1));
+        assert_eq!(publisher.publish("sports.tennis", "match point"), Ok(1));
+
+        assert_eq!(
+            subscription.next_message(),
+            Ok(Message {
+                channel: String::from("news"),
+                pattern: None,
+                payload: String::from("hello"),
+            })
+        );
+        assert_eq!(
+            subscription.next(),
+            Some(Ok(Message {
+                channel: String::from("sports.tennis"),
+                pattern: Some(String::from("sports.*")),
+                payload: String::from("match point"),
+            }))
+        );
+
+        let mut client = subscription.into_client().unwrap();
+        assert_eq!(publisher.publish("news", "nobody"), Ok(0));
+        assert_eq!(client.ping(), Ok(String::from("PONG")));
+
+        // Messages are pushes in RESP3
+        let mut client = connect(&address);
+        let properties = client.hello(3).unwrap();
+        assert!(properties.contains(&(String::from("proto"), RESPOutput::Integer(3))));
+        let mut subscription = client.subscribe(&["news"]).unwrap();
+        assert_eq!(publisher.publish("news", "pushed"), Ok(1));
+        assert_eq!(subscription.next_message().unwrap().payload, "pushed");
+    });
+}
+
+#[test]
+#[serial]
+fn pool_reuses_connections() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let server = start_server();
+        let address = server.address();
+        let pool = Arc::new(Pool::new(&address, 2));
+
+        let id = pool.get().unwrap().client_id().unwrap();
+        assert_eq!(pool.idle_count(), 1);
+        assert_eq!(pool.get().unwrap().client_id(), Ok(id));
+
+        let workers: Vec<_> = (0..4)
+            .map(|worker| {
+                let pool = Arc::clone(&pool);
+                thread::spawn(move || {
+                    let mut client = pool.get().unwrap();
+                    let key = format!("worker:{}", worker);
+                    client.set(&key, "done").unwrap();
+                    client.get(&key).unwrap()
+                })
+            })
+            .collect();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap(), Some(String::from("done")));
+        }
+        // Only as many connections as allowed are kept
+        assert_eq!(pool.idle_count(), 2);
+
+        // Connections that were closed are not reused
+        let mut client = pool.get().unwrap();
+        let id = client.client_id().unwrap().to_string();
+        assert_eq!(client.command(&["CLIENT", "KILL", "ID", &id, "SKIPME", "NO"]), Ok(RESPOutput::Integer(1)));
+        assert!(client.ping().is_err());
+        assert!(client.is_broken());
+        drop(client);
+        assert_eq!(pool.idle_count(), 1);
+    });
+}
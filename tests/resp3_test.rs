@@ -0,0 +1,87 @@
+use serial_test::serial;
+
+use redis_starter_rust::request_response::client_input::ClientInput;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::{pending_messages, send_command};
+use mock::common::reset_redis::with_reset_redis;
+
+#[test]
+#[serial]
+fn hello_switches_the_protocol_of_the_connection() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+
+        let reply = send_command(&mut client, &["HELLO", "3"]);
+        assert!(reply.starts_with("%7\r\n$6\r\nserver\r\n$5\r\nredis\r\n$7\r\nversion\r\n"));
+        assert!(reply.contains(&format!("$5\r\nproto\r\n:3\r\n$2\r\nid\r\n:{}\r\n", client.id())));
+        assert!(reply.ends_with("$4\r\nrole\r\n$6\r\nmaster\r\n$7\r\nmodules\r\n*0\r\n"));
+
+        assert_eq!(send_command(&mut client, &["GET", "missing"]), "_\r\n");
+        assert_eq!(
+            send_command(&mut client, &["CONFIG", "GET", "databases"]),
+            "%1\r\n$9\r\ndatabases\r\n$2\r\n16\r\n"
+        );
+        assert!(send_command(&mut client, &["CLIENT", "INFO"]).starts_with("="));
+
+        // Without a version, HELLO replies with the current protocol
+        assert!(send_command(&mut client, &["HELLO", "2"]).starts_with("*14\r\n"));
+        assert!(send_command(&mut client, &["HELLO"]).contains("$5\r\nproto\r\n:2\r\n"));
+        assert_eq!(send_command(&mut client, &["GET", "missing"]), "$-1\r\n");
+
+        assert_eq!(send_command(&mut client, &["HELLO", "4"]), "-NOPROTO unsupported protocol version\r\n");
+        assert_eq!(
+            send_command(&mut client, &["HELLO", "three"]),
+            "-ERR Protocol version is not an integer or out of range\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["HELLO", "3", "AUTH", "default"]),
+            "-ERR Syntax error in HELLO option 'AUTH'\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn hello_authenticates_and_sets_the_client_name() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut admin = ClientInput::new();
+        send_command(&mut admin, &["CONFIG", "SET", "requirepass", "secret"]);
+
+        let mut client = ClientInput::new();
+        assert!(send_command(&mut client, &["HELLO", "3"]).starts_with("-NOAUTH HELLO must be called with the client already authenticated"));
+        assert_eq!(
+            send_command(&mut client, &["HELLO", "3", "AUTH", "default", "wrong"]),
+            "-WRONGPASS invalid username-password pair or user is disabled.\r\n"
+        );
+        assert!(send_command(&mut client, &["HELLO", "3", "AUTH", "default", "secret", "SETNAME", "worker"]).starts_with("%7\r\n"));
+        assert_eq!(send_command(&mut client, &["CLIENT", "GETNAME"]), "$6\r\nworker\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn pubsub_messages_are_pushes_in_resp3() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut subscriber = ClientInput::new();
+        let mut publisher = ClientInput::new();
+
+        send_command(&mut subscriber, &["HELLO", "3"]);
+        assert_eq!(
+            send_command(&mut subscriber, &["SUBSCRIBE", "news"]),
+            ">3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n"
+        );
+        send_command(&mut publisher, &["PUBLISH", "news", "hello"]);
+        assert_eq!(pending_messages(&subscriber), ">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n");
+
+        // Commands can be run while subscribed, since their replies can be told apart from messages
+        send_command(&mut publisher, &["SET", "hello", "world"]);
+        assert_eq!(send_command(&mut subscriber, &["GET", "hello"]), "$5\r\nworld\r\n");
+        assert_eq!(send_command(&mut subscriber, &["PING"]), "+PONG\r\n");
+    });
+}
@@ -1,4 +1,7 @@
-use redis_starter_rust::request_response::response_helper::send_bulk_string_response;
+use redis_starter_rust::parser::parser::RESPOutput;
+use redis_starter_rust::request_response::response_helper::{
+    format_resp_output, send_bulk_string_response, with_protocol, Protocol,
+};
 
 mod mock;
 use mock::tcp_stream::mock_tcp_stream::MockTcpStream;
@@ -20,4 +23,49 @@ fn send_bulk_string_response_return_null_if_input_is_null() {
     send_bulk_string_response(&mut mock_tcp_stream, None);
 
     assert_eq!(mock_tcp_stream.write_buffer, "$-1\r\n".as_bytes());
-}
\ No newline at end of file
+}
+
+#[test]
+fn send_bulk_string_response_return_resp3_null_if_input_is_null() {
+    let mut mock_tcp_stream = MockTcpStream::new();
+
+    with_protocol(Protocol::Resp3, || send_bulk_string_response(&mut mock_tcp_stream, None));
+
+    assert_eq!(mock_tcp_stream.write_buffer, "_\r\n".as_bytes());
+}
+
+#[test]
+fn format_resp_output_encodes_resp3_types_per_protocol() {
+    let bulk = |string: &str| RESPOutput::BulkString(String::from(string));
+    let input = [
+        RESPOutput::Map(vec![(bulk("key"), RESPOutput::Integer(1))]),
+        RESPOutput::Set(vec![bulk("a")]),
+        RESPOutput::Push(vec![bulk("message")]),
+        RESPOutput::Double(1.5),
+        RESPOutput::Double(f64::NEG_INFINITY),
+        RESPOutput::Boolean(true),
+        RESPOutput::BigNumber(String::from("12345678901234567890")),
+        RESPOutput::VerbatimString(String::from("txt"), String::from("hello")),
+        RESPOutput::Attribute(vec![(bulk("ttl"), RESPOutput::Integer(10))], Box::new(bulk("value"))),
+        RESPOutput::Null,
+        RESPOutput::NullArray,
+    ];
+    let expected = [
+        ("*2\r\n$3\r\nkey\r\n:1\r\n", "%1\r\n$3\r\nkey\r\n:1\r\n"),
+        ("*1\r\n$1\r\na\r\n", "~1\r\n$1\r\na\r\n"),
+        ("*1\r\n$7\r\nmessage\r\n", ">1\r\n$7\r\nmessage\r\n"),
+        ("$3\r\n1.5\r\n", ",1.5\r\n"),
+        ("$4\r\n-inf\r\n", ",-inf\r\n"),
+        (":1\r\n", "#t\r\n"),
+        ("$20\r\n12345678901234567890\r\n", "(12345678901234567890\r\n"),
+        ("$5\r\nhello\r\n", "=9\r\ntxt:hello\r\n"),
+        ("$5\r\nvalue\r\n", "|1\r\n$3\r\nttl\r\n:10\r\n$5\r\nvalue\r\n"),
+        ("$-1\r\n", "_\r\n"),
+        ("*-1\r\n", "_\r\n"),
+    ];
+
+    for (index, output) in input.iter().enumerate() {
+        assert_eq!(format_resp_output(output), expected[index].0);
+        assert_eq!(with_protocol(Protocol::Resp3, || format_resp_output(output)), expected[index].1);
+    }
+}
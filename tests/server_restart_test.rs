@@ -0,0 +1,46 @@
+// Only built without init_redis_test, where the store is not initialised again by each test,
+// e.g. `cargo test --test server_restart_test`
+#![cfg(not(feature = "init_redis_test"))]
+
+use redis_starter_rust::client::connection::{ClientError, RedisClient};
+use redis_starter_rust::config::config::Config;
+use redis_starter_rust::parser::parser::RESPOutput;
+use redis_starter_rust::server::server::Server;
+
+fn bulk_strings(values: &[&str]) -> RESPOutput {
+    RESPOutput::Array(values.iter().map(|value| RESPOutput::BulkString(String::from(*value))).collect())
+}
+
+#[test]
+fn a_server_keeps_nothing_of_the_one_that_ran_before_it() {
+    let server = Server::builder().port(0).start().unwrap();
+    let mut client = RedisClient::connect(server.address()).unwrap();
+    client.command(&["SET", "key", "value"]).unwrap();
+    client.command(&["SELECT", "5"]).unwrap();
+    client.command(&["ACL", "SETUSER", "alice", "on", ">secret", "+@all", "~*"]).unwrap();
+    client.command(&["CONFIG", "SET", "notify-keyspace-events", "KEA"]).unwrap();
+    let mut subscriber = RedisClient::connect(server.address()).unwrap();
+    subscriber.command(&["SUBSCRIBE", "news"]).unwrap();
+    server.shutdown();
+
+    let mut config = Config::new();
+    config.databases = 2;
+    let server = Server::builder().config(config).port(0).start().unwrap();
+    let mut client = RedisClient::connect(server.address()).unwrap();
+    assert_eq!(
+        client.command(&["SELECT", "5"]),
+        Err(ClientError::Server(String::from("ERR DB index is out of range")))
+    );
+    assert_eq!(client.command(&["GET", "key"]), Ok(RESPOutput::Null));
+    assert_eq!(client.command(&["ACL", "GETUSER", "alice"]), Ok(RESPOutput::Null));
+    assert_eq!(
+        client.command(&["CONFIG", "GET", "notify-keyspace-events"]),
+        Ok(bulk_strings(&["notify-keyspace-events", ""]))
+    );
+    assert_eq!(client.command(&["PUBSUB", "CHANNELS"]), Ok(bulk_strings(&[])));
+    match client.command(&["CLIENT", "LIST"]) {
+        Ok(RESPOutput::BulkString(list)) => assert_eq!(list.lines().count(), 1, "{}", list),
+        reply => panic!("Unexpected reply: {:?}", reply),
+    }
+    server.shutdown();
+}
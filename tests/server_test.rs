@@ -0,0 +1,165 @@
+use std::io::Read;
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::Duration;
+
+use serial_test::serial;
+
+use redis_starter_rust::config::config::{with_config, Config};
+use redis_starter_rust::server::listener::{bind_listeners, Listener};
+use redis_starter_rust::server::server::Server;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::reset_redis::with_reset_redis;
+use mock::common::server::{connect, free_port, send_socket_command};
+
+#[test]
+#[serial]
+fn listens_on_every_bind_address() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut config = Config::new();
+        config.port = free_port();
+        // An address that cannot be assigned is skipped when prefixed with `-`
+        config.bind = vec![String::from("127.0.0.1"), String::from("127.0.0.2"), String::from("-192.0.2.1")];
+
+        let listeners = bind_listeners(&config).unwrap();
+        assert_eq!(listeners.len(), 2);
+        let addresses: Vec<String> = listeners.iter().map(Listener::address).collect();
+        for listener in listeners {
+            listener.spawn();
+        }
+
+        assert_eq!(send_socket_command(&mut connect(&addresses[0]), &["SET", "hello", "world"]), "$2\r\nOK\r\n");
+        assert_eq!(send_socket_command(&mut connect(&addresses[1]), &["GET", "hello"]), "$5\r\nworld\r\n");
+
+        config.bind = vec![String::from("192.0.2.1")];
+        assert!(bind_listeners(&config)
+            .unwrap_err()
+            .starts_with("Could not create server TCP listening socket 192.0.2.1:"));
+
+        config.port = 0;
+        assert_eq!(
+            bind_listeners(&config).unwrap_err(),
+            "Configured to not listen anywhere, exiting."
+        );
+    });
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn listens_on_a_unix_socket() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let path = std::env::temp_dir().join(format!("redis-test-{}.sock", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+
+        let mut config = Config::new();
+        config.port = 0;
+        config.unixsocket = path.clone();
+        config.unixsocketperm = 0o700;
+
+        // Binding twice replaces the socket file left by the first listener
+        bind_listeners(&config).unwrap();
+        let listeners = bind_listeners(&config).unwrap();
+        assert_eq!(listeners.len(), 1);
+        assert_eq!(listeners[0].address(), path);
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o700);
+        for listener in listeners {
+            listener.spawn();
+        }
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(send_socket_command(&mut stream, &["PING"]), "+PONG\r\n");
+        std::fs::remove_file(&path).unwrap();
+    });
+}
+
+#[test]
+#[serial]
+fn server_starts_on_a_free_port_and_shuts_down() {
+    with_reset_redis(|| {
+        let mut config = Config::new();
+        config.databases = 2;
+        let server = Server::builder().config(config).port(0).start().unwrap();
+        let address = server.address();
+        let port = with_config(|config| config.port);
+        assert!(address.ends_with(&format!(":{}", port)) && port != 0);
+
+        let mut stream = connect(&address);
+        assert_eq!(send_socket_command(&mut stream, &["SET", "hello", "world"]), "$2\r\nOK\r\n");
+        assert_eq!(send_socket_command(&mut stream, &["SELECT", "2"]), "-ERR DB index is out of range\r\n");
+
+        // Shut down from another thread, while the owner waits
+        let handle = server.shutdown_handle();
+        let shutdown = thread::spawn(move || handle.shutdown());
+        server.wait();
+        shutdown.join().unwrap();
+
+        // The open connection is closed, and no new ones are accepted
+        let mut buffer = [0; 16];
+        assert!(!matches!(stream.read(&mut buffer), Ok(size) if size > 0));
+        assert!(TcpStream::connect(&address).is_err());
+    });
+}
+
+#[test]
+#[serial]
+fn only_one_server_runs_in_a_process_at_a_time() {
+    with_reset_redis(|| {
+        let first = Server::builder().bind(&["127.0.0.1"]).port(0).start().unwrap();
+        assert_eq!(
+            Server::builder().port(0).start().unwrap_err(),
+            "ERR another server is already running in this process"
+        );
+
+        let mut stream = connect(&first.address());
+        assert_eq!(send_socket_command(&mut stream, &["PING"]), "+PONG\r\n");
+
+        // Dropping a server shuts it down, after which another one can start
+        let handle = first.shutdown_handle();
+        drop(first);
+        assert!(handle.is_shutdown());
+        let second = Server::builder().port(0).start().unwrap();
+        let mut stream = connect(&second.address());
+        assert_eq!(send_socket_command(&mut stream, &["PING"]), "+PONG\r\n");
+        second.shutdown();
+
+        // A server that fails to start does not prevent another from starting
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+        assert!(Server::builder().bind(&["127.0.0.1"]).port(port).start().is_err());
+        Server::builder().port(0).start().unwrap().shutdown();
+    });
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn server_removes_its_unix_socket_when_shut_down() {
+    with_reset_redis(|| {
+        let path = std::env::temp_dir().join(format!("redis-server-test-{}.sock", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let mut config = Config::new();
+        config.port = 0;
+        let server = Server::builder().config(config).unixsocket(&path).start().unwrap();
+        assert_eq!(server.address(), path);
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(send_socket_command(&mut stream, &["PING"]), "+PONG\r\n");
+
+        server.shutdown();
+        assert!(std::fs::metadata(&path).is_err());
+        let mut buffer = [0; 16];
+        assert!(!matches!((&stream).read(&mut buffer), Ok(size) if size > 0));
+    });
+}
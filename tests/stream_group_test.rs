@@ -0,0 +1,190 @@
+use std::thread;
+use std::time::Duration;
+
+use serial_test::serial;
+
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::send_command_on_new_connection;
+use mock::common::reset_redis::with_reset_redis;
+
+fn add_entries(key: &str, ids: &[&str]) {
+    for id in ids {
+        send_command_on_new_connection(&["XADD", key, id, "field", "value"]);
+    }
+}
+
+#[test]
+#[serial]
+fn xgroup_create_and_destroy() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        assert_eq!(
+            send_command_on_new_connection(&["XGROUP", "CREATE", "events", "group", "$"]),
+            "-ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.\r\n"
+        );
+        assert_eq!(send_command_on_new_connection(&["XGROUP", "CREATE", "events", "group", "$", "MKSTREAM"]), "+OK\r\n");
+        assert_eq!(
+            send_command_on_new_connection(&["XGROUP", "CREATE", "events", "group", "0"]),
+            "-BUSYGROUP Consumer Group name already exists\r\n"
+        );
+        assert_eq!(send_command_on_new_connection(&["XGROUP", "CREATECONSUMER", "events", "group", "alice"]), ":1\r\n");
+        assert_eq!(send_command_on_new_connection(&["XGROUP", "CREATECONSUMER", "events", "group", "alice"]), ":0\r\n");
+        assert_eq!(
+            send_command_on_new_connection(&["XGROUP", "SETID", "events", "missing", "0"]),
+            "-NOGROUP No such consumer group 'missing' for key name 'events'\r\n"
+        );
+        assert_eq!(send_command_on_new_connection(&["XGROUP", "DESTROY", "events", "group"]), ":1\r\n");
+        assert_eq!(send_command_on_new_connection(&["XGROUP", "DESTROY", "events", "group"]), ":0\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn xreadgroup_delivers_new_and_pending_entries() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        add_entries("events", &["1-0", "2-0"]);
+        send_command_on_new_connection(&["XGROUP", "CREATE", "events", "group", "0"]);
+
+        assert_eq!(
+            send_command_on_new_connection(&["XREADGROUP", "GROUP", "group", "alice", "COUNT", "1", "STREAMS", "events", ">"]),
+            "*1\r\n*2\r\n$6\r\nevents\r\n*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$5\r\nfield\r\n$5\r\nvalue\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["XREADGROUP", "GROUP", "group", "bob", "STREAMS", "events", ">"]),
+            "*1\r\n*2\r\n$6\r\nevents\r\n*1\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$5\r\nfield\r\n$5\r\nvalue\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["XREADGROUP", "GROUP", "group", "bob", "STREAMS", "events", ">"]),
+            "*-1\r\n"
+        );
+
+        // Reading history only returns the consumer's own pending entries
+        assert_eq!(
+            send_command_on_new_connection(&["XREADGROUP", "GROUP", "group", "alice", "STREAMS", "events", "0"]),
+            "*1\r\n*2\r\n$6\r\nevents\r\n*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$5\r\nfield\r\n$5\r\nvalue\r\n"
+        );
+        assert_eq!(send_command_on_new_connection(&["XACK", "events", "group", "1-0", "2-0", "3-0"]), ":2\r\n");
+        assert_eq!(
+            send_command_on_new_connection(&["XREADGROUP", "GROUP", "group", "alice", "STREAMS", "events", "0"]),
+            "*1\r\n*2\r\n$6\r\nevents\r\n*0\r\n"
+        );
+
+        assert_eq!(
+            send_command_on_new_connection(&["XREADGROUP", "GROUP", "missing", "alice", "STREAMS", "events", ">"]),
+            "-NOGROUP No such key 'events' or consumer group 'missing' in XREADGROUP with GROUP option\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn xpending_summary_and_extended_form() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        add_entries("events", &["1-0", "2-0", "3-0"]);
+        send_command_on_new_connection(&["XGROUP", "CREATE", "events", "group", "0"]);
+
+        assert_eq!(send_command_on_new_connection(&["XPENDING", "events", "group"]), "*4\r\n:0\r\n$-1\r\n$-1\r\n$-1\r\n");
+
+        send_command_on_new_connection(&["XREADGROUP", "GROUP", "group", "alice", "COUNT", "2", "STREAMS", "events", ">"]);
+        send_command_on_new_connection(&["XREADGROUP", "GROUP", "group", "bob", "STREAMS", "events", ">"]);
+        assert_eq!(
+            send_command_on_new_connection(&["XPENDING", "events", "group"]),
+            "*4\r\n:3\r\n$3\r\n1-0\r\n$3\r\n3-0\r\n*2\r\n*2\r\n$5\r\nalice\r\n$1\r\n2\r\n*2\r\n$3\r\nbob\r\n$1\r\n1\r\n"
+        );
+
+        let extended = send_command_on_new_connection(&["XPENDING", "events", "group", "-", "+", "10", "bob"]);
+        assert!(extended.starts_with("*1\r\n*4\r\n$3\r\n3-0\r\n$3\r\nbob\r\n:"));
+        assert!(extended.ends_with(":1\r\n"));
+        assert_eq!(
+            send_command_on_new_connection(&["XPENDING", "events", "group", "IDLE", "100000", "-", "+", "10"]),
+            "*0\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["XPENDING", "events", "missing"]),
+            "-NOGROUP No such key 'events' or consumer group 'missing'\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn xclaim_and_xautoclaim_transfer_entries() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        add_entries("events", &["1-0", "2-0", "3-0"]);
+        send_command_on_new_connection(&["XGROUP", "CREATE", "events", "group", "0"]);
+        send_command_on_new_connection(&["XREADGROUP", "GROUP", "group", "alice", "STREAMS", "events", ">"]);
+
+        assert_eq!(
+            send_command_on_new_connection(&["XCLAIM", "events", "group", "bob", "100000", "1-0"]),
+            "*0\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["XCLAIM", "events", "group", "bob", "0", "1-0", "JUSTID"]),
+            "*1\r\n$3\r\n1-0\r\n"
+        );
+
+        send_command_on_new_connection(&["XDEL", "events", "2-0"]);
+        assert_eq!(
+            send_command_on_new_connection(&["XAUTOCLAIM", "events", "group", "bob", "0", "0", "COUNT", "1", "JUSTID"]),
+            "*3\r\n$3\r\n2-0\r\n*1\r\n$3\r\n1-0\r\n*0\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["XAUTOCLAIM", "events", "group", "bob", "0", "2-0"]),
+            "*3\r\n$3\r\n0-0\r\n*1\r\n*2\r\n$3\r\n3-0\r\n*2\r\n$5\r\nfield\r\n$5\r\nvalue\r\n*1\r\n$3\r\n2-0\r\n"
+        );
+
+        let pending = send_command_on_new_connection(&["XPENDING", "events", "group"]);
+        assert!(pending.ends_with("*1\r\n*2\r\n$3\r\nbob\r\n$1\r\n2\r\n"));
+    });
+}
+
+#[test]
+#[serial]
+fn xinfo_describes_stream_groups_and_consumers() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        add_entries("events", &["1-0", "2-0"]);
+        send_command_on_new_connection(&["XGROUP", "CREATE", "events", "group", "0"]);
+        send_command_on_new_connection(&["XREADGROUP", "GROUP", "group", "alice", "COUNT", "1", "STREAMS", "events", ">"]);
+
+        let info = send_command_on_new_connection(&["XINFO", "STREAM", "events"]);
+        assert!(info.starts_with("*16\r\n$6\r\nlength\r\n:2\r\n"));
+        assert!(info.contains("$6\r\ngroups\r\n:1\r\n"));
+
+        assert_eq!(
+            send_command_on_new_connection(&["XINFO", "GROUPS", "events"]),
+            "*1\r\n*12\r\n$4\r\nname\r\n$5\r\ngroup\r\n$9\r\nconsumers\r\n:1\r\n$7\r\npending\r\n:1\r\n$17\r\nlast-delivered-id\r\n$3\r\n1-0\r\n$12\r\nentries-read\r\n:1\r\n$3\r\nlag\r\n:1\r\n"
+        );
+
+        let consumers = send_command_on_new_connection(&["XINFO", "CONSUMERS", "events", "group"]);
+        assert!(consumers.starts_with("*1\r\n*8\r\n$4\r\nname\r\n$5\r\nalice\r\n$7\r\npending\r\n:1\r\n"));
+        assert_eq!(send_command_on_new_connection(&["XINFO", "STREAM", "missing"]), "-ERR no such key\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn xreadgroup_block_wakes_up_on_xadd() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        send_command_on_new_connection(&["XGROUP", "CREATE", "events", "group", "$", "MKSTREAM"]);
+
+        let reader = thread::spawn(|| {
+            send_command_on_new_connection(&["XREADGROUP", "GROUP", "group", "alice", "BLOCK", "0", "STREAMS", "events", ">"])
+        });
+        thread::sleep(Duration::from_millis(50));
+        send_command_on_new_connection(&["XADD", "events", "1-0", "field", "value"]);
+
+        assert_eq!(
+            reader.join().unwrap(),
+            "*1\r\n*2\r\n$6\r\nevents\r\n*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$5\r\nfield\r\n$5\r\nvalue\r\n"
+        );
+    });
+}
@@ -0,0 +1,180 @@
+use std::thread;
+use std::time::Duration;
+
+use serial_test::serial;
+
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::send_command_on_new_connection;
+use mock::common::reset_redis::with_reset_redis;
+
+#[test]
+#[serial]
+fn xadd_generates_ids_and_xlen_counts_entries() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        let first = send_command_on_new_connection(&["XADD", "events", "*", "type", "login"]);
+        let second = send_command_on_new_connection(&["XADD", "events", "*", "type", "logout"]);
+        assert!(first.starts_with('$'));
+        assert_ne!(first, second);
+
+        assert_eq!(send_command_on_new_connection(&["XLEN", "events"]), ":2\r\n");
+        assert_eq!(send_command_on_new_connection(&["XLEN", "missing"]), ":0\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn xadd_validates_ids_against_last_id() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        assert_eq!(send_command_on_new_connection(&["XADD", "events", "5-1", "a", "1"]), "$3\r\n5-1\r\n");
+        assert_eq!(send_command_on_new_connection(&["XADD", "events", "5-*", "a", "1"]), "$3\r\n5-2\r\n");
+        assert_eq!(send_command_on_new_connection(&["XADD", "events", "6", "a", "1"]), "$3\r\n6-0\r\n");
+        assert_eq!(
+            send_command_on_new_connection(&["XADD", "events", "5-3", "a", "1"]),
+            "-ERR The ID specified in XADD is equal or smaller than the target stream top item\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["XADD", "other", "0-0", "a", "1"]),
+            "-ERR The ID specified in XADD must be greater than 0-0\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["XADD", "events", "abc", "a", "1"]),
+            "-ERR Invalid stream ID specified as stream command argument\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["XADD", "events", "*", "a"]),
+            "-ERR wrong number of arguments for 'xadd' command\r\n"
+        );
+        assert_eq!(send_command_on_new_connection(&["XADD", "missing", "NOMKSTREAM", "*", "a", "1"]), "$-1\r\n");
+        assert_eq!(send_command_on_new_connection(&["XLEN", "missing"]), ":0\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn xadd_returns_wrong_type_error_for_string_keys() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        send_command_on_new_connection(&["SET", "hello", "world"]);
+        assert_eq!(
+            send_command_on_new_connection(&["XADD", "hello", "*", "a", "1"]),
+            "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn xrange_and_xrevrange_return_entries_within_bounds() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        for id in ["1-0", "2-0", "3-0"] {
+            send_command_on_new_connection(&["XADD", "events", id, "id", id]);
+        }
+
+        assert_eq!(
+            send_command_on_new_connection(&["XRANGE", "events", "-", "+", "COUNT", "2"]),
+            "*2\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$2\r\nid\r\n$3\r\n1-0\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$2\r\nid\r\n$3\r\n2-0\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["XRANGE", "events", "(1-0", "2"]),
+            "*1\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$2\r\nid\r\n$3\r\n2-0\r\n"
+        );
+        assert_eq!(
+            send_command_on_new_connection(&["XREVRANGE", "events", "+", "-", "COUNT", "1"]),
+            "*1\r\n*2\r\n$3\r\n3-0\r\n*2\r\n$2\r\nid\r\n$3\r\n3-0\r\n"
+        );
+        assert_eq!(send_command_on_new_connection(&["XRANGE", "events", "3", "1"]), "*0\r\n");
+        assert_eq!(send_command_on_new_connection(&["XRANGE", "missing", "-", "+"]), "*0\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn xdel_and_xtrim_remove_entries() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        for id in ["1-0", "2-0", "3-0", "4-0", "5-0"] {
+            send_command_on_new_connection(&["XADD", "events", id, "id", id]);
+        }
+
+        assert_eq!(send_command_on_new_connection(&["XDEL", "events", "1-0", "9-0"]), ":1\r\n");
+        assert_eq!(send_command_on_new_connection(&["XTRIM", "events", "MAXLEN", "3"]), ":1\r\n");
+        assert_eq!(send_command_on_new_connection(&["XTRIM", "events", "MINID", "=", "4"]), ":1\r\n");
+        assert_eq!(send_command_on_new_connection(&["XLEN", "events"]), ":2\r\n");
+
+        send_command_on_new_connection(&["XADD", "events", "MAXLEN", "1", "6-0", "id", "6-0"]);
+        assert_eq!(
+            send_command_on_new_connection(&["XRANGE", "events", "-", "+"]),
+            "*1\r\n*2\r\n$3\r\n6-0\r\n*2\r\n$2\r\nid\r\n$3\r\n6-0\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn xread_returns_entries_after_ids() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        for id in ["1-0", "2-0"] {
+            send_command_on_new_connection(&["XADD", "events", id, "id", id]);
+        }
+
+        assert_eq!(
+            send_command_on_new_connection(&["XREAD", "COUNT", "1", "STREAMS", "events", "missing", "0", "0"]),
+            "*1\r\n*2\r\n$6\r\nevents\r\n*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$2\r\nid\r\n$3\r\n1-0\r\n"
+        );
+        assert_eq!(send_command_on_new_connection(&["XREAD", "STREAMS", "events", "2-0"]), "*-1\r\n");
+        assert_eq!(send_command_on_new_connection(&["XREAD", "BLOCK", "10", "STREAMS", "events", "$"]), "*-1\r\n");
+        assert_eq!(
+            send_command_on_new_connection(&["XREAD", "STREAMS", "events", "other", "0"]),
+            "-ERR Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn xread_count_zero_returns_every_entry() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+
+        for id in ["1-0", "2-0"] {
+            send_command_on_new_connection(&["XADD", "events", id, "id", id]);
+        }
+
+        let expected = "*1\r\n*2\r\n$6\r\nevents\r\n*2\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$2\r\nid\r\n$3\r\n1-0\r\n\
+            *2\r\n$3\r\n2-0\r\n*2\r\n$2\r\nid\r\n$3\r\n2-0\r\n";
+        assert_eq!(send_command_on_new_connection(&["XREAD", "COUNT", "0", "STREAMS", "events", "0"]), expected);
+        assert_eq!(send_command_on_new_connection(&["XREAD", "COUNT", "0", "BLOCK", "0", "STREAMS", "events", "0"]), expected);
+    });
+}
+
+#[test]
+#[serial]
+fn xread_block_wakes_up_on_xadd() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        send_command_on_new_connection(&["XADD", "events", "1-0", "id", "1-0"]);
+
+        let reader = thread::spawn(|| send_command_on_new_connection(&["XREAD", "BLOCK", "0", "STREAMS", "events", "$"]));
+
+        thread::sleep(Duration::from_millis(50));
+        send_command_on_new_connection(&["XADD", "events", "2-0", "id", "2-0"]);
+
+        assert_eq!(
+            reader.join().unwrap(),
+            "*1\r\n*2\r\n$6\r\nevents\r\n*1\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$2\r\nid\r\n$3\r\n2-0\r\n"
+        );
+    });
+}
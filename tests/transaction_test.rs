@@ -0,0 +1,193 @@
+use serial_test::serial;
+
+use redis_starter_rust::request_response::client_input::ClientInput;
+use redis_starter_rust::store::redis::{RedisStore, Store};
+
+mod mock;
+
+use mock::common::commands::send_command;
+use mock::common::reset_redis::with_reset_redis;
+
+#[test]
+#[serial]
+fn exec_replies_with_the_replies_of_queued_commands() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+        let mut other = ClientInput::new();
+
+        assert_eq!(send_command(&mut client, &["MULTI"]), "+OK\r\n");
+        assert_eq!(send_command(&mut client, &["SET", "hello", "world"]), "+QUEUED\r\n");
+        assert_eq!(send_command(&mut client, &["XADD", "hello", "*", "field", "value"]), "+QUEUED\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "+QUEUED\r\n");
+
+        // Queued commands are not executed before EXEC
+        assert_eq!(send_command(&mut other, &["GET", "hello"]), "$-1\r\n");
+
+        assert_eq!(
+            send_command(&mut client, &["EXEC"]),
+            "*3\r\n$2\r\nOK\r\n-WRONGTYPE Operation against a key holding the wrong kind of value\r\n$5\r\nworld\r\n"
+        );
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$5\r\nworld\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn exec_aborts_if_a_command_is_rejected_when_queued() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(send_command(&mut client, &["SET", "hello", "world"]), "+QUEUED\r\n");
+        assert_eq!(
+            send_command(&mut client, &["GET"]),
+            "-ERR wrong number of arguments for 'get' command\r\n"
+        );
+        assert_eq!(
+            send_command(&mut client, &["EXEC"]),
+            "-EXECABORT Transaction discarded because of previous errors.\r\n"
+        );
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$-1\r\n");
+
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(send_command(&mut client, &["NOTACOMMAND"]), "-Unrecognised command\r\n");
+        assert_eq!(
+            send_command(&mut client, &["EXEC"]),
+            "-EXECABORT Transaction discarded because of previous errors.\r\n"
+        );
+    });
+}
+
+#[test]
+#[serial]
+fn discard_and_misplaced_transaction_commands() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+
+        assert_eq!(send_command(&mut client, &["EXEC"]), "-ERR EXEC without MULTI\r\n");
+        assert_eq!(send_command(&mut client, &["DISCARD"]), "-ERR DISCARD without MULTI\r\n");
+
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(send_command(&mut client, &["MULTI"]), "-ERR MULTI calls can not be nested\r\n");
+        send_command(&mut client, &["SET", "hello", "world"]);
+        assert_eq!(send_command(&mut client, &["DISCARD"]), "+OK\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "hello"]), "$-1\r\n");
+
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*0\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn blocking_reads_do_not_block_in_a_transaction() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+
+        send_command(&mut client, &["MULTI"]);
+        send_command(&mut client, &["XREAD", "BLOCK", "0", "STREAMS", "events", "$"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*1\r\n*-1\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn exec_fails_if_a_watched_key_is_modified() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+        let mut other = ClientInput::new();
+
+        send_command(&mut client, &["SET", "balance", "10"]);
+        assert_eq!(send_command(&mut client, &["WATCH", "balance", "missing"]), "+OK\r\n");
+        send_command(&mut other, &["SET", "balance", "20"]);
+
+        send_command(&mut client, &["MULTI"]);
+        send_command(&mut client, &["SET", "balance", "30"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*-1\r\n");
+        assert_eq!(send_command(&mut client, &["GET", "balance"]), "$2\r\n20\r\n");
+
+        // Keys are no longer watched after EXEC
+        send_command(&mut client, &["MULTI"]);
+        send_command(&mut client, &["SET", "balance", "30"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*1\r\n$2\r\nOK\r\n");
+
+        // Creating a key that did not exist when watched counts as a modification
+        send_command(&mut client, &["WATCH", "missing"]);
+        send_command(&mut other, &["XADD", "missing", "*", "field", "value"]);
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*-1\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn exec_succeeds_if_watched_keys_are_only_read_or_unwatched() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+        let mut other = ClientInput::new();
+
+        send_command(&mut client, &["SET", "balance", "10"]);
+        send_command(&mut client, &["WATCH", "balance"]);
+        send_command(&mut other, &["GET", "balance"]);
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(
+            send_command(&mut client, &["WATCH", "balance"]),
+            "-ERR WATCH inside MULTI is not allowed\r\n"
+        );
+        send_command(&mut client, &["GET", "balance"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*1\r\n$2\r\n10\r\n");
+
+        send_command(&mut client, &["WATCH", "balance"]);
+        send_command(&mut other, &["SET", "balance", "20"]);
+        assert_eq!(send_command(&mut client, &["UNWATCH"]), "+OK\r\n");
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*0\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn exec_fails_if_a_watched_key_expires() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+
+        send_command(&mut client, &["SET", "session", "token", "PX", "10"]);
+        send_command(&mut client, &["WATCH", "session"]);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*-1\r\n");
+    });
+}
+
+#[test]
+#[serial]
+fn exec_fails_if_a_watched_key_is_modified_after_another_watcher_leaves() {
+    with_reset_redis(|| {
+        RedisStore::initialise();
+        let mut client = ClientInput::new();
+        let mut other = ClientInput::new();
+
+        send_command(&mut client, &["WATCH", "balance"]);
+        send_command(&mut other, &["WATCH", "balance"]);
+        drop(other);
+
+        let mut other = ClientInput::new();
+        send_command(&mut other, &["SET", "balance", "20"]);
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*-1\r\n");
+
+        // Swapping databases modifies the keys watched in either of them
+        send_command(&mut client, &["WATCH", "balance"]);
+        send_command(&mut other, &["SWAPDB", "0", "1"]);
+        send_command(&mut client, &["MULTI"]);
+        assert_eq!(send_command(&mut client, &["EXEC"]), "*-1\r\n");
+    });
+}